@@ -0,0 +1,37 @@
+//! Time the solver on generated datasets of growing size:
+//!
+//!     cargo bench --features generator
+
+use eve_pi::{generate_repository, Distribution, Solver};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const TARGETS: [&str; 3] = ["bacteria", "coolant", "mechanical_parts"];
+const ITERATIONS: u32 = 5;
+
+fn main() {
+    for (planets, characters) in [(50, 10), (300, 40), (1000, 120)] {
+        let repository = generate_repository(1234, planets, characters, Distribution::Realistic)
+            .expect("The realistic distribution weights every planet type");
+        let solver = Solver::new(&repository);
+
+        for target in TARGETS {
+            let mut elapsed = Duration::ZERO;
+            for _ in 0..ITERATIONS {
+                let start = Instant::now();
+                let plan = black_box(solver.solve(black_box(target)));
+                elapsed += start.elapsed();
+                if let Err(err) = plan {
+                    eprintln!("Failed to solve {}: {}", target, err);
+                }
+            }
+            println!(
+                "{:>5} planets {:>4} characters  {:<16} {:>10.2?}",
+                planets,
+                characters,
+                target,
+                elapsed / ITERATIONS
+            );
+        }
+    }
+}
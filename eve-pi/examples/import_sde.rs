@@ -0,0 +1,36 @@
+//! Regenerate the embedded product database from an extracted Static Data Export:
+//!
+//!     cargo run --example import_sde --features sde -- path/to/sde > src/data/products.json
+//!
+//! or, with `--names`, export every product's localized names for the frontend to pass to
+//! `load_product_translations`:
+//!
+//!     cargo run --example import_sde --features sde -- path/to/sde --names > product_names.json
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let Some(sde_dir) = std::env::args().nth(1).map(PathBuf::from) else {
+        eprintln!("Usage: import_sde <sde directory> [--names]");
+        return ExitCode::FAILURE;
+    };
+
+    let json = if std::env::args().nth(2).as_deref() == Some("--names") {
+        eve_pi::load_sde_product_names(&sde_dir).and_then(|names| {
+            serde_json::to_string_pretty(&names).map_err(|e| eve_pi::SdeError::Parse(e.to_string()))
+        })
+    } else {
+        eve_pi::load_sde_products(&sde_dir).and_then(|p| eve_pi::products_to_json(&p))
+    };
+    match json {
+        Ok(json) => {
+            print!("{}", json);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
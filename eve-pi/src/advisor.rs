@@ -0,0 +1,100 @@
+use crate::domain::PlanetType;
+use crate::scenario::PlanetarySkill;
+use serde::{Deserialize, Serialize};
+
+/// Planet types that would make an infeasible product solvable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanetAcquisition {
+    pub product: String,
+    pub already_feasible: bool,
+    pub solvable: bool, // Whether any combination within the search limit works
+    pub planet_types: Vec<PlanetType>, // Smallest set of planets to add, one entry per planet
+}
+
+/// Effect of training one skill level on a character
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillUpgrade {
+    pub character: String,
+    pub skill: PlanetarySkill,
+    pub from_level: u8,
+    pub to_level: u8,
+    pub coverage_before: f64, // Share of the quota achievable with current skills
+    pub coverage_after: f64,  // Share achievable after training
+    pub gain: f64,
+}
+
+/// Skill upgrades that would increase output for a set of quotas, most useful first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillAdvice {
+    pub coverage: f64, // Share of the quota achievable with current skills
+    pub upgrades: Vec<SkillUpgrade>,
+}
+
+impl SkillAdvice {
+    /// The most useful upgrade for each character, in the order of `upgrades`
+    pub fn best_per_character(&self) -> Vec<&SkillUpgrade> {
+        let mut best: Vec<&SkillUpgrade> = Vec::new();
+        for upgrade in &self.upgrades {
+            if !best.iter().any(|b| b.character == upgrade.character) {
+                best.push(upgrade);
+            }
+        }
+        best
+    }
+}
+
+/// Extra characters needed to reach a set of quotas
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AltEstimate {
+    pub additional_characters: usize,
+    pub planets_per_character: usize, // Planets each extra character can manage
+    pub planets_required: usize,      // Planets the quotas need in total
+    pub sufficient: bool,             // Whether the quotas are met with the extra characters
+    pub limited_by_planets: bool, // Character slots are left over, so more planets are needed instead
+    pub coverage: f64, // Share of the quotas achievable, given every character's command center skill
+}
+
+/// Most planets `Solver::advise_planets` will add. Every combination up to this size gets a
+/// full solve, and there are already 120 ways to add three planets.
+pub const MAX_ADVISOR_ADDITIONS: usize = 3;
+
+/// Every multiset of `size` planet types, in `PlanetType::ALL` order
+pub fn planet_type_combinations(size: usize) -> Vec<Vec<PlanetType>> {
+    fn extend(
+        start: usize,
+        size: usize,
+        current: &mut Vec<PlanetType>,
+        combinations: &mut Vec<Vec<PlanetType>>,
+    ) {
+        if current.len() == size {
+            combinations.push(current.clone());
+            return;
+        }
+        for i in start..PlanetType::ALL.len() {
+            current.push(PlanetType::ALL[i]);
+            extend(i, size, current, combinations);
+            current.pop();
+        }
+    }
+
+    let mut combinations = Vec::new();
+    extend(0, size, &mut Vec::new(), &mut combinations);
+    combinations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_planet_type_combinations() {
+        assert_eq!(planet_type_combinations(0), vec![Vec::new()]);
+        assert_eq!(planet_type_combinations(1).len(), 8);
+        // Combinations with repetition: C(8 + 2 - 1, 2)
+        assert_eq!(planet_type_combinations(2).len(), 36);
+        assert_eq!(
+            planet_type_combinations(2)[1],
+            vec![PlanetType::Barren, PlanetType::Gas]
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use crate::domain::{Character, Planet, PlanetType, Product};
+use crate::repository::{MemoryRepository, RepositoryError};
+use std::collections::HashMap;
+use std::future::Future;
+
+/// Product data behind a web service or database. Lookups can fail, unlike the in-memory
+/// repositories the solver reads from.
+pub trait AsyncProductRepository {
+    fn get_all_products(&self) -> impl Future<Output = Result<Vec<Product>, RepositoryError>>;
+
+    /// Look up a product by name; the default fetches every product
+    fn get_product_by_name(
+        &self,
+        name: &str,
+    ) -> impl Future<Output = Result<Option<Product>, RepositoryError>> {
+        async move {
+            let products = self.get_all_products().await?;
+            Ok(products.into_iter().find(|p| p.name == name))
+        }
+    }
+}
+
+/// Planet data behind a web service or database
+pub trait AsyncPlanetRepository {
+    fn get_all_planets(&self) -> impl Future<Output = Result<Vec<Planet>, RepositoryError>>;
+
+    /// Look up a planet by ID; the default fetches every planet
+    fn get_planet_by_id(
+        &self,
+        id: &str,
+    ) -> impl Future<Output = Result<Option<Planet>, RepositoryError>> {
+        async move {
+            let planets = self.get_all_planets().await?;
+            Ok(planets.into_iter().find(|p| p.id == id))
+        }
+    }
+
+    /// Planet types each P0 resource can be found on, or `None` to keep the built-in map
+    fn resource_map(
+        &self,
+    ) -> impl Future<Output = Result<Option<HashMap<String, Vec<PlanetType>>>, RepositoryError>>
+    {
+        async { Ok(None) }
+    }
+}
+
+/// Character data behind a web service or database
+pub trait AsyncCharacterRepository {
+    fn get_all_characters(&self) -> impl Future<Output = Result<Vec<Character>, RepositoryError>>;
+
+    /// Look up a character by name; the default fetches every character
+    fn get_character_by_name(
+        &self,
+        name: &str,
+    ) -> impl Future<Output = Result<Option<Character>, RepositoryError>> {
+        async move {
+            let characters = self.get_all_characters().await?;
+            Ok(characters.into_iter().find(|c| c.name == name))
+        }
+    }
+}
+
+/// Combined async repository trait for accessing all data
+pub trait AsyncRepository:
+    AsyncProductRepository + AsyncPlanetRepository + AsyncCharacterRepository
+{
+}
+
+impl<T: AsyncProductRepository + AsyncPlanetRepository + AsyncCharacterRepository> AsyncRepository
+    for T
+{
+}
+
+/// Fetch everything from an async repository into memory, so the synchronous solver can plan
+/// with it without blocking on the backend mid-solve
+pub async fn load_async_repository(
+    repository: &impl AsyncRepository,
+) -> Result<MemoryRepository, RepositoryError> {
+    let mut builder = MemoryRepository::builder()
+        .with_product_set(repository.get_all_products().await?)
+        .with_planets(repository.get_all_planets().await?)
+        .with_characters(repository.get_all_characters().await?);
+    if let Some(resource_map) = repository.resource_map().await? {
+        builder = builder.with_resource_map(resource_map);
+    }
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::ProductTier;
+    use crate::repository::{CharacterRepository, PlanetRepository, ProductRepository, Repository};
+    use crate::solver::Solver;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    /// Backend serving a fixed dataset, failing planet lookups when offline
+    struct RemoteRepository {
+        data: MemoryRepository,
+        offline: bool,
+    }
+
+    impl AsyncProductRepository for RemoteRepository {
+        async fn get_all_products(&self) -> Result<Vec<Product>, RepositoryError> {
+            Ok(self.data.get_all_products())
+        }
+    }
+
+    impl AsyncPlanetRepository for RemoteRepository {
+        async fn get_all_planets(&self) -> Result<Vec<Planet>, RepositoryError> {
+            if self.offline {
+                return Err(RepositoryError::InvalidData("backend offline".to_string()));
+            }
+            Ok(self.data.get_all_planets())
+        }
+    }
+
+    impl AsyncCharacterRepository for RemoteRepository {
+        async fn get_all_characters(&self) -> Result<Vec<Character>, RepositoryError> {
+            Ok(self.data.get_all_characters())
+        }
+    }
+
+    /// Run a future that never waits on anything
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("test future should not wait"),
+        }
+    }
+
+    fn remote_repository(offline: bool) -> RemoteRepository {
+        let mut data = MemoryRepository::new();
+        data.load_planets(
+            r#"[{"id": "planet_1", "planet_type": "Oceanic", "resources": ["aqueous_liquids"]}]"#,
+        )
+        .unwrap();
+        data.load_characters(
+            r#"[{"name": "Alice", "planets": 1, "skills": {"command_center_upgrades": 4, "interplanetary_consolidation": 0}}]"#,
+        )
+        .unwrap();
+        RemoteRepository { data, offline }
+    }
+
+    #[test]
+    fn test_load_async_repository() {
+        let remote = remote_repository(false);
+        let water = block_on(remote.get_product_by_name("water")).unwrap();
+        assert_eq!(water.unwrap().tier, ProductTier::P1);
+        assert!(block_on(remote.get_planet_by_id("planet_1"))
+            .unwrap()
+            .is_some());
+
+        let memory = block_on(load_async_repository(&remote)).unwrap();
+        assert_eq!(memory.get_all_planets().len(), 1);
+        assert!(memory.get_character_by_name("Alice").is_some());
+
+        // The loaded data drives the synchronous solver
+        let repository: &dyn Repository = &memory;
+        let plan = Solver::new(repository).solve("water").unwrap();
+        assert_eq!(plan.assignments.len(), 1);
+
+        assert!(block_on(load_async_repository(&remote_repository(true))).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+use crate::domain::{PlanetAssignment, ProductTier, ProductionPlan};
+use crate::logistics::map_import_sources;
+use crate::quantity::{assignment_daily_output, assignment_inputs_per_unit};
+use crate::repository::Repository;
+use serde::{Deserialize, Serialize};
+
+/// Mismatches below this many units per hour are rounding noise, not imbalances
+pub const BALANCE_TOLERANCE: f64 = 0.01;
+
+/// Which way a planet is out of balance with the rest of the plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImbalanceKind {
+    Deficit, // The planet's upstream producers can't cover one of its inputs
+    Surplus, // The planet makes more of a product than downstream planets consume
+}
+
+/// A planet whose input or output doesn't match the rest of the plan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssignmentImbalance {
+    pub planet: String,
+    pub product: String,
+    pub kind: ImbalanceKind,
+    pub units_per_hour: f64, // Size of the shortfall or excess
+}
+
+/// Supply and demand of a product made and consumed within a plan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProductBalance {
+    pub product: String,
+    pub supply_per_hour: f64, // Units made each hour by the planets producing it
+    pub demand_per_hour: f64, // Units the planets importing it need each hour
+}
+
+impl ProductBalance {
+    /// Surplus when positive, deficit when negative
+    pub fn net_per_hour(&self) -> f64 {
+        self.supply_per_hour - self.demand_per_hour
+    }
+}
+
+/// Result of checking a plan's production against its consumption
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BalanceReport {
+    pub products: Vec<ProductBalance>,
+    pub imbalances: Vec<AssignmentImbalance>,
+}
+
+impl BalanceReport {
+    /// Whether every planet gets the inputs it needs and ships all it makes
+    pub fn is_balanced(&self) -> bool {
+        self.imbalances.is_empty()
+    }
+
+    /// Imbalances of one kind
+    pub fn of_kind(&self, kind: ImbalanceKind) -> impl Iterator<Item = &AssignmentImbalance> {
+        self.imbalances.iter().filter(move |i| i.kind == kind)
+    }
+}
+
+/// Flag planets whose imports aren't covered by the planets producing them, and planets
+/// making more than the planets importing their output consume. Products nothing in the
+/// plan makes are taken to be bought in, and end products to leave the plan, so neither is
+/// flagged.
+pub fn check_plan_balance(repository: &dyn Repository, plan: &ProductionPlan) -> BalanceReport {
+    let flows = if plan.flows.is_empty() {
+        map_import_sources(repository, &plan.assignments)
+    } else {
+        plan.flows.clone()
+    };
+    let hourly_output: Vec<f64> = plan
+        .assignments
+        .iter()
+        .map(|a| assignment_daily_output(repository, a) / 24.0)
+        .collect();
+    let hauled = |product: &str, matches: &dyn Fn(&str, &str) -> bool| -> f64 {
+        flows
+            .iter()
+            .filter(|f| f.product == product && matches(&f.from_planet, &f.to_planet))
+            .map(|f| f.units_per_hour)
+            .sum()
+    };
+    let demand_of = |assignment: &PlanetAssignment, hourly: f64, product: &str| {
+        assignment_inputs_per_unit(repository, assignment)
+            .get(product)
+            .copied()
+            .unwrap_or(0.0)
+            * hourly
+    };
+
+    let mut products: Vec<&String> = plan
+        .assignments
+        .iter()
+        .flat_map(|a| &a.imported_inputs)
+        .filter(|product| plan.assignments.iter().any(|a| a.produces(product)))
+        .collect();
+    products.sort();
+    products.dedup();
+
+    let mut report = BalanceReport::default();
+    for product in products {
+        let mut supply_per_hour = 0.0;
+        let mut demand_per_hour = 0.0;
+        for (assignment, &hourly) in plan.assignments.iter().zip(&hourly_output) {
+            if assignment.produces(product) {
+                let produced = hourly * assignment.output_share(product);
+                supply_per_hour += produced;
+                let shipped = hauled(product, &|from, _| from == assignment.planet);
+                let surplus = produced - shipped;
+                if surplus > BALANCE_TOLERANCE {
+                    report.imbalances.push(AssignmentImbalance {
+                        planet: assignment.planet.clone(),
+                        product: product.clone(),
+                        kind: ImbalanceKind::Surplus,
+                        units_per_hour: surplus,
+                    });
+                }
+            }
+            if assignment.imported_inputs.contains(product) {
+                let needed = demand_of(assignment, hourly, product);
+                demand_per_hour += needed;
+                let received = hauled(product, &|_, to| to == assignment.planet);
+                let deficit = needed - received;
+                if deficit > BALANCE_TOLERANCE {
+                    report.imbalances.push(AssignmentImbalance {
+                        planet: assignment.planet.clone(),
+                        product: product.clone(),
+                        kind: ImbalanceKind::Deficit,
+                        units_per_hour: deficit,
+                    });
+                }
+            }
+        }
+        report.products.push(ProductBalance {
+            product: product.clone(),
+            supply_per_hour,
+            demand_per_hour,
+        });
+    }
+    report
+}
+
+/// Units each planet of a plan actually makes per hour once it is starved of any input its
+/// upstream planets can't fully supply, in assignment order. Inputs nothing in the plan makes
+/// are taken to be bought in whatever amount is needed.
+pub fn effective_hourly_output(repository: &dyn Repository, plan: &ProductionPlan) -> Vec<f64> {
+    let flows = if plan.flows.is_empty() {
+        map_import_sources(repository, &plan.assignments)
+    } else {
+        plan.flows.clone()
+    };
+    let capacity: Vec<f64> = plan
+        .assignments
+        .iter()
+        .map(|a| assignment_daily_output(repository, a) / 24.0)
+        .collect();
+    let index_of = |planet: &str| plan.assignments.iter().position(|a| a.planet == planet);
+
+    // Producers always sit at a lower tier than their consumers, so walk tiers upwards
+    let mut ordered: Vec<usize> = (0..plan.assignments.len()).collect();
+    ordered.sort_by_key(|&i| {
+        repository
+            .get_product_by_name(plan.assignments[i].output())
+            .map(|p| p.tier)
+            .unwrap_or(ProductTier::P0)
+    });
+
+    let mut effective = capacity.clone();
+    for i in ordered {
+        let assignment = &plan.assignments[i];
+        let inputs = assignment_inputs_per_unit(repository, assignment);
+        let mut ratio: f64 = 1.0;
+        for product in &assignment.imported_inputs {
+            if !plan.assignments.iter().any(|a| a.produces(product)) {
+                continue;
+            }
+            let needed = inputs.get(product).copied().unwrap_or(0.0) * capacity[i];
+            if needed <= 0.0 {
+                continue;
+            }
+            // Flows are routed at full capacity, so shrink them with a starved producer
+            let received: f64 = flows
+                .iter()
+                .filter(|f| &f.product == product && f.to_planet == assignment.planet)
+                .map(|f| {
+                    let producer_ratio = index_of(&f.from_planet)
+                        .filter(|&p| capacity[p] > 0.0)
+                        .map(|p| effective[p] / capacity[p])
+                        .unwrap_or(1.0);
+                    f.units_per_hour * producer_ratio
+                })
+                .sum();
+            ratio = ratio.min(received / needed);
+        }
+        effective[i] = capacity[i] * ratio.clamp(0.0, 1.0);
+    }
+    effective
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::MemoryRepository;
+
+    fn plan(assignments: Vec<PlanetAssignment>) -> ProductionPlan {
+        ProductionPlan {
+            assignments,
+            flows: Vec::new(),
+            schedule: None,
+        }
+    }
+
+    #[test]
+    fn test_deficit() {
+        let repo = MemoryRepository::new();
+        let mut water = PlanetAssignment::for_test("Character1", "Oceanic1", &["water"], &[]);
+        water.mined_inputs = vec!["aqueous_liquids".to_string()];
+        let plan = plan(vec![
+            water,
+            PlanetAssignment::for_test(
+                "Character1",
+                "Barren1",
+                &["coolant"],
+                &["water", "electrolytes"],
+            ),
+        ]);
+        let report = check_plan_balance(&repo, &plan);
+
+        // Electrolytes are bought in, so only the water shortfall is flagged
+        assert_eq!(report.products.len(), 1);
+        let water = &report.products[0];
+        assert!(water.net_per_hour() < 0.0);
+        assert!(!report.is_balanced());
+        let deficits: Vec<_> = report.of_kind(ImbalanceKind::Deficit).collect();
+        assert_eq!(deficits.len(), 1);
+        assert_eq!(deficits[0].planet, "Barren1");
+        assert!((deficits[0].units_per_hour + water.net_per_hour()).abs() < 1e-6);
+        assert_eq!(report.of_kind(ImbalanceKind::Surplus).count(), 0);
+
+        // The coolant planet only runs as fast as its water arrives
+        let effective = effective_hourly_output(&repo, &plan);
+        assert!((effective[0] - water.supply_per_hour).abs() < 1e-6);
+        let coolant_capacity = assignment_daily_output(&repo, &plan.assignments[1]) / 24.0;
+        let expected = coolant_capacity * water.supply_per_hour / water.demand_per_hour;
+        assert!((effective[1] - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_surplus() {
+        let repo = MemoryRepository::new();
+        let plan = plan(vec![
+            PlanetAssignment::for_test(
+                "Character1",
+                "Barren1",
+                &["neocoms"],
+                &["biocells", "construction_blocks", "microfiber_shielding"],
+            ),
+            PlanetAssignment::for_test(
+                "Character1",
+                "Barren2",
+                &["broadcast_node"],
+                &["neocoms", "data_chips", "high_tech_transmitters"],
+            ),
+        ]);
+        let report = check_plan_balance(&repo, &plan);
+
+        // A P3 factory makes 18 neocoms an hour, and a P4 factory uses 12 of them
+        let surplus: Vec<_> = report.of_kind(ImbalanceKind::Surplus).collect();
+        assert_eq!(surplus.len(), 1);
+        assert_eq!(surplus[0].planet, "Barren1");
+        assert_eq!(surplus[0].product, "neocoms");
+        assert!((surplus[0].units_per_hour - 6.0).abs() < 1e-6);
+        assert_eq!(report.of_kind(ImbalanceKind::Deficit).count(), 0);
+        assert!((report.products[0].net_per_hour() - 6.0).abs() < 1e-6);
+    }
+}
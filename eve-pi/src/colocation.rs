@@ -0,0 +1,148 @@
+use crate::domain::{PlanetAssignment, ProductTier};
+use crate::repository::Repository;
+
+/// Most P2 products a single hub planet makes; past this its facilities are spread too thin
+pub const MAX_OUTPUTS_PER_HUB: usize = 3;
+
+/// Whether an assignment is a P2 factory running purely on imported P1 products
+fn is_p2_factory(repository: &dyn Repository, assignment: &PlanetAssignment) -> bool {
+    let tier_of = |product: &str| repository.get_product_by_name(product).map(|p| p.tier);
+
+    assignment.mined_inputs.is_empty()
+        && tier_of(assignment.output()) == Some(ProductTier::P2)
+        && assignment
+            .imported_inputs
+            .iter()
+            .all(|input| tier_of(input) == Some(ProductTier::P1))
+}
+
+/// Merge P2 factory planets that share an imported ingredient onto a single hub planet,
+/// freeing the planets that are no longer needed. Earlier assignments host the hubs, and
+/// a merge is skipped if the hub would import more than `max_imports` distinct products.
+pub fn colocate_p2_factories(
+    repository: &dyn Repository,
+    assignments: Vec<PlanetAssignment>,
+    max_imports: Option<usize>,
+) -> Vec<PlanetAssignment> {
+    let mut merged: Vec<PlanetAssignment> = Vec::new();
+    // Indices into `merged` of assignments that can host more products
+    let mut hubs: Vec<usize> = Vec::new();
+
+    for assignment in assignments {
+        if !is_p2_factory(repository, &assignment) {
+            merged.push(assignment);
+            continue;
+        }
+
+        let host = hubs.iter().copied().find(|&index| {
+            let hub = &merged[index];
+            let mut imports = hub.imported_inputs.clone();
+            for input in &assignment.imported_inputs {
+                if !imports.contains(input) {
+                    imports.push(input.clone());
+                }
+            }
+
+            hub.outputs.len() + assignment.outputs.len() <= MAX_OUTPUTS_PER_HUB
+                && !assignment.outputs.iter().any(|output| hub.produces(output))
+                && assignment
+                    .imported_inputs
+                    .iter()
+                    .any(|input| hub.imported_inputs.contains(input))
+                && max_imports.is_none_or(|limit| imports.len() <= limit)
+        });
+
+        match host {
+            Some(index) => {
+                let hub = &mut merged[index];
+                for input in &assignment.imported_inputs {
+                    if !hub.imported_inputs.contains(input) {
+                        hub.imported_inputs.push(input.clone());
+                    }
+                }
+                hub.outputs.extend(assignment.outputs.iter().cloned());
+            }
+            None => {
+                hubs.push(merged.len());
+                merged.push(assignment);
+            }
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::MemoryRepository;
+
+    #[test]
+    fn test_colocate_p2_factories_merges_shared_ingredients() {
+        let repo = MemoryRepository::new();
+        let assignments = vec![
+            PlanetAssignment::for_test(
+                "Character1",
+                "Barren1",
+                &["coolant"],
+                &["water", "electrolytes"],
+            ),
+            PlanetAssignment::for_test(
+                "Character1",
+                "Barren2",
+                &["synthetic_oil"],
+                &["oxygen", "electrolytes"],
+            ),
+            PlanetAssignment::for_test(
+                "Character1",
+                "Barren3",
+                &["mechanical_parts"],
+                &["reactive_metals", "precious_metals"],
+            ),
+        ];
+
+        let merged = colocate_p2_factories(&repo, assignments, None);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].planet, "Barren1");
+        assert_eq!(merged[0].extra_outputs(), vec!["synthetic_oil"]);
+        assert_eq!(
+            merged[0].imported_inputs,
+            vec!["water", "electrolytes", "oxygen"]
+        );
+        assert_eq!(merged[1].output(), "mechanical_parts");
+    }
+
+    #[test]
+    fn test_colocate_p2_factories_respects_limits() {
+        let repo = MemoryRepository::new();
+        let assignments = vec![
+            PlanetAssignment::for_test(
+                "Character1",
+                "Barren1",
+                &["coolant"],
+                &["water", "electrolytes"],
+            ),
+            PlanetAssignment::for_test(
+                "Character1",
+                "Barren2",
+                &["synthetic_oil"],
+                &["oxygen", "electrolytes"],
+            ),
+            PlanetAssignment::for_test(
+                "Character1",
+                "Barren3",
+                &["coolant"],
+                &["water", "electrolytes"],
+            ),
+        ];
+
+        // The hub would need three imports
+        let merged = colocate_p2_factories(&repo, assignments.clone(), Some(2));
+        assert_eq!(merged.len(), 3);
+
+        // A second coolant planet isn't folded into a hub already making coolant
+        let merged = colocate_p2_factories(&repo, assignments, None);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[1].planet, "Barren3");
+    }
+}
@@ -0,0 +1,96 @@
+use crate::domain::{PlanetType, PlanetTypeSet};
+use crate::graph::DependencyGraph;
+use std::collections::HashMap;
+
+/// Which planet types can mine each raw resource, and which products each planet type can
+/// make entirely from its own extraction, as bitmasks worked out once per product set and
+/// resource map
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatibilityMatrix {
+    minable: HashMap<String, PlanetTypeSet>, // Planet types each P0 resource is found on
+    self_sufficient: HashMap<String, PlanetTypeSet>, // Planet types able to mine a product's whole chain
+}
+
+impl CompatibilityMatrix {
+    /// Build the matrix for a resource map and the products of a dependency graph
+    pub fn new(resource_map: &HashMap<String, Vec<PlanetType>>, graph: &DependencyGraph) -> Self {
+        let minable: HashMap<String, PlanetTypeSet> = resource_map
+            .iter()
+            .map(|(resource, types)| (resource.clone(), types.iter().copied().collect()))
+            .collect();
+
+        let self_sufficient = graph
+            .products()
+            .filter_map(|product| {
+                let raw = graph.raw_resources(product)?;
+                if raw.is_empty() {
+                    return None;
+                }
+                let types = raw.iter().fold(PlanetTypeSet::ALL, |types, resource| {
+                    types.intersection(minable.get(resource).copied().unwrap_or_default())
+                });
+                Some((product.to_string(), types))
+            })
+            .collect();
+
+        Self {
+            minable,
+            self_sufficient,
+        }
+    }
+
+    /// Planet types a P0 resource is found on, or `None` for an unknown resource
+    pub fn mining_types(&self, resource: &str) -> Option<PlanetTypeSet> {
+        self.minable.get(resource).copied()
+    }
+
+    /// Whether a planet type can mine a resource
+    pub fn can_mine(&self, planet_type: PlanetType, resource: &str) -> bool {
+        self.mining_types(resource)
+            .is_some_and(|types| types.contains(planet_type))
+    }
+
+    /// Planet types that can mine every raw resource in a product's chain
+    pub fn self_sufficient_types(&self, product: &str) -> PlanetTypeSet {
+        self.self_sufficient
+            .get(product)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Products a planet type can make from its own extraction alone, sorted by name
+    pub fn products_for(&self, planet_type: PlanetType) -> Vec<&str> {
+        let mut products: Vec<&str> = self
+            .self_sufficient
+            .iter()
+            .filter(|(_, types)| types.contains(planet_type))
+            .map(|(product, _)| product.as_str())
+            .collect();
+        products.sort();
+        products
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::{MemoryRepository, Repository};
+
+    #[test]
+    fn test_compatibility_matrix() {
+        let repo = MemoryRepository::new();
+        let matrix = repo.compatibility();
+
+        assert!(matrix.can_mine(PlanetType::Oceanic, "aqueous_liquids"));
+        assert!(!matrix.can_mine(PlanetType::Gas, "base_metals"));
+        assert_eq!(matrix.mining_types("water"), None);
+
+        // Water comes from aqueous liquids alone, so every planet type mining them makes it
+        assert_eq!(
+            matrix.self_sufficient_types("water"),
+            matrix.mining_types("aqueous_liquids").unwrap()
+        );
+        assert!(matrix.products_for(PlanetType::Oceanic).contains(&"water"));
+        assert!(matrix.self_sufficient_types("nano_factory").is_empty());
+    }
+}
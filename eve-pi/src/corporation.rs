@@ -0,0 +1,143 @@
+use crate::domain::{Character, Planet, PlanetAssignment, ProductionPlan};
+use crate::repository::{MemoryRepository, RepositoryError};
+use crate::solver::{Solver, SolverError, SolverOptions};
+use serde::{Deserialize, Serialize};
+
+/// A group of characters sharing a pool of scouted planets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Corporation {
+    pub name: String,
+    pub members: Vec<Character>,
+    pub planets: Vec<Planet>, // Scouted planets any member may colonize
+}
+
+impl Corporation {
+    /// Build a repository holding the corporation's members and planet pool
+    pub fn repository(&self) -> Result<MemoryRepository, RepositoryError> {
+        let mut repository = MemoryRepository::new();
+        repository.load_planets_data(self.planets.clone())?;
+        repository.load_characters_data(self.members.clone())?;
+        Ok(repository)
+    }
+}
+
+/// The colonies a single member is asked to set up
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemberPlan {
+    pub member: String,
+    pub assignments: Vec<PlanetAssignment>,
+    pub free_planets: usize, // Planet slots the member has left over
+}
+
+/// A corporation's production plan, split up per member for distribution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorporationPlan {
+    pub corporation: String,
+    pub plan: ProductionPlan,
+    pub members: Vec<MemberPlan>, // Every member, including those with nothing to do
+}
+
+/// Plan target products for a corporation, drawing on its shared planet pool and
+/// deciding which member colonizes each planet
+pub fn solve_for_corporation(
+    corporation: &Corporation,
+    target_products: &[&str],
+    options: SolverOptions,
+) -> Result<CorporationPlan, SolverError> {
+    let repository = corporation.repository()?;
+    let plan = Solver::with_options(&repository, options).solve_many(target_products)?;
+    Ok(group_by_member(corporation, plan))
+}
+
+/// Split a plan into per-member assignment lists, keeping the plan's order within each
+pub fn group_by_member(corporation: &Corporation, plan: ProductionPlan) -> CorporationPlan {
+    let mut members: Vec<MemberPlan> = corporation
+        .members
+        .iter()
+        .map(|member| {
+            let assignments: Vec<PlanetAssignment> = plan
+                .assignments
+                .iter()
+                .filter(|a| a.character == member.name)
+                .cloned()
+                .collect();
+            MemberPlan {
+                member: member.name.clone(),
+                free_planets: member.planets.saturating_sub(assignments.len()),
+                assignments,
+            }
+        })
+        .collect();
+    members.sort_by(|a, b| a.member.cmp(&b.member));
+
+    CorporationPlan {
+        corporation: corporation.name.clone(),
+        plan,
+        members,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{CharacterSkills, PlanetType};
+    use crate::scenario::hypothetical_planet;
+
+    fn member(name: &str, planets: usize) -> Character {
+        Character {
+            name: name.to_string(),
+            planets,
+            skills: CharacterSkills {
+                command_center_upgrades: 5,
+                interplanetary_consolidation: planets as u8 - 1,
+                remote_sensing: None,
+                planetary_production: None,
+                planetology: None,
+                advanced_planetology: None,
+                customs_code_expertise: None,
+            },
+            poco_tax_rate: None,
+            program_hours: None,
+            home_system: None,
+            service_range: None,
+        }
+    }
+
+    fn planet(id: &str, planet_type: PlanetType) -> Planet {
+        Planet {
+            id: id.to_string(),
+            ..hypothetical_planet(&MemoryRepository::new(), planet_type, 1)
+        }
+    }
+
+    #[test]
+    fn test_corporation_plan_groups_by_member() {
+        let corporation = Corporation {
+            name: "Test Corp".to_string(),
+            members: vec![member("Bravo", 1), member("Alpha", 2), member("Idle", 1)],
+            planets: vec![
+                planet("Oceanic1", PlanetType::Oceanic),
+                planet("Storm1", PlanetType::Storm),
+                planet("Barren1", PlanetType::Barren),
+            ],
+        };
+
+        let corp_plan =
+            solve_for_corporation(&corporation, &["coolant"], SolverOptions::default()).unwrap();
+
+        assert_eq!(corp_plan.corporation, "Test Corp");
+        assert_eq!(corp_plan.members.len(), 3);
+        assert_eq!(corp_plan.members[0].member, "Alpha");
+
+        let assigned: usize = corp_plan.members.iter().map(|m| m.assignments.len()).sum();
+        assert_eq!(assigned, corp_plan.plan.assignments.len());
+        for member_plan in &corp_plan.members {
+            assert!(member_plan
+                .assignments
+                .iter()
+                .all(|a| a.character == member_plan.member));
+        }
+        let free: usize = corp_plan.members.iter().map(|m| m.free_planets).sum();
+        assert_eq!(free, 4 - assigned);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::domain::{PlanetType, ProductTier};
+use crate::factory::find_valid_factory_configurations_for_planet;
+use crate::repository::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+
+/// Whether a product can be made with the planets in a repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Producibility {
+    /// Every raw resource in the chain can be mined on the available planets
+    Producible,
+    /// Some raw resources are missing, but the product can be built from bought inputs
+    ProducibleWithImports,
+    /// No available planet can host any factory for the product
+    Impossible,
+}
+
+/// Producibility of a single product
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductCoverage {
+    pub product: String,
+    pub tier: ProductTier,
+    pub status: Producibility,
+    pub missing_resources: Vec<String>, // P0 resources in the chain no available planet can mine
+}
+
+/// Producibility of every product considered
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub planet_types: Vec<PlanetType>, // Planet types available in the repository
+    pub products: Vec<ProductCoverage>,
+}
+
+impl CoverageReport {
+    /// Products with a given status
+    pub fn with_status(&self, status: Producibility) -> impl Iterator<Item = &ProductCoverage> {
+        self.products.iter().filter(move |p| p.status == status)
+    }
+}
+
+/// Classify every product, or only those of one tier, by whether the planets in the
+/// repository can produce it
+pub fn coverage_report(repository: &dyn Repository, tier: Option<ProductTier>) -> CoverageReport {
+    let planets = repository.get_all_planets();
+    let planet_types: HashSet<PlanetType> = planets.iter().map(|p| p.planet_type).collect();
+    let resource_map = repository.resource_map();
+    // A resource counts only if a planet of a fitting type actually lists it
+    let minable = |resource: &str| {
+        resource_map.get(resource).is_some_and(|types| {
+            planets.iter().any(|p| {
+                types.contains(&p.planet_type) && p.resources.iter().any(|r| r == resource)
+            })
+        })
+    };
+
+    let mut products: Vec<ProductCoverage> = repository
+        .get_all_products()
+        .into_iter()
+        .filter(|p| p.tier != ProductTier::P0 && tier.is_none_or(|t| p.tier == t))
+        .map(|product| {
+            let mut resources = BTreeSet::new();
+            collect_raw_resources(repository, &product.name, &mut resources);
+            let missing_resources: Vec<String> =
+                resources.into_iter().filter(|r| !minable(r)).collect();
+
+            let hostable = planets.iter().any(|planet| {
+                !find_valid_factory_configurations_for_planet(repository, planet, &product.name)
+                    .is_empty()
+            });
+            let status = if !hostable {
+                Producibility::Impossible
+            } else if missing_resources.is_empty() {
+                Producibility::Producible
+            } else {
+                Producibility::ProducibleWithImports
+            };
+
+            ProductCoverage {
+                product: product.name,
+                tier: product.tier,
+                status,
+                missing_resources,
+            }
+        })
+        .collect();
+    products.sort_by(|a, b| a.tier.cmp(&b.tier).then(a.product.cmp(&b.product)));
+
+    let mut planet_types: Vec<PlanetType> = planet_types.into_iter().collect();
+    planet_types.sort_by_key(|t| format!("{:?}", t));
+
+    CoverageReport {
+        planet_types,
+        products,
+    }
+}
+
+/// Collect the P0 resources at the bottom of a product's chain
+pub(crate) fn collect_raw_resources(
+    repository: &dyn Repository,
+    product: &str,
+    resources: &mut BTreeSet<String>,
+) {
+    let Some(product) = repository.get_product_ref(product) else {
+        return;
+    };
+    if let Some(raw) = repository.dependency_graph().raw_resources(&product.name) {
+        resources.extend(raw.iter().cloned());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::MemoryRepository;
+
+    fn coverage_of<'a>(report: &'a CoverageReport, product: &str) -> &'a ProductCoverage {
+        report
+            .products
+            .iter()
+            .find(|p| p.product == product)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_coverage_report_classifies_products() {
+        let mut repo = MemoryRepository::new();
+        repo.load_planets(
+            r#"[{ "id": "Oceanic1", "planet_type": "Oceanic", "resources": ["aqueous_liquids"] }]"#,
+        )
+        .unwrap();
+
+        let report = coverage_report(&repo, None);
+        assert_eq!(report.planet_types, vec![PlanetType::Oceanic]);
+        assert!(report.products.iter().all(|p| p.tier != ProductTier::P0));
+
+        assert_eq!(
+            coverage_of(&report, "water").status,
+            Producibility::Producible
+        );
+
+        // Electrolytes need ionic solutions, which oceanic planets don't have
+        let electrolytes = coverage_of(&report, "electrolytes");
+        assert_eq!(electrolytes.status, Producibility::Impossible);
+        assert_eq!(electrolytes.missing_resources, vec!["ionic_solutions"]);
+
+        // Coolant can still be built from bought electrolytes
+        let coolant = coverage_of(&report, "coolant");
+        assert_eq!(coolant.status, Producibility::ProducibleWithImports);
+        assert_eq!(coolant.missing_resources, vec!["ionic_solutions"]);
+    }
+
+    #[test]
+    fn test_coverage_report_by_tier() {
+        let repo = MemoryRepository::new();
+        let report = coverage_report(&repo, Some(ProductTier::P4));
+
+        assert!(!report.products.is_empty());
+        assert!(report.products.iter().all(|p| p.tier == ProductTier::P4));
+        // Without any planets nothing can be built
+        assert_eq!(
+            report.with_status(Producibility::Impossible).count(),
+            report.products.len()
+        );
+    }
+}
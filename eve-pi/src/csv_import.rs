@@ -0,0 +1,213 @@
+//! Planets and characters from spreadsheet CSV exports. The first row names the columns,
+//! matched case-insensitively with spaces treated as underscores; unknown columns are ignored
+//! and empty cells count as missing.
+//!
+//! Planet columns:
+//!
+//! | Column            | Required | Example                          |
+//! |-------------------|----------|----------------------------------|
+//! | `id`              | yes      | `Jita IV`                        |
+//! | `planet_type`     | yes      | `Barren`                         |
+//! | `resources`       | yes      | `Base Metals; Heavy Metals`      |
+//! | `system`          | no       | `Jita`                           |
+//! | `constellation`   | no       | `Kimotoro`                       |
+//! | `region`          | no       | `The Forge`                      |
+//! | `security_status` | no       | `0.9`                            |
+//! | `poco_tax_rate`   | no       | `0.1`                            |
+//! | `radius_km`       | no       | `5000`                           |
+//! | `owner`           | no       | `Alice`                          |
+//!
+//! Character columns:
+//!
+//! | Column                         | Required | Example           |
+//! |--------------------------------|----------|-------------------|
+//! | `name`                         | yes      | `Alice`           |
+//! | `planets`                      | yes      | `6`               |
+//! | `command_center_upgrades`      | yes      | `5`               |
+//! | `interplanetary_consolidation` | yes      | `5`               |
+//! | `remote_sensing`               | no       | `3`               |
+//! | `planetary_production`         | no       | `4`               |
+//! | `planetology`                  | no       | `3`               |
+//! | `advanced_planetology`         | no       | `2`               |
+//! | `customs_code_expertise`       | no       | `4`               |
+//! | `poco_tax_rate`                | no       | `0.05`            |
+//! | `program_hours`                | no       | `72`              |
+//! | `home_system`                  | no       | `Jita`            |
+//! | `service_range`                | no       | `same_region`     |
+//!
+//! Resources are separated by `;` or `|`, and resource names and planet types may use
+//! in-game spelling. Rows become untyped records, so bad cells are reported per row by the
+//! lenient loaders with the row's index among the data rows.
+
+use crate::domain::normalize_name;
+use crate::repository::RepositoryError;
+use serde_json::{Map, Number, Value};
+
+/// How a column's cells are turned into record values
+#[derive(Debug, Clone, Copy)]
+enum Cell {
+    Text,
+    Number,
+    List,  // Names separated by `;` or `|`
+    Name,  // snake_case, like enum variants and product names
+    Skill, // Number nested under `skills`
+}
+
+const PLANET_COLUMNS: [(&str, Cell); 10] = [
+    ("id", Cell::Text),
+    ("planet_type", Cell::Text),
+    ("resources", Cell::List),
+    ("system", Cell::Text),
+    ("constellation", Cell::Text),
+    ("region", Cell::Text),
+    ("security_status", Cell::Number),
+    ("poco_tax_rate", Cell::Number),
+    ("radius_km", Cell::Number),
+    ("owner", Cell::Text),
+];
+
+const CHARACTER_COLUMNS: [(&str, Cell); 13] = [
+    ("name", Cell::Text),
+    ("planets", Cell::Number),
+    ("command_center_upgrades", Cell::Skill),
+    ("interplanetary_consolidation", Cell::Skill),
+    ("remote_sensing", Cell::Skill),
+    ("planetary_production", Cell::Skill),
+    ("planetology", Cell::Skill),
+    ("advanced_planetology", Cell::Skill),
+    ("customs_code_expertise", Cell::Skill),
+    ("poco_tax_rate", Cell::Number),
+    ("program_hours", Cell::Number),
+    ("home_system", Cell::Text),
+    ("service_range", Cell::Name),
+];
+
+/// Record value of a cell. Numbers that don't parse stay text, so deserializing the record
+/// reports which field is wrong.
+fn cell_value(cell: Cell, text: &str) -> Value {
+    let number = || {
+        text.parse::<u64>()
+            .ok()
+            .map(Number::from)
+            .or_else(|| text.parse::<f64>().ok().and_then(Number::from_f64))
+            .map_or_else(|| Value::String(text.to_string()), Value::Number)
+    };
+    match cell {
+        Cell::Text => Value::String(text.to_string()),
+        Cell::Number | Cell::Skill => number(),
+        Cell::List => Value::Array(
+            text.split([';', '|'])
+                .map(normalize_name)
+                .filter(|name| !name.is_empty())
+                .map(Value::String)
+                .collect(),
+        ),
+        Cell::Name => Value::String(normalize_name(text)),
+    }
+}
+
+/// Turn CSV rows into records for the given column layout
+fn records_from_csv(csv: &str, columns: &[(&str, Cell)]) -> Result<Vec<Value>, RepositoryError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(csv.as_bytes());
+    let headers: Vec<Option<(&str, Cell)>> = reader
+        .headers()
+        .map_err(|e| RepositoryError::DeserializationError(e.to_string()))?
+        .iter()
+        .map(|header| {
+            let header = normalize_name(header);
+            columns.iter().find(|(name, _)| *name == header).copied()
+        })
+        .collect();
+
+    let mut records = Vec::new();
+    for row in reader.records() {
+        let row = row.map_err(|e| RepositoryError::DeserializationError(e.to_string()))?;
+        let mut record = Map::new();
+        let mut skills = Map::new();
+        for (column, text) in headers.iter().zip(row.iter()) {
+            let Some((name, cell)) = column else {
+                continue;
+            };
+            if text.is_empty() {
+                continue;
+            }
+            let target = match cell {
+                Cell::Skill => &mut skills,
+                _ => &mut record,
+            };
+            target.insert(name.to_string(), cell_value(*cell, text));
+        }
+        if columns.iter().any(|(_, cell)| matches!(cell, Cell::Skill)) {
+            record.insert("skills".to_string(), Value::Object(skills));
+        }
+        records.push(Value::Object(record));
+    }
+    Ok(records)
+}
+
+/// Planet records from a CSV export in the layout documented on this module
+pub fn planet_records_from_csv(csv: &str) -> Result<Vec<Value>, RepositoryError> {
+    records_from_csv(csv, &PLANET_COLUMNS)
+}
+
+/// Character records from a CSV export in the layout documented on this module
+pub fn character_records_from_csv(csv: &str) -> Result<Vec<Value>, RepositoryError> {
+    records_from_csv(csv, &CHARACTER_COLUMNS)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::PlanetType;
+    use crate::repository::{CharacterRepository, MemoryRepository, PlanetRepository};
+
+    #[test]
+    fn test_load_planets_csv() {
+        let mut repo = MemoryRepository::new();
+        let csv = "\
+ID,Planet Type,Resources,System,Security Status,Notes
+Jita IV,barren,Base Metals; Heavy Metals,Jita,0.9,home
+\"Perimeter II, moon\",Gas,noble_gas|reactive_gas,Perimeter,,
+Broken,Rocky,base_metals,,,
+";
+        let report = repo.load_planets_csv(csv).unwrap();
+
+        assert_eq!(report.loaded, 2);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].index, 2);
+        assert_eq!(report.errors[0].field.as_deref(), Some("planet_type"));
+
+        let jita = repo.get_planet_by_id("Jita IV").unwrap();
+        assert_eq!(jita.planet_type, PlanetType::Barren);
+        assert_eq!(jita.resources, vec!["base_metals", "heavy_metals"]);
+        assert_eq!(jita.security_status, Some(0.9));
+        let perimeter = repo.get_planet_by_id("Perimeter II, moon").unwrap();
+        assert_eq!(perimeter.resources, vec!["noble_gas", "reactive_gas"]);
+        assert_eq!(perimeter.security_status, None);
+    }
+
+    #[test]
+    fn test_load_characters_csv() {
+        let mut repo = MemoryRepository::new();
+        let csv = "\
+name,planets,command_center_upgrades,interplanetary_consolidation,remote_sensing,program_hours,service_range
+Alice,6,5,5,3,72,Same Region
+Bob,three,4,2,,,
+";
+        let report = repo.load_characters_csv(csv).unwrap();
+
+        assert_eq!(report.loaded, 1);
+        assert_eq!(report.errors[0].index, 1);
+        assert_eq!(report.errors[0].field.as_deref(), Some("planets"));
+
+        let alice = repo.get_character_by_name("Alice").unwrap();
+        assert_eq!(alice.planets, 6);
+        assert_eq!(alice.skills.command_center_upgrades, 5);
+        assert_eq!(alice.skills.remote_sensing, Some(3));
+        assert_eq!(alice.skills.planetology, None);
+        assert_eq!(alice.program_hours, Some(72.0));
+        assert!(alice.service_range.is_some());
+    }
+}
@@ -1,8 +1,13 @@
-use serde::{Deserialize, Serialize};
+use crate::logistics::LogisticsReport;
+use serde::{de, Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
+use std::str::FromStr;
+use tsify::Tsify;
 
 /// Represents the tier of a product in the production chain
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, PartialOrd, Ord)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, PartialOrd, Ord, Tsify,
+)]
 pub enum ProductTier {
     P0, // Raw materials
     P1, // Basic processed materials
@@ -11,8 +16,9 @@ pub enum ProductTier {
     P4, // Advanced commodities
 }
 
-/// Represents the type of planet in EVE Online
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Represents the type of planet in EVE Online. Deserializes from any casing, like
+/// "barren" or "BARREN".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Tsify)]
 pub enum PlanetType {
     Barren,
     Gas,
@@ -24,44 +30,396 @@ pub enum PlanetType {
     Temperate,
 }
 
+impl PlanetType {
+    /// Every planet type, in declaration order
+    pub const ALL: [PlanetType; 8] = [
+        PlanetType::Barren,
+        PlanetType::Gas,
+        PlanetType::Ice,
+        PlanetType::Lava,
+        PlanetType::Oceanic,
+        PlanetType::Plasma,
+        PlanetType::Storm,
+        PlanetType::Temperate,
+    ];
+}
+
+/// A set of planet types packed into one byte, for compatibility checks in hot loops
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct PlanetTypeSet(u8);
+
+impl PlanetTypeSet {
+    /// Set without any planet type
+    pub const EMPTY: PlanetTypeSet = PlanetTypeSet(0);
+    /// Set with every planet type, one bit each
+    pub const ALL: PlanetTypeSet = PlanetTypeSet(u8::MAX);
+
+    fn bit(planet_type: PlanetType) -> u8 {
+        1 << planet_type as u8
+    }
+
+    /// Whether the set holds a planet type
+    pub fn contains(self, planet_type: PlanetType) -> bool {
+        self.0 & Self::bit(planet_type) != 0
+    }
+
+    /// Add a planet type
+    pub fn insert(&mut self, planet_type: PlanetType) {
+        self.0 |= Self::bit(planet_type);
+    }
+
+    /// Planet types in either set
+    pub fn union(self, other: PlanetTypeSet) -> PlanetTypeSet {
+        PlanetTypeSet(self.0 | other.0)
+    }
+
+    /// Planet types in both sets
+    pub fn intersection(self, other: PlanetTypeSet) -> PlanetTypeSet {
+        PlanetTypeSet(self.0 & other.0)
+    }
+
+    /// Whether the sets share a planet type
+    pub fn intersects(self, other: PlanetTypeSet) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Planet types in the set, in declaration order
+    pub fn iter(self) -> impl Iterator<Item = PlanetType> {
+        PlanetType::ALL
+            .into_iter()
+            .filter(move |t| self.contains(*t))
+    }
+}
+
+impl FromIterator<PlanetType> for PlanetTypeSet {
+    fn from_iter<I: IntoIterator<Item = PlanetType>>(iter: I) -> Self {
+        let mut set = PlanetTypeSet::EMPTY;
+        for planet_type in iter {
+            set.insert(planet_type);
+        }
+        set
+    }
+}
+
+impl FromStr for PlanetType {
+    type Err = String;
+
+    /// Parse a planet type ignoring case and surrounding whitespace
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim();
+        PlanetType::ALL
+            .into_iter()
+            .find(|t| format!("{:?}", t).eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let expected: Vec<String> =
+                    PlanetType::ALL.iter().map(|t| format!("{:?}", t)).collect();
+                format!(
+                    "unknown planet type `{}`, expected one of {}",
+                    s,
+                    expected.join(", ")
+                )
+            })
+    }
+}
+
+impl<'de> Deserialize<'de> for PlanetType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Recipe a facility runs to make a product: how many units of each input one cycle
+/// consumes, and how many units of the product it makes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Schematic {
+    pub inputs: Vec<(String, u32)>, // Input product names with the units consumed per cycle
+    pub output_quantity: u32,       // Units made per cycle
+}
+
+impl Schematic {
+    /// Create a schematic from its inputs and the units it makes per cycle
+    pub fn new(inputs: Vec<(String, u32)>, output_quantity: u32) -> Self {
+        Self {
+            inputs,
+            output_quantity,
+        }
+    }
+
+    /// The standard schematic for a product of a tier, taking the same quantity of every input
+    /// (P4 schematics with a P1 input need it set explicitly)
+    pub fn standard(tier: ProductTier, ingredients: Vec<String>) -> Self {
+        let (input_quantity, output_quantity) = match tier {
+            ProductTier::P0 => (0, 0),
+            ProductTier::P1 => (3_000, 20),
+            ProductTier::P2 => (40, 5),
+            ProductTier::P3 => (10, 3),
+            ProductTier::P4 => (6, 1),
+        };
+        Self::new(
+            ingredients
+                .into_iter()
+                .map(|ingredient| (ingredient, input_quantity))
+                .collect(),
+            output_quantity,
+        )
+    }
+
+    /// Names of the inputs, in recipe order
+    pub fn ingredients(&self) -> Vec<String> {
+        self.inputs.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// Units of an input consumed per cycle, if the schematic uses it
+    pub fn quantity_of(&self, input: &str) -> Option<u32> {
+        self.inputs
+            .iter()
+            .find(|(name, _)| name == input)
+            .map(|(_, quantity)| *quantity)
+    }
+
+    /// Units of an input consumed per unit of output
+    pub fn per_unit(&self, input: &str) -> f64 {
+        match (self.quantity_of(input), self.output_quantity) {
+            (Some(quantity), output) if output > 0 => quantity as f64 / output as f64,
+            _ => 0.0,
+        }
+    }
+}
+
 /// Represents a product in the planetary production chain
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Product {
     pub name: String,
+    #[serde(default)]
+    pub display_name: String, // Name as shown in game, e.g. "Nano-Factory"
     pub tier: ProductTier,
-    pub ingredients: Vec<String>, // Names of products required to produce this product
+    #[serde(default)]
+    pub type_id: u32, // EVE type ID, or 0 when unknown
+    #[serde(default)]
+    pub schematic: Schematic, // Recipe producing this product, empty for raw materials
+    #[serde(default)]
+    pub requires_mining: bool, // P4 products whose factory also has to mine one of its inputs
 }
 
 impl Product {
-    /// Create a new product
+    /// Create a new product made with the standard schematic for its tier
     pub fn new(name: String, tier: ProductTier, ingredients: Vec<String>) -> Self {
         Self {
+            display_name: default_display_name(&name),
             name,
             tier,
-            ingredients,
+            type_id: 0,
+            schematic: Schematic::standard(tier, ingredients),
+            requires_mining: false,
         }
     }
 
     /// Create a P0 raw material (no ingredients)
     pub fn new_raw_material(name: String) -> Self {
         Self {
+            display_name: default_display_name(&name),
             name,
             tier: ProductTier::P0,
-            ingredients: Vec::new(),
+            type_id: 0,
+            schematic: Schematic::default(),
+            requires_mining: false,
+        }
+    }
+
+    /// Names of the products required to produce this product
+    pub fn ingredients(&self) -> Vec<String> {
+        self.schematic.ingredients()
+    }
+
+    /// Set the product's EVE type ID
+    pub fn with_type_id(mut self, type_id: u32) -> Self {
+        self.type_id = type_id;
+        self
+    }
+
+    /// Replace the product's schematic
+    pub fn with_schematic(mut self, schematic: Schematic) -> Self {
+        self.schematic = schematic;
+        self
+    }
+
+    /// Mark the product as needing one of its inputs mined on the factory planet
+    pub fn with_required_mining(mut self) -> Self {
+        self.requires_mining = true;
+        self
+    }
+}
+
+/// Kind of space a planet's system is in, which sets how rich its resources are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SecurityBand {
+    Highsec,
+    Lowsec,
+    Nullsec,
+    Wormhole,
+}
+
+impl SecurityBand {
+    /// Band of a system given its name and security status. Wormhole systems are named like
+    /// "J123456", apart from Thera.
+    pub fn of(system: Option<&str>, security_status: f64) -> Self {
+        let wormhole = system.is_some_and(|name| {
+            name == "Thera"
+                || (name.len() == 7
+                    && name.starts_with('J')
+                    && name[1..].chars().all(|c| c.is_ascii_digit()))
+        });
+        if wormhole {
+            SecurityBand::Wormhole
+        } else if security_status >= 0.45 {
+            // The game rounds security to one decimal, so 0.45 shows as 0.5
+            SecurityBand::Highsec
+        } else if security_status > 0.0 {
+            SecurityBand::Lowsec
+        } else {
+            SecurityBand::Nullsec
+        }
+    }
+
+    /// Extraction yield relative to the planned per-planet extraction rate, which assumes
+    /// nullsec or wormhole deposits
+    pub fn yield_modifier(self) -> f64 {
+        match self {
+            SecurityBand::Highsec => 0.6,
+            SecurityBand::Lowsec => 0.8,
+            SecurityBand::Nullsec | SecurityBand::Wormhole => 1.0,
         }
     }
 }
 
 /// Represents a planet in EVE Online
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
 pub struct Planet {
     pub id: String,
     pub planet_type: PlanetType,
     pub resources: Vec<String>, // Names of P0 resources available on this planet
+    #[serde(default)]
+    pub system: Option<String>, // Solar system the planet orbits
+    #[serde(default)]
+    pub constellation: Option<String>,
+    #[serde(default)]
+    pub region: Option<String>,
+    #[serde(default)]
+    pub security_status: Option<f64>, // Security status of the system, from -1.0 to 1.0
+    #[serde(default)]
+    pub poco_tax_rate: Option<f64>, // Rate set by the planet's customs office owner, if known
+    #[serde(default)]
+    pub richness: HashMap<String, f64>, // Scanned deposit richness by resource, 1.0 is average
+    #[serde(default)]
+    pub radius_km: Option<f64>, // Larger planets need longer links between pins
+    #[serde(default)]
+    pub owner: Option<String>, // Character that already colonized the planet, if any
+}
+
+impl Planet {
+    /// Create a planet without location metadata
+    pub fn new(id: String, planet_type: PlanetType, resources: Vec<String>) -> Self {
+        Self {
+            id,
+            planet_type,
+            resources,
+            system: None,
+            constellation: None,
+            region: None,
+            security_status: None,
+            poco_tax_rate: None,
+            richness: HashMap::new(),
+            radius_km: None,
+            owner: None,
+        }
+    }
+
+    /// Whether a character may be assigned this planet, which is only its owner once
+    /// it has been colonized
+    pub fn assignable_to(&self, character: &str) -> bool {
+        self.owner.as_deref().is_none_or(|owner| owner == character)
+    }
+
+    /// Typical length of a link between neighbouring pins, if the planet's size is known
+    pub fn link_length_km(&self) -> Option<f64> {
+        self.radius_km
+            .map(|radius| radius * LINK_LENGTH_PER_KM_RADIUS)
+    }
+
+    /// Richness of a resource's deposits relative to an average one, 1.0 when not scanned
+    pub fn richness_of(&self, resource: &str) -> f64 {
+        self.richness
+            .get(resource)
+            .map(|richness| richness.max(0.0))
+            .unwrap_or(1.0)
+    }
+
+    /// Kind of space the planet is in, if its security status is known
+    pub fn security_band(&self) -> Option<SecurityBand> {
+        self.security_status
+            .map(|security| SecurityBand::of(self.system.as_deref(), security))
+    }
+
+    /// Extraction yield relative to the planned per-planet rate, 1.0 when the location
+    /// is unknown
+    pub fn extraction_yield(&self) -> f64 {
+        self.security_band()
+            .map(SecurityBand::yield_modifier)
+            .unwrap_or(1.0)
+    }
+
+    /// Set where the planet is, as reported by ESI
+    pub fn with_location(
+        mut self,
+        system: &str,
+        constellation: &str,
+        region: &str,
+        security_status: f64,
+    ) -> Self {
+        self.system = Some(system.to_string());
+        self.constellation = Some(constellation.to_string());
+        self.region = Some(region.to_string());
+        self.security_status = Some(security_status);
+        self
+    }
+
+    /// Record a resource's scanned deposit richness
+    pub fn with_richness(mut self, resource: &str, richness: f64) -> Self {
+        self.richness.insert(resource.to_string(), richness);
+        self
+    }
+
+    /// Bind the planet to the character that colonized it
+    pub fn with_owner(mut self, character: &str) -> Self {
+        self.owner = Some(character.to_string());
+        self
+    }
+
+    /// Set the planet's radius
+    pub fn with_radius(mut self, radius_km: f64) -> Self {
+        self.radius_km = Some(radius_km);
+        self
+    }
+
+    /// Set the tax rate charged by the planet's customs office. A rate outside 0 to 1 is
+    /// dropped, leaving the planet on its character's rate.
+    pub fn with_tax_rate(mut self, tax_rate: f64) -> Self {
+        self.poco_tax_rate = Some(tax_rate).filter(|rate| is_valid_tax_rate(*rate));
+        self
+    }
 }
 
 /// Represents character skills for planetary industry
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
 pub struct CharacterSkills {
     pub command_center_upgrades: u8,
     pub interplanetary_consolidation: u8,
@@ -73,14 +431,170 @@ pub struct CharacterSkills {
     pub planetology: Option<u8>,
     #[serde(default)]
     pub advanced_planetology: Option<u8>,
+    #[serde(default)]
+    pub customs_code_expertise: Option<u8>, // Reduces customs office taxes by 10% per level
 }
 
 /// Represents a character in EVE Online
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
 pub struct Character {
     pub name: String,
     pub planets: usize,          // Number of planets the character can manage
     pub skills: CharacterSkills, // Skill levels for different planetary skills
+    #[serde(default)]
+    pub poco_tax_rate: Option<f64>, // Customs office tax rate paid, reflecting the character's standings
+    #[serde(default)]
+    pub program_hours: Option<f64>, // How often the character resets extractors, in hours
+    #[serde(default)]
+    pub home_system: Option<String>, // Solar system the character is based in
+    #[serde(default)]
+    pub service_range: Option<ServiceRange>, // How far from home the character will fly
+}
+
+/// How far from their home system a character is willing to service planets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceRange {
+    SameSystem,
+    SameConstellation,
+    SameRegion,
+    /// At most this many jumps from home
+    Jumps(u32),
+}
+
+impl Character {
+    /// Customs office tax rate this character pays, falling back to the default rate
+    pub fn effective_tax_rate(&self) -> f64 {
+        self.poco_tax_rate.unwrap_or(DEFAULT_POCO_TAX_RATE) * self.customs_tax_multiplier()
+    }
+
+    /// Tax rate this character pays on a planet, preferring the rate its customs office sets
+    pub fn tax_rate_on(&self, planet: &Planet) -> f64 {
+        planet
+            .poco_tax_rate
+            .or(self.poco_tax_rate)
+            .unwrap_or(DEFAULT_POCO_TAX_RATE)
+            * self.customs_tax_multiplier()
+    }
+
+    /// Base the character in a system, servicing only planets within a range of it
+    pub fn with_home(mut self, system: &str, range: ServiceRange) -> Self {
+        self.home_system = Some(system.to_string());
+        self.service_range = Some(range);
+        self
+    }
+
+    /// Reset extractors every given number of hours, like 24 for daily or 168 for weekly.
+    /// Hours that aren't a positive, finite number are dropped, leaving the solver's default.
+    pub fn with_program_hours(mut self, hours: f64) -> Self {
+        self.program_hours = Some(hours).filter(|hours| is_valid_program_hours(*hours));
+        self
+    }
+
+    /// How precisely the character's scans resolve deposit hotspots, from 0 to 1, based on
+    /// Planetology and Advanced Planetology. Remote Sensing only extends scan range.
+    pub fn scan_quality(&self) -> f64 {
+        let planetology = self.skills.planetology.unwrap_or(0).min(5);
+        let advanced = self.skills.advanced_planetology.unwrap_or(0).min(5);
+        (planetology + advanced) as f64 / 10.0
+    }
+
+    /// Extraction relative to the planned rate once the character places extractor heads
+    /// on the hotspots their scans reveal
+    pub fn deposit_factor(&self) -> f64 {
+        1.0 + MAX_HOTSPOT_BONUS * self.scan_quality()
+    }
+
+    /// Share of the tax rate left after the Customs Code Expertise discount
+    pub fn customs_tax_multiplier(&self) -> f64 {
+        let level = self.skills.customs_code_expertise.unwrap_or(0).min(5);
+        1.0 - 0.1 * level as f64
+    }
+}
+
+/// Extra extraction a character with perfect scans gets from heads placed on hotspots
+pub const MAX_HOTSPOT_BONUS: f64 = 0.2;
+
+/// EVE type IDs of the planetary skills read from ESI
+pub const COMMAND_CENTER_UPGRADES_SKILL_ID: u32 = 2505;
+pub const INTERPLANETARY_CONSOLIDATION_SKILL_ID: u32 = 2495;
+pub const REMOTE_SENSING_SKILL_ID: u32 = 13279;
+pub const PLANETOLOGY_SKILL_ID: u32 = 2406;
+pub const ADVANCED_PLANETOLOGY_SKILL_ID: u32 = 2403;
+
+/// A skill as listed by ESI's `/characters/{character_id}/skills/`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsiSkill {
+    pub skill_id: u32,
+    pub active_skill_level: u8, // Level usable right now, lower than trained on Alpha clones
+    #[serde(default)]
+    pub trained_skill_level: u8,
+}
+
+/// Response of ESI's `/characters/{character_id}/skills/`; fields other than the skill
+/// list are ignored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsiSkills {
+    pub skills: Vec<EsiSkill>,
+}
+
+impl EsiSkills {
+    /// Active level of a skill, or 0 when it isn't trained
+    pub fn level(&self, skill_id: u32) -> u8 {
+        self.skills
+            .iter()
+            .find(|s| s.skill_id == skill_id)
+            .map_or(0, |s| s.active_skill_level.min(5))
+    }
+}
+
+impl CharacterSkills {
+    /// Planetary skills from an ESI skills response. Customs Code Expertise and planetary
+    /// production aren't read from ESI and are left unset.
+    pub fn from_esi(esi: &EsiSkills) -> Self {
+        Self {
+            command_center_upgrades: esi.level(COMMAND_CENTER_UPGRADES_SKILL_ID),
+            interplanetary_consolidation: esi.level(INTERPLANETARY_CONSOLIDATION_SKILL_ID),
+            remote_sensing: Some(esi.level(REMOTE_SENSING_SKILL_ID)),
+            planetary_production: None,
+            planetology: Some(esi.level(PLANETOLOGY_SKILL_ID)),
+            advanced_planetology: Some(esi.level(ADVANCED_PLANETOLOGY_SKILL_ID)),
+            customs_code_expertise: None,
+        }
+    }
+
+    /// Planets a character can colonize: one, plus one per Interplanetary Consolidation level
+    pub fn planet_limit(&self) -> usize {
+        1 + self.interplanetary_consolidation.min(5) as usize
+    }
+}
+
+impl Character {
+    /// A character with planetary skills and planet limit from an ESI skills response
+    pub fn from_esi_skills(name: &str, esi: &EsiSkills) -> Self {
+        let skills = CharacterSkills::from_esi(esi);
+        Self {
+            name: name.to_string(),
+            planets: skills.planet_limit(),
+            skills,
+            poco_tax_rate: None,
+            program_hours: None,
+            home_system: None,
+            service_range: None,
+        }
+    }
+
+    /// Take skills and planet limit from an ESI skills response, keeping skills ESI doesn't
+    /// provide and every other setting
+    pub fn apply_esi_skills(&mut self, esi: &EsiSkills) {
+        let skills = CharacterSkills::from_esi(esi);
+        self.skills = CharacterSkills {
+            planetary_production: self.skills.planetary_production,
+            customs_code_expertise: self.skills.customs_code_expertise,
+            ..skills
+        };
+        self.planets = self.skills.planet_limit();
+    }
 }
 
 /// Represents a factory configuration for a planet
@@ -91,31 +605,382 @@ pub struct FactoryConfiguration {
     pub imported_inputs: Vec<String>, // Names of products that need to be imported
     pub mined_inputs: Vec<String>,    // Names of products that can be mined on the planet
     pub outputs: Vec<String>,         // Names of products that can be produced
+    #[serde(default)]
+    pub score: f64, // How attractive the setup is; higher is better
+    #[serde(default)]
+    pub pins: PinCounts, // Estimated pins needed to build the setup
+    #[serde(default)]
+    pub extraction: Vec<ExtractionRate>, // Extraction needed for each mined resource
+    #[serde(default)]
+    pub extraction_shortfall: bool, // Whether the planet can't extract enough to feed its factories
+    #[serde(default)]
+    pub cargo: CargoVolume, // Cargo moved through the launchpads
+    #[serde(default)]
+    pub hourly_output: f64, // Units made per hour across the outputs, once sized to fit
 }
 
-/// Represents an assignment of a planet to produce a specific product
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Cargo a factory configuration moves through its launchpads
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CargoVolume {
+    pub import_m3_per_cycle: f64, // Imported inputs consumed per output cycle
+    pub export_m3_per_cycle: f64, // Outputs made per output cycle
+    pub daily_import_m3: f64,
+    pub daily_export_m3: f64,
+    pub hours_between_runs: f64, // How long the launchpads buffer before a customs office run is due
+}
+
+/// Extraction a factory configuration needs for one mined resource
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtractionRate {
+    pub resource: String,
+    pub units_per_hour: f64, // Consumed by the planet's factories
+    pub heads: usize,        // Extractor heads needed to keep up
+}
+
+/// Estimated pins a colony needs to run a factory configuration
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinCounts {
+    pub extractors: usize, // Extractor control units, one per mined resource
+    #[serde(default)]
+    pub extractor_heads: usize, // Heads spread across every extractor
+    pub basic_factories: usize, // P0 to P1 facilities
+    pub advanced_factories: usize, // P1 to P2 and P2 to P3 facilities
+    pub high_tech_factories: usize, // P3 to P4 facilities
+    pub launchpads: usize, // For imports and exports
+    pub storage: usize,    // Buffers extracted resources for the factories
+}
+
+impl PinCounts {
+    /// Processing facilities of every kind
+    pub fn factories(&self) -> usize {
+        self.basic_factories + self.advanced_factories + self.high_tech_factories
+    }
+
+    /// Every pin besides the command center
+    pub fn total(&self) -> usize {
+        self.extractors + self.factories() + self.launchpads + self.storage
+    }
+
+    /// Number of pins of each kind
+    fn by_kind(&self) -> [(PinKind, usize); 7] {
+        [
+            (PinKind::ExtractorControlUnit, self.extractors),
+            (PinKind::ExtractorHead, self.extractor_heads),
+            (PinKind::BasicFactory, self.basic_factories),
+            (PinKind::AdvancedFactory, self.advanced_factories),
+            (PinKind::HighTechFactory, self.high_tech_factories),
+            (PinKind::Launchpad, self.launchpads),
+            (PinKind::Storage, self.storage),
+        ]
+    }
+
+    /// CPU in teraflops the pins use
+    pub fn cpu(&self) -> u32 {
+        self.by_kind()
+            .iter()
+            .map(|(kind, count)| kind.cpu() * *count as u32)
+            .sum()
+    }
+
+    /// Powergrid in megawatts the pins use
+    pub fn powergrid(&self) -> u32 {
+        self.by_kind()
+            .iter()
+            .map(|(kind, count)| kind.powergrid() * *count as u32)
+            .sum()
+    }
+
+    /// Links needed to connect every pin to the colony, one per pin in a tree
+    pub fn links(&self) -> usize {
+        self.total()
+    }
+
+    /// Whether a command center at a Command Center Upgrades level can power the pins
+    pub fn fits_command_center(&self, level: u8) -> bool {
+        self.fits_command_center_with_links(level, None)
+    }
+
+    /// Whether a command center can power the pins and the links between them. Links are
+    /// left out when their length isn't known.
+    pub fn fits_command_center_with_links(&self, level: u8, link_length_km: Option<f64>) -> bool {
+        let level = (level as usize).min(5);
+        let (link_cpu, link_powergrid) = link_length_km.map(link_cost).unwrap_or((0, 0));
+        let links = self.links() as u32;
+        self.cpu() + links * link_cpu <= COMMAND_CENTER_CPU[level]
+            && self.powergrid() + links * link_powergrid <= COMMAND_CENTER_POWERGRID[level]
+    }
+}
+
+/// Link length between neighbouring pins per kilometre of planet radius. Pins can't be
+/// packed tighter than a fixed angle apart, so links on gas giants run hundreds of km.
+pub const LINK_LENGTH_PER_KM_RADIUS: f64 = 0.015;
+
+/// CPU in teraflops and powergrid in megawatts a link of a given length uses
+pub fn link_cost(length_km: f64) -> (u32, u32) {
+    let length_km = length_km.max(0.0);
+    (
+        (15.0 + 0.2 * length_km).round() as u32,
+        (10.0 + 0.15 * length_km).round() as u32,
+    )
+}
+
+/// Kinds of pin that can be placed around a command center
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PinKind {
+    ExtractorControlUnit,
+    ExtractorHead,
+    BasicFactory,
+    AdvancedFactory,
+    HighTechFactory,
+    Launchpad,
+    Storage,
+}
+
+impl PinKind {
+    /// CPU in teraflops the pin uses
+    pub fn cpu(self) -> u32 {
+        match self {
+            PinKind::ExtractorControlUnit => 400,
+            PinKind::ExtractorHead => 110,
+            PinKind::BasicFactory => 200,
+            PinKind::AdvancedFactory => 500,
+            PinKind::HighTechFactory => 400,
+            PinKind::Launchpad => 3_600,
+            PinKind::Storage => 500,
+        }
+    }
+
+    /// Powergrid in megawatts the pin uses
+    pub fn powergrid(self) -> u32 {
+        match self {
+            PinKind::ExtractorControlUnit => 2_600,
+            PinKind::ExtractorHead => 550,
+            PinKind::BasicFactory => 800,
+            PinKind::AdvancedFactory => 700,
+            PinKind::HighTechFactory => 1_100,
+            PinKind::Launchpad => 700,
+            PinKind::Storage => 700,
+        }
+    }
+}
+
+/// CPU in teraflops a command center provides at each Command Center Upgrades level
+pub const COMMAND_CENTER_CPU: [u32; 6] = [1_675, 7_057, 9_212, 12_136, 15_000, 17_590];
+
+/// Powergrid in megawatts a command center provides at each Command Center Upgrades level
+pub const COMMAND_CENTER_POWERGRID: [u32; 6] = [6_000, 9_000, 12_000, 15_000, 17_000, 19_000];
+
+/// ISK spent upgrading a command center from Basic to each Command Center Upgrades level
+pub const COMMAND_CENTER_UPGRADE_COST: [f64; 6] = [
+    0.0,
+    580_000.0,
+    1_510_000.0,
+    2_710_000.0,
+    4_210_000.0,
+    6_310_000.0,
+];
+
+/// Represents an assignment of a planet to produce one or more products
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
+#[serde(from = "StoredAssignment")]
 pub struct PlanetAssignment {
     pub character: String, // Character name
     pub planet: String,    // Planet ID
     pub planet_type: PlanetType,
     pub imported_inputs: Vec<String>, // Products imported to this planet
     pub mined_inputs: Vec<String>,    // Products mined on this planet
-    pub output: String,               // Product being produced
+    pub outputs: Vec<String>,         // Products made on the planet, main output first
+    pub program_hours: Option<f64>,   // Extractor program length, a daily program when unset
+    pub command_center_level: Option<u8>, // Upgrade level the command center needs
+    pub import_m3_per_hour: HashMap<String, f64>, // Volume hauled in for each imported input
+}
+
+impl PlanetAssignment {
+    /// Barren planet making its outputs from imports alone, without mining or upgrades
+    #[cfg(test)]
+    pub(crate) fn for_test(
+        character: &str,
+        planet: &str,
+        outputs: &[&str],
+        imported: &[&str],
+    ) -> Self {
+        PlanetAssignment {
+            character: character.to_string(),
+            planet: planet.to_string(),
+            planet_type: PlanetType::Barren,
+            imported_inputs: imported.iter().map(|s| s.to_string()).collect(),
+            mined_inputs: Vec::new(),
+            outputs: outputs.iter().map(|s| s.to_string()).collect(),
+            program_hours: None,
+            command_center_level: None,
+            import_m3_per_hour: HashMap::new(),
+        }
+    }
+
+    /// The planet's main output
+    pub fn output(&self) -> &str {
+        self.outputs.first().map(String::as_str).unwrap_or_default()
+    }
+
+    /// Products made alongside the main output from the same imports
+    pub fn extra_outputs(&self) -> &[String] {
+        self.outputs.get(1..).unwrap_or_default()
+    }
+
+    /// Whether the planet makes a product, as its main output or alongside it
+    pub fn produces(&self, product: &str) -> bool {
+        self.outputs.iter().any(|output| output == product)
+    }
+
+    /// Share of the planet's output that goes to a product. A planet making several products
+    /// splits its output evenly between them.
+    pub fn output_share(&self, product: &str) -> f64 {
+        if self.produces(product) {
+            1.0 / self.outputs.len() as f64
+        } else {
+            0.0
+        }
+    }
+
+    /// Total volume (m³) hauled onto the planet each hour
+    pub fn hauling_m3_per_hour(&self) -> f64 {
+        self.import_m3_per_hour.values().sum()
+    }
+}
+
+/// Planet assignment as stored, also accepting plans saved when an assignment had a single
+/// `output` and a separate `extra_outputs` list
+#[derive(Deserialize)]
+struct StoredAssignment {
+    character: String,
+    planet: String,
+    planet_type: PlanetType,
+    imported_inputs: Vec<String>,
+    mined_inputs: Vec<String>,
+    #[serde(default)]
+    outputs: Vec<String>,
+    #[serde(default)]
+    output: Option<String>,
+    #[serde(default)]
+    extra_outputs: Vec<String>,
+    #[serde(default)]
+    program_hours: Option<f64>,
+    #[serde(default)]
+    command_center_level: Option<u8>,
+    #[serde(default)]
+    import_m3_per_hour: HashMap<String, f64>,
+}
+
+impl From<StoredAssignment> for PlanetAssignment {
+    fn from(stored: StoredAssignment) -> Self {
+        let outputs = if stored.outputs.is_empty() {
+            stored
+                .output
+                .into_iter()
+                .chain(stored.extra_outputs)
+                .collect()
+        } else {
+            stored.outputs
+        };
+        Self {
+            character: stored.character,
+            planet: stored.planet,
+            planet_type: stored.planet_type,
+            imported_inputs: stored.imported_inputs,
+            mined_inputs: stored.mined_inputs,
+            outputs,
+            program_hours: stored.program_hours,
+            command_center_level: stored.command_center_level,
+            import_m3_per_hour: stored.import_m3_per_hour,
+        }
+    }
+}
+
+/// A product hauled from the planet making it to a planet importing it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Tsify)]
+pub struct ProductFlow {
+    pub product: String,
+    pub from_planet: String, // Planet ID of the producer
+    pub to_planet: String,   // Planet ID of the consumer
+    pub units_per_hour: f64, // Units hauled along the route each hour
 }
 
 /// Represents a complete production plan
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
 pub struct ProductionPlan {
     pub assignments: Vec<PlanetAssignment>,
+    #[serde(default)]
+    pub flows: Vec<ProductFlow>, // Where each planet's imports are made
+    #[serde(default)]
+    pub schedule: Option<LogisticsReport>, // How often each planet needs visiting
 }
 
-/// Specialized products in P4 tier that require direct P0 mining
-pub fn requires_p4_mined(product_name: &str) -> bool {
-    matches!(
-        product_name,
-        "nano_factory" | "organic_mortar_applicators" | "sterile_conduit"
-    )
+impl ProductionPlan {
+    /// Routes bringing imports onto a planet
+    pub fn flows_into<'a>(&'a self, planet: &'a str) -> impl Iterator<Item = &'a ProductFlow> {
+        self.flows
+            .iter()
+            .filter(move |flow| flow.to_planet == planet)
+    }
+
+    /// Routes carrying a planet's outputs away
+    pub fn flows_from<'a>(&'a self, planet: &'a str) -> impl Iterator<Item = &'a ProductFlow> {
+        self.flows
+            .iter()
+            .filter(move |flow| flow.from_planet == planet)
+    }
+
+    /// ISK needed to upgrade every planet's command center to its chosen level
+    pub fn command_center_upgrade_cost(&self) -> f64 {
+        self.assignments
+            .iter()
+            .filter_map(|a| a.command_center_level)
+            .map(|level| COMMAND_CENTER_UPGRADE_COST[(level as usize).min(5)])
+            .sum()
+    }
+
+    /// Total volume (m³) hauled between planets each hour
+    pub fn hauling_m3_per_hour(&self) -> f64 {
+        self.assignments
+            .iter()
+            .map(PlanetAssignment::hauling_m3_per_hour)
+            .sum()
+    }
+}
+
+/// Customs office tax rate assumed for characters without a standings-specific rate
+pub const DEFAULT_POCO_TAX_RATE: f64 = 0.10;
+
+/// Whether a customs office tax rate is a share from 0 to 1
+pub fn is_valid_tax_rate(tax_rate: f64) -> bool {
+    (0.0..=1.0).contains(&tax_rate)
+}
+
+/// Whether an extractor program length is a positive, finite number of hours
+pub fn is_valid_program_hours(hours: f64) -> bool {
+    hours.is_finite() && hours > 0.0
+}
+
+/// Fixed base value per unit that customs offices apply their tax rate to
+pub fn customs_base_value(tier: ProductTier) -> f64 {
+    match tier {
+        ProductTier::P0 => 5.0,
+        ProductTier::P1 => 400.0,
+        ProductTier::P2 => 7_200.0,
+        ProductTier::P3 => 60_000.0,
+        ProductTier::P4 => 1_200_000.0,
+    }
+}
+
+/// Cargo volume in m³ of a single unit of a product of the given tier
+pub fn commodity_volume(tier: ProductTier) -> f64 {
+    match tier {
+        ProductTier::P0 => 0.01,
+        ProductTier::P1 => 0.38,
+        ProductTier::P2 => 1.5,
+        ProductTier::P3 => 6.0,
+        ProductTier::P4 => 100.0,
+    }
 }
 
 /// Maps each P0 resource to the planet types it can be found on
@@ -159,288 +1024,165 @@ pub fn planet_resource_map() -> HashMap<&'static str, Vec<PlanetType>> {
     map
 }
 
-// Define the product database
+/// Normalize a product or preset name for lookups, ignoring case and accepting spaces or
+/// dashes for underscores, so "Nano-Factory" matches `nano_factory`
+pub fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase().replace([' ', '-'], "_")
+}
+
+/// Title-cased display name for a snake_case product name
+pub fn default_display_name(name: &str) -> String {
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Product database generated from CCP's Static Data Export. Regenerate it with the `sde`
+/// feature's importer after a patch changes the recipes.
+const PRODUCT_DATA: &str = include_str!("data/products.json");
+
+/// The built-in product database, keyed by product name
 pub fn create_product_database() -> HashMap<String, Product> {
-    let mut products = HashMap::new();
+    let products: Vec<Product> =
+        serde_json::from_str(PRODUCT_DATA).expect("embedded product data is valid JSON");
+    products
+        .into_iter()
+        .map(|product| (product.name.clone(), product))
+        .collect()
+}
 
-    // P0 Products (raw materials)
-    let p0_names = vec![
-        "aqueous_liquids",
-        "autotrophs",
-        "base_metals",
-        "carbon_compounds",
-        "complex_organisms",
-        "felsic_magma",
-        "heavy_metals",
-        "ionic_solutions",
-        "micro_organisms",
-        "noble_gas",
-        "noble_metals",
-        "non_cs_crystals",
-        "planktic_colonies",
-        "reactive_gas",
-        "suspended_plasma",
-    ];
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for name in p0_names {
-        products.insert(
-            name.to_string(),
-            Product::new_raw_material(name.to_string()),
+    #[test]
+    fn test_assignment_reads_single_output_plans() {
+        let old: PlanetAssignment = serde_json::from_str(
+            r#"{
+                "character": "Character1",
+                "planet": "Barren1",
+                "planet_type": "Barren",
+                "imported_inputs": ["reactive_metals", "toxic_metals"],
+                "mined_inputs": [],
+                "output": "construction_blocks",
+                "extra_outputs": ["mechanical_parts"]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(old.output(), "construction_blocks");
+        assert_eq!(old.extra_outputs(), ["mechanical_parts"]);
+        assert!(old.produces("mechanical_parts"));
+
+        // Plans round trip through the new list of outputs
+        let json = serde_json::to_value(&old).unwrap();
+        assert_eq!(
+            json["outputs"],
+            serde_json::json!(["construction_blocks", "mechanical_parts"])
         );
+        assert!(json.get("output").is_none());
+        let new: PlanetAssignment = serde_json::from_value(json).unwrap();
+        assert_eq!(new.outputs, old.outputs);
     }
 
-    // P1 Products (basic processed materials)
-    let p1_products = vec![
-        ("bacteria", vec!["micro_organisms"]),
-        ("biofuels", vec!["carbon_compounds"]),
-        ("biomass", vec!["planktic_colonies"]),
-        ("chiral_structures", vec!["non_cs_crystals"]),
-        ("electrolytes", vec!["ionic_solutions"]),
-        ("industrial_fibers", vec!["autotrophs"]),
-        ("oxidizing_compound", vec!["reactive_gas"]),
-        ("oxygen", vec!["noble_gas"]),
-        ("plasmoids", vec!["suspended_plasma"]),
-        ("precious_metals", vec!["noble_metals"]),
-        ("proteins", vec!["complex_organisms"]),
-        ("reactive_metals", vec!["base_metals"]),
-        ("silicon", vec!["felsic_magma"]),
-        ("toxic_metals", vec!["heavy_metals"]),
-        ("water", vec!["aqueous_liquids"]),
-    ];
-
-    for (name, ingredients) in p1_products {
-        products.insert(
-            name.to_string(),
-            Product::new(
-                name.to_string(),
+    #[test]
+    fn test_embedded_product_database() {
+        let products = create_product_database();
+        let count = |tier: ProductTier| products.values().filter(|p| p.tier == tier).count();
+        assert_eq!(
+            [
+                ProductTier::P0,
                 ProductTier::P1,
-                ingredients.iter().map(|s| s.to_string()).collect(),
-            ),
+                ProductTier::P2,
+                ProductTier::P3,
+                ProductTier::P4,
+            ]
+            .map(count),
+            [15, 15, 21, 21, 8]
         );
+
+        for product in products.values() {
+            assert_ne!(product.type_id, 0, "{} has no type ID", product.name);
+            for ingredient in product.ingredients() {
+                assert!(
+                    products.contains_key(&ingredient),
+                    "{} is unknown",
+                    ingredient
+                );
+            }
+        }
+        assert_eq!(products["nano_factory"].display_name, "Nano-Factory");
+        assert!(products["sterile_conduit"].requires_mining);
+        assert_eq!(products["coolant"].schematic.output_quantity, 5);
     }
 
-    // P2 Products (simplified list for brevity)
-    let p2_products = vec![
-        ("biocells", vec!["precious_metals", "biofuels"]),
-        (
-            "construction_blocks",
-            vec!["toxic_metals", "reactive_metals"],
-        ),
-        (
-            "consumer_electronics",
-            vec!["chiral_structures", "toxic_metals"],
-        ),
-        ("coolant", vec!["water", "electrolytes"]),
-        ("enriched_uranium", vec!["toxic_metals", "precious_metals"]),
-        ("fertilizer", vec!["proteins", "bacteria"]),
-        ("livestock", vec!["biofuels", "proteins"]),
-        (
-            "mechanical_parts",
-            vec!["precious_metals", "reactive_metals"],
-        ),
-        ("microfiber_shielding", vec!["silicon", "industrial_fibers"]),
-        (
-            "miniature_electronics",
-            vec!["silicon", "chiral_structures"],
-        ),
-        ("nanites", vec!["reactive_metals", "bacteria"]),
-        ("oxides", vec!["oxygen", "oxidizing_compound"]),
-        (
-            "polyaramids",
-            vec!["industrial_fibers", "oxidizing_compound"],
-        ),
-        ("polytextiles", vec!["industrial_fibers", "biofuels"]),
-        ("rocket_fuel", vec!["electrolytes", "plasmoids"]),
-        ("silicate_glass", vec!["silicon", "oxidizing_compound"]),
-        ("superconductors", vec!["water", "plasmoids"]),
-        ("supertensile_plastics", vec!["oxygen", "biomass"]),
-        ("synthetic_oil", vec!["oxygen", "electrolytes"]),
-        ("test_cultures", vec!["water", "bacteria"]),
-        ("viral_agent", vec!["biomass", "bacteria"]),
-    ];
+    #[test]
+    fn test_character_from_esi_skills() {
+        let esi: EsiSkills = serde_json::from_str(
+            r#"{
+                "skills": [
+                    {"skill_id": 2505, "active_skill_level": 4, "trained_skill_level": 4, "skillpoints_in_skill": 181020},
+                    {"skill_id": 2495, "active_skill_level": 3, "trained_skill_level": 5, "skillpoints_in_skill": 256000},
+                    {"skill_id": 2406, "active_skill_level": 2, "trained_skill_level": 2, "skillpoints_in_skill": 1415},
+                    {"skill_id": 3300, "active_skill_level": 5, "trained_skill_level": 5, "skillpoints_in_skill": 256000}
+                ],
+                "total_sp": 5000000
+            }"#,
+        )
+        .unwrap();
 
-    for (name, ingredients) in p2_products {
-        products.insert(
-            name.to_string(),
-            Product::new(
-                name.to_string(),
-                ProductTier::P2,
-                ingredients.iter().map(|s| s.to_string()).collect(),
-            ),
-        );
-    }
+        let mut character = Character::from_esi_skills("Alice", &esi);
+        assert_eq!(character.skills.command_center_upgrades, 4);
+        // Alpha clones only get their active level
+        assert_eq!(character.skills.interplanetary_consolidation, 3);
+        assert_eq!(character.planets, 4);
+        assert_eq!(character.skills.planetology, Some(2));
+        assert_eq!(character.skills.remote_sensing, Some(0));
 
-    // P3 Products (simplified for brevity)
-    let p3_products = vec![
-        (
-            "biotech_research_reports",
-            vec!["nanites", "livestock", "construction_blocks"],
-        ),
-        (
-            "camera_drones",
-            vec!["silicate_glass", "rocket_fuel", "mechanical_parts"],
-        ),
-        ("condensates", vec!["oxides", "coolant", "precious_metals"]),
-        (
-            "cryoprotectant_solution",
-            vec!["synthetic_oil", "fertilizer", "polytextiles"],
-        ),
-        (
-            "data_chips",
-            vec!["nanites", "silicate_glass", "consumer_electronics"],
-        ),
-        (
-            "gel_matrix_biopaste",
-            vec!["oxides", "biocells", "industrial_fibers"],
-        ),
-        (
-            "guidance_systems",
-            vec![
-                "consumer_electronics",
-                "mechanical_parts",
-                "miniature_electronics",
-            ],
-        ),
-        (
-            "hazmat_detection_systems",
-            vec!["industrial_fibers", "oxides", "microfiber_shielding"],
-        ),
-        (
-            "hermetic_membranes",
-            vec!["polytextiles", "silicate_glass", "coolant"],
-        ),
-        (
-            "high_tech_transmitters",
-            vec![
-                "chiral_structures",
-                "miniature_electronics",
-                "superconductors",
-            ],
-        ),
-        (
-            "industrial_explosives",
-            vec!["fertilizer", "polytextiles", "reactive_metals"],
-        ),
-        (
-            "neocoms",
-            vec!["biocells", "construction_blocks", "microfiber_shielding"],
-        ),
-        (
-            "nuclear_reactors",
-            vec![
-                "enriched_uranium",
-                "microfiber_shielding",
-                "consumer_electronics",
-            ],
-        ),
-        (
-            "planetary_vehicles",
-            vec!["rocket_fuel", "consumer_electronics", "mechanical_parts"],
-        ),
-        (
-            "robotics",
-            vec![
-                "mechanical_parts",
-                "consumer_electronics",
-                "precious_metals",
-            ],
-        ),
-        (
-            "smartfab_units",
-            vec!["construction_blocks", "miniature_electronics", "nanites"],
-        ),
-        (
-            "supercomputers",
-            vec!["coolant", "consumer_electronics", "miniature_electronics"],
-        ),
-        (
-            "synthetic_synapses",
-            vec!["supertensile_plastics", "test_cultures", "biocells"],
-        ),
-        (
-            "transcranial_microcontrollers",
-            vec!["biocells", "nanites", "silicate_glass"],
-        ),
-        (
-            "ukomi_super_conductors",
-            vec!["synthetic_oil", "superconductors", "coolant"],
-        ),
-        ("vaccines", vec!["livestock", "viral_agent"]),
-    ];
+        character.skills.customs_code_expertise = Some(4);
+        character.program_hours = Some(24.0);
+        character.apply_esi_skills(&EsiSkills { skills: Vec::new() });
+        assert_eq!(character.planets, 1);
+        assert_eq!(character.skills.customs_code_expertise, Some(4));
+        assert_eq!(character.program_hours, Some(24.0));
+    }
 
-    for (name, ingredients) in p3_products {
-        products.insert(
-            name.to_string(),
-            Product::new(
-                name.to_string(),
-                ProductTier::P3,
-                ingredients.iter().map(|s| s.to_string()).collect(),
-            ),
+    #[test]
+    fn test_planet_type_any_casing() {
+        for name in ["\"Barren\"", "\"barren\"", "\"BARREN\"", "\" barren \""] {
+            let planet_type: PlanetType = serde_json::from_str(name).unwrap();
+            assert_eq!(planet_type, PlanetType::Barren);
+        }
+        assert_eq!(
+            serde_json::to_string(&PlanetType::Temperate).unwrap(),
+            "\"Temperate\""
         );
-    }
 
-    // P4 Products
-    let p4_products = vec![
-        (
-            "broadcast_node",
-            vec!["neocoms", "data_chips", "high_tech_transmitters"],
-        ),
-        (
-            "integrity_response_drones",
-            vec![
-                "gel_matrix_biopaste",
-                "hazmat_detection_systems",
-                "planetary_vehicles",
-            ],
-        ),
-        (
-            "nano_factory",
-            vec![
-                "industrial_explosives",
-                "ukomi_super_conductors",
-                "reactive_metals",
-            ],
-        ),
-        (
-            "organic_mortar_applicators",
-            vec!["condensates", "robotics", "bacteria"],
-        ),
-        (
-            "recursive_computing_module",
-            vec![
-                "synthetic_synapses",
-                "guidance_systems",
-                "transcranial_microcontrollers",
-            ],
-        ),
-        (
-            "self_harmonizing_power_core",
-            vec!["camera_drones", "nuclear_reactors", "hermetic_membranes"],
-        ),
-        (
-            "sterile_conduit",
-            vec!["smartfab_units", "vaccines", "water"],
-        ),
-        (
-            "wetware_mainframe",
-            vec![
-                "supercomputers",
-                "biotech_research_reports",
-                "cryoprotectant_solution",
-            ],
-        ),
-    ];
+        let err = serde_json::from_str::<PlanetType>("\"rocky\"").unwrap_err();
+        assert!(err.to_string().contains("unknown planet type `rocky`"));
+        assert!(err.to_string().contains("Barren, Gas, Ice"));
+    }
 
-    for (name, ingredients) in p4_products {
-        products.insert(
-            name.to_string(),
-            Product::new(
-                name.to_string(),
-                ProductTier::P4,
-                ingredients.iter().map(|s| s.to_string()).collect(),
-            ),
+    #[test]
+    fn test_planet_type_set() {
+        let set: PlanetTypeSet = [PlanetType::Gas, PlanetType::Storm, PlanetType::Gas]
+            .into_iter()
+            .collect();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(PlanetType::Storm) && !set.contains(PlanetType::Ice));
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            vec![PlanetType::Gas, PlanetType::Storm]
         );
+        assert!(set.intersects(PlanetTypeSet::ALL));
+        assert_eq!(PlanetTypeSet::ALL.len(), PlanetType::ALL.len());
+        assert!(set.intersection(PlanetTypeSet::EMPTY).is_empty());
     }
-
-    products
 }
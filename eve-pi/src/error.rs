@@ -0,0 +1,366 @@
+use crate::domain::{PlanetType, ProductTier};
+#[cfg(all(feature = "esi", not(target_arch = "wasm32")))]
+use crate::esi::EsiError;
+use crate::factory::FactoryError;
+use crate::repository::RepositoryError;
+#[cfg(all(feature = "sde", not(target_arch = "wasm32")))]
+use crate::sde::SdeError;
+use crate::solver::SolverError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tsify::Tsify;
+
+/// Stable, machine-readable name for what went wrong. Codes are never renamed, so callers
+/// can match on them instead of on messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    DeserializationFailed,
+    InvalidData,
+    ProductNotFound,
+    PlanetNotFound,
+    CharacterNotFound,
+    InvalidProductTier,
+    MissingIngredients,
+    RequiresMining,
+    DoesNotRequireMining,
+    NoMinableResource,
+    InputOutputMismatch,
+    PlanetCannotMine,
+    TooManyPins,
+    ResourceNotPresent,
+    CommandCenterTooWeak,
+    NoSolutionFound,
+    Cancelled,
+    BranchNotFound,
+    InvalidInput,
+    SerializationFailed,
+    LockFailed,
+    RequestFailed,
+    InvalidResponse,
+    ReadFailed,
+    MissingSchematic,
+}
+
+impl ErrorCode {
+    /// The code as it appears in serialized errors, like `product_not_found`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::DeserializationFailed => "deserialization_failed",
+            ErrorCode::InvalidData => "invalid_data",
+            ErrorCode::ProductNotFound => "product_not_found",
+            ErrorCode::PlanetNotFound => "planet_not_found",
+            ErrorCode::CharacterNotFound => "character_not_found",
+            ErrorCode::InvalidProductTier => "invalid_product_tier",
+            ErrorCode::MissingIngredients => "missing_ingredients",
+            ErrorCode::RequiresMining => "requires_mining",
+            ErrorCode::DoesNotRequireMining => "does_not_require_mining",
+            ErrorCode::NoMinableResource => "no_minable_resource",
+            ErrorCode::InputOutputMismatch => "input_output_mismatch",
+            ErrorCode::PlanetCannotMine => "planet_cannot_mine",
+            ErrorCode::TooManyPins => "too_many_pins",
+            ErrorCode::ResourceNotPresent => "resource_not_present",
+            ErrorCode::CommandCenterTooWeak => "command_center_too_weak",
+            ErrorCode::NoSolutionFound => "no_solution_found",
+            ErrorCode::Cancelled => "cancelled",
+            ErrorCode::BranchNotFound => "branch_not_found",
+            ErrorCode::InvalidInput => "invalid_input",
+            ErrorCode::SerializationFailed => "serialization_failed",
+            ErrorCode::LockFailed => "lock_failed",
+            ErrorCode::RequestFailed => "request_failed",
+            ErrorCode::InvalidResponse => "invalid_response",
+            ErrorCode::ReadFailed => "read_failed",
+            ErrorCode::MissingSchematic => "missing_schematic",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What an error is about, so callers can point at the product or planet involved without
+/// parsing the message. Only the fields that apply to the error are set.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Tsify)]
+pub struct ErrorContext {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub product: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub planet: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub character: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub planet_type: Option<PlanetType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_tier: Option<ProductTier>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual_tier: Option<ProductTier>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[tsify(optional)]
+    pub missing: Vec<String>, // Ingredients that couldn't be found
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<usize>, // Facilities a layout needs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available: Option<usize>, // Facilities a planet fits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<u8>, // Command center upgrade level
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>, // Scenario branch name
+}
+
+impl ErrorContext {
+    fn product(product: &str) -> Self {
+        ErrorContext {
+            product: Some(product.to_string()),
+            ..ErrorContext::default()
+        }
+    }
+}
+
+/// Any error the crate reports, with a stable code and the data it concerns
+#[derive(Debug, thiserror::Error)]
+pub enum PiError {
+    #[error(transparent)]
+    Repository(#[from] RepositoryError),
+    #[error(transparent)]
+    Factory(#[from] FactoryError),
+    #[error(transparent)]
+    Solver(SolverError),
+    #[cfg(all(feature = "esi", not(target_arch = "wasm32")))]
+    #[error(transparent)]
+    Esi(#[from] EsiError),
+    #[cfg(all(feature = "sde", not(target_arch = "wasm32")))]
+    #[error(transparent)]
+    Sde(#[from] SdeError),
+}
+
+impl From<SolverError> for PiError {
+    /// Repository errors the solver passed along keep their own code
+    fn from(err: SolverError) -> Self {
+        match err {
+            SolverError::RepositoryError(err) => PiError::Repository(err),
+            err => PiError::Solver(err),
+        }
+    }
+}
+
+impl PiError {
+    /// Stable code of the error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            PiError::Repository(err) => repository_code(err),
+            PiError::Factory(err) => match err {
+                FactoryError::ProductNotFound(_) => ErrorCode::ProductNotFound,
+                FactoryError::InvalidProductTier { .. } => ErrorCode::InvalidProductTier,
+                FactoryError::MissingIngredients { .. } => ErrorCode::MissingIngredients,
+                FactoryError::RequiresMining(_) => ErrorCode::RequiresMining,
+                FactoryError::DoesNotRequireMining(_) => ErrorCode::DoesNotRequireMining,
+                FactoryError::NoMinableResource => ErrorCode::NoMinableResource,
+                FactoryError::InputOutputMismatch => ErrorCode::InputOutputMismatch,
+                FactoryError::PlanetCannotMine { .. } => ErrorCode::PlanetCannotMine,
+                FactoryError::TooManyPins { .. } => ErrorCode::TooManyPins,
+                FactoryError::ResourceNotPresent(_) => ErrorCode::ResourceNotPresent,
+                FactoryError::CommandCenterTooWeak { .. } => ErrorCode::CommandCenterTooWeak,
+            },
+            PiError::Solver(err) => match err {
+                SolverError::RepositoryError(err) => repository_code(err),
+                SolverError::ProductNotFound(_) => ErrorCode::ProductNotFound,
+                SolverError::NoSolutionFound(_) => ErrorCode::NoSolutionFound,
+                SolverError::InvalidInput(_) => ErrorCode::InvalidInput,
+                SolverError::Cancelled => ErrorCode::Cancelled,
+            },
+            #[cfg(all(feature = "esi", not(target_arch = "wasm32")))]
+            PiError::Esi(err) => match err {
+                EsiError::Http(_) => ErrorCode::RequestFailed,
+                EsiError::Deserialization(_) => ErrorCode::InvalidResponse,
+            },
+            #[cfg(all(feature = "sde", not(target_arch = "wasm32")))]
+            PiError::Sde(err) => match err {
+                SdeError::Io(_) => ErrorCode::ReadFailed,
+                SdeError::Parse(_) => ErrorCode::InvalidData,
+                SdeError::MissingSchematic(_) => ErrorCode::MissingSchematic,
+            },
+        }
+    }
+
+    /// The product, planet, or other data the error concerns
+    pub fn context(&self) -> ErrorContext {
+        match self {
+            PiError::Repository(err) => repository_context(err),
+            PiError::Factory(err) => match err {
+                FactoryError::ProductNotFound(product)
+                | FactoryError::RequiresMining(product)
+                | FactoryError::DoesNotRequireMining(product) => ErrorContext::product(product),
+                FactoryError::InvalidProductTier {
+                    product,
+                    expected,
+                    actual,
+                } => ErrorContext {
+                    expected_tier: Some(*expected),
+                    actual_tier: Some(*actual),
+                    ..ErrorContext::product(product)
+                },
+                FactoryError::MissingIngredients { product, missing } => ErrorContext {
+                    missing: missing.clone(),
+                    ..ErrorContext::product(product)
+                },
+                FactoryError::PlanetCannotMine {
+                    planet_type,
+                    resource,
+                } => ErrorContext {
+                    planet_type: Some(*planet_type),
+                    resource: Some(resource.clone()),
+                    ..ErrorContext::default()
+                },
+                FactoryError::TooManyPins {
+                    product,
+                    required,
+                    available,
+                } => ErrorContext {
+                    required: Some(*required),
+                    available: Some(*available),
+                    ..ErrorContext::product(product)
+                },
+                FactoryError::ResourceNotPresent(resource) => ErrorContext {
+                    resource: Some(resource.clone()),
+                    ..ErrorContext::default()
+                },
+                FactoryError::CommandCenterTooWeak { product, level } => ErrorContext {
+                    level: Some(*level),
+                    ..ErrorContext::product(product)
+                },
+                FactoryError::NoMinableResource | FactoryError::InputOutputMismatch => {
+                    ErrorContext::default()
+                }
+            },
+            PiError::Solver(err) => match err {
+                SolverError::RepositoryError(err) => repository_context(err),
+                SolverError::ProductNotFound(product) => ErrorContext::product(product),
+                SolverError::NoSolutionFound(_)
+                | SolverError::InvalidInput(_)
+                | SolverError::Cancelled => ErrorContext::default(),
+            },
+            #[cfg(all(feature = "esi", not(target_arch = "wasm32")))]
+            PiError::Esi(_) => ErrorContext::default(),
+            #[cfg(all(feature = "sde", not(target_arch = "wasm32")))]
+            PiError::Sde(_) => ErrorContext::default(),
+        }
+    }
+}
+
+fn repository_code(err: &RepositoryError) -> ErrorCode {
+    match err {
+        RepositoryError::DeserializationError(_) => ErrorCode::DeserializationFailed,
+        RepositoryError::ProductNotFound(_) => ErrorCode::ProductNotFound,
+        RepositoryError::InvalidData(_) => ErrorCode::InvalidData,
+        RepositoryError::PlanetNotFound(_) => ErrorCode::PlanetNotFound,
+        RepositoryError::CharacterNotFound(_) => ErrorCode::CharacterNotFound,
+    }
+}
+
+fn repository_context(err: &RepositoryError) -> ErrorContext {
+    match err {
+        RepositoryError::ProductNotFound(product) => ErrorContext::product(product),
+        RepositoryError::PlanetNotFound(planet) => ErrorContext {
+            planet: Some(planet.clone()),
+            ..ErrorContext::default()
+        },
+        RepositoryError::CharacterNotFound(character) => ErrorContext {
+            character: Some(character.clone()),
+            ..ErrorContext::default()
+        },
+        RepositoryError::DeserializationError(_) | RepositoryError::InvalidData(_) => {
+            ErrorContext::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::MemoryRepository;
+    use crate::solver::Solver;
+
+    fn solve(repository: &MemoryRepository, product: &str) -> Result<usize, PiError> {
+        let plan = Solver::new(repository).solve(product)?;
+        Ok(plan.assignments.len())
+    }
+
+    #[test]
+    fn test_error_codes_and_context() {
+        let repository = MemoryRepository::new();
+        let err = solve(&repository, "unobtainium").unwrap_err();
+        assert_eq!(err.code(), ErrorCode::ProductNotFound);
+        assert_eq!(err.context().product.as_deref(), Some("unobtainium"));
+        assert_eq!(err.to_string(), "Product not found: unobtainium");
+
+        // Repository errors passed through the solver keep their own code
+        let err = PiError::from(SolverError::RepositoryError(
+            RepositoryError::PlanetNotFound("Jita IV".to_string()),
+        ));
+        assert!(matches!(err, PiError::Repository(_)));
+        assert_eq!(err.code().as_str(), "planet_not_found");
+        assert_eq!(err.context().planet.as_deref(), Some("Jita IV"));
+
+        let err = PiError::from(FactoryError::TooManyPins {
+            product: "coolant".to_string(),
+            required: 20,
+            available: 16,
+        });
+        assert_eq!(err.code(), ErrorCode::TooManyPins);
+        assert_eq!(
+            serde_json::to_value(err.context()).unwrap(),
+            serde_json::json!({"product": "coolant", "required": 20, "available": 16})
+        );
+        assert_eq!(
+            serde_json::to_string(&ErrorCode::NoSolutionFound).unwrap(),
+            "\"no_solution_found\""
+        );
+    }
+
+    #[cfg(all(feature = "esi", not(target_arch = "wasm32")))]
+    #[test]
+    fn test_esi_error_codes() {
+        let err = PiError::from(EsiError::Http("timed out".to_string()));
+        assert_eq!(err.code(), ErrorCode::RequestFailed);
+        assert_eq!(err.to_string(), "ESI request failed: timed out");
+        let err = PiError::from(EsiError::Deserialization("not JSON".to_string()));
+        assert_eq!(err.code(), ErrorCode::InvalidResponse);
+    }
+
+    #[cfg(all(feature = "sde", not(target_arch = "wasm32")))]
+    #[test]
+    fn test_sde_error_codes() {
+        let err = PiError::from(SdeError::Io("fsd/typeIDs.yaml".to_string()));
+        assert_eq!(err.code(), ErrorCode::ReadFailed);
+        assert_eq!(err.to_string(), "Failed to read SDE: fsd/typeIDs.yaml");
+        assert_eq!(
+            PiError::from(SdeError::Parse(String::new())).code(),
+            ErrorCode::InvalidData
+        );
+        assert_eq!(
+            PiError::from(SdeError::MissingSchematic("Coolant".to_string())).code(),
+            ErrorCode::MissingSchematic
+        );
+    }
+
+    #[test]
+    fn test_typescript_declarations() {
+        use tsify::Tsify;
+
+        // The TypeScript union has to list exactly the codes errors serialize with
+        for code in [
+            ErrorCode::ProductNotFound,
+            ErrorCode::CommandCenterTooWeak,
+            ErrorCode::LockFailed,
+        ] {
+            assert!(ErrorCode::DECL.contains(&format!("\"{}\"", code.as_str())));
+        }
+        assert!(ErrorContext::DECL.contains("missing?: string[];"));
+        assert!(ErrorContext::DECL.contains("planet_type?: PlanetType;"));
+    }
+}
@@ -0,0 +1,236 @@
+use crate::pricing::{PriceProvider, PriceSide};
+use crate::repository::ProductRepository;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+/// Root of the ESI API
+pub const ESI_BASE_URL: &str = "https://esi.evetech.net/latest";
+
+/// The Forge, the region Jita is in
+pub const THE_FORGE_REGION_ID: u32 = 10_000_002;
+
+/// Jita IV - Moon 4 - Caldari Navy Assembly Plant, the main trade hub
+pub const JITA_STATION_ID: u64 = 60_003_760;
+
+/// How long fetched prices are reused; ESI itself caches market orders for five minutes
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Error talking to ESI
+#[derive(Debug, thiserror::Error)]
+pub enum EsiError {
+    #[error("ESI request failed: {0}")]
+    Http(String),
+    #[error("Invalid ESI response: {0}")]
+    Deserialization(String),
+}
+
+/// A single market order as returned by ESI
+#[derive(Debug, Clone, Deserialize)]
+struct MarketOrder {
+    price: f64,
+    is_buy_order: bool,
+    location_id: u64,
+}
+
+/// Live prices from the ESI market orders of a region, optionally limited to one station.
+/// Prices are cached per product, and a failed request falls back to the last known price.
+pub struct EsiPrices {
+    base_url: String,
+    region_id: u32,
+    location_id: Option<u64>,
+    side: PriceSide,
+    cache_ttl: Duration,
+    type_ids: HashMap<String, u32>,
+    agent: ureq::Agent,
+    cache: Mutex<HashMap<u32, (Instant, Option<f64>)>>,
+}
+
+impl EsiPrices {
+    /// Prices at the Jita 4-4 trade hub
+    pub fn jita(products: &dyn ProductRepository) -> Self {
+        Self::region(products, THE_FORGE_REGION_ID).at_station(JITA_STATION_ID)
+    }
+
+    /// Prices across every station of a region
+    pub fn region(products: &dyn ProductRepository, region_id: u32) -> Self {
+        let type_ids = products
+            .get_all_products()
+            .into_iter()
+            .filter(|p| p.type_id != 0)
+            .map(|p| (p.name, p.type_id))
+            .collect();
+        Self {
+            base_url: ESI_BASE_URL.to_string(),
+            region_id,
+            location_id: None,
+            side: PriceSide::Sell,
+            cache_ttl: DEFAULT_CACHE_TTL,
+            type_ids,
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(10))
+                .user_agent(concat!("eve-pi/", env!("CARGO_PKG_VERSION")))
+                .build(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Only consider orders at one station
+    pub fn at_station(mut self, location_id: u64) -> Self {
+        self.location_id = Some(location_id);
+        self
+    }
+
+    /// Price products from buy or sell orders
+    pub fn with_side(mut self, side: PriceSide) -> Self {
+        self.side = side;
+        self
+    }
+
+    /// Reuse fetched prices for this long
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// Talk to another ESI host, like a mirror or a proxy
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Fetch the current price of a type from ESI, skipping the cache. `None` when there
+    /// are no matching orders.
+    pub fn fetch(&self, type_id: u32) -> Result<Option<f64>, EsiError> {
+        let order_type = match self.side {
+            PriceSide::Sell => "sell",
+            PriceSide::Buy => "buy",
+        };
+        let url = format!("{}/markets/{}/orders/", self.base_url, self.region_id);
+
+        let mut orders = Vec::new();
+        let mut page = 1;
+        loop {
+            let response = self
+                .agent
+                .get(&url)
+                .query("type_id", &type_id.to_string())
+                .query("order_type", order_type)
+                .query("page", &page.to_string())
+                .call()
+                .map_err(|e| EsiError::Http(e.to_string()))?;
+            let pages: u32 = response
+                .header("x-pages")
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(1);
+            let batch: Vec<MarketOrder> = response
+                .into_json()
+                .map_err(|e| EsiError::Deserialization(e.to_string()))?;
+            orders.extend(batch);
+            if page >= pages {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(self.best_price(&orders))
+    }
+
+    /// Best price among orders on the chosen side at the chosen station
+    fn best_price(&self, orders: &[MarketOrder]) -> Option<f64> {
+        let prices = orders
+            .iter()
+            .filter(|o| o.is_buy_order == (self.side == PriceSide::Buy))
+            .filter(|o| self.location_id.is_none_or(|id| o.location_id == id))
+            .map(|o| o.price);
+        match self.side {
+            PriceSide::Sell => prices.reduce(f64::min),
+            PriceSide::Buy => prices.reduce(f64::max),
+        }
+    }
+}
+
+impl PriceProvider for EsiPrices {
+    fn price(&self, product: &str) -> Option<f64> {
+        let type_id = *self.type_ids.get(product)?;
+        let cached = self.cache.lock().ok()?.get(&type_id).copied();
+        if let Some((fetched_at, price)) = cached {
+            if fetched_at.elapsed() < self.cache_ttl {
+                return price;
+            }
+        }
+
+        // The lock isn't held while fetching, so slow requests don't block cached lookups
+        match self.fetch(type_id) {
+            Ok(price) => {
+                info!("Fetched ESI price of {}: {:?}", product, price);
+                self.cache
+                    .lock()
+                    .ok()?
+                    .insert(type_id, (Instant::now(), price));
+                price
+            }
+            Err(err) => {
+                error!("Failed to fetch ESI price of {}: {}", product, err);
+                cached.and_then(|(_, price)| price)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::MemoryRepository;
+
+    fn order(price: f64, is_buy_order: bool, location_id: u64) -> MarketOrder {
+        MarketOrder {
+            price,
+            is_buy_order,
+            location_id,
+        }
+    }
+
+    #[test]
+    fn test_best_price() {
+        let repo = MemoryRepository::new();
+        let orders = vec![
+            order(100.0, false, JITA_STATION_ID),
+            order(90.0, false, 1),
+            order(80.0, true, JITA_STATION_ID),
+            order(85.0, true, 1),
+        ];
+
+        let region = EsiPrices::region(&repo, THE_FORGE_REGION_ID);
+        assert_eq!(region.best_price(&orders), Some(90.0));
+        let jita = EsiPrices::jita(&repo);
+        assert_eq!(jita.best_price(&orders), Some(100.0));
+        assert_eq!(
+            jita.with_side(PriceSide::Buy).best_price(&orders),
+            Some(80.0)
+        );
+        assert_eq!(region.best_price(&[]), None);
+    }
+
+    #[test]
+    fn test_cached_prices_skip_requests() {
+        let repo = MemoryRepository::new();
+        // Nothing listens here, so any request would fail
+        let prices = EsiPrices::jita(&repo).with_base_url("http://127.0.0.1:9/");
+        let water = prices.type_ids["water"];
+        prices
+            .cache
+            .lock()
+            .unwrap()
+            .insert(water, (Instant::now(), Some(512.0)));
+
+        assert_eq!(prices.price("water"), Some(512.0));
+        assert_eq!(prices.price("not_a_product"), None);
+
+        // Stale entries are refetched, and the old price kept when that fails
+        let prices = prices.with_cache_ttl(Duration::ZERO);
+        assert_eq!(prices.price("water"), Some(512.0));
+    }
+}
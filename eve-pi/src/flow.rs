@@ -0,0 +1,173 @@
+use crate::domain::{commodity_volume, PlanetType, ProductionPlan};
+use crate::logistics::map_import_sources;
+use crate::quantity::assignment_daily_output;
+use crate::repository::Repository;
+use serde::{Deserialize, Serialize};
+
+/// A planet in the flow graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlowNode {
+    pub planet: String, // Planet ID
+    pub planet_type: PlanetType,
+    pub character: String,    // Character running the colony
+    pub outputs: Vec<String>, // Products made on the planet, main output first
+    pub units_per_hour: f64,  // Units of the main output made each hour
+}
+
+/// A product moving between two planets in the flow graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlowEdge {
+    pub product: String,
+    pub from: String, // Planet ID of the producer
+    pub to: String,   // Planet ID of the consumer
+    pub units_per_hour: f64,
+    pub m3_per_hour: f64,
+}
+
+/// A solved plan as a graph of planets linked by the products hauled between them, ready
+/// to draw as a Sankey diagram
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FlowGraph {
+    pub nodes: Vec<FlowNode>,
+    pub edges: Vec<FlowEdge>,
+}
+
+impl FlowGraph {
+    /// Build the graph of a plan, routing its imports when the plan has no flows yet
+    pub fn from_plan(repository: &dyn Repository, plan: &ProductionPlan) -> Self {
+        let nodes = plan
+            .assignments
+            .iter()
+            .map(|assignment| FlowNode {
+                planet: assignment.planet.clone(),
+                planet_type: assignment.planet_type,
+                character: assignment.character.clone(),
+                outputs: assignment.outputs.clone(),
+                units_per_hour: assignment_daily_output(repository, assignment) / 24.0,
+            })
+            .collect();
+
+        let flows = if plan.flows.is_empty() {
+            map_import_sources(repository, &plan.assignments)
+        } else {
+            plan.flows.clone()
+        };
+        let edges = flows
+            .into_iter()
+            .map(|flow| {
+                let volume = repository
+                    .get_product_by_name(&flow.product)
+                    .map(|p| commodity_volume(p.tier))
+                    .unwrap_or(0.0);
+                FlowEdge {
+                    m3_per_hour: flow.units_per_hour * volume,
+                    product: flow.product,
+                    from: flow.from_planet,
+                    to: flow.to_planet,
+                    units_per_hour: flow.units_per_hour,
+                }
+            })
+            .collect();
+
+        Self { nodes, edges }
+    }
+
+    /// The node of a planet
+    pub fn node(&self, planet: &str) -> Option<&FlowNode> {
+        self.nodes.iter().find(|node| node.planet == planet)
+    }
+
+    /// Edges arriving at a planet
+    pub fn inbound<'a>(&'a self, planet: &'a str) -> impl Iterator<Item = &'a FlowEdge> {
+        self.edges.iter().filter(move |edge| edge.to == planet)
+    }
+
+    /// Edges leaving a planet
+    pub fn outbound<'a>(&'a self, planet: &'a str) -> impl Iterator<Item = &'a FlowEdge> {
+        self.edges.iter().filter(move |edge| edge.from == planet)
+    }
+
+    /// Edges carrying a product
+    pub fn edges_of<'a>(&'a self, product: &'a str) -> impl Iterator<Item = &'a FlowEdge> {
+        self.edges
+            .iter()
+            .filter(move |edge| edge.product == product)
+    }
+
+    /// Planets nothing is hauled into, where the chains start
+    pub fn sources(&self) -> impl Iterator<Item = &FlowNode> {
+        self.nodes
+            .iter()
+            .filter(|node| self.inbound(&node.planet).next().is_none())
+    }
+
+    /// Planets whose output isn't hauled anywhere, where the chains end
+    pub fn sinks(&self) -> impl Iterator<Item = &FlowNode> {
+        self.nodes
+            .iter()
+            .filter(|node| self.outbound(&node.planet).next().is_none())
+    }
+
+    /// Total volume hauled along every edge each hour
+    pub fn total_m3_per_hour(&self) -> f64 {
+        self.edges.iter().map(|edge| edge.m3_per_hour).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::MemoryRepository;
+    use crate::solver::Solver;
+
+    fn create_test_repository() -> MemoryRepository {
+        let mut repo = MemoryRepository::new();
+        repo.load_planets(
+            r#"[
+                { "id": "Oceanic1", "planet_type": "Oceanic", "resources": ["aqueous_liquids"] },
+                { "id": "Gas1", "planet_type": "Gas", "resources": ["ionic_solutions"] },
+                { "id": "Barren1", "planet_type": "Barren", "resources": ["base_metals"] }
+            ]"#,
+        )
+        .unwrap();
+        repo.load_characters(
+            r#"[{
+                "name": "Character1",
+                "planets": 3,
+                "skills": { "command_center_upgrades": 5, "interplanetary_consolidation": 2 }
+            }]"#,
+        )
+        .unwrap();
+        repo
+    }
+
+    #[test]
+    fn test_flow_graph_from_plan() {
+        let repo = create_test_repository();
+        let plan = Solver::new(&repo).solve("coolant").unwrap();
+        let graph = FlowGraph::from_plan(&repo, &plan);
+
+        assert_eq!(graph.nodes.len(), plan.assignments.len());
+        let factory = graph
+            .nodes
+            .iter()
+            .find(|node| node.outputs[0] == "coolant")
+            .unwrap();
+        assert_eq!(graph.inbound(&factory.planet).count(), 2);
+        assert_eq!(graph.sinks().count(), 1);
+        assert_eq!(graph.sources().count(), 2);
+
+        // P1 products take 0.38 m³ a unit
+        for edge in &graph.edges {
+            assert!((edge.m3_per_hour - edge.units_per_hour * 0.38).abs() < 1e-9);
+        }
+        assert_eq!(graph.edges_of("water").count(), 1);
+        assert!(graph.total_m3_per_hour() > 0.0);
+        assert_eq!(graph.node(&factory.planet), Some(factory));
+
+        // Plans saved without flows get routed when the graph is built
+        let mut stored = plan.clone();
+        stored.flows.clear();
+        assert_eq!(FlowGraph::from_plan(&repo, &stored), graph);
+    }
+}
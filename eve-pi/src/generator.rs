@@ -0,0 +1,268 @@
+use crate::domain::{planet_resource_map, Character, CharacterSkills, Planet, PlanetType};
+use crate::repository::{MemoryRepository, RepositoryError};
+use serde::{Deserialize, Serialize};
+
+/// Chance (in percent) that a resource possible on a planet type is present on a generated planet
+const RESOURCE_PRESENCE_PERCENT: u64 = 80;
+
+/// Controls how planet types are distributed across a generated dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Distribution {
+    /// Every planet type is equally likely
+    Uniform,
+    /// Roughly the planet type mix found across New Eden, with plenty of
+    /// Barren and Temperate planets and comparatively few Plasma and Storm ones
+    Realistic,
+    /// Explicit relative weight per planet type. At least one type needs a non-zero weight.
+    Weighted(Vec<(PlanetType, u32)>),
+}
+
+impl Distribution {
+    /// Relative weight of each planet type, in `PlanetType::ALL` order. Weights are summed as
+    /// u64 so repeated entries for one type can't overflow.
+    fn weights(&self) -> Vec<u64> {
+        match self {
+            Distribution::Uniform => vec![1; PlanetType::ALL.len()],
+            Distribution::Realistic => vec![22, 14, 12, 10, 9, 6, 7, 20],
+            Distribution::Weighted(weights) => PlanetType::ALL
+                .iter()
+                .map(|planet_type| {
+                    weights
+                        .iter()
+                        .filter(|(t, _)| t == planet_type)
+                        .map(|(_, weight)| u64::from(*weight))
+                        .sum()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Small deterministic PRNG (SplitMix64) so generated datasets are reproducible per seed
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..bound`
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// Pick an index according to relative weights
+    fn weighted_index(&mut self, weights: &[u64]) -> usize {
+        let total = weights
+            .iter()
+            .fold(0u64, |total, w| total.saturating_add(*w));
+        let mut roll = self.below(total.max(1));
+        for (i, weight) in weights.iter().enumerate() {
+            if roll < *weight {
+                return i;
+            }
+            roll -= *weight;
+        }
+        weights.len() - 1
+    }
+}
+
+/// Generate a list of planets with resources drawn from the planet resource map. Fails
+/// when the distribution gives every planet type a weight of zero.
+pub fn generate_planets(
+    seed: u64,
+    n_planets: usize,
+    distribution: &Distribution,
+) -> Result<Vec<Planet>, RepositoryError> {
+    let mut rng = SplitMix64::new(seed);
+    let weights = distribution.weights();
+    if weights.iter().all(|weight| *weight == 0) {
+        return Err(RepositoryError::InvalidData(format!(
+            "Distribution gives every planet type a weight of zero: {:?}",
+            distribution
+        )));
+    }
+    let resource_map = planet_resource_map();
+
+    // Sort resources so output only depends on the seed, not on HashMap ordering
+    let resources_by_type: Vec<Vec<&str>> = PlanetType::ALL
+        .iter()
+        .map(|planet_type| {
+            let mut resources: Vec<&str> = resource_map
+                .iter()
+                .filter(|(_, types)| types.contains(planet_type))
+                .map(|(resource, _)| *resource)
+                .collect();
+            resources.sort_unstable();
+            resources
+        })
+        .collect();
+
+    Ok((0..n_planets)
+        .map(|i| {
+            let type_index = rng.weighted_index(&weights);
+            let possible = &resources_by_type[type_index];
+
+            let mut resources: Vec<String> = possible
+                .iter()
+                .filter(|_| rng.below(100) < RESOURCE_PRESENCE_PERCENT)
+                .map(|r| r.to_string())
+                .collect();
+
+            // Every scanned planet has at least one resource
+            if resources.is_empty() {
+                let pick = rng.below(possible.len() as u64) as usize;
+                resources.push(possible[pick].to_string());
+            }
+
+            Planet::new(
+                format!("planet_{}", i + 1),
+                PlanetType::ALL[type_index],
+                resources,
+            )
+        })
+        .collect())
+}
+
+/// Generate a list of characters with randomized planetary skills
+pub fn generate_characters(seed: u64, n_characters: usize) -> Vec<Character> {
+    // Offset the seed so planets and characters don't share a stream
+    let mut rng = SplitMix64::new(seed ^ 0xC0FF_EE00_D15E_A5E5);
+
+    (0..n_characters)
+        .map(|i| {
+            let interplanetary_consolidation = rng.below(6) as u8;
+            Character {
+                name: format!("Character {}", i + 1),
+                planets: 1 + interplanetary_consolidation as usize,
+                skills: CharacterSkills {
+                    command_center_upgrades: rng.below(6) as u8,
+                    interplanetary_consolidation,
+                    remote_sensing: Some(rng.below(6) as u8),
+                    planetary_production: Some(rng.below(6) as u8),
+                    planetology: Some(rng.below(6) as u8),
+                    advanced_planetology: Some(rng.below(6) as u8),
+                    customs_code_expertise: None,
+                },
+                poco_tax_rate: None,
+                program_hours: None,
+                home_system: None,
+                service_range: None,
+            }
+        })
+        .collect()
+}
+
+/// Generate a reproducible repository for benchmarks and stress tests. Fails when the
+/// distribution gives every planet type a weight of zero.
+pub fn generate_repository(
+    seed: u64,
+    n_planets: usize,
+    n_characters: usize,
+    distribution: Distribution,
+) -> Result<MemoryRepository, RepositoryError> {
+    let mut repository = MemoryRepository::new();
+
+    repository.load_planets_data(generate_planets(seed, n_planets, &distribution)?)?;
+    repository.load_characters_data(generate_characters(seed, n_characters))?;
+
+    Ok(repository)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::{CharacterRepository, PlanetRepository};
+    use crate::solver::Solver;
+
+    #[test]
+    fn test_generation_is_deterministic() {
+        let first = generate_planets(42, 50, &Distribution::Realistic).unwrap();
+        let second = generate_planets(42, 50, &Distribution::Realistic).unwrap();
+
+        assert_eq!(first.len(), 50);
+        for (a, b) in first.iter().zip(second.iter()) {
+            assert_eq!(a.id, b.id);
+            assert_eq!(a.planet_type, b.planet_type);
+            assert_eq!(a.resources, b.resources);
+        }
+    }
+
+    #[test]
+    fn test_generated_resources_match_planet_types() {
+        let resource_map = planet_resource_map();
+
+        for planet in generate_planets(7, 200, &Distribution::Uniform).unwrap() {
+            assert!(!planet.resources.is_empty());
+            for resource in &planet.resources {
+                assert!(
+                    resource_map[resource.as_str()].contains(&planet.planet_type),
+                    "{} cannot be found on {:?}",
+                    resource,
+                    planet.planet_type
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_weighted_distribution() {
+        let distribution = Distribution::Weighted(vec![(PlanetType::Lava, 1)]);
+        let planets = generate_planets(3, 25, &distribution).unwrap();
+        assert!(planets.iter().all(|p| p.planet_type == PlanetType::Lava));
+
+        // Weights repeated past u32::MAX still add up
+        let distribution = Distribution::Weighted(vec![
+            (PlanetType::Gas, u32::MAX),
+            (PlanetType::Gas, u32::MAX),
+        ]);
+        let planets = generate_planets(3, 25, &distribution).unwrap();
+        assert!(planets.iter().all(|p| p.planet_type == PlanetType::Gas));
+    }
+
+    #[test]
+    fn test_weighted_distribution_rejects_zero_weights() {
+        let distribution = Distribution::Weighted(vec![(PlanetType::Lava, 0)]);
+        assert!(matches!(
+            generate_planets(3, 25, &distribution),
+            Err(RepositoryError::InvalidData(_))
+        ));
+        assert!(matches!(
+            generate_repository(3, 25, 5, distribution),
+            Err(RepositoryError::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn test_weighted_distribution_rejects_empty_weights() {
+        assert!(matches!(
+            generate_planets(3, 25, &Distribution::Weighted(Vec::new())),
+            Err(RepositoryError::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn test_stress_solve_generated_repository() {
+        let repository = generate_repository(1234, 300, 40, Distribution::Realistic).unwrap();
+        assert_eq!(repository.get_all_planets().len(), 300);
+        assert_eq!(repository.get_all_characters().len(), 40);
+
+        let solver = Solver::new(&repository);
+        for target in ["bacteria", "coolant", "mechanical_parts"] {
+            let plan = solver
+                .solve(target)
+                .unwrap_or_else(|err| panic!("Failed to solve {}: {}", target, err));
+            assert!(plan.assignments.iter().any(|a| a.output() == target));
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::domain::{Product, ProductTier};
+use std::collections::{BTreeSet, HashMap};
+
+/// Recipe dependencies of every product, with each product's transitive closure worked out
+/// once when the products are loaded. Ingredients that aren't products are left out, and a
+/// recipe cycle stops the walk where it closes rather than looping.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyGraph {
+    ingredients: HashMap<String, Vec<String>>, // Direct recipe inputs
+    upstream: HashMap<String, BTreeSet<String>>, // Every product a product is made from
+    raw_resources: HashMap<String, BTreeSet<String>>, // P0 resources at the bottom of the chain
+}
+
+impl DependencyGraph {
+    /// Build the graph for a product set
+    pub fn new<'a>(products: impl IntoIterator<Item = &'a Product>) -> Self {
+        let products: HashMap<&str, &Product> =
+            products.into_iter().map(|p| (p.name.as_str(), p)).collect();
+
+        let mut graph = DependencyGraph {
+            ingredients: products
+                .values()
+                .map(|p| {
+                    let known = p
+                        .ingredients()
+                        .into_iter()
+                        .filter(|i| products.contains_key(i.as_str()))
+                        .collect();
+                    (p.name.clone(), known)
+                })
+                .collect(),
+            ..DependencyGraph::default()
+        };
+
+        let mut names: Vec<&str> = products.keys().copied().collect();
+        names.sort();
+        for name in names {
+            graph.close(name, &products, &mut Vec::new());
+        }
+        graph
+    }
+
+    /// Work out the closure of a product after those of its ingredients
+    fn close(&mut self, name: &str, products: &HashMap<&str, &Product>, path: &mut Vec<String>) {
+        if self.upstream.contains_key(name) || path.iter().any(|p| p == name) {
+            return;
+        }
+
+        path.push(name.to_string());
+        let mut upstream = BTreeSet::new();
+        let mut raw = BTreeSet::new();
+        if products[name].tier == ProductTier::P0 {
+            raw.insert(name.to_string());
+        }
+        for ingredient in self.ingredients[name].clone() {
+            self.close(&ingredient, products, path);
+            if let Some(closure) = self.upstream.get(&ingredient) {
+                upstream.extend(closure.iter().cloned());
+                raw.extend(self.raw_resources[&ingredient].iter().cloned());
+            }
+            upstream.insert(ingredient);
+        }
+        path.pop();
+
+        self.upstream.insert(name.to_string(), upstream);
+        self.raw_resources.insert(name.to_string(), raw);
+    }
+
+    /// Whether the product is in the graph
+    pub fn contains(&self, product: &str) -> bool {
+        self.ingredients.contains_key(product)
+    }
+
+    /// Every product in the graph
+    pub fn products(&self) -> impl Iterator<Item = &str> {
+        self.ingredients.keys().map(String::as_str)
+    }
+
+    /// Known direct ingredients of a product
+    pub fn ingredients(&self, product: &str) -> &[String] {
+        self.ingredients.get(product).map_or(&[], Vec::as_slice)
+    }
+
+    /// Every product the product is made from, directly or further down its chain
+    pub fn upstream(&self, product: &str) -> Option<&BTreeSet<String>> {
+        self.upstream.get(product)
+    }
+
+    /// P0 resources at the bottom of a product's chain, or the product itself for a P0
+    pub fn raw_resources(&self, product: &str) -> Option<&BTreeSet<String>> {
+        self.raw_resources.get(product)
+    }
+
+    /// Whether making a product needs another one somewhere in its chain
+    pub fn depends_on(&self, product: &str, other: &str) -> bool {
+        self.upstream(product).is_some_and(|u| u.contains(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::{MemoryRepository, ProductRepository};
+
+    #[test]
+    fn test_dependency_closures() {
+        let repo = MemoryRepository::new();
+        let graph = repo.dependency_graph();
+
+        assert!(graph.depends_on("coolant", "water"));
+        assert!(graph.depends_on("coolant", "aqueous_liquids"));
+        assert!(!graph.depends_on("water", "coolant"));
+        assert_eq!(
+            graph.raw_resources("coolant").unwrap(),
+            &BTreeSet::from(["aqueous_liquids".to_string(), "ionic_solutions".to_string()])
+        );
+        assert_eq!(graph.raw_resources("aqueous_liquids").unwrap().len(), 1);
+        assert!(graph.upstream("aqueous_liquids").unwrap().is_empty());
+        assert!(graph.upstream("nano_factory").unwrap().len() > 5);
+        assert!(graph.raw_resources("unknown").is_none());
+    }
+
+    #[test]
+    fn test_cycles_terminate() {
+        let graph = DependencyGraph::new(&[
+            Product::new(
+                "coolant".to_string(),
+                ProductTier::P2,
+                vec!["oxides".to_string()],
+            ),
+            Product::new(
+                "oxides".to_string(),
+                ProductTier::P2,
+                vec!["coolant".to_string(), "missing".to_string()],
+            ),
+        ]);
+
+        assert!(graph.depends_on("coolant", "oxides"));
+        assert!(graph.depends_on("oxides", "coolant"));
+        assert_eq!(graph.ingredients("oxides"), ["coolant".to_string()]);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::domain::{Character, CharacterSkills, Planet, PlanetType, ProductTier};
+use crate::repository::{MemoryRepository, ProductRepository, Repository};
+use crate::solver::Solver;
+use crate::validation::validate_products;
+use serde::{Deserialize, Serialize};
+
+/// Outcome of a single self-test check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub name: String,
+    pub passed: bool,
+    pub details: Vec<String>, // Problems found, empty when the check passed
+}
+
+impl HealthCheck {
+    fn from_problems(name: &str, details: Vec<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            passed: details.is_empty(),
+            details,
+        }
+    }
+}
+
+/// Structured result of running the built-in self-test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub version: String, // Crate version, so frontends can detect stale cached modules
+    pub healthy: bool,
+    pub checks: Vec<HealthCheck>,
+}
+
+/// Verify every product's ingredients exist, come from lower tiers, and form no cycles
+pub fn verify_product_database(repository: &dyn ProductRepository) -> Vec<String> {
+    validate_products(repository)
+        .issues
+        .iter()
+        .map(|issue| issue.to_string())
+        .collect()
+}
+
+/// Verify the planet resource map covers exactly the P0 products
+pub fn verify_resource_map(repository: &dyn Repository) -> Vec<String> {
+    let mut problems = Vec::new();
+    let resource_map = repository.resource_map();
+
+    let mut p0_names: Vec<String> = repository
+        .iter_products_by_tier(ProductTier::P0)
+        .map(|p| p.name.clone())
+        .collect();
+    p0_names.sort();
+
+    for name in &p0_names {
+        match resource_map.get(name.as_str()) {
+            Some(planet_types) if planet_types.is_empty() => {
+                problems.push(format!("Resource {} is not found on any planet type", name))
+            }
+            Some(_) => {}
+            None => problems.push(format!(
+                "Resource {} is missing from the resource map",
+                name
+            )),
+        }
+    }
+
+    let mut mapped: Vec<&String> = resource_map.keys().collect();
+    mapped.sort();
+    for resource in mapped {
+        if !p0_names.iter().any(|name| name == resource) {
+            problems.push(format!(
+                "Resource map lists unknown P0 product {}",
+                resource
+            ));
+        }
+    }
+
+    problems
+}
+
+/// Solve a tiny canned scenario that is known to have a solution
+pub fn verify_canned_solve() -> Vec<String> {
+    let mut repository = MemoryRepository::new();
+
+    let planet = |id: &str, planet_type: PlanetType, resources: &[&str]| {
+        Planet::new(
+            id.to_string(),
+            planet_type,
+            resources.iter().map(|r| r.to_string()).collect(),
+        )
+    };
+    let planets = vec![
+        planet(
+            "self_test_oceanic",
+            PlanetType::Oceanic,
+            &["aqueous_liquids"],
+        ),
+        planet("self_test_storm", PlanetType::Storm, &["ionic_solutions"]),
+        planet("self_test_barren", PlanetType::Barren, &["base_metals"]),
+    ];
+    let characters = vec![Character {
+        name: "self_test_character".to_string(),
+        planets: 3,
+        skills: CharacterSkills {
+            command_center_upgrades: 5,
+            interplanetary_consolidation: 2,
+            remote_sensing: None,
+            planetary_production: None,
+            planetology: None,
+            advanced_planetology: None,
+            customs_code_expertise: None,
+        },
+        poco_tax_rate: None,
+        program_hours: None,
+        home_system: None,
+        service_range: None,
+    }];
+
+    if let Err(err) = repository
+        .load_planets_data(planets)
+        .and_then(|_| repository.load_characters_data(characters))
+    {
+        return vec![format!("Failed to load canned scenario: {}", err)];
+    }
+
+    match Solver::new(&repository).solve("coolant") {
+        Ok(plan) if plan.assignments.iter().any(|a| a.output() == "coolant") => Vec::new(),
+        Ok(_) => vec!["Canned solve did not produce coolant".to_string()],
+        Err(err) => vec![format!("Canned solve failed: {}", err)],
+    }
+}
+
+/// Run every self-test check against the built-in data
+pub fn run_self_test() -> HealthReport {
+    let repository = MemoryRepository::new();
+
+    let checks = vec![
+        HealthCheck::from_problems("product_database", verify_product_database(&repository)),
+        HealthCheck::from_problems("resource_map", verify_resource_map(&repository)),
+        HealthCheck::from_problems("canned_solve", verify_canned_solve()),
+    ];
+
+    HealthReport {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        healthy: checks.iter().all(|c| c.passed),
+        checks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Product;
+    use std::collections::HashMap;
+
+    struct BrokenProducts(HashMap<String, Product>);
+
+    impl ProductRepository for BrokenProducts {
+        fn iter_products(&self) -> Box<dyn Iterator<Item = &Product> + '_> {
+            Box::new(self.0.values())
+        }
+
+        fn get_product_ref(&self, name: &str) -> Option<&Product> {
+            self.0.get(name)
+        }
+    }
+
+    #[test]
+    fn test_self_test_is_healthy() {
+        let report = run_self_test();
+        for check in &report.checks {
+            assert!(check.passed, "{} failed: {:?}", check.name, check.details);
+        }
+        assert!(report.healthy);
+        assert_eq!(report.checks.len(), 3);
+    }
+
+    #[test]
+    fn test_verify_product_database_reports_problems() {
+        let mut products = HashMap::new();
+        products.insert(
+            "water".to_string(),
+            Product::new(
+                "water".to_string(),
+                ProductTier::P1,
+                vec!["missing".to_string()],
+            ),
+        );
+        products.insert(
+            "coolant".to_string(),
+            Product::new("coolant".to_string(), ProductTier::P2, Vec::new()),
+        );
+
+        let problems = verify_product_database(&BrokenProducts(products));
+        assert_eq!(problems.len(), 2);
+        assert!(problems
+            .iter()
+            .any(|p| p.contains("unknown ingredient missing")));
+        assert!(problems
+            .iter()
+            .any(|p| p.contains("coolant has no ingredients")));
+    }
+}
@@ -0,0 +1,212 @@
+use crate::domain::{default_display_name, PlanetType, Product};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Languages the EVE client is localized in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    En,
+    De,
+    Fr,
+    Ru,
+    Ja,
+    Zh,
+    Ko,
+}
+
+impl Language {
+    /// Every language, in declaration order
+    pub const ALL: [Language; 7] = [
+        Language::En,
+        Language::De,
+        Language::Fr,
+        Language::Ru,
+        Language::Ja,
+        Language::Zh,
+        Language::Ko,
+    ];
+
+    /// Two-letter language code, as used by ESI and the static data export
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::En => "en",
+            Language::De => "de",
+            Language::Fr => "fr",
+            Language::Ru => "ru",
+            Language::Ja => "ja",
+            Language::Zh => "zh",
+            Language::Ko => "ko",
+        }
+    }
+
+    /// Look up a language by code, ignoring case and any region suffix like "-DE"
+    pub fn from_code(code: &str) -> Option<Self> {
+        let code = code.trim().to_lowercase();
+        let code = code.split(['-', '_']).next().unwrap_or_default();
+        Self::ALL
+            .into_iter()
+            .find(|language| language.code() == code)
+    }
+}
+
+/// Name of a planet type as shown in game
+pub fn planet_type_name(planet_type: PlanetType, language: Language) -> &'static str {
+    use Language::*;
+    use PlanetType::*;
+    match (planet_type, language) {
+        (Barren, En) => "Barren",
+        (Barren, De) => "Unfruchtbar",
+        (Barren, Fr) => "Stérile",
+        (Barren, Ru) => "Бесплодная",
+        (Barren, Ja) => "不毛",
+        (Barren, Zh) => "贫瘠",
+        (Barren, Ko) => "불모",
+        (Gas, En) => "Gas",
+        (Gas, De) => "Gas",
+        (Gas, Fr) => "Gazeuse",
+        (Gas, Ru) => "Газовая",
+        (Gas, Ja) => "ガス",
+        (Gas, Zh) => "气体",
+        (Gas, Ko) => "가스",
+        (Ice, En) => "Ice",
+        (Ice, De) => "Eis",
+        (Ice, Fr) => "Glace",
+        (Ice, Ru) => "Ледяная",
+        (Ice, Ja) => "氷",
+        (Ice, Zh) => "冰体",
+        (Ice, Ko) => "얼음",
+        (Lava, En) => "Lava",
+        (Lava, De) => "Lava",
+        (Lava, Fr) => "Lave",
+        (Lava, Ru) => "Лавовая",
+        (Lava, Ja) => "溶岩",
+        (Lava, Zh) => "熔岩",
+        (Lava, Ko) => "용암",
+        (Oceanic, En) => "Oceanic",
+        (Oceanic, De) => "Ozean",
+        (Oceanic, Fr) => "Océanique",
+        (Oceanic, Ru) => "Океаническая",
+        (Oceanic, Ja) => "海洋",
+        (Oceanic, Zh) => "海洋",
+        (Oceanic, Ko) => "해양",
+        (Plasma, En) => "Plasma",
+        (Plasma, De) => "Plasma",
+        (Plasma, Fr) => "Plasma",
+        (Plasma, Ru) => "Плазменная",
+        (Plasma, Ja) => "プラズマ",
+        (Plasma, Zh) => "等离子",
+        (Plasma, Ko) => "플라즈마",
+        (Storm, En) => "Storm",
+        (Storm, De) => "Sturm",
+        (Storm, Fr) => "Orageuse",
+        (Storm, Ru) => "Грозовая",
+        (Storm, Ja) => "嵐",
+        (Storm, Zh) => "风暴",
+        (Storm, Ko) => "폭풍",
+        (Temperate, En) => "Temperate",
+        (Temperate, De) => "Gemäßigt",
+        (Temperate, Fr) => "Tempérée",
+        (Temperate, Ru) => "Умеренная",
+        (Temperate, Ja) => "温暖",
+        (Temperate, Zh) => "温和",
+        (Temperate, Ko) => "온대",
+    }
+}
+
+/// Localized product names, keyed by EVE type ID so they can be loaded straight from the
+/// static data export or ESI. English falls back to the product's display name.
+#[derive(Debug, Clone, Default)]
+pub struct Localization {
+    product_names: HashMap<u32, HashMap<Language, String>>,
+}
+
+impl Localization {
+    /// Create an empty localization table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add product names in the static data export's layout: type ID to a map of language
+    /// codes to names. Unknown language codes are skipped.
+    pub fn load_product_names(&mut self, names: HashMap<u32, HashMap<String, String>>) {
+        for (type_id, by_code) in names {
+            let entry = self.product_names.entry(type_id).or_default();
+            for (code, name) in by_code {
+                if let Some(language) = Language::from_code(&code) {
+                    entry.insert(language, name);
+                }
+            }
+        }
+    }
+
+    /// Name of a product in a language, falling back to its English display name
+    pub fn product_name(&self, product: &Product, language: Language) -> String {
+        self.product_names
+            .get(&product.type_id)
+            .filter(|_| product.type_id != 0)
+            .and_then(|names| names.get(&language))
+            .cloned()
+            .unwrap_or_else(|| {
+                if product.display_name.is_empty() {
+                    default_display_name(&product.name)
+                } else {
+                    product.display_name.clone()
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::{MemoryRepository, ProductRepository};
+
+    #[test]
+    fn test_language_codes() {
+        for language in Language::ALL {
+            assert_eq!(Language::from_code(language.code()), Some(language));
+        }
+        assert_eq!(Language::from_code("de-DE"), Some(Language::De));
+        assert_eq!(Language::from_code(" ZH "), Some(Language::Zh));
+        assert_eq!(Language::from_code("es"), None);
+    }
+
+    #[test]
+    fn test_localized_names() {
+        assert_eq!(
+            planet_type_name(PlanetType::Temperate, Language::De),
+            "Gemäßigt"
+        );
+        for planet_type in PlanetType::ALL {
+            for language in Language::ALL {
+                assert!(!planet_type_name(planet_type, language).is_empty());
+            }
+        }
+
+        let repo = MemoryRepository::new();
+        let node = repo.get_product_by_name("broadcast_node").unwrap();
+        let mut localization = Localization::new();
+        assert_eq!(
+            localization.product_name(&node, Language::Fr),
+            "Broadcast Node"
+        );
+
+        let names = HashMap::from([(
+            node.type_id,
+            HashMap::from([
+                ("fr".to_string(), "Nœud de diffusion".to_string()),
+                ("xx".to_string(), "ignored".to_string()),
+            ]),
+        )]);
+        localization.load_product_names(names);
+        assert_eq!(
+            localization.product_name(&node, Language::Fr),
+            "Nœud de diffusion"
+        );
+        assert_eq!(
+            localization.product_name(&node, Language::De),
+            "Broadcast Node"
+        );
+    }
+}
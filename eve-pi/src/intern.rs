@@ -0,0 +1,159 @@
+use crate::domain::{Product, ProductTier};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Compact handle for a product key, only meaningful with the `ProductIds` that issued it.
+/// Unlike the EVE type ID every product gets one, numbered densely from zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProductId(u32);
+
+impl ProductId {
+    /// Position of the product in name order, for indexing per-product tables
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Product keys numbered in name order, resolved once when products are loaded so the
+/// solver's search can compare and hash integers instead of strings
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProductIds {
+    ids: HashMap<String, ProductId>,
+    names: Vec<String>,
+}
+
+impl ProductIds {
+    /// Number a set of product keys
+    pub fn new<'a>(names: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut names: Vec<String> = names.into_iter().map(str::to_string).collect();
+        names.sort();
+        names.dedup();
+        let ids = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.clone(), ProductId(i as u32)))
+            .collect();
+        Self { ids, names }
+    }
+
+    /// ID of a product key
+    pub fn id(&self, name: &str) -> Option<ProductId> {
+        self.ids.get(name).copied()
+    }
+
+    /// Product key of an ID issued by this table
+    pub fn name(&self, id: ProductId) -> &str {
+        &self.names[id.index()]
+    }
+
+    /// Number of products
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Whether there are no products
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+/// A product's recipe with its ingredients resolved to IDs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recipe {
+    pub tier: ProductTier,
+    pub inputs: Vec<(ProductId, f64)>, // Units of each ingredient per unit of output
+    pub unknown_inputs: Vec<(String, f64)>, // Ingredients that aren't products, with units per unit
+}
+
+/// Recipes of every product indexed by ID, so factory derivation and quantity walks follow
+/// a chain without looking up or cloning product keys
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recipes {
+    ids: Arc<ProductIds>,
+    recipes: Vec<Option<Recipe>>,
+}
+
+impl Recipes {
+    /// Resolve the recipes of a product set numbered by `ids`
+    pub fn new<'a>(ids: Arc<ProductIds>, products: impl IntoIterator<Item = &'a Product>) -> Self {
+        let mut recipes = vec![None; ids.len()];
+        for product in products {
+            let Some(id) = ids.id(&product.name) else {
+                continue;
+            };
+            let mut recipe = Recipe {
+                tier: product.tier,
+                inputs: Vec::new(),
+                unknown_inputs: Vec::new(),
+            };
+            for (ingredient, _) in &product.schematic.inputs {
+                let per_unit = product.schematic.per_unit(ingredient);
+                match ids.id(ingredient) {
+                    Some(input) => recipe.inputs.push((input, per_unit)),
+                    None => recipe.unknown_inputs.push((ingredient.clone(), per_unit)),
+                }
+            }
+            recipes[id.index()] = Some(recipe);
+        }
+        Self { ids, recipes }
+    }
+
+    /// Numbering the recipes are indexed by
+    pub fn ids(&self) -> &ProductIds {
+        &self.ids
+    }
+
+    /// Recipe of a product
+    pub fn get(&self, id: ProductId) -> Option<&Recipe> {
+        self.recipes.get(id.index()).and_then(Option::as_ref)
+    }
+
+    /// ID and recipe of a product key
+    pub fn find(&self, name: &str) -> Option<(ProductId, &Recipe)> {
+        let id = self.ids.id(name)?;
+        Some((id, self.get(id)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::{MemoryRepository, ProductRepository};
+
+    #[test]
+    fn test_product_ids() {
+        let ids = ProductIds::new(["water", "coolant", "water", "aqueous_liquids"]);
+        assert_eq!(ids.len(), 3);
+        let water = ids.id("water").unwrap();
+        assert_eq!(ids.name(water), "water");
+        assert!(ids.id("aqueous_liquids").unwrap() < ids.id("coolant").unwrap());
+        assert_eq!(ids.id("nano_factory"), None);
+
+        // The repository numbers every product and renumbers when they change
+        let mut repo = MemoryRepository::new();
+        assert_eq!(repo.product_ids().len(), repo.iter_products().count());
+        repo.replace_products_data(vec![crate::domain::Product::new_raw_material(
+            "aqueous_liquids".to_string(),
+        )])
+        .unwrap();
+        assert_eq!(repo.product_ids().len(), 1);
+        assert!(repo.product_ids().id("aqueous_liquids").is_some());
+    }
+
+    #[test]
+    fn test_recipes() {
+        let repo = MemoryRepository::new();
+        let recipes = repo.recipes();
+        let (_, coolant) = recipes.find("coolant").unwrap();
+        assert_eq!(coolant.tier, ProductTier::P2);
+        let inputs: Vec<(&str, f64)> = coolant
+            .inputs
+            .iter()
+            .map(|(id, per_unit)| (recipes.ids().name(*id), *per_unit))
+            .collect();
+        assert!(inputs.contains(&("water", 8.0)));
+        assert!(inputs.contains(&("electrolytes", 8.0)));
+        assert!(coolant.unknown_inputs.is_empty());
+        assert!(recipes.find("unobtainium").is_none());
+    }
+}
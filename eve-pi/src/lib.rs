@@ -1,14 +1,153 @@
+//! Planetary Industry planner for EVE Online. The crate compiles to WebAssembly for the
+//! frontend through [`PiSolver`], and works as a normal Rust library through the types
+//! re-exported here:
+//!
+//! ```
+//! use eve_pi::{MemoryRepository, Solver};
+//!
+//! let mut repository = MemoryRepository::new();
+//! repository
+//!     .load_planets(r#"[{"id": "Ocean I", "planet_type": "Oceanic", "resources": ["aqueous_liquids"]}]"#)
+//!     .unwrap();
+//! repository
+//!     .load_characters(r#"[{"name": "Alice", "planets": 1, "skills": {"command_center_upgrades": 4, "interplanetary_consolidation": 0}}]"#)
+//!     .unwrap();
+//!
+//! let plan = Solver::new(&repository).solve("water").unwrap();
+//! assert_eq!(plan.assignments[0].planet, "Ocean I");
+//! ```
+//!
+//! Everything exported from the crate root is the supported API; the modules behind it are
+//! private and may be reorganized.
+
+mod advisor;
+mod async_repository;
+mod balance;
+mod colocation;
+mod compatibility;
+mod corporation;
+mod coverage;
+mod csv_import;
 mod domain;
+mod error;
+#[cfg(all(feature = "esi", not(target_arch = "wasm32")))]
+mod esi;
 mod factory;
+mod flow;
+#[cfg(any(test, feature = "generator"))]
+mod generator;
+mod graph;
+mod health;
+mod i18n;
+mod intern;
+mod logistics;
+mod migration;
+mod ordering;
+mod presets;
+mod pricing;
+mod priority;
+mod profitability;
+mod quantity;
+mod recommend;
 mod repository;
+mod scenario;
+#[cfg(all(feature = "sde", not(target_arch = "wasm32")))]
+mod sde;
+mod sensitivity;
 mod solver;
+mod tax;
 mod utils;
+mod validation;
 mod wasm;
 
 // Re-export the WASM API
 pub use wasm::format_production_plan;
 pub use wasm::PiSolver;
 
+// Planning
+pub use error::{ErrorCode, ErrorContext, PiError};
+pub use solver::{CancellationToken, Solver, SolverError, SolverOptions};
+
+// Data access
+pub use async_repository::{
+    load_async_repository, AsyncCharacterRepository, AsyncPlanetRepository, AsyncProductRepository,
+    AsyncRepository,
+};
+pub use compatibility::CompatibilityMatrix;
+pub use graph::DependencyGraph;
+pub use intern::{ProductId, ProductIds, Recipe, Recipes};
+pub use repository::{
+    CharacterRepository, LoadMode, LoadReport, MemoryRepository, MemoryRepositoryBuilder,
+    PlanetRepository, ProductRepository, RecordError, Repository, RepositoryError, RepositoryState,
+    STATE_VERSION,
+};
+pub use scenario::{
+    BranchOutcome, PlanetarySkill, ScenarioChange, ScenarioRepository, WhatIfReport,
+};
+pub use validation::{
+    IssueKind, PlanetIssueKind, PlanetWarning, ValidationIssue, ValidationReport,
+};
+
+// Domain types
+pub use domain::{
+    CargoVolume, Character, CharacterSkills, EsiSkill, EsiSkills, ExtractionRate,
+    FactoryConfiguration, PinCounts, PinKind, Planet, PlanetAssignment, PlanetType, PlanetTypeSet,
+    Product, ProductFlow, ProductTier, ProductionPlan, Schematic, SecurityBand, ServiceRange,
+};
+pub use factory::{
+    derive_configurations, factory_planet, find_valid_factory_configurations,
+    search_factory_configurations, FactoryError, FactoryRejection, FactorySearch, FactorySpec,
+    FactoryType,
+};
+
+// Solver options and reports
+pub use advisor::{
+    AltEstimate, PlanetAcquisition, SkillAdvice, SkillUpgrade, MAX_ADVISOR_ADDITIONS,
+};
+pub use balance::{
+    check_plan_balance, AssignmentImbalance, BalanceReport, ImbalanceKind, ProductBalance,
+};
+pub use corporation::{solve_for_corporation, Corporation, CorporationPlan, MemberPlan};
+pub use coverage::{coverage_report, CoverageReport, Producibility, ProductCoverage};
+pub use flow::{FlowEdge, FlowGraph, FlowNode};
+pub use health::{run_self_test, HealthCheck, HealthReport};
+pub use i18n::{Language, Localization};
+pub use logistics::{
+    CharacterSchedule, LaunchCadence, LogisticsOptions, LogisticsReport, SystemSchedule,
+};
+pub use migration::{ColonyChange, ColonyMigration, ExistingColony, MigrationPlan};
+pub use ordering::PlanOrder;
+pub use presets::{preset_of, ColonyPreset};
+pub use pricing::{
+    buy_vs_build, value_plan, BuildOrBuy, MarketQuote, OrderType, PlanValuation, PriceProvider,
+    PriceSide, PriceSnapshot, ProductValue, SnapshotEntry, StaticPrices, TradeSettings,
+};
+pub use priority::{DropReason, DroppedTarget, PrioritizedPlan};
+pub use profitability::{profitability_report, CharacterProfit, PlanetProfit, ProfitabilityReport};
+pub use quantity::{
+    PhasedDemand, PlanetRole, ProductRequirement, QuotaPlan, QuotaTarget, Stockpile,
+};
+pub use recommend::{ProductRanking, TargetRecommendation};
+pub use sensitivity::{SensitivityReport, Unlock};
+pub use tax::{evaluate_plan_taxes, AssignmentTax, CustomsOfficeTax, ExportBreakEven, TaxReport};
+
+// Synthetic dataset generation for downstream performance testing
+#[cfg(feature = "generator")]
+pub use generator::{generate_characters, generate_planets, generate_repository, Distribution};
+
+// Live market prices, on native builds only
+#[cfg(all(feature = "esi", not(target_arch = "wasm32")))]
+pub use esi::{
+    EsiError, EsiPrices, DEFAULT_CACHE_TTL, ESI_BASE_URL, JITA_STATION_ID, THE_FORGE_REGION_ID,
+};
+
+// Rebuilding the embedded product database from the Static Data Export
+#[cfg(all(feature = "sde", not(target_arch = "wasm32")))]
+pub use sde::{
+    load_sde_product_names, load_sde_products, product_names_from_sde, products_from_sde,
+    products_to_json, SdeError,
+};
+
 // Initialize WASM module with panic hook
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
@@ -60,7 +199,7 @@ mod tests {
         );
 
         // Verify at least one assignment is for the target product
-        let has_target = plan.assignments.iter().any(|a| a.output == "bacteria");
+        let has_target = plan.assignments.iter().any(|a| a.output() == "bacteria");
         assert!(has_target, "Plan should include the target product");
     }
 }
@@ -0,0 +1,562 @@
+use crate::domain::{
+    commodity_volume, Character, Planet, PlanetAssignment, ProductFlow, ProductionPlan,
+    ServiceRange,
+};
+use crate::quantity::{assignment_daily_output, assignment_inputs_per_unit, DEFAULT_PROGRAM_HOURS};
+use crate::repository::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tsify::Tsify;
+
+/// Storage capacity of a launchpad in m³
+pub const LAUNCHPAD_CAPACITY_M3: f64 = 10_000.0;
+
+/// Practical limits on how often a colony can be serviced
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
+#[serde(default)]
+pub struct LogisticsOptions {
+    /// Volume the launchpad buffers between customs office runs
+    pub launchpad_capacity_m3: f64,
+    /// Shortest practical interval between launches from a single planet
+    pub min_hours_between_launches: f64,
+}
+
+impl Default for LogisticsOptions {
+    fn default() -> Self {
+        Self {
+            launchpad_capacity_m3: LAUNCHPAD_CAPACITY_M3,
+            min_hours_between_launches: 12.0,
+        }
+    }
+}
+
+/// Launch cadence required by a single planet assignment
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
+pub struct LaunchCadence {
+    pub character: String,
+    pub planet: String,
+    #[serde(default)]
+    pub system: Option<String>, // Solar system of the planet, when known
+    pub outputs: Vec<String>, // Products exported, main output first
+    pub daily_export_m3: f64,
+    pub daily_import_m3: f64,
+    pub hours_to_fill_launchpad: f64,
+    pub launches_per_day: f64,
+    pub exceeds_cadence: bool, // Whether the planet needs launching more often than is practical
+    pub suggested_planets: usize, // Planets to split production across to stay within cadence
+    #[serde(default)]
+    pub visit_every_hours: f64, // Longest the planet can go unvisited
+}
+
+/// How often a character has to log in to keep their planets running
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
+pub struct CharacterSchedule {
+    pub character: String,
+    pub planets: Vec<String>,
+    pub visits_per_day: f64,    // Planet visits the character makes each day
+    pub visit_every_hours: f64, // Interval of their most demanding planet
+}
+
+/// How often a system has to be flown to, servicing every planet of the plan in it
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
+pub struct SystemSchedule {
+    pub system: Option<String>, // None gathers planets without a known system
+    pub planets: Vec<String>,
+    pub characters: Vec<String>,
+    pub visit_every_hours: f64, // Interval of its most demanding planet
+}
+
+/// Launch cadence for every planet in a production plan
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
+pub struct LogisticsReport {
+    pub cadences: Vec<LaunchCadence>,
+    #[serde(default)]
+    pub characters: Vec<CharacterSchedule>, // Most frequently visited first
+    #[serde(default)]
+    pub systems: Vec<SystemSchedule>, // Most frequently visited first
+    pub total_daily_m3: f64,
+}
+
+impl LogisticsReport {
+    /// Planets whose throughput exceeds the practical launch cadence
+    pub fn flagged(&self) -> impl Iterator<Item = &LaunchCadence> {
+        self.cadences.iter().filter(|c| c.exceeds_cadence)
+    }
+}
+
+/// Whether a planet lies within the range a character is willing to fly from home. Planets
+/// without a known system can't be ruled out; anything else whose distance can't be worked
+/// out is treated as out of range.
+pub fn in_service_range(
+    repository: &dyn Repository,
+    character: &Character,
+    planet: &Planet,
+) -> bool {
+    let (Some(home), Some(range)) = (&character.home_system, character.service_range) else {
+        return true;
+    };
+    let Some(system) = &planet.system else {
+        return true;
+    };
+    if system == home {
+        return true;
+    }
+
+    let home_planet = || {
+        repository
+            .iter_planets()
+            .find(|p| p.system.as_ref() == Some(home))
+    };
+    match range {
+        ServiceRange::SameSystem => false,
+        ServiceRange::SameConstellation => {
+            planet.constellation.is_some()
+                && home_planet().is_some_and(|h| h.constellation == planet.constellation)
+        }
+        ServiceRange::SameRegion => {
+            planet.region.is_some() && home_planet().is_some_and(|h| h.region == planet.region)
+        }
+        ServiceRange::Jumps(max) => repository
+            .jumps_between(home, system)
+            .is_some_and(|jumps| jumps <= max),
+    }
+}
+
+fn volume_of(repository: &dyn Repository, product: &str) -> f64 {
+    repository
+        .get_product_by_name(product)
+        .map(|p| commodity_volume(p.tier))
+        .unwrap_or(0.0)
+}
+
+/// Volume (m³) of each imported input hauled onto a planet per hour
+pub fn import_volume_per_hour(
+    repository: &dyn Repository,
+    assignment: &PlanetAssignment,
+) -> HashMap<String, f64> {
+    let hourly_output = assignment_daily_output(repository, assignment) / 24.0;
+    assignment_inputs_per_unit(repository, assignment)
+        .into_iter()
+        .filter(|(input, _)| assignment.imported_inputs.contains(input))
+        .map(|(input, per_unit)| {
+            let m3 = per_unit * hourly_output * volume_of(repository, &input);
+            (input, m3)
+        })
+        .collect()
+}
+
+/// Route each planet's imports from the planets producing them. Consumers draw on producers
+/// in plan order, filling up from one before moving to the next so haulers fly as few routes
+/// as possible; when producers fall short, every consumer's demand is scaled down evenly.
+/// Imports nobody in the plan makes, like purchased products, get no route.
+pub fn map_import_sources(
+    repository: &dyn Repository,
+    assignments: &[PlanetAssignment],
+) -> Vec<ProductFlow> {
+    let hourly_output: Vec<f64> = assignments
+        .iter()
+        .map(|a| assignment_daily_output(repository, a) / 24.0)
+        .collect();
+    let demand_of = |consumer: usize, product: &str| {
+        let assignment = &assignments[consumer];
+        assignment_inputs_per_unit(repository, assignment)
+            .get(product)
+            .copied()
+            .unwrap_or(0.0)
+            * hourly_output[consumer]
+    };
+
+    let mut products: Vec<&String> = assignments
+        .iter()
+        .flat_map(|a| &a.imported_inputs)
+        .collect();
+    products.sort();
+    products.dedup();
+
+    let mut flows = Vec::new();
+    for product in products {
+        let mut supply: Vec<(usize, f64)> = (0..assignments.len())
+            .filter(|&i| assignments[i].produces(product))
+            .map(|i| (i, hourly_output[i] * assignments[i].output_share(product)))
+            .collect();
+        if supply.is_empty() {
+            continue;
+        }
+        let demand: Vec<(usize, f64)> = (0..assignments.len())
+            .filter(|&i| assignments[i].imported_inputs.contains(product))
+            .map(|i| (i, demand_of(i, product)))
+            .collect();
+
+        let total_supply: f64 = supply.iter().map(|(_, units)| units).sum();
+        let total_demand: f64 = demand.iter().map(|(_, units)| units).sum();
+        let scale = if total_demand > total_supply {
+            total_supply / total_demand
+        } else {
+            1.0
+        };
+
+        for (consumer, units) in demand {
+            let mut needed = units * scale;
+            for (producer, available) in supply.iter_mut() {
+                if needed <= 1e-9 {
+                    break;
+                }
+                if *available <= 1e-9
+                    || assignments[*producer].planet == assignments[consumer].planet
+                {
+                    continue;
+                }
+                let hauled = needed.min(*available);
+                *available -= hauled;
+                needed -= hauled;
+                flows.push(ProductFlow {
+                    product: product.clone(),
+                    from_planet: assignments[*producer].planet.clone(),
+                    to_planet: assignments[consumer].planet.clone(),
+                    units_per_hour: hauled,
+                });
+            }
+        }
+    }
+    flows
+}
+
+/// Daily import and export volume (m³) moved through a planet's launchpad
+pub fn assignment_daily_volume(
+    repository: &dyn Repository,
+    assignment: &PlanetAssignment,
+) -> (f64, f64) {
+    let daily_output = assignment_daily_output(repository, assignment);
+    let export: f64 = assignment
+        .outputs
+        .iter()
+        .map(|output| {
+            daily_output * assignment.output_share(output) * volume_of(repository, output)
+        })
+        .sum();
+    let import = import_volume_per_hour(repository, assignment)
+        .values()
+        .sum::<f64>()
+        * 24.0;
+
+    (export, import)
+}
+
+/// Compute how often each planet in a plan must launch its cargo
+pub fn launch_schedule(
+    plan: &ProductionPlan,
+    repository: &dyn Repository,
+    options: &LogisticsOptions,
+) -> LogisticsReport {
+    let cadences: Vec<LaunchCadence> = plan
+        .assignments
+        .iter()
+        .map(|assignment| {
+            let (daily_export_m3, daily_import_m3) =
+                assignment_daily_volume(repository, assignment);
+            let daily_m3 = daily_export_m3 + daily_import_m3;
+
+            let hours_to_fill_launchpad = if daily_m3 > 0.0 {
+                options.launchpad_capacity_m3 / daily_m3 * 24.0
+            } else {
+                f64::INFINITY
+            };
+            let exceeds_cadence = hours_to_fill_launchpad < options.min_hours_between_launches;
+            let suggested_planets = if exceeds_cadence {
+                (options.min_hours_between_launches / hours_to_fill_launchpad).ceil() as usize
+            } else {
+                1
+            };
+
+            // Extraction planets also need their programs restarted
+            let visit_every_hours = if assignment.mined_inputs.is_empty() {
+                hours_to_fill_launchpad
+            } else {
+                hours_to_fill_launchpad
+                    .min(assignment.program_hours.unwrap_or(DEFAULT_PROGRAM_HOURS))
+            };
+
+            LaunchCadence {
+                character: assignment.character.clone(),
+                planet: assignment.planet.clone(),
+                system: repository
+                    .get_planet_by_id(&assignment.planet)
+                    .and_then(|p| p.system),
+                outputs: assignment.outputs.clone(),
+                daily_export_m3,
+                daily_import_m3,
+                hours_to_fill_launchpad,
+                launches_per_day: daily_m3 / options.launchpad_capacity_m3,
+                exceeds_cadence,
+                suggested_planets,
+                visit_every_hours,
+            }
+        })
+        .collect();
+
+    let total_daily_m3 = cadences
+        .iter()
+        .map(|c| c.daily_export_m3 + c.daily_import_m3)
+        .sum();
+
+    let mut characters: Vec<CharacterSchedule> = Vec::new();
+    let mut systems: Vec<SystemSchedule> = Vec::new();
+    for cadence in &cadences {
+        let visits_per_day = 24.0 / cadence.visit_every_hours;
+        match characters
+            .iter_mut()
+            .find(|c| c.character == cadence.character)
+        {
+            Some(character) => {
+                character.planets.push(cadence.planet.clone());
+                character.visits_per_day += visits_per_day;
+                character.visit_every_hours =
+                    character.visit_every_hours.min(cadence.visit_every_hours);
+            }
+            None => characters.push(CharacterSchedule {
+                character: cadence.character.clone(),
+                planets: vec![cadence.planet.clone()],
+                visits_per_day,
+                visit_every_hours: cadence.visit_every_hours,
+            }),
+        }
+
+        match systems.iter_mut().find(|s| s.system == cadence.system) {
+            Some(system) => {
+                system.planets.push(cadence.planet.clone());
+                if !system.characters.contains(&cadence.character) {
+                    system.characters.push(cadence.character.clone());
+                }
+                system.visit_every_hours = system.visit_every_hours.min(cadence.visit_every_hours);
+            }
+            None => systems.push(SystemSchedule {
+                system: cadence.system.clone(),
+                planets: vec![cadence.planet.clone()],
+                characters: vec![cadence.character.clone()],
+                visit_every_hours: cadence.visit_every_hours,
+            }),
+        }
+    }
+    characters.sort_by(|a, b| b.visits_per_day.total_cmp(&a.visits_per_day));
+    systems.sort_by(|a, b| a.visit_every_hours.total_cmp(&b.visit_every_hours));
+
+    LogisticsReport {
+        cadences,
+        characters,
+        systems,
+        total_daily_m3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::PlanetType;
+    use crate::repository::MemoryRepository;
+
+    fn create_test_plan() -> ProductionPlan {
+        ProductionPlan {
+            assignments: vec![
+                PlanetAssignment {
+                    character: "Character1".to_string(),
+                    planet: "Oceanic1".to_string(),
+                    planet_type: PlanetType::Oceanic,
+                    imported_inputs: Vec::new(),
+                    mined_inputs: vec!["aqueous_liquids".to_string()],
+                    outputs: vec!["water".to_string()],
+                    program_hours: None,
+                    command_center_level: None,
+                    import_m3_per_hour: HashMap::new(),
+                },
+                PlanetAssignment {
+                    character: "Character1".to_string(),
+                    planet: "Barren1".to_string(),
+                    planet_type: PlanetType::Barren,
+                    imported_inputs: vec!["water".to_string(), "electrolytes".to_string()],
+                    mined_inputs: Vec::new(),
+                    outputs: vec!["coolant".to_string()],
+                    program_hours: None,
+                    command_center_level: None,
+                    import_m3_per_hour: HashMap::new(),
+                },
+            ],
+            flows: Vec::new(),
+            schedule: None,
+        }
+    }
+
+    #[test]
+    fn test_assignment_daily_volume() {
+        let repo = MemoryRepository::new();
+        let plan = create_test_plan();
+
+        // 1200 coolant/day exported, 9600 of each P1 imported
+        let (export, import) = assignment_daily_volume(&repo, &plan.assignments[1]);
+        assert!((export - 1_200.0 * 1.5).abs() < 1e-6);
+        assert!((import - 2.0 * 9_600.0 * 0.38).abs() < 1e-6);
+
+        let hourly = import_volume_per_hour(&repo, &plan.assignments[1]);
+        assert_eq!(hourly.len(), 2);
+        assert!((hourly["water"] - 400.0 * 0.38).abs() < 1e-6);
+        assert!(import_volume_per_hour(&repo, &plan.assignments[0]).is_empty());
+    }
+
+    #[test]
+    fn test_map_import_sources() {
+        let repo = MemoryRepository::new();
+        let mut plan = create_test_plan();
+
+        // Electrolytes aren't made anywhere in the plan, so only water gets a route
+        let flows = map_import_sources(&repo, &plan.assignments);
+        assert_eq!(flows.len(), 1);
+        assert_eq!(flows[0].product, "water");
+        assert_eq!(flows[0].from_planet, "Oceanic1");
+        assert_eq!(flows[0].to_planet, "Barren1");
+        let water_per_hour = assignment_daily_output(&repo, &plan.assignments[0]) / 24.0;
+        assert!((flows[0].units_per_hour - water_per_hour).abs() < 1e-9);
+
+        // One water planet can't keep up with two coolant planets, so they split its output
+        let mut second = plan.assignments[1].clone();
+        second.planet = "Barren2".to_string();
+        plan.assignments.push(second);
+        let flows = map_import_sources(&repo, &plan.assignments);
+        assert_eq!(flows.len(), 2);
+        assert_eq!(flows[1].to_planet, "Barren2");
+        assert!((flows[0].units_per_hour - flows[1].units_per_hour).abs() < 1e-9);
+        assert!((flows[0].units_per_hour * 2.0 - water_per_hour).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_default_cadence_is_practical() {
+        let repo = MemoryRepository::new();
+        let report = launch_schedule(&create_test_plan(), &repo, &LogisticsOptions::default());
+
+        assert_eq!(report.cadences.len(), 2);
+        assert_eq!(report.flagged().count(), 0);
+        assert!(report.cadences.iter().all(|c| c.suggested_planets == 1));
+    }
+
+    #[test]
+    fn test_schedule_groups_by_character_and_system() {
+        let mut repo = MemoryRepository::new();
+        repo.load_planets_data(vec![
+            Planet::new("Oceanic1".to_string(), PlanetType::Oceanic, Vec::new()).with_location(
+                "Jita",
+                "Kimotoro",
+                "The Forge",
+                0.9,
+            ),
+            Planet::new("Barren1".to_string(), PlanetType::Barren, Vec::new()).with_location(
+                "Jita",
+                "Kimotoro",
+                "The Forge",
+                0.9,
+            ),
+        ])
+        .unwrap();
+        let mut plan = create_test_plan();
+        plan.assignments[0].program_hours = Some(72.0);
+        let report = launch_schedule(&plan, &repo, &LogisticsOptions::default());
+
+        // The water planet's three-day program runs out before its launchpad fills
+        let water = &report.cadences[0];
+        assert_eq!(water.system.as_deref(), Some("Jita"));
+        assert!(water.hours_to_fill_launchpad > 72.0);
+        assert_eq!(water.visit_every_hours, 72.0);
+        let coolant = &report.cadences[1];
+        assert_eq!(coolant.visit_every_hours, coolant.hours_to_fill_launchpad);
+
+        assert_eq!(report.characters.len(), 1);
+        let character = &report.characters[0];
+        assert_eq!(character.planets.len(), 2);
+        let expected = 24.0 / water.visit_every_hours + 24.0 / coolant.visit_every_hours;
+        assert!((character.visits_per_day - expected).abs() < 1e-9);
+
+        assert_eq!(report.systems.len(), 1);
+        assert_eq!(report.systems[0].characters, vec!["Character1".to_string()]);
+        assert_eq!(
+            report.systems[0].visit_every_hours,
+            water.visit_every_hours.min(coolant.visit_every_hours)
+        );
+
+        // Without the program the launchpad sets the pace
+        plan.assignments[0].program_hours = Some(10_000.0);
+        let report = launch_schedule(&plan, &repo, &LogisticsOptions::default());
+        let water = &report.cadences[0];
+        assert_eq!(water.visit_every_hours, water.hours_to_fill_launchpad);
+    }
+
+    #[test]
+    fn test_high_throughput_planet_is_flagged() {
+        let repo = MemoryRepository::new();
+        let options = LogisticsOptions {
+            min_hours_between_launches: 96.0,
+            ..LogisticsOptions::default()
+        };
+        let report = launch_schedule(&create_test_plan(), &repo, &options);
+
+        let flagged: Vec<_> = report.flagged().collect();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].outputs, ["coolant"]);
+        assert!(flagged[0].suggested_planets >= 2);
+    }
+
+    #[test]
+    fn test_in_service_range() {
+        let mut repo = MemoryRepository::new();
+        let planet = |id: &str, system: &str, constellation: &str, region: &str| {
+            Planet::new(id.to_string(), PlanetType::Barren, Vec::new()).with_location(
+                system,
+                constellation,
+                region,
+                0.9,
+            )
+        };
+        let home = planet("Home1", "Jita", "Kimotoro", "The Forge");
+        let neighbour = planet("Next1", "Perimeter", "Kimotoro", "The Forge");
+        let far = planet("Far1", "Amarr", "Throne Worlds", "Domain");
+        let unknown = Planet::new("Unknown1".to_string(), PlanetType::Barren, Vec::new());
+        repo.load_planets_data(vec![home.clone(), neighbour.clone(), far.clone()])
+            .unwrap();
+        repo.set_jumps("Jita", "Perimeter", 1);
+
+        let character = |range: ServiceRange| {
+            Character {
+                name: "Hauler".to_string(),
+                planets: 6,
+                skills: crate::domain::CharacterSkills {
+                    command_center_upgrades: 5,
+                    interplanetary_consolidation: 5,
+                    remote_sensing: None,
+                    planetary_production: None,
+                    planetology: None,
+                    advanced_planetology: None,
+                    customs_code_expertise: None,
+                },
+                poco_tax_rate: None,
+                program_hours: None,
+                home_system: None,
+                service_range: None,
+            }
+            .with_home("Jita", range)
+        };
+
+        let same_system = character(ServiceRange::SameSystem);
+        assert!(in_service_range(&repo, &same_system, &home));
+        assert!(!in_service_range(&repo, &same_system, &neighbour));
+        assert!(in_service_range(&repo, &same_system, &unknown));
+
+        let constellation = character(ServiceRange::SameConstellation);
+        assert!(in_service_range(&repo, &constellation, &neighbour));
+        assert!(!in_service_range(&repo, &constellation, &far));
+
+        let one_jump = character(ServiceRange::Jumps(1));
+        assert!(in_service_range(&repo, &one_jump, &neighbour));
+        assert!(!in_service_range(&repo, &one_jump, &far));
+        assert!(!in_service_range(
+            &repo,
+            &character(ServiceRange::Jumps(0)),
+            &neighbour
+        ));
+    }
+}
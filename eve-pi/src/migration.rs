@@ -0,0 +1,398 @@
+use crate::domain::{PlanetAssignment, ProductTier, ProductionPlan};
+use crate::repository::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// A colony that is already set up in game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "StoredColony")]
+pub struct ExistingColony {
+    pub character: String,            // Character owning the colony
+    pub planet: String,               // Planet ID
+    pub imported_inputs: Vec<String>, // Products currently imported
+    pub mined_inputs: Vec<String>,    // Resources currently extracted
+    pub outputs: Vec<String>,         // Products currently produced, main output first
+}
+
+impl ExistingColony {
+    /// The colony's main output
+    pub fn output(&self) -> &str {
+        self.outputs.first().map(String::as_str).unwrap_or_default()
+    }
+
+    /// Whether the colony makes a product, as its main output or alongside it
+    pub fn produces(&self, product: &str) -> bool {
+        self.outputs.iter().any(|output| output == product)
+    }
+
+    /// Whether the colony already matches a planned assignment exactly
+    pub fn matches(&self, assignment: &PlanetAssignment) -> bool {
+        self.character == assignment.character
+            && self.planet == assignment.planet
+            && same_inputs(&self.outputs, &assignment.outputs)
+            && same_inputs(&self.imported_inputs, &assignment.imported_inputs)
+            && same_inputs(&self.mined_inputs, &assignment.mined_inputs)
+    }
+}
+
+/// Existing colony as stored, also accepting colonies saved with a single `output`
+#[derive(Deserialize)]
+struct StoredColony {
+    character: String,
+    planet: String,
+    #[serde(default)]
+    imported_inputs: Vec<String>,
+    #[serde(default)]
+    mined_inputs: Vec<String>,
+    #[serde(default)]
+    outputs: Vec<String>,
+    #[serde(default)]
+    output: Option<String>,
+}
+
+impl From<StoredColony> for ExistingColony {
+    fn from(stored: StoredColony) -> Self {
+        let outputs = if stored.outputs.is_empty() {
+            stored.output.into_iter().collect()
+        } else {
+            stored.outputs
+        };
+        Self {
+            character: stored.character,
+            planet: stored.planet,
+            imported_inputs: stored.imported_inputs,
+            mined_inputs: stored.mined_inputs,
+            outputs,
+        }
+    }
+}
+
+impl From<&PlanetAssignment> for ExistingColony {
+    fn from(assignment: &PlanetAssignment) -> Self {
+        Self {
+            character: assignment.character.clone(),
+            planet: assignment.planet.clone(),
+            imported_inputs: assignment.imported_inputs.clone(),
+            mined_inputs: assignment.mined_inputs.clone(),
+            outputs: assignment.outputs.clone(),
+        }
+    }
+}
+
+/// What happens to a planet when moving from the existing colonies to the new plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColonyChange {
+    /// The colony already matches the plan
+    Keep,
+    /// The colony stays but is rebuilt for a different setup
+    Repurpose,
+    /// The colony is no longer needed
+    Abandon,
+    /// A new colony has to be set up
+    Establish,
+}
+
+/// Change required on a single planet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColonyMigration {
+    pub planet: String,
+    pub change: ColonyChange,
+    pub current: Option<ExistingColony>, // Colony as it is set up today
+    pub planned: Option<PlanetAssignment>, // Assignment in the new plan
+    pub pins_torn_down: usize,           // Existing pins that have to be removed
+    pub pins_built: usize,               // New pins that have to be placed
+}
+
+/// New plan together with the changes needed to get there from the existing colonies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationPlan {
+    pub plan: ProductionPlan,
+    pub changes: Vec<ColonyMigration>,
+    pub total_pins_torn_down: usize,
+    pub total_pins_built: usize,
+}
+
+impl MigrationPlan {
+    /// Changes of a given kind
+    pub fn changes_of(&self, change: ColonyChange) -> impl Iterator<Item = &ColonyMigration> {
+        self.changes.iter().filter(move |c| c.change == change)
+    }
+}
+
+/// Whether two input lists contain the same products, regardless of order
+pub(crate) fn same_inputs(a: &[String], b: &[String]) -> bool {
+    a.len() == b.len() && a.iter().all(|input| b.contains(input))
+}
+
+/// Estimate the pins a colony setup needs: one extractor per mined resource and one
+/// processing stage per product built on the planet, for every output. The command center
+/// and launchpad are shared by every setup and never counted.
+pub fn colony_pins(
+    repository: &dyn Repository,
+    outputs: &[String],
+    imported_inputs: &[String],
+    mined_inputs: &[String],
+) -> BTreeSet<String> {
+    let mut pins: BTreeSet<String> = mined_inputs
+        .iter()
+        .map(|resource| format!("extractor:{}", resource))
+        .collect();
+    for output in outputs {
+        processing_pins(repository, output, imported_inputs, mined_inputs, &mut pins);
+    }
+    pins
+}
+
+fn processing_pins(
+    repository: &dyn Repository,
+    product: &str,
+    imported_inputs: &[String],
+    mined_inputs: &[String],
+    pins: &mut BTreeSet<String>,
+) {
+    let Some(product) = repository.get_product_by_name(product) else {
+        return;
+    };
+    if product.tier == ProductTier::P0 || !pins.insert(format!("processor:{}", product.name)) {
+        return;
+    }
+
+    for ingredient in &product.ingredients() {
+        if !imported_inputs.contains(ingredient) && !mined_inputs.contains(ingredient) {
+            processing_pins(repository, ingredient, imported_inputs, mined_inputs, pins);
+        }
+    }
+}
+
+/// Diff the existing colonies against a new plan, planet by planet
+pub fn plan_migration(
+    repository: &dyn Repository,
+    existing: &[ExistingColony],
+    plan: ProductionPlan,
+) -> MigrationPlan {
+    let mut changes = Vec::new();
+
+    for colony in existing {
+        let current_pins = colony_pins(
+            repository,
+            &colony.outputs,
+            &colony.imported_inputs,
+            &colony.mined_inputs,
+        );
+        let planned = plan
+            .assignments
+            .iter()
+            .find(|a| a.planet == colony.planet)
+            .cloned();
+
+        let (change, pins_torn_down, pins_built) = match &planned {
+            Some(assignment) if colony.matches(assignment) => (ColonyChange::Keep, 0, 0),
+            Some(assignment) => {
+                let planned_pins = colony_pins(
+                    repository,
+                    &assignment.outputs,
+                    &assignment.imported_inputs,
+                    &assignment.mined_inputs,
+                );
+                // A colony handed to another character is rebuilt from scratch
+                if assignment.character == colony.character {
+                    (
+                        ColonyChange::Repurpose,
+                        current_pins.difference(&planned_pins).count(),
+                        planned_pins.difference(&current_pins).count(),
+                    )
+                } else {
+                    (
+                        ColonyChange::Repurpose,
+                        current_pins.len(),
+                        planned_pins.len(),
+                    )
+                }
+            }
+            None => (ColonyChange::Abandon, current_pins.len(), 0),
+        };
+
+        changes.push(ColonyMigration {
+            planet: colony.planet.clone(),
+            change,
+            current: Some(colony.clone()),
+            planned,
+            pins_torn_down,
+            pins_built,
+        });
+    }
+
+    for assignment in &plan.assignments {
+        if existing.iter().any(|c| c.planet == assignment.planet) {
+            continue;
+        }
+        changes.push(ColonyMigration {
+            planet: assignment.planet.clone(),
+            change: ColonyChange::Establish,
+            current: None,
+            planned: Some(assignment.clone()),
+            pins_torn_down: 0,
+            pins_built: colony_pins(
+                repository,
+                &assignment.outputs,
+                &assignment.imported_inputs,
+                &assignment.mined_inputs,
+            )
+            .len(),
+        });
+    }
+
+    changes.sort_by(|a, b| a.planet.cmp(&b.planet));
+
+    MigrationPlan {
+        total_pins_torn_down: changes.iter().map(|c| c.pins_torn_down).sum(),
+        total_pins_built: changes.iter().map(|c| c.pins_built).sum(),
+        plan,
+        changes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::PlanetType;
+    use crate::repository::MemoryRepository;
+    use std::collections::HashMap;
+
+    fn colony(planet: &str, outputs: &[&str], imported: &[&str], mined: &[&str]) -> ExistingColony {
+        ExistingColony {
+            character: "Character1".to_string(),
+            planet: planet.to_string(),
+            imported_inputs: imported.iter().map(|s| s.to_string()).collect(),
+            mined_inputs: mined.iter().map(|s| s.to_string()).collect(),
+            outputs: outputs.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    fn assignment(existing: &ExistingColony) -> PlanetAssignment {
+        PlanetAssignment {
+            character: existing.character.clone(),
+            planet: existing.planet.clone(),
+            planet_type: PlanetType::Barren,
+            imported_inputs: existing.imported_inputs.clone(),
+            mined_inputs: existing.mined_inputs.clone(),
+            outputs: existing.outputs.clone(),
+            program_hours: None,
+            command_center_level: None,
+            import_m3_per_hour: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_colony_pins() {
+        let repo = MemoryRepository::new();
+
+        let extraction = colony_pins(
+            &repo,
+            &["water".to_string()],
+            &[],
+            &["aqueous_liquids".to_string()],
+        );
+        assert_eq!(
+            extraction.into_iter().collect::<Vec<_>>(),
+            vec!["extractor:aqueous_liquids", "processor:water"]
+        );
+
+        let factory = colony_pins(
+            &repo,
+            &["coolant".to_string()],
+            &["water".to_string(), "electrolytes".to_string()],
+            &[],
+        );
+        assert_eq!(factory.len(), 1);
+    }
+
+    #[test]
+    fn test_plan_migration_diff() {
+        let repo = MemoryRepository::new();
+
+        let kept = colony("Oceanic1", &["water"], &[], &["aqueous_liquids"]);
+        let retooled = colony("Storm1", &["electrolytes"], &[], &["ionic_solutions"]);
+        let dropped = colony("Barren1", &["reactive_metals"], &[], &["base_metals"]);
+
+        let mut coolant = assignment(&retooled);
+        coolant.outputs = vec!["coolant".to_string()];
+        coolant.imported_inputs = vec!["water".to_string(), "electrolytes".to_string()];
+        coolant.mined_inputs = Vec::new();
+        let mut new_electrolytes = assignment(&retooled);
+        new_electrolytes.planet = "Gas1".to_string();
+
+        let plan = ProductionPlan {
+            assignments: vec![assignment(&kept), coolant, new_electrolytes],
+            flows: Vec::new(),
+            schedule: None,
+        };
+        let migration = plan_migration(&repo, &[kept, retooled, dropped], plan);
+
+        let change_of = |planet: &str| {
+            migration
+                .changes
+                .iter()
+                .find(|c| c.planet == planet)
+                .unwrap()
+        };
+        assert_eq!(change_of("Oceanic1").change, ColonyChange::Keep);
+        assert_eq!(change_of("Storm1").change, ColonyChange::Repurpose);
+        assert_eq!(change_of("Storm1").pins_torn_down, 2);
+        assert_eq!(change_of("Storm1").pins_built, 1);
+        assert_eq!(change_of("Barren1").change, ColonyChange::Abandon);
+        assert_eq!(change_of("Gas1").change, ColonyChange::Establish);
+        assert_eq!(migration.changes_of(ColonyChange::Keep).count(), 1);
+        assert_eq!(migration.total_pins_torn_down, 4);
+    }
+
+    #[test]
+    fn test_plan_migration_of_multi_output_hub() {
+        let repo = MemoryRepository::new();
+        let hub = colony(
+            "Barren1",
+            &["construction_blocks", "mechanical_parts"],
+            &["reactive_metals", "toxic_metals", "precious_metals"],
+            &[],
+        );
+        assert_eq!(
+            colony_pins(&repo, &hub.outputs, &hub.imported_inputs, &hub.mined_inputs).len(),
+            2
+        );
+
+        // The same outputs in another order keep the hub as it is
+        let mut reordered = assignment(&hub);
+        reordered.outputs.reverse();
+        assert!(hub.matches(&reordered));
+        assert_eq!(ExistingColony::from(&reordered).outputs, reordered.outputs);
+
+        // Dropping an extra output tears down its processor
+        let mut narrowed = assignment(&hub);
+        narrowed.outputs.truncate(1);
+        narrowed.imported_inputs.truncate(2);
+        assert!(!hub.matches(&narrowed));
+        let plan = ProductionPlan {
+            assignments: vec![narrowed],
+            flows: Vec::new(),
+            schedule: None,
+        };
+        let migration = plan_migration(&repo, &[hub], plan);
+        assert_eq!(migration.changes[0].change, ColonyChange::Repurpose);
+        assert_eq!(migration.changes[0].pins_torn_down, 1);
+        assert_eq!(migration.changes[0].pins_built, 0);
+    }
+
+    #[test]
+    fn test_existing_colony_reads_single_output() {
+        let old: ExistingColony = serde_json::from_str(
+            r#"{"character": "Character1", "planet": "Oceanic1", "output": "water"}"#,
+        )
+        .unwrap();
+        assert_eq!(old.outputs, ["water"]);
+        assert_eq!(old.output(), "water");
+
+        let json = serde_json::to_value(&old).unwrap();
+        assert_eq!(json["outputs"], serde_json::json!(["water"]));
+        assert!(json.get("output").is_none());
+    }
+}
@@ -0,0 +1,219 @@
+use crate::domain::{PlanetAssignment, ProductTier, ProductionPlan};
+use crate::repository::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tsify::Tsify;
+
+/// Controls the order of assignments in a production plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+pub enum PlanOrder {
+    /// Grouped by character name
+    ByCharacter,
+    /// Lowest tier outputs first
+    ByTier,
+    /// Sorted by planet ID
+    ByPlanet,
+    /// Producers before the planets that import their output
+    Topological,
+    /// Grouped by solar system, planets without a known system last
+    BySystem,
+}
+
+/// Sort a plan's assignments and their input lists so serialized output is stable
+pub fn sort_plan(plan: &mut ProductionPlan, order: PlanOrder, repository: &dyn Repository) {
+    for assignment in &mut plan.assignments {
+        assignment.imported_inputs.sort();
+        assignment.mined_inputs.sort();
+    }
+
+    let tiers: HashMap<String, ProductTier> = plan
+        .assignments
+        .iter()
+        .filter_map(|a| {
+            repository
+                .get_product_by_name(a.output())
+                .map(|p| (a.output().to_string(), p.tier))
+        })
+        .collect();
+    let depths = dependency_depths(&plan.assignments);
+    let systems: HashMap<String, String> = plan
+        .assignments
+        .iter()
+        .filter_map(|a| {
+            let planet = repository.get_planet_by_id(&a.planet)?;
+            Some((a.planet.clone(), planet.system?))
+        })
+        .collect();
+
+    // Planet ID is always the final tie-breaker, since every planet appears at most once
+    let by_planet = |a: &PlanetAssignment, b: &PlanetAssignment| a.planet.cmp(&b.planet);
+    let by_output = |a: &PlanetAssignment, b: &PlanetAssignment| a.output().cmp(b.output());
+
+    plan.assignments.sort_by(|a, b| match order {
+        PlanOrder::ByCharacter => a.character.cmp(&b.character).then_with(|| by_planet(a, b)),
+        PlanOrder::ByTier => tiers
+            .get(a.output())
+            .cmp(&tiers.get(b.output()))
+            .then_with(|| by_output(a, b))
+            .then_with(|| by_planet(a, b)),
+        PlanOrder::ByPlanet => by_planet(a, b),
+        PlanOrder::Topological => depths
+            .get(a.output())
+            .cmp(&depths.get(b.output()))
+            .then_with(|| by_output(a, b))
+            .then_with(|| by_planet(a, b)),
+        PlanOrder::BySystem => {
+            let system = |a: &PlanetAssignment| systems.get(&a.planet);
+            system(a)
+                .is_none()
+                .cmp(&system(b).is_none())
+                .then_with(|| system(a).cmp(&system(b)))
+                .then_with(|| by_planet(a, b))
+        }
+    });
+}
+
+/// Length of the longest chain of upstream producers feeding each output
+fn dependency_depths(assignments: &[PlanetAssignment]) -> HashMap<String, usize> {
+    let mut depths = HashMap::new();
+    for assignment in assignments {
+        depth_of(
+            assignment.output(),
+            assignments,
+            &mut depths,
+            &mut Vec::new(),
+        );
+    }
+    depths
+}
+
+fn depth_of(
+    product: &str,
+    assignments: &[PlanetAssignment],
+    depths: &mut HashMap<String, usize>,
+    visiting: &mut Vec<String>,
+) -> usize {
+    if let Some(depth) = depths.get(product) {
+        return *depth;
+    }
+
+    // Guard against malformed plans that import their own output
+    if visiting.iter().any(|p| p == product) {
+        return 0;
+    }
+    visiting.push(product.to_string());
+
+    let mut depth = 0;
+    for assignment in assignments.iter().filter(|a| a.produces(product)) {
+        for input in &assignment.imported_inputs {
+            if assignments.iter().any(|a| a.produces(input)) {
+                depth = depth.max(depth_of(input, assignments, depths, visiting) + 1);
+            }
+        }
+    }
+
+    visiting.pop();
+    depths.insert(product.to_string(), depth);
+    depth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Planet, PlanetType};
+    use crate::repository::MemoryRepository;
+
+    fn create_test_plan() -> ProductionPlan {
+        ProductionPlan {
+            assignments: vec![
+                PlanetAssignment::for_test("B", "planet_2", &["water"], &[]),
+                PlanetAssignment::for_test(
+                    "A",
+                    "planet_3",
+                    &["coolant"],
+                    &["water", "electrolytes"],
+                ),
+                PlanetAssignment::for_test("B", "planet_1", &["electrolytes"], &[]),
+                PlanetAssignment::for_test(
+                    "A",
+                    "planet_4",
+                    &["ukomi_super_conductors"],
+                    &["coolant"],
+                ),
+            ],
+            flows: Vec::new(),
+            schedule: None,
+        }
+    }
+
+    fn planets(plan: &ProductionPlan) -> Vec<&str> {
+        plan.assignments.iter().map(|a| a.planet.as_str()).collect()
+    }
+
+    #[test]
+    fn test_sort_by_character_and_planet() {
+        let repo = MemoryRepository::new();
+
+        let mut plan = create_test_plan();
+        sort_plan(&mut plan, PlanOrder::ByCharacter, &repo);
+        assert_eq!(
+            planets(&plan),
+            vec!["planet_3", "planet_4", "planet_1", "planet_2"]
+        );
+
+        sort_plan(&mut plan, PlanOrder::ByPlanet, &repo);
+        assert_eq!(
+            planets(&plan),
+            vec!["planet_1", "planet_2", "planet_3", "planet_4"]
+        );
+    }
+
+    #[test]
+    fn test_sort_by_system() {
+        let mut repo = MemoryRepository::new();
+        let planet = |id: &str, system: Option<&str>| {
+            let planet = Planet::new(id.to_string(), PlanetType::Barren, Vec::new());
+            match system {
+                Some(system) => planet.with_location(system, "Kimotoro", "The Forge", 0.9),
+                None => planet,
+            }
+        };
+        repo.load_planets_data(vec![
+            planet("planet_1", Some("Perimeter")),
+            planet("planet_2", None),
+            planet("planet_3", Some("Jita")),
+            planet("planet_4", Some("Perimeter")),
+        ])
+        .unwrap();
+
+        let mut plan = create_test_plan();
+        sort_plan(&mut plan, PlanOrder::BySystem, &repo);
+        assert_eq!(
+            planets(&plan),
+            vec!["planet_3", "planet_1", "planet_4", "planet_2"]
+        );
+    }
+
+    #[test]
+    fn test_sort_by_tier_and_topological() {
+        let repo = MemoryRepository::new();
+
+        let mut plan = create_test_plan();
+        sort_plan(&mut plan, PlanOrder::ByTier, &repo);
+        assert_eq!(
+            planets(&plan),
+            vec!["planet_1", "planet_2", "planet_3", "planet_4"]
+        );
+
+        let mut plan = create_test_plan();
+        sort_plan(&mut plan, PlanOrder::Topological, &repo);
+        assert_eq!(plan.assignments[2].output(), "coolant");
+        assert_eq!(plan.assignments[3].output(), "ukomi_super_conductors");
+
+        // Input lists are normalized too
+        assert_eq!(
+            plan.assignments[2].imported_inputs,
+            vec!["electrolytes", "water"]
+        );
+    }
+}
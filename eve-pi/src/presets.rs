@@ -0,0 +1,128 @@
+use crate::domain::{normalize_name, FactoryConfiguration, ProductTier};
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+/// Colony layouts players commonly build, so plans map onto setups they already know
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Tsify)]
+pub enum ColonyPreset {
+    /// Imports P3 products into high-tech facilities making a P4 product
+    StandardP4Factory,
+    /// Two extractors, each feeding basic facilities that refine their own P1 product
+    DualExtractor,
+    /// Imports P1 products into advanced facilities making one or more P2 products
+    P2Hub,
+}
+
+impl ColonyPreset {
+    /// Every preset, in declaration order
+    pub const ALL: [ColonyPreset; 3] = [
+        ColonyPreset::StandardP4Factory,
+        ColonyPreset::DualExtractor,
+        ColonyPreset::P2Hub,
+    ];
+
+    /// Short name used to select the preset
+    pub fn name(self) -> &'static str {
+        match self {
+            ColonyPreset::StandardP4Factory => "standard_p4_factory",
+            ColonyPreset::DualExtractor => "dual_extractor",
+            ColonyPreset::P2Hub => "p2_hub",
+        }
+    }
+
+    /// Look up a preset by name, ignoring case and accepting spaces or dashes for underscores
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = normalize_name(name);
+        Self::ALL.into_iter().find(|preset| preset.name() == name)
+    }
+
+    /// What the colony looks like in game
+    pub fn description(self) -> &'static str {
+        match self {
+            ColonyPreset::StandardP4Factory => {
+                "Launchpad importing three P3 products into high-tech facilities making a P4 product"
+            }
+            ColonyPreset::DualExtractor => {
+                "Two extractors with basic facilities refining each resource into its P1 product"
+            }
+            ColonyPreset::P2Hub => {
+                "Launchpad importing P1 products into advanced facilities making P2 products"
+            }
+        }
+    }
+
+    /// Whether a factory configuration follows this layout
+    pub fn matches(self, config: &FactoryConfiguration) -> bool {
+        let tiers = (config.start_tier, config.end_tier);
+        match self {
+            ColonyPreset::StandardP4Factory => {
+                tiers == (ProductTier::P3, ProductTier::P4) && config.mined_inputs.is_empty()
+            }
+            ColonyPreset::DualExtractor => {
+                tiers == (ProductTier::P0, ProductTier::P1)
+                    && config.imported_inputs.is_empty()
+                    && config.mined_inputs.len() == 2
+                    && config.outputs.len() == 2
+            }
+            ColonyPreset::P2Hub => {
+                tiers == (ProductTier::P1, ProductTier::P2) && config.mined_inputs.is_empty()
+            }
+        }
+    }
+}
+
+/// The preset a factory configuration follows, if any
+pub fn preset_of(config: &FactoryConfiguration) -> Option<ColonyPreset> {
+    ColonyPreset::ALL
+        .into_iter()
+        .find(|preset| preset.matches(config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::PlanetType;
+    use crate::factory::find_valid_factory_configurations;
+    use crate::repository::MemoryRepository;
+
+    #[test]
+    fn test_preset_names() {
+        for preset in ColonyPreset::ALL {
+            assert_eq!(ColonyPreset::from_name(preset.name()), Some(preset));
+        }
+        assert_eq!(
+            ColonyPreset::from_name("Standard P4 Factory"),
+            Some(ColonyPreset::StandardP4Factory)
+        );
+        assert_eq!(ColonyPreset::from_name("p2-hub"), Some(ColonyPreset::P2Hub));
+        assert_eq!(ColonyPreset::from_name("moon_mining"), None);
+    }
+
+    #[test]
+    fn test_presets_match_generated_configurations() {
+        let repo = MemoryRepository::new();
+
+        let configs =
+            find_valid_factory_configurations(&repo, PlanetType::Barren, "broadcast_node");
+        assert!(configs
+            .iter()
+            .any(|c| preset_of(c) == Some(ColonyPreset::StandardP4Factory)));
+
+        let configs = find_valid_factory_configurations(&repo, PlanetType::Barren, "coolant");
+        assert!(configs
+            .iter()
+            .all(|c| preset_of(c) == Some(ColonyPreset::P2Hub)));
+
+        // Barren planets can extract both base and noble metals
+        let configs =
+            find_valid_factory_configurations(&repo, PlanetType::Barren, "reactive_metals");
+        let dual = configs
+            .iter()
+            .find(|c| preset_of(c) == Some(ColonyPreset::DualExtractor))
+            .expect("Expected a dual extractor");
+        assert_eq!(dual.outputs[0], "reactive_metals");
+        assert!(configs
+            .iter()
+            .any(|c| c.outputs.len() == 1 && preset_of(c).is_none()));
+    }
+}
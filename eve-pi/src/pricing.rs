@@ -0,0 +1,645 @@
+use crate::balance::effective_hourly_output;
+use crate::domain::{PlanetAssignment, ProductionPlan};
+use crate::logistics::map_import_sources;
+use crate::quantity::assignment_inputs_per_unit;
+use crate::repository::{ProductRepository, Repository, RepositoryError};
+use crate::tax::assignment_tax;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Source of market prices for products, in ISK per unit
+pub trait PriceProvider {
+    /// Price a unit of the product sells for, if known
+    fn price(&self, product: &str) -> Option<f64>;
+
+    /// Buy and sell price of the product; providers with a single price use it for both
+    fn quote(&self, product: &str) -> Option<MarketQuote> {
+        self.price(product).map(|price| MarketQuote {
+            buy: Some(price),
+            sell: Some(price),
+        })
+    }
+}
+
+impl PriceProvider for HashMap<String, f64> {
+    fn price(&self, product: &str) -> Option<f64> {
+        self.get(product).copied()
+    }
+}
+
+/// Which side of the order book a product is priced from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSide {
+    #[default]
+    Sell, // Lowest sell order, what buying right away costs
+    Buy, // Highest buy order, what selling right away earns
+}
+
+/// Buy and sell price of a product, in ISK per unit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct MarketQuote {
+    #[serde(default)]
+    pub buy: Option<f64>,
+    #[serde(default)]
+    pub sell: Option<f64>,
+}
+
+/// A price snapshot entry: a bare price, or separate buy and sell prices
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+pub enum SnapshotEntry {
+    Price(f64),
+    Quote(MarketQuote),
+}
+
+/// Market prices fetched elsewhere, like by the frontend in a browser, keyed by product
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PriceSnapshot {
+    pub quotes: HashMap<String, MarketQuote>, // Product name to its prices
+    pub side: PriceSide,                      // Side prices are read from, the other as a fallback
+}
+
+impl PriceSnapshot {
+    /// Build a snapshot from entries keyed by product name, in-game name or type ID. Entries
+    /// that aren't planetary products are skipped; prices must not be negative.
+    pub fn from_entries(
+        products: &dyn ProductRepository,
+        entries: HashMap<String, SnapshotEntry>,
+    ) -> Result<Self, RepositoryError> {
+        let mut quotes = HashMap::new();
+        for (key, entry) in entries {
+            let quote = match entry {
+                SnapshotEntry::Price(price) => MarketQuote {
+                    buy: Some(price),
+                    sell: Some(price),
+                },
+                SnapshotEntry::Quote(quote) => quote,
+            };
+            if [quote.buy, quote.sell]
+                .into_iter()
+                .flatten()
+                .any(|p| p < 0.0)
+            {
+                return Err(RepositoryError::InvalidData(format!(
+                    "Negative price for {}",
+                    key
+                )));
+            }
+
+            let product = match key.trim().parse::<u32>() {
+                Ok(type_id) => products.get_product_by_type_id(type_id),
+                Err(_) => products.get_product_by_name(&key),
+            };
+            if let Some(product) = product {
+                quotes.insert(product.name, quote);
+            }
+        }
+        Ok(Self {
+            quotes,
+            side: PriceSide::default(),
+        })
+    }
+
+    /// Read prices from the other side of the order book
+    pub fn with_side(mut self, side: PriceSide) -> Self {
+        self.side = side;
+        self
+    }
+
+    /// Whether the snapshot prices nothing
+    pub fn is_empty(&self) -> bool {
+        self.quotes.is_empty()
+    }
+}
+
+impl PriceProvider for PriceSnapshot {
+    fn price(&self, product: &str) -> Option<f64> {
+        let quote = self.quotes.get(product)?;
+        match self.side {
+            PriceSide::Sell => quote.sell.or(quote.buy),
+            PriceSide::Buy => quote.buy.or(quote.sell),
+        }
+    }
+
+    fn quote(&self, product: &str) -> Option<MarketQuote> {
+        self.quotes.get(product).copied()
+    }
+}
+
+/// Sales tax charged on every sale with Accounting untrained
+pub const BASE_SALES_TAX: f64 = 0.075;
+
+/// Share of the sales tax each level of Accounting removes
+pub const ACCOUNTING_REDUCTION_PER_LEVEL: f64 = 0.11;
+
+/// Broker fee charged for placing a market order with Broker Relations untrained
+pub const BASE_BROKER_FEE: f64 = 0.03;
+
+/// Broker fee each level of Broker Relations removes
+pub const BROKER_RELATIONS_REDUCTION_PER_LEVEL: f64 = 0.003;
+
+/// How a trade goes through the market
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderType {
+    #[default]
+    Order, // Place an order and wait for it to fill, paying the broker fee
+    Immediate, // Fill against the other side of the order book right away
+}
+
+/// Trading skills and habits of the character selling the plan's output
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TradeSettings {
+    #[serde(default)]
+    pub accounting: u8, // Accounting skill level, lowering sales tax
+    #[serde(default)]
+    pub broker_relations: u8, // Broker Relations skill level, lowering broker fees
+    #[serde(default)]
+    pub sales: OrderType, // Sell orders or selling to buy orders
+    #[serde(default)]
+    pub purchases: OrderType, // Buy orders or buying from sell orders
+}
+
+impl TradeSettings {
+    /// Set the trading skill levels
+    pub fn with_skills(mut self, accounting: u8, broker_relations: u8) -> Self {
+        self.accounting = accounting.min(5);
+        self.broker_relations = broker_relations.min(5);
+        self
+    }
+
+    /// Choose how sales and purchases go through the market
+    pub fn with_order_types(mut self, sales: OrderType, purchases: OrderType) -> Self {
+        self.sales = sales;
+        self.purchases = purchases;
+        self
+    }
+
+    /// Share of every sale paid in sales tax
+    pub fn sales_tax_rate(&self) -> f64 {
+        BASE_SALES_TAX * (1.0 - ACCOUNTING_REDUCTION_PER_LEVEL * self.accounting.min(5) as f64)
+    }
+
+    /// Share of an order's value paid in broker fees when it's placed
+    pub fn broker_fee_rate(&self) -> f64 {
+        BASE_BROKER_FEE - BROKER_RELATIONS_REDUCTION_PER_LEVEL * self.broker_relations.min(5) as f64
+    }
+
+    /// Price a unit sells for: the lowest sell order when placing one, else the best buy order
+    pub fn sale_price(&self, quote: MarketQuote) -> Option<f64> {
+        match self.sales {
+            OrderType::Order => quote.sell.or(quote.buy),
+            OrderType::Immediate => quote.buy.or(quote.sell),
+        }
+    }
+
+    /// Price a unit costs: the best buy order when placing one, else the lowest sell order
+    pub fn purchase_price(&self, quote: MarketQuote) -> Option<f64> {
+        match self.purchases {
+            OrderType::Order => quote.buy.or(quote.sell),
+            OrderType::Immediate => quote.sell.or(quote.buy),
+        }
+    }
+}
+
+/// Fixed prices loaded from a JSON object mapping product names to ISK per unit
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StaticPrices {
+    pub prices: HashMap<String, f64>,
+}
+
+impl StaticPrices {
+    /// Load prices from JSON like `{ "coolant": 9500.0 }`, rejecting negative prices
+    pub fn from_json(json: &str) -> Result<Self, RepositoryError> {
+        let prices: HashMap<String, f64> = serde_json::from_str(json)
+            .map_err(|e| RepositoryError::DeserializationError(e.to_string()))?;
+        if let Some((product, price)) = prices.iter().find(|(_, price)| **price < 0.0) {
+            return Err(RepositoryError::InvalidData(format!(
+                "Invalid price {} for {}",
+                price, product
+            )));
+        }
+        Ok(Self { prices })
+    }
+
+    /// Set the price of a single product
+    pub fn with_price(mut self, product: &str, price: f64) -> Self {
+        self.prices.insert(product.to_string(), price);
+        self
+    }
+}
+
+impl PriceProvider for StaticPrices {
+    fn price(&self, product: &str) -> Option<f64> {
+        self.prices.price(product)
+    }
+}
+
+/// Hourly trade of a single product in a plan valuation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProductValue {
+    pub product: String,
+    pub units_per_hour: f64,
+    pub unit_price: f64, // ISK per unit, zero when unpriced
+    pub isk_per_hour: f64,
+}
+
+/// ISK a production plan makes once running, per hour
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlanValuation {
+    pub sales: Vec<ProductValue>,     // End products sold
+    pub purchases: Vec<ProductValue>, // Inputs bought because nothing in the plan makes them
+    pub revenue_per_hour: f64,
+    pub input_cost_per_hour: f64,
+    pub tax_per_hour: f64, // Customs import and export tax
+    #[serde(default)]
+    pub sales_tax_per_hour: f64,
+    #[serde(default)]
+    pub broker_fees_per_hour: f64, // Fees for placing sell and buy orders
+    pub net_isk_per_hour: f64,
+    pub unpriced: Vec<String>, // Products without a known price, valued at zero
+}
+
+/// Inputs of an assignment nothing in the plan makes, with the units bought per hour
+pub(crate) fn purchased_inputs(
+    repository: &dyn Repository,
+    plan: &ProductionPlan,
+    assignment: &PlanetAssignment,
+    units_per_hour: f64,
+) -> Vec<(String, f64)> {
+    let inputs = assignment_inputs_per_unit(repository, assignment);
+    assignment
+        .imported_inputs
+        .iter()
+        .filter(|input| !plan.assignments.iter().any(|a| a.produces(input)))
+        .map(|input| {
+            let per_unit = inputs.get(input).copied().unwrap_or(0.0);
+            (input.clone(), per_unit * units_per_hour)
+        })
+        .collect()
+}
+
+/// Value a plan at the given prices. Planets run as fast as their inputs arrive, end
+/// products are sold, inputs nothing in the plan makes are bought, and every planet pays
+/// customs tax on what it moves. Sales and purchases pay the market's sales tax and broker
+/// fees for the chosen order types. Intermediate surpluses are left unsold.
+pub fn value_plan(
+    repository: &dyn Repository,
+    plan: &ProductionPlan,
+    prices: &dyn PriceProvider,
+    trade: &TradeSettings,
+) -> PlanValuation {
+    let hourly = effective_hourly_output(repository, plan);
+    let consumed = |product: &str| {
+        plan.assignments
+            .iter()
+            .any(|a| a.imported_inputs.iter().any(|i| i == product))
+    };
+
+    let mut sold: BTreeMap<String, f64> = BTreeMap::new();
+    let mut bought: BTreeMap<String, f64> = BTreeMap::new();
+    let mut tax_per_hour = 0.0;
+    for (assignment, &units) in plan.assignments.iter().zip(&hourly) {
+        for output in &assignment.outputs {
+            if !consumed(output) {
+                *sold.entry(output.clone()).or_default() += units * assignment.output_share(output);
+            }
+        }
+
+        for (input, units) in purchased_inputs(repository, plan, assignment, units) {
+            *bought.entry(input).or_default() += units;
+        }
+
+        tax_per_hour += assignment_tax(repository, assignment, units * 24.0).daily_tax / 24.0;
+    }
+
+    let mut unpriced = Vec::new();
+    let mut value = |trades: BTreeMap<String, f64>, pick: &dyn Fn(MarketQuote) -> Option<f64>| {
+        trades
+            .into_iter()
+            .map(|(product, units_per_hour)| {
+                let unit_price = prices.quote(&product).and_then(pick).unwrap_or_else(|| {
+                    unpriced.push(product.clone());
+                    0.0
+                });
+                ProductValue {
+                    isk_per_hour: units_per_hour * unit_price,
+                    product,
+                    units_per_hour,
+                    unit_price,
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    let sales = value(sold, &|quote| trade.sale_price(quote));
+    let purchases = value(bought, &|quote| trade.purchase_price(quote));
+
+    let revenue_per_hour: f64 = sales.iter().map(|s| s.isk_per_hour).sum();
+    let input_cost_per_hour: f64 = purchases.iter().map(|p| p.isk_per_hour).sum();
+    let sales_tax_per_hour = revenue_per_hour * trade.sales_tax_rate();
+    let broker_fee = |order_type: OrderType, isk: f64| match order_type {
+        OrderType::Order => isk * trade.broker_fee_rate(),
+        OrderType::Immediate => 0.0,
+    };
+    let broker_fees_per_hour = broker_fee(trade.sales, revenue_per_hour)
+        + broker_fee(trade.purchases, input_cost_per_hour);
+    PlanValuation {
+        sales,
+        purchases,
+        revenue_per_hour,
+        input_cost_per_hour,
+        tax_per_hour,
+        sales_tax_per_hour,
+        broker_fees_per_hour,
+        net_isk_per_hour: revenue_per_hour
+            - input_cost_per_hour
+            - tax_per_hour
+            - sales_tax_per_hour
+            - broker_fees_per_hour,
+        unpriced,
+    }
+}
+
+/// Cost of making an intermediate within the plan against buying it off the market
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildOrBuy {
+    pub product: String,
+    pub units_per_hour: f64,      // Units the plan makes for its own use
+    pub planets: Vec<String>,     // Planets freed by buying it: its producers and their feeders
+    pub tax_per_hour: f64,        // Customs tax those planets pay
+    pub input_cost_per_hour: f64, // Inputs those planets buy
+    pub slot_cost_per_hour: f64,  // What those planets would earn running something else
+    pub build_cost_per_hour: f64,
+    pub buy_cost_per_hour: Option<f64>, // Unknown when the product has no price
+}
+
+impl BuildOrBuy {
+    /// Whether importing the intermediate costs less than making it
+    pub fn cheaper_to_buy(&self) -> bool {
+        self.buy_cost_per_hour
+            .is_some_and(|buy| buy < self.build_cost_per_hour)
+    }
+}
+
+/// For each intermediate a plan makes and consumes itself, compare building it against
+/// buying it. Building costs the customs tax and bought inputs of every planet that would
+/// be freed, plus `slot_isk_per_hour` for each of them: what a planet slot earns elsewhere,
+/// the plan's average net ISK per planet when `None`. Cheapest to buy first.
+pub fn buy_vs_build(
+    repository: &dyn Repository,
+    plan: &ProductionPlan,
+    prices: &dyn PriceProvider,
+    slot_isk_per_hour: Option<f64>,
+) -> Vec<BuildOrBuy> {
+    let hourly = effective_hourly_output(repository, plan);
+    let slot_isk_per_hour = slot_isk_per_hour.unwrap_or_else(|| {
+        let planets = plan.assignments.len().max(1) as f64;
+        (value_plan(repository, plan, prices, &TradeSettings::default()).net_isk_per_hour / planets)
+            .max(0.0)
+    });
+    let flows = if plan.flows.is_empty() {
+        map_import_sources(repository, &plan.assignments)
+    } else {
+        plan.flows.clone()
+    };
+
+    let mut intermediates: Vec<&String> = plan
+        .assignments
+        .iter()
+        .flat_map(|a| &a.imported_inputs)
+        .filter(|product| plan.assignments.iter().any(|a| a.produces(product)))
+        .collect();
+    intermediates.sort();
+    intermediates.dedup();
+
+    let mut comparisons: Vec<BuildOrBuy> = intermediates
+        .into_iter()
+        .map(|product| {
+            // Producers, then every planet shipping only into the ones already freed
+            let mut freed: Vec<usize> = (0..plan.assignments.len())
+                .filter(|&i| plan.assignments[i].produces(product))
+                .collect();
+            loop {
+                let feeders: Vec<usize> = (0..plan.assignments.len())
+                    .filter(|i| !freed.contains(i))
+                    .filter(|&i| {
+                        let mut outbound = flows
+                            .iter()
+                            .filter(|f| f.from_planet == plan.assignments[i].planet)
+                            .peekable();
+                        outbound.peek().is_some()
+                            && outbound.all(|f| {
+                                freed
+                                    .iter()
+                                    .any(|&j| plan.assignments[j].planet == f.to_planet)
+                            })
+                    })
+                    .collect();
+                if feeders.is_empty() {
+                    break;
+                }
+                freed.extend(feeders);
+            }
+
+            let units_per_hour: f64 = freed
+                .iter()
+                .filter(|&&i| plan.assignments[i].produces(product))
+                .map(|&i| hourly[i])
+                .sum();
+            let mut tax_per_hour = 0.0;
+            let mut input_cost_per_hour = 0.0;
+            for &i in &freed {
+                let assignment = &plan.assignments[i];
+                tax_per_hour +=
+                    assignment_tax(repository, assignment, hourly[i] * 24.0).daily_tax / 24.0;
+                input_cost_per_hour += purchased_inputs(repository, plan, assignment, hourly[i])
+                    .into_iter()
+                    .map(|(input, units)| units * prices.price(&input).unwrap_or(0.0))
+                    .sum::<f64>();
+            }
+            let slot_cost_per_hour = freed.len() as f64 * slot_isk_per_hour;
+
+            let mut planets: Vec<String> = freed
+                .iter()
+                .map(|&i| plan.assignments[i].planet.clone())
+                .collect();
+            planets.sort();
+            BuildOrBuy {
+                product: product.clone(),
+                units_per_hour,
+                planets,
+                tax_per_hour,
+                input_cost_per_hour,
+                slot_cost_per_hour,
+                build_cost_per_hour: tax_per_hour + input_cost_per_hour + slot_cost_per_hour,
+                buy_cost_per_hour: prices.price(product).map(|price| price * units_per_hour),
+            }
+        })
+        .collect();
+
+    let savings = |c: &BuildOrBuy| {
+        c.buy_cost_per_hour
+            .map(|buy| c.build_cost_per_hour - buy)
+            .unwrap_or(f64::NEG_INFINITY)
+    };
+    comparisons.sort_by(|a, b| savings(b).total_cmp(&savings(a)));
+    comparisons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::MemoryRepository;
+
+    #[test]
+    fn test_static_prices_from_json() {
+        let prices = StaticPrices::from_json(r#"{ "coolant": 9500.0, "water": 400 }"#).unwrap();
+        assert_eq!(prices.price("coolant"), Some(9500.0));
+        assert_eq!(prices.price("oxygen"), None);
+
+        assert!(matches!(
+            StaticPrices::from_json(r#"{ "coolant": -1 }"#),
+            Err(RepositoryError::InvalidData(_))
+        ));
+        assert!(matches!(
+            StaticPrices::from_json("not json"),
+            Err(RepositoryError::DeserializationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_price_snapshot() {
+        let repo = MemoryRepository::new();
+        let water = repo.get_product_by_name("water").unwrap();
+        let entries: HashMap<String, SnapshotEntry> = serde_json::from_str(&format!(
+            r#"{{
+                "{}": {{ "buy": 400, "sell": 450 }},
+                "Nano-Factory": 1200000,
+                "coolant": {{ "buy": 9000 }},
+                "34": 5.0
+            }}"#,
+            water.type_id
+        ))
+        .unwrap();
+        let snapshot = PriceSnapshot::from_entries(&repo, entries).unwrap();
+
+        // Tritanium (type 34) isn't a planetary product
+        assert_eq!(snapshot.quotes.len(), 3);
+        assert_eq!(snapshot.price("water"), Some(450.0));
+        assert_eq!(snapshot.price("nano_factory"), Some(1_200_000.0));
+        assert_eq!(snapshot.price("coolant"), Some(9000.0));
+        let snapshot = snapshot.with_side(PriceSide::Buy);
+        assert_eq!(snapshot.price("water"), Some(400.0));
+
+        let negative = HashMap::from([("water".to_string(), SnapshotEntry::Price(-1.0))]);
+        assert!(PriceSnapshot::from_entries(&repo, negative).is_err());
+    }
+
+    #[test]
+    fn test_value_plan() {
+        let repo = MemoryRepository::new();
+        let plan = ProductionPlan {
+            assignments: vec![PlanetAssignment::for_test(
+                "Character1",
+                "Barren1",
+                &["coolant"],
+                &["water", "electrolytes"],
+            )],
+            flows: Vec::new(),
+            schedule: None,
+        };
+        let prices = StaticPrices::default()
+            .with_price("coolant", 10_000.0)
+            .with_price("water", 500.0);
+        let valuation = value_plan(&repo, &plan, &prices, &TradeSettings::default());
+
+        // A P2 factory makes 50 coolant an hour from 400 of each P1
+        assert_eq!(valuation.sales.len(), 1);
+        assert!((valuation.sales[0].units_per_hour - 50.0).abs() < 1e-6);
+        assert!((valuation.revenue_per_hour - 500_000.0).abs() < 1e-6);
+        assert!((valuation.input_cost_per_hour - 400.0 * 500.0).abs() < 1e-6);
+        assert_eq!(valuation.unpriced, vec!["electrolytes".to_string()]);
+        assert!(valuation.tax_per_hour > 0.0);
+
+        // Untrained, both orders pay a 3% broker fee and the sale 7.5% sales tax
+        assert!((valuation.sales_tax_per_hour - 37_500.0).abs() < 1e-6);
+        assert!((valuation.broker_fees_per_hour - 21_000.0).abs() < 1e-6);
+        assert!(
+            (valuation.net_isk_per_hour
+                - (500_000.0 - 200_000.0 - valuation.tax_per_hour - 37_500.0 - 21_000.0))
+                .abs()
+                < 1e-6
+        );
+
+        // Filling immediately skips the broker, and Accounting V cuts sales tax by 55%
+        let trade = TradeSettings::default()
+            .with_skills(5, 5)
+            .with_order_types(OrderType::Immediate, OrderType::Immediate);
+        let valuation = value_plan(&repo, &plan, &prices, &trade);
+        assert_eq!(valuation.broker_fees_per_hour, 0.0);
+        assert!((valuation.sales_tax_per_hour - 500_000.0 * 0.03375).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_order_types_pick_quote_sides() {
+        let quote = MarketQuote {
+            buy: Some(90.0),
+            sell: Some(100.0),
+        };
+        let orders = TradeSettings::default();
+        assert_eq!(orders.sale_price(quote), Some(100.0));
+        assert_eq!(orders.purchase_price(quote), Some(90.0));
+
+        let immediate = orders.with_order_types(OrderType::Immediate, OrderType::Immediate);
+        assert_eq!(immediate.sale_price(quote), Some(90.0));
+        assert_eq!(immediate.purchase_price(quote), Some(100.0));
+        assert!((immediate.with_skills(0, 5).broker_fee_rate() - 0.015).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_buy_vs_build() {
+        let repo = MemoryRepository::new();
+        let mut water = PlanetAssignment::for_test("Character1", "Oceanic1", &["water"], &[]);
+        water.mined_inputs = vec!["aqueous_liquids".to_string()];
+        let plan = ProductionPlan {
+            assignments: vec![
+                water,
+                PlanetAssignment::for_test(
+                    "Character1",
+                    "Barren1",
+                    &["coolant"],
+                    &["water", "electrolytes"],
+                ),
+            ],
+            flows: Vec::new(),
+            schedule: None,
+        };
+
+        // Only water is both made and used within the plan
+        let cheap = StaticPrices::default().with_price("water", 1.0);
+        let comparisons = buy_vs_build(&repo, &plan, &cheap, Some(10_000.0));
+        assert_eq!(comparisons.len(), 1);
+        let water = &comparisons[0];
+        assert_eq!(water.planets, vec!["Oceanic1".to_string()]);
+        assert_eq!(water.slot_cost_per_hour, 10_000.0);
+        assert!(water.tax_per_hour > 0.0);
+        assert!(
+            (water.build_cost_per_hour
+                - water.tax_per_hour
+                - water.input_cost_per_hour
+                - water.slot_cost_per_hour)
+                .abs()
+                < 1e-6
+        );
+        assert!(water.cheaper_to_buy());
+
+        let dear = StaticPrices::default().with_price("water", 1_000_000.0);
+        assert!(!buy_vs_build(&repo, &plan, &dear, Some(10_000.0))[0].cheaper_to_buy());
+
+        // Without a price there's nothing to compare against
+        let unpriced = buy_vs_build(&repo, &plan, &StaticPrices::default(), None);
+        assert_eq!(unpriced[0].buy_cost_per_hour, None);
+        assert!(!unpriced[0].cheaper_to_buy());
+    }
+}
@@ -0,0 +1,29 @@
+use crate::domain::ProductionPlan;
+use serde::{Deserialize, Serialize};
+
+/// Why a lower-priority target was left out of a plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DropReason {
+    /// The product doesn't exist
+    UnknownProduct,
+    /// The product can't be planned even on its own
+    Infeasible,
+    /// The product fits on its own, but not alongside the higher-priority targets
+    OutOfCapacity,
+}
+
+/// A target that didn't make it into the plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DroppedTarget {
+    pub product: String,
+    pub priority: usize, // Position in the requested list, 0 being the most important
+    pub reason: DropReason,
+}
+
+/// Plan for as many targets as fit, taken in priority order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrioritizedPlan {
+    pub plan: ProductionPlan,
+    pub satisfied: Vec<String>, // Targets in the plan, in priority order
+    pub dropped: Vec<DroppedTarget>,
+}
@@ -0,0 +1,216 @@
+use crate::balance::effective_hourly_output;
+use crate::domain::ProductionPlan;
+use crate::pricing::{purchased_inputs, OrderType, PriceProvider, TradeSettings};
+use crate::quantity::assignment_inputs_per_unit;
+use crate::repository::Repository;
+use crate::tax::assignment_tax;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// ISK a single planet adds per hour. Intermediates change hands at market prices, so a
+/// planet is credited with the value of what it makes and charged for what it consumes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanetProfit {
+    pub planet: String,
+    pub character: String,
+    pub outputs: Vec<String>, // Products made on the planet, main output first
+    pub units_per_hour: f64,
+    pub output_value_per_hour: f64, // Market value of what the planet makes
+    pub input_cost_per_hour: f64,   // Market value of what the planet consumes
+    pub tax_per_hour: f64,          // Customs import and export tax
+    pub fees_per_hour: f64,         // Sales tax and broker fees on its market trades
+    pub net_isk_per_hour: f64,
+}
+
+/// ISK a character's planets add per hour
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterProfit {
+    pub character: String,
+    pub planets: usize,
+    pub net_isk_per_hour: f64,
+}
+
+/// A valued plan broken down by planet and by character, most profitable first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfitabilityReport {
+    pub planets: Vec<PlanetProfit>,
+    pub characters: Vec<CharacterProfit>,
+}
+
+impl ProfitabilityReport {
+    /// Planets adding less than a given ISK per hour, least profitable first
+    pub fn marginal_planets(&self, below_isk_per_hour: f64) -> Vec<&PlanetProfit> {
+        let mut marginal: Vec<&PlanetProfit> = self
+            .planets
+            .iter()
+            .filter(|p| p.net_isk_per_hour < below_isk_per_hour)
+            .collect();
+        marginal.reverse();
+        marginal
+    }
+}
+
+/// Break a plan's ISK per hour down by planet and character. End products pay sales tax
+/// and broker fees as in a plan valuation; unpriced products count as worthless.
+pub fn profitability_report(
+    repository: &dyn Repository,
+    plan: &ProductionPlan,
+    prices: &dyn PriceProvider,
+    trade: &TradeSettings,
+) -> ProfitabilityReport {
+    let hourly = effective_hourly_output(repository, plan);
+    let consumed = |product: &str| {
+        plan.assignments
+            .iter()
+            .any(|a| a.imported_inputs.iter().any(|i| i == product))
+    };
+    let sale_price = |product: &str| {
+        prices
+            .quote(product)
+            .and_then(|q| trade.sale_price(q))
+            .unwrap_or(0.0)
+    };
+    let purchase_price = |product: &str| {
+        prices
+            .quote(product)
+            .and_then(|q| trade.purchase_price(q))
+            .unwrap_or(0.0)
+    };
+    let broker_fee = |order_type: OrderType, isk: f64| match order_type {
+        OrderType::Order => isk * trade.broker_fee_rate(),
+        OrderType::Immediate => 0.0,
+    };
+
+    let mut planets: Vec<PlanetProfit> = plan
+        .assignments
+        .iter()
+        .zip(&hourly)
+        .map(|(assignment, &units)| {
+            let value_of =
+                |output: &String| units * assignment.output_share(output) * sale_price(output);
+            let output_value_per_hour: f64 = assignment.outputs.iter().map(value_of).sum();
+
+            // Bought inputs at purchase prices, intermediates at what their makers sell for
+            let bought = purchased_inputs(repository, plan, assignment, units);
+            let bought_cost: f64 = bought
+                .iter()
+                .map(|(input, units)| units * purchase_price(input))
+                .sum();
+            let inputs = assignment_inputs_per_unit(repository, assignment);
+            let intermediate_cost: f64 = assignment
+                .imported_inputs
+                .iter()
+                .filter(|input| !bought.iter().any(|(b, _)| b == *input))
+                .map(|input| inputs.get(input).copied().unwrap_or(0.0) * units * sale_price(input))
+                .sum();
+
+            let tax_per_hour =
+                assignment_tax(repository, assignment, units * 24.0).daily_tax / 24.0;
+            let mut fees_per_hour = broker_fee(trade.purchases, bought_cost);
+            let sold_value_per_hour: f64 = assignment
+                .outputs
+                .iter()
+                .filter(|output| !consumed(output))
+                .map(value_of)
+                .sum();
+            fees_per_hour += sold_value_per_hour * trade.sales_tax_rate()
+                + broker_fee(trade.sales, sold_value_per_hour);
+
+            let input_cost_per_hour = bought_cost + intermediate_cost;
+            PlanetProfit {
+                planet: assignment.planet.clone(),
+                character: assignment.character.clone(),
+                outputs: assignment.outputs.clone(),
+                units_per_hour: units,
+                output_value_per_hour,
+                input_cost_per_hour,
+                tax_per_hour,
+                fees_per_hour,
+                net_isk_per_hour: output_value_per_hour
+                    - input_cost_per_hour
+                    - tax_per_hour
+                    - fees_per_hour,
+            }
+        })
+        .collect();
+    planets.sort_by(|a, b| b.net_isk_per_hour.total_cmp(&a.net_isk_per_hour));
+
+    let mut by_character: BTreeMap<&str, (usize, f64)> = BTreeMap::new();
+    for planet in &planets {
+        let entry = by_character.entry(&planet.character).or_default();
+        entry.0 += 1;
+        entry.1 += planet.net_isk_per_hour;
+    }
+    let mut characters: Vec<CharacterProfit> = by_character
+        .into_iter()
+        .map(|(character, (planets, net_isk_per_hour))| CharacterProfit {
+            character: character.to_string(),
+            planets,
+            net_isk_per_hour,
+        })
+        .collect();
+    characters.sort_by(|a, b| b.net_isk_per_hour.total_cmp(&a.net_isk_per_hour));
+
+    ProfitabilityReport {
+        planets,
+        characters,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::PlanetAssignment;
+    use crate::pricing::{value_plan, StaticPrices};
+    use crate::repository::MemoryRepository;
+
+    #[test]
+    fn test_profitability_report() {
+        let repo = MemoryRepository::new();
+        let mut water = PlanetAssignment::for_test("Miner", "Oceanic1", &["water"], &[]);
+        water.mined_inputs = vec!["aqueous_liquids".to_string()];
+        let plan = ProductionPlan {
+            assignments: vec![
+                water,
+                PlanetAssignment::for_test(
+                    "Builder",
+                    "Barren1",
+                    &["coolant"],
+                    &["water", "electrolytes"],
+                ),
+            ],
+            flows: Vec::new(),
+            schedule: None,
+        };
+        let prices = StaticPrices::default()
+            .with_price("coolant", 20_000.0)
+            .with_price("water", 500.0)
+            .with_price("electrolytes", 500.0);
+        let trade = TradeSettings::default();
+        let report = profitability_report(&repo, &plan, &prices, &trade);
+
+        assert_eq!(report.planets.len(), 2);
+        assert_eq!(report.characters.len(), 2);
+        let water = report
+            .planets
+            .iter()
+            .find(|p| p.outputs == ["water"])
+            .unwrap();
+        assert_eq!(water.character, "Miner");
+        assert_eq!(water.input_cost_per_hour, 0.0);
+        assert_eq!(water.fees_per_hour, 0.0);
+        assert!(water.net_isk_per_hour > 0.0);
+
+        // Intermediates cancel out, so the planets add up to the plan's net ISK
+        let valuation = value_plan(&repo, &plan, &prices, &trade);
+        let total: f64 = report.planets.iter().map(|p| p.net_isk_per_hour).sum();
+        assert!((total - valuation.net_isk_per_hour).abs() < 1e-6);
+        let by_character: f64 = report.characters.iter().map(|c| c.net_isk_per_hour).sum();
+        assert!((by_character - total).abs() < 1e-6);
+
+        // Sorted most profitable first, and the marginal list starts from the bottom
+        assert!(report.planets[0].net_isk_per_hour >= report.planets[1].net_isk_per_hour);
+        let marginal = report.marginal_planets(f64::INFINITY);
+        assert_eq!(marginal[0].planet, report.planets[1].planet);
+    }
+}
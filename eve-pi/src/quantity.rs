@@ -0,0 +1,933 @@
+use crate::domain::{Planet, PlanetAssignment, ProductTier, ProductionPlan, COMMAND_CENTER_CPU};
+use crate::intern::{ProductId, Recipe, Recipes};
+use crate::repository::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// P0 units a single planet's extractors yield per day
+pub const DAILY_EXTRACTION_PER_PLANET: f64 = 100_000.0;
+
+/// Most heads a single extractor control unit can run
+pub const MAX_EXTRACTOR_HEADS: usize = 10;
+
+/// P0 units a single extractor head yields per hour, so a planet's full extraction takes
+/// one fully headed extractor
+pub const HEAD_HOURLY_YIELD: f64 = DAILY_EXTRACTION_PER_PLANET / 24.0 / MAX_EXTRACTOR_HEADS as f64;
+
+/// Extractor program length the daily extraction rate assumes
+pub const DEFAULT_PROGRAM_HOURS: f64 = 24.0;
+
+/// Shortest and longest extractor programs the game allows
+pub const MIN_PROGRAM_HOURS: f64 = 1.0;
+pub const MAX_PROGRAM_HOURS: f64 = 336.0;
+
+/// How strongly a longer program spreads the same deposit over more hours, lowering the
+/// hourly rate
+const PROGRAM_LENGTH_EXPONENT: f64 = 0.35;
+
+/// Hourly decay of an extractor's yield over the course of a program as its deposit depletes
+const DEPLETION_PER_HOUR: f64 = 1.0 / MAX_PROGRAM_HOURS;
+
+/// Extraction rates of an extractor program relative to the daily extraction rate
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProgramYield {
+    pub program_hours: f64,
+    pub cycle_hours: f64,    // Length of each extraction cycle in the program
+    pub peak_factor: f64,    // Hourly rate at the start of the program
+    pub average_factor: f64, // Hourly rate averaged over the whole program
+}
+
+/// Extraction cycle length of a program: 15 minutes, doubling each time the program
+/// passes 25, 50, 100 and 200 hours
+pub fn program_cycle_hours(program_hours: f64) -> f64 {
+    let mut cycle = 0.25;
+    let mut limit = 25.0;
+    while program_hours > limit && cycle < 4.0 {
+        cycle *= 2.0;
+        limit *= 2.0;
+    }
+    cycle
+}
+
+/// Peak and average yield of an extractor program of a given length. Short programs run
+/// hot and need resetting often; long ones deplete their deposit over many days and
+/// average far less per hour than their first cycles suggest.
+pub fn program_yield(program_hours: f64) -> ProgramYield {
+    let program_hours = program_hours.clamp(MIN_PROGRAM_HOURS, MAX_PROGRAM_HOURS);
+    let decay = |hours: f64| {
+        let depletion = DEPLETION_PER_HOUR * hours;
+        (1.0 - (-depletion).exp()) / depletion
+    };
+
+    let length_factor = (program_hours / DEFAULT_PROGRAM_HOURS).powf(-PROGRAM_LENGTH_EXPONENT);
+    let average_factor = length_factor * decay(program_hours) / decay(DEFAULT_PROGRAM_HOURS);
+    ProgramYield {
+        program_hours,
+        cycle_hours: program_cycle_hours(program_hours),
+        peak_factor: average_factor / decay(program_hours),
+        average_factor,
+    }
+}
+
+/// Units a fully built factory planet outputs per day, limited by its facilities
+pub fn factory_daily_output(tier: ProductTier) -> f64 {
+    match tier {
+        ProductTier::P0 => DAILY_EXTRACTION_PER_PLANET,
+        _ => facilities_per_planet(tier) as f64 * facility_daily_output(tier),
+    }
+}
+
+/// Hours a facility making a product of the given tier takes per production cycle
+pub fn cycle_hours(tier: ProductTier) -> f64 {
+    match tier {
+        ProductTier::P1 => 0.5,
+        _ => 1.0,
+    }
+}
+
+/// Units a facility outputs per production cycle, following the standard schematics
+pub fn units_per_cycle(tier: ProductTier) -> f64 {
+    match tier {
+        ProductTier::P0 => 0.0,
+        ProductTier::P1 => 20.0,
+        ProductTier::P2 => 5.0,
+        ProductTier::P3 => 3.0,
+        ProductTier::P4 => 1.0,
+    }
+}
+
+/// Processing facilities a fully built factory planet runs for a tier
+pub fn facilities_per_planet(tier: ProductTier) -> usize {
+    match tier {
+        ProductTier::P0 => 0,
+        ProductTier::P1 => 12,
+        ProductTier::P2 => 10,
+        ProductTier::P3 => 6,
+        ProductTier::P4 => 2,
+    }
+}
+
+/// Units a single processing facility outputs per hour
+pub fn facility_hourly_output(tier: ProductTier) -> f64 {
+    match tier {
+        ProductTier::P0 => DAILY_EXTRACTION_PER_PLANET / 24.0,
+        _ => units_per_cycle(tier) / cycle_hours(tier),
+    }
+}
+
+/// Units a single processing facility outputs per day
+pub fn facility_daily_output(tier: ProductTier) -> f64 {
+    facility_hourly_output(tier) * 24.0
+}
+
+/// Units of each imported and mined input needed per unit of output.
+/// Ingredients that are neither imported nor mined are assumed to be built on the planet.
+pub fn inputs_per_unit(
+    repository: &dyn Repository,
+    product: &str,
+    imported_inputs: &[String],
+    mined_inputs: &[String],
+) -> HashMap<String, f64> {
+    let recipes = repository.recipes();
+    let walk = InputWalk::new(&recipes, imported_inputs.iter().chain(mined_inputs));
+    let mut totals = InputTotals::default();
+    if let Some((_, recipe)) = recipes.find(product) {
+        walk.accumulate(recipe, 1.0, &mut totals);
+    }
+    totals.by_name(&recipes)
+}
+
+/// Units of each imported and mined input needed per unit of an assignment's output. A
+/// planet making several products splits its output evenly between them.
+pub fn assignment_inputs_per_unit(
+    repository: &dyn Repository,
+    assignment: &PlanetAssignment,
+) -> HashMap<String, f64> {
+    let recipes = repository.recipes();
+    let boundary = assignment
+        .imported_inputs
+        .iter()
+        .chain(&assignment.mined_inputs);
+    let walk = InputWalk::new(&recipes, boundary);
+    let mut totals = InputTotals::default();
+    let share = 1.0 / assignment.outputs.len().max(1) as f64;
+    for output in &assignment.outputs {
+        if let Some((_, recipe)) = recipes.find(output) {
+            walk.accumulate(recipe, share, &mut totals);
+        }
+    }
+    totals.by_name(&recipes)
+}
+
+/// Units of each boundary input a recipe walk reached
+#[derive(Debug, Default)]
+pub(crate) struct InputTotals {
+    pub products: HashMap<ProductId, f64>,
+    unknown: HashMap<String, f64>, // Boundary inputs that aren't products
+}
+
+impl InputTotals {
+    /// Totals keyed by product key
+    fn by_name(self, recipes: &Recipes) -> HashMap<String, f64> {
+        let mut inputs = self.unknown;
+        inputs.extend(
+            self.products
+                .into_iter()
+                .map(|(id, units)| (recipes.ids().name(id).to_string(), units)),
+        );
+        inputs
+    }
+}
+
+/// Walks recipes by ID down to the imported and mined inputs, treating every ingredient
+/// above them as built on the planet
+pub(crate) struct InputWalk<'a> {
+    recipes: &'a Recipes,
+    boundary: Vec<ProductId>,
+    unknown_boundary: Vec<&'a str>, // Boundary inputs that aren't products, matched by name
+}
+
+impl<'a> InputWalk<'a> {
+    /// Walk stopping at a set of product keys
+    pub fn new(recipes: &'a Recipes, boundary: impl IntoIterator<Item = &'a String>) -> Self {
+        let mut walk = Self::from_ids(recipes, Vec::new());
+        for input in boundary {
+            match recipes.ids().id(input) {
+                Some(id) => walk.boundary.push(id),
+                None => walk.unknown_boundary.push(input),
+            }
+        }
+        walk
+    }
+
+    /// Walk stopping at a set of products
+    pub fn from_ids(recipes: &'a Recipes, boundary: Vec<ProductId>) -> Self {
+        Self {
+            recipes,
+            boundary,
+            unknown_boundary: Vec::new(),
+        }
+    }
+
+    /// Add the boundary inputs needed to make a number of units of a recipe
+    pub fn accumulate(&self, recipe: &Recipe, units: f64, totals: &mut InputTotals) {
+        for (ingredient, per_unit) in &recipe.inputs {
+            let needed = units * per_unit;
+
+            if self.boundary.contains(ingredient) {
+                *totals.products.entry(*ingredient).or_insert(0.0) += needed;
+            } else if let Some(input) = self.recipes.get(*ingredient) {
+                self.accumulate(input, needed, totals);
+            }
+        }
+
+        for (ingredient, per_unit) in &recipe.unknown_inputs {
+            if self.unknown_boundary.contains(&ingredient.as_str()) {
+                *totals.unknown.entry(ingredient.clone()).or_insert(0.0) += units * per_unit;
+            }
+        }
+    }
+}
+
+/// How much faster than average a planet's deposits yield the mined inputs of a product.
+/// Extractor heads are shared, so poor deposits slow down everything they're mined with.
+pub fn richness_factor(
+    repository: &dyn Repository,
+    planet: &Planet,
+    output: &str,
+    imported_inputs: &[String],
+    mined_inputs: &[String],
+) -> f64 {
+    let inputs = inputs_per_unit(repository, output, imported_inputs, mined_inputs);
+    mined_richness(planet, &inputs, mined_inputs)
+}
+
+fn mined_richness(planet: &Planet, inputs: &HashMap<String, f64>, mined_inputs: &[String]) -> f64 {
+    let (needed, head_time) = mined_inputs
+        .iter()
+        .filter_map(|m| inputs.get(m).map(|units| (m, units)))
+        .fold((0.0, 0.0), |(needed, head_time), (resource, units)| {
+            (
+                needed + units,
+                head_time + units / planet.richness_of(resource),
+            )
+        });
+    if needed > 0.0 {
+        needed / head_time
+    } else {
+        1.0
+    }
+}
+
+/// Units a planet running this assignment can output per day, with extraction scaled by
+/// the richness of the planet's space and of its scanned deposits, by how well its character
+/// scans, and by its program length. A planet making several products splits this evenly
+/// between them.
+pub fn assignment_daily_output(repository: &dyn Repository, assignment: &PlanetAssignment) -> f64 {
+    let inputs = assignment_inputs_per_unit(repository, assignment);
+    let extraction_yield = repository
+        .get_planet_ref(&assignment.planet)
+        .map(|planet| {
+            planet.extraction_yield() * mined_richness(planet, &inputs, &assignment.mined_inputs)
+        })
+        .unwrap_or(1.0);
+    let deposit_factor = repository
+        .get_character_ref(&assignment.character)
+        .map(|character| character.deposit_factor())
+        .unwrap_or(1.0);
+    let program = program_yield(assignment.program_hours.unwrap_or(DEFAULT_PROGRAM_HOURS));
+    let daily_extraction =
+        DAILY_EXTRACTION_PER_PLANET * extraction_yield * deposit_factor * program.average_factor;
+
+    // The facilities are shared, so the slowest product limits the whole planet
+    let mut factory_limit = f64::INFINITY;
+    for output in &assignment.outputs {
+        let Some(product) = repository.get_product_ref(output) else {
+            return 0.0;
+        };
+        factory_limit = factory_limit.min(match product.tier {
+            ProductTier::P0 => daily_extraction,
+            tier => factory_daily_output(tier),
+        });
+    }
+    if !factory_limit.is_finite() {
+        return 0.0;
+    }
+    extraction_limited(
+        factory_limit,
+        &inputs,
+        &assignment.mined_inputs,
+        daily_extraction,
+    )
+}
+
+/// Units of a product a planet with the given imports and mined resources can output per day
+pub fn daily_output(
+    repository: &dyn Repository,
+    output: &str,
+    imported_inputs: &[String],
+    mined_inputs: &[String],
+) -> f64 {
+    planet_daily_output(
+        repository,
+        output,
+        imported_inputs,
+        mined_inputs,
+        DAILY_EXTRACTION_PER_PLANET,
+    )
+}
+
+/// Units of a product a planet can output per day when its extractors yield a given number
+/// of P0 units per day
+pub fn planet_daily_output(
+    repository: &dyn Repository,
+    output: &str,
+    imported_inputs: &[String],
+    mined_inputs: &[String],
+    daily_extraction: f64,
+) -> f64 {
+    let Some(product) = repository.get_product_ref(output) else {
+        return 0.0;
+    };
+
+    let factory_limit = match product.tier {
+        ProductTier::P0 => daily_extraction,
+        tier => factory_daily_output(tier),
+    };
+    if mined_inputs.is_empty() {
+        return factory_limit;
+    }
+
+    let inputs = inputs_per_unit(repository, output, imported_inputs, mined_inputs);
+    extraction_limited(factory_limit, &inputs, mined_inputs, daily_extraction)
+}
+
+/// Extraction-bound: the planet can only mine so much P0 per day
+fn extraction_limited(
+    factory_limit: f64,
+    inputs: &HashMap<String, f64>,
+    mined_inputs: &[String],
+    daily_extraction: f64,
+) -> f64 {
+    let mined_per_unit: f64 = mined_inputs.iter().filter_map(|m| inputs.get(m)).sum();
+    if mined_per_unit > 0.0 {
+        factory_limit.min(daily_extraction / mined_per_unit)
+    } else {
+        factory_limit
+    }
+}
+
+/// Share of a fully built colony's facilities a command center can power at a given
+/// Command Center Upgrades level, based on its CPU output
+pub fn command_center_factor(level: u8) -> f64 {
+    COMMAND_CENTER_CPU[(level as usize).min(5)] as f64 / COMMAND_CENTER_CPU[5] as f64
+}
+
+/// Units a planet outputs per day once the owning character's command center skill is
+/// taken into account
+pub fn character_daily_output(repository: &dyn Repository, assignment: &PlanetAssignment) -> f64 {
+    let factor = repository
+        .get_character_ref(&assignment.character)
+        .map(|c| command_center_factor(c.skills.command_center_upgrades))
+        .unwrap_or(1.0);
+    assignment_daily_output(repository, assignment) * factor
+}
+
+/// How many times over the target quantities a plan can supply per day, limited by its
+/// slowest product. `output` gives the daily units of each planet.
+pub fn achievable_scale(
+    repository: &dyn Repository,
+    assignments: &[PlanetAssignment],
+    targets: &[(&str, f64)],
+    output: impl Fn(&PlanetAssignment) -> f64,
+) -> f64 {
+    // Propagate demand through one assignment per product, since copies share the load
+    let mut templates: Vec<PlanetAssignment> = Vec::new();
+    for assignment in assignments {
+        if !templates.iter().any(|t| t.output() == assignment.output()) {
+            templates.push(assignment.clone());
+        }
+    }
+    let demand = propagate_demand(repository, &templates, targets);
+
+    // Purchased inputs have no producers and never limit the plan
+    let scale = demand
+        .iter()
+        .filter(|(product, units)| **units > 0.0 && assignments.iter().any(|a| a.produces(product)))
+        .map(|(product, units)| {
+            // Planets making several products split their facilities between them
+            let supply: f64 = assignments
+                .iter()
+                .filter(|a| a.produces(product))
+                .map(|a| output(a) / a.outputs.len() as f64)
+                .sum();
+            supply / units
+        })
+        .fold(f64::INFINITY, f64::min);
+
+    if scale.is_finite() {
+        scale
+    } else {
+        0.0
+    }
+}
+
+/// Whether a planet is primarily an extraction planet or a factory planet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlanetRole {
+    Extraction,
+    Factory,
+}
+
+/// How many planets a product needs to hit a daily quota
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductRequirement {
+    pub product: String,
+    pub tier: ProductTier,
+    pub role: PlanetRole,
+    pub daily_units: f64,             // Units required per day
+    pub daily_output_per_planet: f64, // Units one planet outputs per day
+    pub planets_required: usize,
+    pub planets_assigned: usize,
+}
+
+/// A daily quantity requested for an end product
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaTarget {
+    pub product: String,
+    pub units_per_day: f64,
+}
+
+/// A production plan sized to produce target quantities per day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaPlan {
+    pub targets: Vec<QuotaTarget>,
+    pub plan: ProductionPlan,
+    pub requirements: Vec<ProductRequirement>,
+    pub extraction_planets: usize,
+    pub factory_planets: usize,
+    pub purchased_inputs: Vec<QuotaTarget>, // Inputs bought from the market instead of produced
+    pub schedule: Vec<PhasedDemand>, // When each product must come online, if a stockpile was given
+    pub sufficient: bool, // Whether the planet and character pool covers every requirement
+}
+
+/// Daily demand for every product in a plan, propagated down from the targets
+pub fn propagate_demand(
+    repository: &dyn Repository,
+    assignments: &[PlanetAssignment],
+    targets: &[(&str, f64)],
+) -> HashMap<String, f64> {
+    let mut demand: HashMap<String, f64> = HashMap::new();
+    for (target, units_per_day) in targets {
+        *demand.entry(target.to_string()).or_insert(0.0) += units_per_day;
+    }
+
+    // Consumers always sit at a higher tier than their imports, so walk tiers downwards
+    let mut ordered: Vec<&PlanetAssignment> = assignments.iter().collect();
+    ordered.sort_by_key(|a| {
+        std::cmp::Reverse(
+            repository
+                .get_product_ref(a.output())
+                .map(|p| p.tier)
+                .unwrap_or(ProductTier::P0),
+        )
+    });
+
+    for assignment in ordered {
+        for output in &assignment.outputs {
+            let output_demand = demand.get(output).copied().unwrap_or(0.0);
+            if output_demand <= 0.0 {
+                continue;
+            }
+
+            let inputs = inputs_per_unit(
+                repository,
+                output,
+                &assignment.imported_inputs,
+                &assignment.mined_inputs,
+            );
+            for imported in &assignment.imported_inputs {
+                if let Some(per_unit) = inputs.get(imported) {
+                    *demand.entry(imported.clone()).or_insert(0.0) += per_unit * output_demand;
+                }
+            }
+        }
+    }
+
+    demand
+}
+
+/// Units already on hand, consumed before new production is needed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stockpile {
+    pub inventory: HashMap<String, f64>, // Product name to units on hand
+    pub horizon_days: f64,               // Planning horizon the stockpile is spread over
+}
+
+/// When a product's factories need to come online, given the stockpile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhasedDemand {
+    pub product: String,
+    pub units_on_hand: f64,
+    pub daily_units: f64, // Units per day consumed once downstream production runs
+    pub online_after_days: f64, // Days until the stockpile runs out and production must start
+    pub units_to_produce: f64, // Units to produce within the horizon after using the stockpile
+}
+
+/// Time-phased demand for every product in a plan. Stock of a product delays the day its
+/// factories must come online, which in turn delays demand on everything upstream of it.
+pub fn phase_demand(
+    repository: &dyn Repository,
+    assignments: &[PlanetAssignment],
+    targets: &[(&str, f64)],
+    stockpile: &Stockpile,
+) -> HashMap<String, PhasedDemand> {
+    let mut rates: HashMap<String, f64> = HashMap::new();
+    let mut needed_from: HashMap<String, f64> = HashMap::new();
+    for (target, units_per_day) in targets {
+        *rates.entry(target.to_string()).or_insert(0.0) += units_per_day;
+        needed_from.insert(target.to_string(), 0.0);
+    }
+
+    let mut phased: HashMap<String, PhasedDemand> = HashMap::new();
+
+    // Consumers always sit at a higher tier than their imports, so walk tiers downwards
+    let mut ordered: Vec<&PlanetAssignment> = assignments.iter().collect();
+    ordered.sort_by_key(|a| {
+        std::cmp::Reverse(
+            repository
+                .get_product_ref(a.output())
+                .map(|p| p.tier)
+                .unwrap_or(ProductTier::P0),
+        )
+    });
+
+    for assignment in ordered {
+        let demand = phase_product(assignment.output(), &rates, &needed_from, stockpile);
+        let online_after_days = demand.online_after_days;
+        phased.insert(assignment.output().to_string(), demand);
+        if online_after_days >= stockpile.horizon_days {
+            continue; // Covered by stock for the whole horizon, so no upstream demand
+        }
+
+        let output_rate = rates.get(assignment.output()).copied().unwrap_or(0.0);
+        let inputs = inputs_per_unit(
+            repository,
+            assignment.output(),
+            &assignment.imported_inputs,
+            &assignment.mined_inputs,
+        );
+        for imported in &assignment.imported_inputs {
+            if let Some(per_unit) = inputs.get(imported) {
+                *rates.entry(imported.clone()).or_insert(0.0) += per_unit * output_rate;
+                let needed = needed_from
+                    .entry(imported.clone())
+                    .or_insert(online_after_days);
+                *needed = needed.min(online_after_days);
+            }
+        }
+    }
+
+    // Purchased inputs have no assignment of their own but still draw down the stockpile
+    let unvisited: Vec<String> = rates
+        .keys()
+        .filter(|product| !phased.contains_key(*product))
+        .cloned()
+        .collect();
+    for product in unvisited {
+        let demand = phase_product(&product, &rates, &needed_from, stockpile);
+        phased.insert(product, demand);
+    }
+
+    phased
+}
+
+fn phase_product(
+    product: &str,
+    rates: &HashMap<String, f64>,
+    needed_from: &HashMap<String, f64>,
+    stockpile: &Stockpile,
+) -> PhasedDemand {
+    let daily_units = rates.get(product).copied().unwrap_or(0.0);
+    let start = needed_from
+        .get(product)
+        .copied()
+        .unwrap_or(stockpile.horizon_days);
+    let units_on_hand = stockpile.inventory.get(product).copied().unwrap_or(0.0);
+
+    let online_after_days = if daily_units > 0.0 {
+        start + units_on_hand / daily_units
+    } else {
+        f64::INFINITY
+    };
+    let units_to_produce =
+        (daily_units * (stockpile.horizon_days - start) - units_on_hand).max(0.0);
+
+    PhasedDemand {
+        product: product.to_string(),
+        units_on_hand,
+        daily_units,
+        online_after_days,
+        units_to_produce,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Planet, PlanetType, Schematic};
+    use crate::repository::{CharacterRepository, MemoryRepository, ProductRepository};
+
+    fn assignment(output: &str, imported: &[&str], mined: &[&str]) -> PlanetAssignment {
+        PlanetAssignment {
+            character: "Character1".to_string(),
+            planet: format!("{}_planet", output),
+            planet_type: PlanetType::Barren,
+            imported_inputs: imported.iter().map(|s| s.to_string()).collect(),
+            mined_inputs: mined.iter().map(|s| s.to_string()).collect(),
+            outputs: vec![output.to_string()],
+            program_hours: None,
+            command_center_level: None,
+            import_m3_per_hour: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_cycle_rates() {
+        assert_eq!(facility_hourly_output(ProductTier::P1), 40.0);
+        assert_eq!(facility_hourly_output(ProductTier::P2), 5.0);
+        assert_eq!(facility_hourly_output(ProductTier::P3), 3.0);
+        assert_eq!(facility_hourly_output(ProductTier::P4), 1.0);
+
+        assert_eq!(factory_daily_output(ProductTier::P1), 11_520.0);
+        assert_eq!(factory_daily_output(ProductTier::P2), 1_200.0);
+        assert_eq!(factory_daily_output(ProductTier::P3), 432.0);
+        assert_eq!(factory_daily_output(ProductTier::P4), 48.0);
+
+        // A P2 facility's hourly cycle consumes what one P1 facility makes in two cycles
+        let repo = MemoryRepository::new();
+        let coolant = repo.get_product_by_name("coolant").unwrap();
+        let p1_per_p2_cycle = coolant.schematic.quantity_of("water").unwrap() as f64;
+        assert_eq!(p1_per_p2_cycle, facility_hourly_output(ProductTier::P1));
+    }
+
+    #[test]
+    fn test_schematic_quantities() {
+        let mut repo = MemoryRepository::new();
+
+        // Every manufactured product has a schematic with exact quantities
+        for product in repo.get_all_products() {
+            assert_eq!(
+                product.schematic.inputs.is_empty(),
+                product.tier == ProductTier::P0
+            );
+            assert_eq!(
+                product.schematic.output_quantity == 0,
+                product.tier == ProductTier::P0
+            );
+        }
+        let nano_factory = repo.get_product_by_name("nano_factory").unwrap();
+        assert_eq!(
+            nano_factory.schematic.quantity_of("reactive_metals"),
+            Some(40)
+        );
+        assert_eq!(
+            nano_factory.schematic.quantity_of("ukomi_super_conductors"),
+            Some(6)
+        );
+        assert_eq!(nano_factory.schematic.quantity_of("water"), None);
+
+        // Input needs follow the schematic, so a cheaper recipe needs less
+        let inputs = inputs_per_unit(&repo, "coolant", &["water".to_string()], &[]);
+        assert_eq!(inputs["water"], 8.0);
+
+        let mut coolant = repo.get_product_by_name("coolant").unwrap();
+        coolant.schematic = Schematic::new(
+            vec![("water".to_string(), 20), ("electrolytes".to_string(), 20)],
+            5,
+        );
+        repo.load_products_data(vec![coolant]).unwrap();
+        let inputs = inputs_per_unit(&repo, "coolant", &["water".to_string()], &[]);
+        assert_eq!(inputs["water"], 4.0);
+    }
+
+    #[test]
+    fn test_inputs_per_unit() {
+        let repo = MemoryRepository::new();
+
+        // P1 -> P2 factory imports 40 of each P1 per 5 P2
+        let inputs = inputs_per_unit(
+            &repo,
+            "coolant",
+            &["water".to_string(), "electrolytes".to_string()],
+            &[],
+        );
+        assert_eq!(inputs["water"], 8.0);
+        assert_eq!(inputs["electrolytes"], 8.0);
+
+        // P0 -> P2 planet mines both P0s and builds the P1s itself
+        let inputs = inputs_per_unit(
+            &repo,
+            "coolant",
+            &[],
+            &["aqueous_liquids".to_string(), "ionic_solutions".to_string()],
+        );
+        assert_eq!(inputs["aqueous_liquids"], 8.0 * 150.0);
+    }
+
+    #[test]
+    fn test_assignment_daily_output() {
+        let repo = MemoryRepository::new();
+
+        let extraction = assignment("water", &[], &["aqueous_liquids"]);
+        let output = assignment_daily_output(&repo, &extraction);
+        assert!((output - DAILY_EXTRACTION_PER_PLANET / 150.0).abs() < 1e-9);
+
+        let factory = assignment("coolant", &["water", "electrolytes"], &[]);
+        assert_eq!(assignment_daily_output(&repo, &factory), 1_200.0);
+    }
+
+    #[test]
+    fn test_security_scales_extraction() {
+        let mut repo = MemoryRepository::new();
+        let planet = |id: &str| Planet::new(id.to_string(), PlanetType::Oceanic, Vec::new());
+        repo.load_planets_data(vec![
+            planet("highsec").with_location("Perimeter", "Kimotoro", "The Forge", 0.95),
+            planet("lowsec").with_location("Tama", "Okomon", "The Citadel", 0.3),
+            planet("wormhole").with_location("J123456", "A-C00001", "A-R00001", -1.0),
+            planet("unknown"),
+        ])
+        .unwrap();
+
+        let output_on = |id: &str, output: &str, mined: &[&str]| {
+            let mut a = assignment(output, &[], mined);
+            a.planet = id.to_string();
+            assignment_daily_output(&repo, &a)
+        };
+        let full = DAILY_EXTRACTION_PER_PLANET / 150.0;
+        assert!((output_on("unknown", "water", &["aqueous_liquids"]) - full).abs() < 1e-9);
+        assert!((output_on("wormhole", "water", &["aqueous_liquids"]) - full).abs() < 1e-9);
+        assert!((output_on("lowsec", "water", &["aqueous_liquids"]) - 0.8 * full).abs() < 1e-9);
+        assert!((output_on("highsec", "water", &["aqueous_liquids"]) - 0.6 * full).abs() < 1e-9);
+        assert!(
+            (output_on("highsec", "aqueous_liquids", &["aqueous_liquids"])
+                - 0.6 * DAILY_EXTRACTION_PER_PLANET)
+                .abs()
+                < 1e-9
+        );
+
+        // Factories don't depend on deposits
+        let mut factory = assignment("coolant", &["water", "electrolytes"], &[]);
+        factory.planet = "highsec".to_string();
+        assert_eq!(assignment_daily_output(&repo, &factory), 1_200.0);
+    }
+
+    #[test]
+    fn test_richness_scales_extraction() {
+        let mut repo = MemoryRepository::new();
+        repo.load_planets_data(vec![Planet::new(
+            "scanned".to_string(),
+            PlanetType::Oceanic,
+            Vec::new(),
+        )
+        .with_richness("aqueous_liquids", 2.0)
+        .with_richness("ionic_solutions", 0.5)])
+            .unwrap();
+
+        let output_on = |id: &str, output: &str, mined: &[&str]| {
+            let mut a = assignment(output, &[], mined);
+            a.planet = id.to_string();
+            assignment_daily_output(&repo, &a)
+        };
+        let water = DAILY_EXTRACTION_PER_PLANET / 150.0;
+        assert!((output_on("scanned", "water", &["aqueous_liquids"]) - 2.0 * water).abs() < 1e-9);
+        assert!(
+            (output_on("scanned", "electrolytes", &["ionic_solutions"]) - 0.5 * water).abs() < 1e-9
+        );
+
+        // Shared extractors spend most of their time on the poor deposit
+        let mined = ["aqueous_liquids", "ionic_solutions"];
+        let baseline = output_on("unscanned", "coolant", &mined);
+        assert!((output_on("scanned", "coolant", &mined) - 0.8 * baseline).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_program_yield() {
+        let daily = program_yield(DEFAULT_PROGRAM_HOURS);
+        assert!((daily.average_factor - 1.0).abs() < 1e-9);
+        assert_eq!(daily.cycle_hours, 0.25);
+
+        let hourly = program_yield(1.0);
+        let weekly = program_yield(168.0);
+        let longest = program_yield(1_000.0);
+        assert_eq!(longest.program_hours, MAX_PROGRAM_HOURS);
+        assert_eq!(longest.cycle_hours, 4.0);
+        assert_eq!(program_cycle_hours(72.0), 1.0);
+        assert!(hourly.average_factor > daily.average_factor);
+        assert!(weekly.average_factor < daily.average_factor);
+        assert!(longest.average_factor < weekly.average_factor);
+        for program in [hourly, daily, weekly, longest] {
+            assert!(program.peak_factor >= program.average_factor);
+        }
+
+        // A weekly program on an extraction planet yields less per day than a daily one
+        let repo = MemoryRepository::new();
+        let mut lazy = assignment("water", &[], &["aqueous_liquids"]);
+        let active = assignment_daily_output(&repo, &lazy);
+        lazy.program_hours = Some(168.0);
+        assert!(
+            (assignment_daily_output(&repo, &lazy) - active * weekly.average_factor).abs() < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_planetology_credits_better_extraction() {
+        let mut repo = MemoryRepository::new();
+        let extraction = assignment("water", &[], &["aqueous_liquids"]);
+        let unskilled = assignment_daily_output(&repo, &extraction);
+
+        repo.load_characters(
+            r#"[
+                {
+                    "name": "Character1",
+                    "planets": 1,
+                    "skills": {
+                        "command_center_upgrades": 5,
+                        "interplanetary_consolidation": 0,
+                        "remote_sensing": 5,
+                        "planetology": 5,
+                        "advanced_planetology": 5
+                    }
+                }
+            ]"#,
+        )
+        .unwrap();
+        let character = repo.get_character_by_name("Character1").unwrap();
+        assert_eq!(character.scan_quality(), 1.0);
+        assert!(
+            (assignment_daily_output(&repo, &extraction)
+                - unskilled * (1.0 + crate::domain::MAX_HOTSPOT_BONUS))
+                .abs()
+                < 1e-9
+        );
+
+        // Factories don't extract, so scanning skills don't change them
+        let factory = assignment("coolant", &["water", "electrolytes"], &[]);
+        assert_eq!(assignment_daily_output(&repo, &factory), 1_200.0);
+    }
+
+    #[test]
+    fn test_propagate_demand() {
+        let repo = MemoryRepository::new();
+        let assignments = vec![
+            assignment("coolant", &["water", "electrolytes"], &[]),
+            assignment("water", &[], &["aqueous_liquids"]),
+            assignment("electrolytes", &[], &["ionic_solutions"]),
+        ];
+
+        let demand = propagate_demand(&repo, &assignments, &[("coolant", 100.0)]);
+        assert_eq!(demand["coolant"], 100.0);
+        assert_eq!(demand["water"], 800.0);
+        assert_eq!(demand["electrolytes"], 800.0);
+    }
+
+    #[test]
+    fn test_skill_aware_achievable_scale() {
+        let mut repo = MemoryRepository::new();
+        repo.load_characters(
+            r#"[{
+                "name": "Character1",
+                "planets": 3,
+                "skills": { "command_center_upgrades": 3, "interplanetary_consolidation": 2 }
+            }]"#,
+        )
+        .unwrap();
+        assert_eq!(command_center_factor(5), 1.0);
+        assert!(command_center_factor(0) < command_center_factor(3));
+
+        let assignments = vec![
+            assignment("coolant", &["water", "electrolytes"], &[]),
+            assignment("water", &[], &["aqueous_liquids"]),
+            assignment("electrolytes", &[], &["ionic_solutions"]),
+        ];
+
+        // Each P1 planet yields 100k / 150 units, and coolant needs 8 of each
+        let full = achievable_scale(&repo, &assignments, &[("coolant", 1.0)], |a| {
+            assignment_daily_output(&repo, a)
+        });
+        assert!((full - 100_000.0 / 150.0 / 8.0).abs() < 1e-9);
+
+        let skilled = achievable_scale(&repo, &assignments, &[("coolant", 1.0)], |a| {
+            character_daily_output(&repo, a)
+        });
+        assert!((skilled - full * command_center_factor(3)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_phase_demand_with_stockpile() {
+        let repo = MemoryRepository::new();
+        let assignments = vec![
+            assignment("coolant", &["water", "electrolytes"], &[]),
+            assignment("water", &[], &["aqueous_liquids"]),
+            assignment("electrolytes", &[], &["ionic_solutions"]),
+        ];
+        let stockpile = Stockpile {
+            inventory: HashMap::from([
+                ("coolant".to_string(), 500.0),
+                ("water".to_string(), 8_000.0),
+            ]),
+            horizon_days: 30.0,
+        };
+
+        let phased = phase_demand(&repo, &assignments, &[("coolant", 100.0)], &stockpile);
+
+        // 500 coolant on hand covers five days of demand
+        assert_eq!(phased["coolant"].online_after_days, 5.0);
+        assert_eq!(phased["coolant"].units_to_produce, 2_500.0);
+
+        // Water is only needed once coolant production starts, and stock covers ten more days
+        assert_eq!(phased["water"].online_after_days, 15.0);
+        assert_eq!(phased["electrolytes"].online_after_days, 5.0);
+        assert_eq!(phased["electrolytes"].units_to_produce, 800.0 * 25.0);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::domain::ProductTier;
+use serde::{Deserialize, Serialize};
+
+/// Estimated daily economics of planning a single end product
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetRecommendation {
+    pub product: String,
+    pub tier: ProductTier,
+    pub feasible: bool, // Whether the planets and characters can run the whole chain
+    pub planets: usize, // Planets the plan uses
+    pub daily_units: f64, // Units per day the slowest link of the chain allows
+    pub daily_revenue: f64, // ISK per day from selling the output
+    pub daily_input_cost: f64, // ISK per day spent on inputs the plan doesn't produce
+    pub daily_tax: f64, // ISK per day paid in customs office export tax
+    pub daily_profit: f64, // Revenue minus input costs and taxes
+    pub reason: Option<String>, // Why the product couldn't be planned
+}
+
+impl TargetRecommendation {
+    /// Recommendation for a product the solver couldn't plan
+    pub fn infeasible(product: &str, tier: ProductTier, reason: String) -> Self {
+        Self {
+            product: product.to_string(),
+            tier,
+            feasible: false,
+            planets: 0,
+            daily_units: 0.0,
+            daily_revenue: 0.0,
+            daily_input_cost: 0.0,
+            daily_tax: 0.0,
+            daily_profit: 0.0,
+            reason: Some(reason),
+        }
+    }
+}
+
+/// Achievable ISK per hour of planning a single product with the available planets and
+/// characters, valued like a plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductRanking {
+    pub product: String,
+    pub tier: ProductTier,
+    pub feasible: bool, // Whether the planets and characters can run the whole chain
+    pub planets: usize, // Planets the plan uses
+    pub units_per_hour: f64, // Units of the product sold each hour
+    pub revenue_per_hour: f64,
+    pub costs_per_hour: f64, // Bought inputs, customs tax, sales tax and broker fees
+    pub net_isk_per_hour: f64,
+    pub unpriced: Vec<String>, // Products without a known price, valued at zero
+    pub reason: Option<String>, // Why the product couldn't be planned
+}
+
+impl ProductRanking {
+    /// Ranking of a product the solver couldn't plan
+    pub fn infeasible(product: &str, tier: ProductTier, reason: String) -> Self {
+        Self {
+            product: product.to_string(),
+            tier,
+            feasible: false,
+            planets: 0,
+            units_per_hour: 0.0,
+            revenue_per_hour: 0.0,
+            costs_per_hour: 0.0,
+            net_isk_per_hour: 0.0,
+            unpriced: Vec::new(),
+            reason: Some(reason),
+        }
+    }
+}
+
+/// Order rankings with feasible products first, then by ISK per hour
+pub fn sort_rankings(rankings: &mut [ProductRanking]) {
+    rankings.sort_by(|a, b| {
+        b.feasible
+            .cmp(&a.feasible)
+            .then(b.net_isk_per_hour.total_cmp(&a.net_isk_per_hour))
+            .then(a.product.cmp(&b.product))
+    });
+}
+
+/// Order recommendations with feasible products first, then by profit
+pub fn rank_recommendations(recommendations: &mut [TargetRecommendation]) {
+    recommendations.sort_by(|a, b| {
+        b.feasible
+            .cmp(&a.feasible)
+            .then(b.daily_profit.total_cmp(&a.daily_profit))
+            .then(a.product.cmp(&b.product))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recommendation(product: &str, profit: f64) -> TargetRecommendation {
+        TargetRecommendation {
+            feasible: true,
+            daily_profit: profit,
+            reason: None,
+            ..TargetRecommendation::infeasible(product, ProductTier::P2, String::new())
+        }
+    }
+
+    #[test]
+    fn test_rank_recommendations() {
+        let mut recommendations = vec![
+            recommendation("coolant", 10.0),
+            TargetRecommendation::infeasible("robotics", ProductTier::P3, "No planets".into()),
+            recommendation("enriched_uranium", 50.0),
+        ];
+        rank_recommendations(&mut recommendations);
+
+        let order: Vec<&str> = recommendations.iter().map(|r| r.product.as_str()).collect();
+        assert_eq!(order, vec!["enriched_uranium", "coolant", "robotics"]);
+    }
+
+    #[test]
+    fn test_sort_rankings() {
+        let ranking = |product: &str, net: f64| ProductRanking {
+            feasible: true,
+            net_isk_per_hour: net,
+            reason: None,
+            ..ProductRanking::infeasible(product, ProductTier::P4, String::new())
+        };
+        let mut rankings = vec![
+            ProductRanking::infeasible("nano_factory", ProductTier::P4, "No planets".into()),
+            ranking("broadcast_node", -5.0),
+            ranking("wetware_mainframe", 100.0),
+        ];
+        sort_rankings(&mut rankings);
+
+        let order: Vec<&str> = rankings.iter().map(|r| r.product.as_str()).collect();
+        assert_eq!(
+            order,
+            vec!["wetware_mainframe", "broadcast_node", "nano_factory"]
+        );
+    }
+}
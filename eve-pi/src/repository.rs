@@ -1,7 +1,21 @@
-use crate::domain::{create_product_database, Character, Planet, Product};
-use std::collections::HashMap;
+use crate::compatibility::CompatibilityMatrix;
+use crate::csv_import::{character_records_from_csv, planet_records_from_csv};
+use crate::domain::{
+    create_product_database, default_display_name, is_valid_program_hours, is_valid_tax_rate,
+    normalize_name, planet_resource_map, Character, EsiSkills, Planet, PlanetType, Product,
+    ProductTier,
+};
+use crate::graph::DependencyGraph;
+use crate::intern::{ProductIds, Recipes};
+use crate::pricing::PriceSnapshot;
+use crate::scenario::{ScenarioChange, ScenarioRepository};
+use crate::validation::{validate_planets, validate_products, PlanetWarning, ValidationReport};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
 use std::fmt;
+use std::sync::Arc;
 use tracing::{debug, error, info};
 
 /// Represents errors that can occur when working with repositories
@@ -13,6 +27,10 @@ pub enum RepositoryError {
     ProductNotFound(String),
     /// Error that occurs when data is invalid
     InvalidData(String),
+    /// Error that occurs when a planet is not found
+    PlanetNotFound(String),
+    /// Error that occurs when a character is not found
+    CharacterNotFound(String),
 }
 
 impl fmt::Display for RepositoryError {
@@ -23,6 +41,8 @@ impl fmt::Display for RepositoryError {
             }
             RepositoryError::ProductNotFound(name) => write!(f, "Product not found: {}", name),
             RepositoryError::InvalidData(msg) => write!(f, "Invalid data: {}", msg),
+            RepositoryError::PlanetNotFound(id) => write!(f, "Planet not found: {}", id),
+            RepositoryError::CharacterNotFound(name) => write!(f, "Character not found: {}", name),
         }
     }
 }
@@ -31,94 +51,684 @@ impl Error for RepositoryError {}
 
 /// Repository trait for accessing product data
 pub trait ProductRepository {
-    fn get_all_products(&self) -> Vec<Product>;
-    fn get_product_by_name(&self, name: &str) -> Option<Product>;
-    fn get_products_by_tier(&self, tier: crate::domain::ProductTier) -> Vec<Product>;
+    /// Borrow every product without cloning, for hot loops
+    fn iter_products(&self) -> Box<dyn Iterator<Item = &Product> + '_>;
+
+    /// Borrow a product by key or in-game display name
+    fn get_product_ref(&self, name: &str) -> Option<&Product>;
+
+    fn get_all_products(&self) -> Vec<Product> {
+        self.iter_products().cloned().collect()
+    }
+
+    fn get_product_by_name(&self, name: &str) -> Option<Product> {
+        self.get_product_ref(name).cloned()
+    }
+
+    /// Numeric IDs for every product key, for hot loops that would otherwise hash strings
+    fn product_ids(&self) -> Arc<ProductIds> {
+        Arc::new(ProductIds::new(
+            self.iter_products().map(|p| p.name.as_str()),
+        ))
+    }
+
+    /// Recipe dependencies of every product with their transitive closures
+    fn dependency_graph(&self) -> Arc<DependencyGraph> {
+        Arc::new(DependencyGraph::new(self.iter_products()))
+    }
+
+    /// Recipes of every product by numeric ID, numbered like `product_ids`
+    fn recipes(&self) -> Arc<Recipes> {
+        Arc::new(Recipes::new(self.product_ids(), self.iter_products()))
+    }
+
+    /// Borrow the products of one tier without cloning
+    fn iter_products_by_tier(&self, tier: ProductTier) -> Box<dyn Iterator<Item = &Product> + '_> {
+        Box::new(self.iter_products().filter(move |p| p.tier == tier))
+    }
+
+    fn get_products_by_tier(&self, tier: ProductTier) -> Vec<Product> {
+        self.iter_products_by_tier(tier).cloned().collect()
+    }
+
+    /// Look up a product by its EVE type ID
+    fn get_product_by_type_id(&self, type_id: u32) -> Option<Product> {
+        if type_id == 0 {
+            return None;
+        }
+        self.iter_products().find(|p| p.type_id == type_id).cloned()
+    }
+
+    /// Products matching a partial name, best match first: exact names, then prefixes, then
+    /// word prefixes, then substrings, then names containing the query's letters in order
+    fn search_products(&self, query: &str) -> Vec<Product> {
+        let query = normalize_name(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut matches: Vec<(u8, &Product)> = self
+            .iter_products()
+            .filter_map(|product| {
+                let rank = match_rank(&query, &product.name)
+                    .into_iter()
+                    .chain(match_rank(&query, &normalize_name(&product.display_name)))
+                    .min()?;
+                Some((rank, product))
+            })
+            .collect();
+        matches.sort_by(|(a_rank, a), (b_rank, b)| {
+            a_rank
+                .cmp(b_rank)
+                .then(a.name.len().cmp(&b.name.len()))
+                .then(a.name.cmp(&b.name))
+        });
+        matches
+            .into_iter()
+            .map(|(_, product)| product.clone())
+            .collect()
+    }
+}
+
+/// How well a normalized query matches a normalized name, lower is better
+fn match_rank(query: &str, name: &str) -> Option<u8> {
+    if name.is_empty() {
+        None
+    } else if name == query {
+        Some(0)
+    } else if name.starts_with(query) {
+        Some(1)
+    } else if name.split('_').any(|word| word.starts_with(query)) {
+        Some(2)
+    } else if name.contains(query) {
+        Some(3)
+    } else {
+        // Every letter of the query appears in order, like "bcn" for broadcast_node
+        let mut letters = name.chars();
+        query
+            .chars()
+            .filter(|c| *c != '_')
+            .all(|c| letters.any(|l| l == c))
+            .then_some(4)
+    }
+}
+
+/// The built-in resource map with owned keys
+fn default_resource_map() -> HashMap<String, Vec<PlanetType>> {
+    planet_resource_map()
+        .into_iter()
+        .map(|(resource, types)| (resource.to_string(), types))
+        .collect()
 }
 
 /// Repository trait for accessing planet data
 pub trait PlanetRepository {
-    fn get_all_planets(&self) -> Vec<Planet>;
-    fn get_planet_by_id(&self, id: &str) -> Option<Planet>;
+    /// Borrow every planet without cloning, for hot loops
+    fn iter_planets(&self) -> Box<dyn Iterator<Item = &Planet> + '_>;
+
+    /// Borrow a planet by ID
+    fn get_planet_ref(&self, id: &str) -> Option<&Planet>;
+
+    fn get_all_planets(&self) -> Vec<Planet> {
+        self.iter_planets().cloned().collect()
+    }
+
+    fn get_planet_by_id(&self, id: &str) -> Option<Planet> {
+        self.get_planet_ref(id).cloned()
+    }
+
+    /// Shortest number of jumps between two solar systems, if known
+    fn jumps_between(&self, from: &str, to: &str) -> Option<u32> {
+        (from == to).then_some(0)
+    }
+
+    /// Planet types each P0 resource can be found on
+    fn resource_map(&self) -> HashMap<String, Vec<PlanetType>> {
+        default_resource_map()
+    }
+
+    /// Planet types a P0 resource can be found on, or `None` for an unknown resource
+    fn resource_planet_types(&self, resource: &str) -> Option<Vec<PlanetType>> {
+        self.resource_map().remove(resource)
+    }
 }
 
 /// Repository trait for accessing character data
 pub trait CharacterRepository {
-    fn get_all_characters(&self) -> Vec<Character>;
-    fn get_character_by_name(&self, name: &str) -> Option<Character>;
+    /// Borrow every character without cloning, for hot loops
+    fn iter_characters(&self) -> Box<dyn Iterator<Item = &Character> + '_>;
+
+    /// Borrow a character by name
+    fn get_character_ref(&self, name: &str) -> Option<&Character>;
+
+    fn get_all_characters(&self) -> Vec<Character> {
+        self.iter_characters().cloned().collect()
+    }
+
+    fn get_character_by_name(&self, name: &str) -> Option<Character> {
+        self.get_character_ref(name).cloned()
+    }
 }
 
 /// Combined repository trait for accessing all data
-pub trait Repository: ProductRepository + PlanetRepository + CharacterRepository {}
+pub trait Repository: ProductRepository + PlanetRepository + CharacterRepository {
+    /// Which planet types can mine each resource and make each product on their own
+    fn compatibility(&self) -> Arc<CompatibilityMatrix> {
+        Arc::new(CompatibilityMatrix::new(
+            &self.resource_map(),
+            &self.dependency_graph(),
+        ))
+    }
+}
+
+/// Make sure every ingredient of a product set is either in the set or already known
+fn check_ingredients(
+    products: &[Product],
+    known: impl Fn(&str) -> bool,
+) -> Result<(), RepositoryError> {
+    for product in products {
+        for ingredient in &product.ingredients() {
+            if !known(ingredient) && !products.iter().any(|p| p.name == *ingredient) {
+                error!(
+                    "Product {} has unknown ingredient {}",
+                    product.name, ingredient
+                );
+                return Err(RepositoryError::ProductNotFound(ingredient.clone()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A record a lenient load skipped
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordError {
+    pub index: usize,          // Position of the record in the loaded array
+    pub field: Option<String>, // Path of the offending field, like `resources[2]`
+    pub reason: String,
+}
 
-/// Memory-based repository implementation
+/// How a load treats records that fail to deserialize
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadMode {
+    Strict,  // Load nothing if any record is malformed
+    Lenient, // Skip malformed records and load the rest
+}
+
+/// Outcome of loading records. Unknown fields, like ones a newer frontend added, and values
+/// out of their valid range never fail a load and are listed as warnings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoadReport {
+    pub loaded: usize,
+    pub errors: Vec<RecordError>, // Records skipped by a lenient load
+    #[serde(default)]
+    pub warnings: Vec<RecordError>, // Unknown fields and out-of-range values that were ignored
+}
+
+impl LoadReport {
+    /// Whether every record was loaded
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Records whose values are range-checked as they're loaded
+trait CheckedRecord {
+    /// Fields holding a value outside their valid range, with the reason
+    fn invalid_values(&self) -> Vec<(&'static str, String)>;
+
+    /// Unset every field `invalid_values` reports
+    fn clear_invalid_values(&mut self);
+}
+
+fn invalid_tax_rate(tax_rate: Option<f64>) -> Option<(&'static str, String)> {
+    tax_rate
+        .filter(|rate| !is_valid_tax_rate(*rate))
+        .map(|rate| {
+            (
+                "poco_tax_rate",
+                format!("Tax rate {} is outside 0 to 1", rate),
+            )
+        })
+}
+
+impl CheckedRecord for Planet {
+    fn invalid_values(&self) -> Vec<(&'static str, String)> {
+        invalid_tax_rate(self.poco_tax_rate).into_iter().collect()
+    }
+
+    fn clear_invalid_values(&mut self) {
+        self.poco_tax_rate = self.poco_tax_rate.filter(|rate| is_valid_tax_rate(*rate));
+    }
+}
+
+impl CheckedRecord for Character {
+    fn invalid_values(&self) -> Vec<(&'static str, String)> {
+        let program_hours = self
+            .program_hours
+            .filter(|hours| !is_valid_program_hours(*hours))
+            .map(|hours| {
+                (
+                    "program_hours",
+                    format!("Program length {} is not a positive number of hours", hours),
+                )
+            });
+        invalid_tax_rate(self.poco_tax_rate)
+            .into_iter()
+            .chain(program_hours)
+            .collect()
+    }
+
+    fn clear_invalid_values(&mut self) {
+        self.poco_tax_rate = self.poco_tax_rate.filter(|rate| is_valid_tax_rate(*rate));
+        self.program_hours = self
+            .program_hours
+            .filter(|hours| is_valid_program_hours(*hours));
+    }
+}
+
+/// Fail on the first value of a planet or character outside its valid range
+fn check_values(name: &str, record: &impl CheckedRecord) -> Result<(), RepositoryError> {
+    match record.invalid_values().into_iter().next() {
+        Some((field, reason)) => {
+            error!("{} has an invalid {}: {}", name, field, reason);
+            Err(RepositoryError::InvalidData(format!(
+                "{} {}: {}",
+                name, field, reason
+            )))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Records that deserialized, with the ones that failed and the unknown fields ignored
+struct ParsedRecords<T> {
+    records: Vec<T>,
+    errors: Vec<RecordError>,
+    warnings: Vec<RecordError>,
+}
+
+/// Deserialize each record on its own, collecting the ones that fail instead of giving up
+fn parse_records<T: DeserializeOwned + CheckedRecord>(
+    records: Vec<serde_json::Value>,
+) -> ParsedRecords<T> {
+    let mut parsed = ParsedRecords {
+        records: Vec::new(),
+        errors: Vec::new(),
+        warnings: Vec::new(),
+    };
+    for (index, record) in records.into_iter().enumerate() {
+        let mut track = serde_path_to_error::Track::new();
+        let mut unknown = Vec::new();
+        let result: Result<T, _> = serde_ignored::deserialize(
+            serde_path_to_error::Deserializer::new(record, &mut track),
+            |path| unknown.push(path.to_string()),
+        );
+        match result {
+            Ok(mut value) => {
+                for (field, reason) in value.invalid_values() {
+                    debug!("Ignoring {} of record {}: {}", field, index, reason);
+                    parsed.warnings.push(RecordError {
+                        index,
+                        field: Some(field.to_string()),
+                        reason: format!("{}, ignored", reason),
+                    });
+                }
+                value.clear_invalid_values();
+                parsed.records.push(value);
+                for field in unknown {
+                    debug!("Ignoring unknown field {} of record {}", field, index);
+                    parsed.warnings.push(RecordError {
+                        index,
+                        field: Some(field),
+                        reason: "Unknown field ignored".to_string(),
+                    });
+                }
+            }
+            Err(err) => {
+                let path = track.path().to_string();
+                let reason = err.to_string();
+                // Missing fields are reported on the record itself, so name them from the message
+                let field = if path == "." {
+                    reason
+                        .strip_prefix("missing field `")
+                        .and_then(|rest| rest.split('`').next())
+                        .map(str::to_string)
+                } else {
+                    Some(path)
+                };
+                error!("Record {} is malformed: {}", index, reason);
+                parsed.errors.push(RecordError {
+                    index,
+                    field,
+                    reason,
+                });
+            }
+        }
+    }
+    parsed
+}
+
+impl<T> ParsedRecords<T> {
+    /// The records to load, or the first error if a strict load can't load them all
+    fn accept(self, mode: LoadMode) -> Result<(Vec<T>, LoadReport), RepositoryError> {
+        if mode == LoadMode::Strict {
+            if let Some(err) = self.errors.first() {
+                return Err(RepositoryError::DeserializationError(match &err.field {
+                    Some(field) => format!("record {}, field {}: {}", err.index, field, err.reason),
+                    None => format!("record {}: {}", err.index, err.reason),
+                }));
+            }
+        }
+        let report = LoadReport {
+            loaded: self.records.len(),
+            errors: self.errors,
+            warnings: self.warnings,
+        };
+        Ok((self.records, report))
+    }
+}
+
+/// Version of the exported state layout, bumped on incompatible changes
+pub const STATE_VERSION: u32 = 1;
+
+/// A whole session in one versioned document, for frontends to persist and restore. Products
+/// and resource map entries are only stored where they differ from the built-in data, so a
+/// restored session picks up dataset updates for everything it didn't override.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepositoryState {
+    pub version: u32,
+    #[serde(default)]
+    pub planets: Vec<Planet>,
+    #[serde(default)]
+    pub characters: Vec<Character>,
+    #[serde(default)]
+    pub products: Vec<Product>, // Products added or changed from the built-in ones
+    #[serde(default)]
+    pub removed_products: Vec<String>, // Built-in products a replaced product set left out
+    #[serde(default)]
+    pub resource_map: BTreeMap<String, Vec<PlanetType>>, // Entries changed from the built-in map
+    #[serde(default)]
+    pub jumps: Vec<(String, String, u32)>, // Each route once, in either direction
+    #[serde(default)]
+    pub prices: PriceSnapshot,
+}
+
+impl RepositoryState {
+    /// Serialize the state to JSON
+    pub fn to_json(&self) -> Result<String, RepositoryError> {
+        serde_json::to_string(self).map_err(|e| RepositoryError::InvalidData(e.to_string()))
+    }
+
+    /// Parse a state exported by this or an earlier version of the crate
+    pub fn from_json(json: &str) -> Result<Self, RepositoryError> {
+        let state: RepositoryState = serde_json::from_str(json)
+            .map_err(|e| RepositoryError::DeserializationError(e.to_string()))?;
+        if state.version > STATE_VERSION {
+            return Err(RepositoryError::InvalidData(format!(
+                "State version {} is newer than the supported version {}",
+                state.version, STATE_VERSION
+            )));
+        }
+        Ok(state)
+    }
+}
+
+/// Memory-based repository implementation. Its data is shared between clones until one of
+/// them changes it, so cloning to branch off a scenario is cheap.
+#[derive(Clone)]
 pub struct MemoryRepository {
-    products: HashMap<String, Product>,
-    planets: HashMap<String, Planet>,
-    characters: HashMap<String, Character>,
+    products: Arc<HashMap<String, Product>>,
+    product_ids: Arc<ProductIds>, // Renumbered whenever the product set changes
+    recipes: Arc<Recipes>,        // Resolved again whenever the product set changes
+    dependencies: Arc<DependencyGraph>, // Rebuilt whenever the product set changes
+    compatibility: Arc<CompatibilityMatrix>, // Rebuilt whenever products or the resource map change
+    tiers: Arc<HashMap<ProductTier, Vec<String>>>, // Product keys of each tier, sorted
+    planets: Arc<HashMap<String, Planet>>,
+    characters: Arc<HashMap<String, Character>>,
+    jumps: Arc<HashMap<(String, String), u32>>, // Route lengths between solar systems
+    resource_map: Arc<HashMap<String, Vec<PlanetType>>>, // Planet types each P0 resource is found on
 }
 
 impl MemoryRepository {
+    /// Start a repository from the built-in products, checking all of its data at once
+    pub fn builder() -> MemoryRepositoryBuilder {
+        MemoryRepositoryBuilder::default()
+    }
+
     /// Create a new empty repository
     pub fn new() -> Self {
-        Self {
-            products: create_product_database(),
-            planets: HashMap::new(),
-            characters: HashMap::new(),
+        let mut repository = Self {
+            products: Arc::new(create_product_database()),
+            product_ids: Arc::default(),
+            recipes: Arc::default(),
+            dependencies: Arc::default(),
+            compatibility: Arc::default(),
+            tiers: Arc::default(),
+            planets: Arc::default(),
+            characters: Arc::default(),
+            jumps: Arc::default(),
+            resource_map: Arc::new(default_resource_map()),
+        };
+        repository.index_products();
+        repository
+    }
+
+    /// Number the current product set and work out its dependencies
+    fn index_products(&mut self) {
+        self.product_ids = Arc::new(ProductIds::new(self.products.keys().map(String::as_str)));
+        self.recipes = Arc::new(Recipes::new(
+            Arc::clone(&self.product_ids),
+            self.products.values(),
+        ));
+        self.dependencies = Arc::new(DependencyGraph::new(self.products.values()));
+        let mut tiers: HashMap<ProductTier, Vec<String>> = HashMap::new();
+        for product in self.products.values() {
+            tiers
+                .entry(product.tier)
+                .or_default()
+                .push(product.name.clone());
         }
+        tiers.values_mut().for_each(|names| names.sort());
+        self.tiers = Arc::new(tiers);
+        self.index_resources();
+    }
+
+    /// Work out which planet types can mine and make what
+    fn index_resources(&mut self) {
+        self.compatibility = Arc::new(CompatibilityMatrix::new(
+            &self.resource_map,
+            &self.dependencies,
+        ));
     }
 
-    /// Load planets from JSON string
-    pub fn load_planets(&mut self, json: &str) -> Result<(), RepositoryError> {
+    fn products_mut(&mut self) -> &mut HashMap<String, Product> {
+        Arc::make_mut(&mut self.products)
+    }
+
+    fn planets_mut(&mut self) -> &mut HashMap<String, Planet> {
+        Arc::make_mut(&mut self.planets)
+    }
+
+    fn characters_mut(&mut self) -> &mut HashMap<String, Character> {
+        Arc::make_mut(&mut self.characters)
+    }
+
+    fn jumps_mut(&mut self) -> &mut HashMap<(String, String), u32> {
+        Arc::make_mut(&mut self.jumps)
+    }
+
+    fn resource_map_mut(&mut self) -> &mut HashMap<String, Vec<PlanetType>> {
+        Arc::make_mut(&mut self.resource_map)
+    }
+
+    /// Load planets from JSON string, failing if any planet is malformed
+    pub fn load_planets(&mut self, json: &str) -> Result<LoadReport, RepositoryError> {
         info!("Loading planets from JSON (length: {})", json.len());
         debug!("JSON content: {}", json);
 
-        // Try the simple approach first
-        let planets: Vec<Planet> = serde_json::from_str(json).map_err(|e| {
-            error!("Simple deserialization failed: {}", e);
+        let records: Vec<serde_json::Value> = serde_json::from_str(json).map_err(|e| {
+            error!("Failed to deserialize planets: {}", e);
             RepositoryError::DeserializationError(e.to_string())
         })?;
+        self.load_planet_records(records, LoadMode::Strict)
+    }
 
-        info!("Successfully deserialized {} planets", planets.len());
+    /// Load characters from JSON string, failing if any character is malformed
+    pub fn load_characters(&mut self, json: &str) -> Result<LoadReport, RepositoryError> {
+        info!("Loading characters from JSON (length: {})", json.len());
+        debug!("JSON content: {}", json);
 
-        for (i, planet) in planets.iter().enumerate() {
-            debug!("Processing planet {}: {:?}", i, planet);
-            self.planets.insert(planet.id.clone(), planet.clone());
+        let records: Vec<serde_json::Value> = serde_json::from_str(json).map_err(|e| {
+            error!("Failed to deserialize characters: {}", e);
+            RepositoryError::DeserializationError(e.to_string())
+        })?;
+        self.load_character_records(records, LoadMode::Strict)
+    }
+
+    /// Load products from JSON string, replacing built-in products with the same name so
+    /// recipes and metadata can be updated without a new release
+    pub fn load_products(&mut self, json: &str) -> Result<(), RepositoryError> {
+        info!("Loading products from JSON (length: {})", json.len());
+        debug!("JSON content: {}", json);
+
+        let products: Vec<Product> = serde_json::from_str(json).map_err(|e| {
+            error!("Failed to deserialize products: {}", e);
+            RepositoryError::DeserializationError(e.to_string())
+        })?;
+
+        self.load_products_data(products)
+    }
+
+    /// Load products directly from deserialized objects, replacing products with the same name
+    pub fn load_products_data(&mut self, products: Vec<Product>) -> Result<(), RepositoryError> {
+        info!("Loading {} products from deserialized data", products.len());
+
+        check_ingredients(&products, |name| self.products.contains_key(name))?;
+
+        for (i, mut product) in products.into_iter().enumerate() {
+            debug!("Processing product {}: {:?}", i, product);
+            if product.display_name.is_empty() {
+                product.display_name = default_display_name(&product.name);
+            }
+            self.products_mut().insert(product.name.clone(), product);
         }
+        self.index_products();
 
-        info!("Finished loading planets");
+        info!("Finished loading products data");
         Ok(())
     }
 
-    /// Load characters from JSON string
-    pub fn load_characters(&mut self, json: &str) -> Result<(), RepositoryError> {
-        info!("Loading characters from JSON (length: {})", json.len());
-        debug!("JSON content: {}", json);
+    /// Replace the whole product set with products from a JSON string, dropping every
+    /// built-in product the JSON leaves out
+    pub fn replace_products(&mut self, json: &str) -> Result<(), RepositoryError> {
+        info!("Replacing products from JSON (length: {})", json.len());
 
-        let characters: Vec<Character> = serde_json::from_str(json).map_err(|e| {
-            error!("Failed to deserialize characters: {}", e);
+        let products: Vec<Product> = serde_json::from_str(json).map_err(|e| {
+            error!("Failed to deserialize products: {}", e);
             RepositoryError::DeserializationError(e.to_string())
         })?;
 
-        info!("Successfully deserialized {} characters", characters.len());
+        self.replace_products_data(products)
+    }
+
+    /// Replace the whole product set with deserialized products. Recipes may only use
+    /// products from the new set.
+    pub fn replace_products_data(&mut self, products: Vec<Product>) -> Result<(), RepositoryError> {
+        info!("Replacing products with {} new ones", products.len());
 
-        for (i, character) in characters.iter().enumerate() {
-            debug!("Processing character {}: {:?}", i, character);
-            self.characters
-                .insert(character.name.clone(), character.clone());
+        check_ingredients(&products, |_| false)?;
+
+        self.products = Arc::new(
+            products
+                .into_iter()
+                .map(|mut product| {
+                    if product.display_name.is_empty() {
+                        product.display_name = default_display_name(&product.name);
+                    }
+                    (product.name.clone(), product)
+                })
+                .collect(),
+        );
+        self.index_products();
+        Ok(())
+    }
+
+    /// Go back to the built-in product database
+    pub fn reset_products(&mut self) {
+        self.products = Arc::new(create_product_database());
+        self.index_products();
+    }
+
+    /// Check the product database for unknown ingredients, bad tier transitions, and
+    /// recipe cycles
+    pub fn validate(&self) -> ValidationReport {
+        validate_products(self)
+    }
+
+    /// Check the loaded planets for unknown resources and resources their planet type
+    /// can't have
+    pub fn validate_planets(&self) -> Vec<PlanetWarning> {
+        validate_planets(self)
+    }
+
+    /// Load planet types per P0 resource from a JSON object like
+    /// `{"base_metals": ["Barren", "Lava"]}`, replacing the built-in entries of the listed
+    /// resources so the crate can follow resource redistribution patches
+    pub fn load_resource_map(&mut self, json: &str) -> Result<(), RepositoryError> {
+        info!("Loading resource map from JSON (length: {})", json.len());
+
+        let resource_map: HashMap<String, Vec<PlanetType>> =
+            serde_json::from_str(json).map_err(|e| {
+                error!("Failed to deserialize resource map: {}", e);
+                RepositoryError::DeserializationError(e.to_string())
+            })?;
+
+        self.load_resource_map_data(resource_map)
+    }
+
+    /// Replace the planet types of the given P0 resources
+    pub fn load_resource_map_data(
+        &mut self,
+        resource_map: HashMap<String, Vec<PlanetType>>,
+    ) -> Result<(), RepositoryError> {
+        info!("Loading planet types for {} resources", resource_map.len());
+
+        for resource in resource_map.keys() {
+            match self.products.get(resource) {
+                Some(product) if product.tier == ProductTier::P0 => {}
+                Some(_) => {
+                    return Err(RepositoryError::InvalidData(format!(
+                        "{} is not a P0 resource",
+                        resource
+                    )))
+                }
+                None => return Err(RepositoryError::ProductNotFound(resource.clone())),
+            }
         }
 
-        info!("Finished loading characters");
+        self.resource_map_mut().extend(resource_map);
+        self.index_resources();
         Ok(())
     }
 
+    /// Go back to the built-in resource map
+    pub fn reset_resource_map(&mut self) {
+        self.resource_map = Arc::new(default_resource_map());
+        self.index_resources();
+    }
+
     /// Load planets data directly from deserialized objects
     pub fn load_planets_data(&mut self, planets: Vec<Planet>) -> Result<(), RepositoryError> {
         info!("Loading {} planets from deserialized data", planets.len());
 
+        for planet in &planets {
+            check_values(&planet.id, planet)?;
+        }
         for (i, planet) in planets.iter().enumerate() {
             debug!("Processing planet {}: {:?}", i, planet);
-            self.planets.insert(planet.id.clone(), planet.clone());
+            self.planets_mut().insert(planet.id.clone(), planet.clone());
         }
 
         info!("Finished loading planets data");
@@ -135,56 +745,501 @@ impl MemoryRepository {
             characters.len()
         );
 
+        for character in &characters {
+            check_values(&character.name, character)?;
+        }
         for (i, character) in characters.iter().enumerate() {
             debug!("Processing character {}: {:?}", i, character);
-            self.characters
+            self.characters_mut()
                 .insert(character.name.clone(), character.clone());
         }
 
         info!("Finished loading characters data");
         Ok(())
     }
-}
 
-impl ProductRepository for MemoryRepository {
-    fn get_all_products(&self) -> Vec<Product> {
-        self.products.values().cloned().collect()
+    /// Load planets from a JSON array, skipping malformed records instead of failing the
+    /// whole load. Only a document that isn't an array is an error.
+    pub fn load_planets_lenient(&mut self, json: &str) -> Result<LoadReport, RepositoryError> {
+        info!(
+            "Leniently loading planets from JSON (length: {})",
+            json.len()
+        );
+
+        let records: Vec<serde_json::Value> = serde_json::from_str(json)
+            .map_err(|e| RepositoryError::DeserializationError(e.to_string()))?;
+        self.load_planet_records(records, LoadMode::Lenient)
     }
 
-    fn get_product_by_name(&self, name: &str) -> Option<Product> {
-        self.products.get(name).cloned()
+    /// Load planets from untyped records
+    pub fn load_planet_records(
+        &mut self,
+        records: Vec<serde_json::Value>,
+        mode: LoadMode,
+    ) -> Result<LoadReport, RepositoryError> {
+        let (planets, report) = parse_records::<Planet>(records).accept(mode)?;
+        for planet in planets {
+            self.planets_mut().insert(planet.id.clone(), planet);
+        }
+
+        info!(
+            "Loaded {} planets, skipped {}, {} unknown fields",
+            report.loaded,
+            report.errors.len(),
+            report.warnings.len()
+        );
+        Ok(report)
+    }
+
+    /// Load characters from a JSON array, skipping malformed records instead of failing the
+    /// whole load. Only a document that isn't an array is an error.
+    pub fn load_characters_lenient(&mut self, json: &str) -> Result<LoadReport, RepositoryError> {
+        info!(
+            "Leniently loading characters from JSON (length: {})",
+            json.len()
+        );
+
+        let records: Vec<serde_json::Value> = serde_json::from_str(json)
+            .map_err(|e| RepositoryError::DeserializationError(e.to_string()))?;
+        self.load_character_records(records, LoadMode::Lenient)
+    }
+
+    /// Load characters from untyped records
+    pub fn load_character_records(
+        &mut self,
+        records: Vec<serde_json::Value>,
+        mode: LoadMode,
+    ) -> Result<LoadReport, RepositoryError> {
+        let (characters, report) = parse_records::<Character>(records).accept(mode)?;
+        for character in characters {
+            self.characters_mut()
+                .insert(character.name.clone(), character);
+        }
+
+        info!(
+            "Loaded {} characters, skipped {}, {} unknown fields",
+            report.loaded,
+            report.errors.len(),
+            report.warnings.len()
+        );
+        Ok(report)
+    }
+
+    /// Load planets from a CSV export in the `csv_import` column layout, skipping bad rows
+    pub fn load_planets_csv(&mut self, csv: &str) -> Result<LoadReport, RepositoryError> {
+        info!("Loading planets from CSV (length: {})", csv.len());
+        self.load_planet_records(planet_records_from_csv(csv)?, LoadMode::Lenient)
     }
 
-    fn get_products_by_tier(&self, tier: crate::domain::ProductTier) -> Vec<Product> {
-        self.products
+    /// Load characters from a CSV export in the `csv_import` column layout, skipping bad rows
+    pub fn load_characters_csv(&mut self, csv: &str) -> Result<LoadReport, RepositoryError> {
+        info!("Loading characters from CSV (length: {})", csv.len());
+        self.load_character_records(character_records_from_csv(csv)?, LoadMode::Lenient)
+    }
+
+    /// Set a character's skills and planet limit from an ESI skills response, adding the
+    /// character if it isn't loaded yet
+    pub fn import_esi_skills(&mut self, name: &str, esi: &EsiSkills) -> Character {
+        info!("Importing {} ESI skills for {}", esi.skills.len(), name);
+        let character = self
+            .characters_mut()
+            .entry(name.to_string())
+            .and_modify(|character| character.apply_esi_skills(esi))
+            .or_insert_with(|| Character::from_esi_skills(name, esi));
+        character.clone()
+    }
+
+    /// Add a single planet, failing if one with the same ID is already loaded
+    pub fn add_planet(&mut self, planet: Planet) -> Result<(), RepositoryError> {
+        if self.planets.contains_key(&planet.id) {
+            return Err(RepositoryError::InvalidData(format!(
+                "Planet {} already exists",
+                planet.id
+            )));
+        }
+        check_values(&planet.id, &planet)?;
+        info!("Adding planet {}", planet.id);
+        self.planets_mut().insert(planet.id.clone(), planet);
+        Ok(())
+    }
+
+    /// Replace a loaded planet with an edited copy of the same ID
+    pub fn update_planet(&mut self, planet: Planet) -> Result<(), RepositoryError> {
+        check_values(&planet.id, &planet)?;
+        let existing = self
+            .planets_mut()
+            .get_mut(&planet.id)
+            .ok_or_else(|| RepositoryError::PlanetNotFound(planet.id.clone()))?;
+        info!("Updating planet {}", planet.id);
+        *existing = planet;
+        Ok(())
+    }
+
+    /// Remove a planet by ID, returning it
+    pub fn remove_planet(&mut self, id: &str) -> Result<Planet, RepositoryError> {
+        info!("Removing planet {}", id);
+        self.planets_mut()
+            .remove(id)
+            .ok_or_else(|| RepositoryError::PlanetNotFound(id.to_string()))
+    }
+
+    /// Add a single character, failing if one with the same name is already loaded
+    pub fn add_character(&mut self, character: Character) -> Result<(), RepositoryError> {
+        if self.characters.contains_key(&character.name) {
+            return Err(RepositoryError::InvalidData(format!(
+                "Character {} already exists",
+                character.name
+            )));
+        }
+        check_values(&character.name, &character)?;
+        info!("Adding character {}", character.name);
+        self.characters_mut()
+            .insert(character.name.clone(), character);
+        Ok(())
+    }
+
+    /// Replace a loaded character with an edited copy of the same name
+    pub fn update_character(&mut self, character: Character) -> Result<(), RepositoryError> {
+        check_values(&character.name, &character)?;
+        let existing = self
+            .characters_mut()
+            .get_mut(&character.name)
+            .ok_or_else(|| RepositoryError::CharacterNotFound(character.name.clone()))?;
+        info!("Updating character {}", character.name);
+        *existing = character;
+        Ok(())
+    }
+
+    /// Remove a character by name, returning it
+    pub fn remove_character(&mut self, name: &str) -> Result<Character, RepositoryError> {
+        info!("Removing character {}", name);
+        self.characters_mut()
+            .remove(name)
+            .ok_or_else(|| RepositoryError::CharacterNotFound(name.to_string()))
+    }
+
+    /// Record the number of jumps between two solar systems, in either direction
+    pub fn set_jumps(&mut self, from: &str, to: &str, jumps: u32) {
+        self.jumps_mut()
+            .insert((from.to_string(), to.to_string()), jumps);
+        self.jumps_mut()
+            .insert((to.to_string(), from.to_string()), jumps);
+    }
+
+    /// Set the customs office tax rate of every loaded planet in a system, returning how
+    /// many planets were updated. The rate has to be from 0 to 1.
+    pub fn set_system_tax_rate(
+        &mut self,
+        system: &str,
+        tax_rate: f64,
+    ) -> Result<usize, RepositoryError> {
+        if !is_valid_tax_rate(tax_rate) {
+            return Err(RepositoryError::InvalidData(format!(
+                "Tax rate {} is outside 0 to 1",
+                tax_rate
+            )));
+        }
+
+        let mut updated = 0;
+        for planet in self.planets_mut().values_mut() {
+            if planet.system.as_deref() == Some(system) {
+                planet.poco_tax_rate = Some(tax_rate);
+                updated += 1;
+            }
+        }
+        info!(
+            "Set tax rate {} on {} planets in {}",
+            tax_rate, updated, system
+        );
+        Ok(updated)
+    }
+
+    /// A copy sharing this repository's data until either of them changes, to branch off a
+    /// scenario without deserializing the inputs again
+    pub fn snapshot(&self) -> Self {
+        self.clone()
+    }
+
+    /// A snapshot with hypothetical changes to planets, characters or skills applied
+    pub fn branch(&self, changes: &[ScenarioChange]) -> Self {
+        let mut branch = self.snapshot();
+        if changes.is_empty() {
+            return branch;
+        }
+
+        let scenario = ScenarioRepository::new(self, changes);
+        branch.planets = Arc::new(
+            scenario
+                .get_all_planets()
+                .into_iter()
+                .map(|p| (p.id.clone(), p))
+                .collect(),
+        );
+        branch.characters = Arc::new(
+            scenario
+                .get_all_characters()
+                .into_iter()
+                .map(|c| (c.name.clone(), c))
+                .collect(),
+        );
+        branch
+    }
+
+    /// Capture planets, characters, jumps, and product and resource map overrides. Prices
+    /// live outside the repository and are left empty for the caller to fill in.
+    pub fn export_state(&self) -> RepositoryState {
+        let built_in = create_product_database();
+        let mut products: Vec<Product> = self
+            .products
             .values()
-            .filter(|p| p.tier == tier)
+            .filter(|p| built_in.get(&p.name) != Some(*p))
             .cloned()
-            .collect()
+            .collect();
+        products.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut removed_products: Vec<String> = built_in
+            .into_keys()
+            .filter(|name| !self.products.contains_key(name))
+            .collect();
+        removed_products.sort();
+
+        let default_map = default_resource_map();
+        let resource_map = self
+            .resource_map
+            .iter()
+            .filter(|(resource, types)| default_map.get(*resource) != Some(*types))
+            .map(|(resource, types)| (resource.clone(), types.clone()))
+            .collect();
+
+        let mut planets: Vec<Planet> = self.planets.values().cloned().collect();
+        planets.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut characters: Vec<Character> = self.characters.values().cloned().collect();
+        characters.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut jumps: Vec<(String, String, u32)> = self
+            .jumps
+            .iter()
+            .filter(|((from, to), _)| from < to)
+            .map(|((from, to), jumps)| (from.clone(), to.clone(), *jumps))
+            .collect();
+        jumps.sort();
+
+        RepositoryState {
+            version: STATE_VERSION,
+            planets,
+            characters,
+            products,
+            removed_products,
+            resource_map,
+            jumps,
+            prices: PriceSnapshot::default(),
+        }
+    }
+
+    /// Replace everything in the repository with an exported state, returning its prices.
+    /// Nothing changes if the state is invalid.
+    pub fn import_state(
+        &mut self,
+        state: RepositoryState,
+    ) -> Result<PriceSnapshot, RepositoryError> {
+        info!(
+            "Importing state version {} with {} planets and {} characters",
+            state.version,
+            state.planets.len(),
+            state.characters.len()
+        );
+
+        let mut products = create_product_database();
+        for name in &state.removed_products {
+            products.remove(name);
+        }
+        products.extend(state.products.into_iter().map(|p| (p.name.clone(), p)));
+
+        let mut builder = MemoryRepository::builder()
+            .with_product_set(products.into_values().collect())
+            .with_resource_map(state.resource_map.into_iter().collect())
+            .with_planets(state.planets)
+            .with_characters(state.characters);
+        for (from, to, jumps) in &state.jumps {
+            builder = builder.with_jumps(from, to, *jumps);
+        }
+
+        *self = builder.build()?;
+        Ok(state.prices)
+    }
+}
+
+impl Default for MemoryRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Collects everything a repository should hold and checks it in one go, so a mistake in
+/// any part fails the build instead of leaving a half-loaded repository behind
+#[derive(Debug, Default)]
+pub struct MemoryRepositoryBuilder {
+    products: Vec<Product>,
+    replace_products: bool, // Use only `products` instead of adding them to the built-in ones
+    resource_map: HashMap<String, Vec<PlanetType>>,
+    planets: Vec<Planet>,
+    characters: Vec<Character>,
+    jumps: Vec<(String, String, u32)>,
+}
+
+impl MemoryRepositoryBuilder {
+    /// Add products, overriding built-in products of the same name
+    pub fn with_products(mut self, products: Vec<Product>) -> Self {
+        self.products.extend(products);
+        self
+    }
+
+    /// Use exactly these products instead of the built-in database
+    pub fn with_product_set(mut self, products: Vec<Product>) -> Self {
+        self.products = products;
+        self.replace_products = true;
+        self
+    }
+
+    /// Override the planet types P0 resources are found on
+    pub fn with_resource_map(mut self, resource_map: HashMap<String, Vec<PlanetType>>) -> Self {
+        self.resource_map.extend(resource_map);
+        self
+    }
+
+    pub fn with_planets(mut self, planets: Vec<Planet>) -> Self {
+        self.planets.extend(planets);
+        self
+    }
+
+    pub fn with_characters(mut self, characters: Vec<Character>) -> Self {
+        self.characters.extend(characters);
+        self
+    }
+
+    /// Record the number of jumps between two solar systems, in either direction
+    pub fn with_jumps(mut self, from: &str, to: &str, jumps: u32) -> Self {
+        self.jumps.push((from.to_string(), to.to_string(), jumps));
+        self
+    }
+
+    /// Build the repository, failing on unknown ingredients, an invalid product database,
+    /// a bad resource map, or duplicate planets and characters
+    pub fn build(self) -> Result<MemoryRepository, RepositoryError> {
+        let mut repository = MemoryRepository::new();
+
+        let custom_products = self.replace_products || !self.products.is_empty();
+        if self.replace_products {
+            repository.replace_products_data(self.products)?;
+        } else if !self.products.is_empty() {
+            repository.load_products_data(self.products)?;
+        }
+        if custom_products {
+            let report = repository.validate();
+            if !report.is_valid() {
+                let issues: Vec<String> = report.issues.iter().map(|i| i.to_string()).collect();
+                error!("Invalid product database: {}", issues.join("; "));
+                return Err(RepositoryError::InvalidData(issues.join("; ")));
+            }
+        }
+
+        if !self.resource_map.is_empty() {
+            repository.load_resource_map_data(self.resource_map)?;
+        }
+        for planet in self.planets {
+            repository.add_planet(planet)?;
+        }
+        for character in self.characters {
+            repository.add_character(character)?;
+        }
+        for (from, to, jumps) in &self.jumps {
+            repository.set_jumps(from, to, *jumps);
+        }
+
+        info!(
+            "Built repository with {} planets and {} characters",
+            repository.planets.len(),
+            repository.characters.len()
+        );
+        Ok(repository)
+    }
+}
+
+impl ProductRepository for MemoryRepository {
+    fn iter_products(&self) -> Box<dyn Iterator<Item = &Product> + '_> {
+        Box::new(self.products.values())
+    }
+
+    fn get_product_ref(&self, name: &str) -> Option<&Product> {
+        if let Some(product) = self.products.get(name) {
+            return Some(product);
+        }
+
+        // Fall back to matching pasted in-game names like "Nano-Factory"
+        let normalized = normalize_name(name);
+        self.products.get(&normalized).or_else(|| {
+            self.products
+                .values()
+                .find(|p| normalize_name(&p.display_name) == normalized)
+        })
+    }
+
+    fn iter_products_by_tier(&self, tier: ProductTier) -> Box<dyn Iterator<Item = &Product> + '_> {
+        let names = self.tiers.get(&tier).map_or(&[][..], Vec::as_slice);
+        Box::new(names.iter().filter_map(|name| self.products.get(name)))
+    }
+
+    fn product_ids(&self) -> Arc<ProductIds> {
+        Arc::clone(&self.product_ids)
+    }
+
+    fn recipes(&self) -> Arc<Recipes> {
+        Arc::clone(&self.recipes)
+    }
+
+    fn dependency_graph(&self) -> Arc<DependencyGraph> {
+        Arc::clone(&self.dependencies)
     }
 }
 
 impl PlanetRepository for MemoryRepository {
-    fn get_all_planets(&self) -> Vec<Planet> {
-        self.planets.values().cloned().collect()
+    fn iter_planets(&self) -> Box<dyn Iterator<Item = &Planet> + '_> {
+        Box::new(self.planets.values())
     }
 
-    fn get_planet_by_id(&self, id: &str) -> Option<Planet> {
-        self.planets.get(id).cloned()
+    fn get_planet_ref(&self, id: &str) -> Option<&Planet> {
+        self.planets.get(id)
+    }
+
+    fn jumps_between(&self, from: &str, to: &str) -> Option<u32> {
+        if from == to {
+            return Some(0);
+        }
+        self.jumps.get(&(from.to_string(), to.to_string())).copied()
+    }
+
+    fn resource_map(&self) -> HashMap<String, Vec<PlanetType>> {
+        (*self.resource_map).clone()
+    }
+
+    fn resource_planet_types(&self, resource: &str) -> Option<Vec<PlanetType>> {
+        self.resource_map.get(resource).cloned()
     }
 }
 
 impl CharacterRepository for MemoryRepository {
-    fn get_all_characters(&self) -> Vec<Character> {
-        self.characters.values().cloned().collect()
+    fn iter_characters(&self) -> Box<dyn Iterator<Item = &Character> + '_> {
+        Box::new(self.characters.values())
     }
 
-    fn get_character_by_name(&self, name: &str) -> Option<Character> {
-        self.characters.get(name).cloned()
+    fn get_character_ref(&self, name: &str) -> Option<&Character> {
+        self.characters.get(name)
     }
 }
 
-impl Repository for MemoryRepository {}
+impl Repository for MemoryRepository {
+    fn compatibility(&self) -> Arc<CompatibilityMatrix> {
+        Arc::clone(&self.compatibility)
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -201,6 +1256,15 @@ mod tests {
                 "id": "planet_1",
                 "planet_type": "Barren",
                 "resources": ["base_metals", "heavy_metals"]
+            },
+            {
+                "id": "planet_2",
+                "planet_type": "Gas",
+                "resources": ["noble_gas"],
+                "system": "Jita",
+                "constellation": "Kimotoro",
+                "region": "The Forge",
+                "security_status": 0.9
             }
         ]"#;
 
@@ -208,8 +1272,15 @@ mod tests {
         assert!(result.is_ok(), "Failed to load basic planets: {:?}", result);
 
         let planets = repo.get_all_planets();
-        assert_eq!(planets.len(), 1);
-        assert_eq!(planets[0].id, "planet_1");
+        assert_eq!(planets.len(), 2);
+        let planet_1 = repo.get_planet_by_id("planet_1").unwrap();
+        assert!(planet_1.system.is_none() && planet_1.security_status.is_none());
+
+        // Location metadata is optional
+        let planet_2 = repo.get_planet_by_id("planet_2").unwrap();
+        assert_eq!(planet_2.system.as_deref(), Some("Jita"));
+        assert_eq!(planet_2.region.as_deref(), Some("The Forge"));
+        assert_eq!(planet_2.security_status, Some(0.9));
     }
 
     #[traced_test]
@@ -305,4 +1376,602 @@ mod tests {
         assert_eq!(characters[0].skills.command_center_upgrades, 5);
         assert_eq!(characters[0].skills.remote_sensing, Some(4));
     }
+
+    #[test]
+    fn test_display_names_and_normalized_lookup() {
+        let repo = MemoryRepository::new();
+
+        let nano = repo.get_product_by_name("nano_factory").unwrap();
+        assert_eq!(nano.display_name, "Nano-Factory");
+        assert_eq!(
+            repo.get_product_by_name("precious_metals")
+                .unwrap()
+                .display_name,
+            "Precious Metals"
+        );
+
+        for query in ["Nano-Factory", "nano factory", "  NANO_FACTORY "] {
+            assert_eq!(
+                repo.get_product_by_name(query).unwrap().name,
+                "nano_factory"
+            );
+        }
+        // Display names that differ from the key still resolve
+        assert_eq!(
+            repo.get_product_by_name("Ukomi Superconductors")
+                .unwrap()
+                .name,
+            "ukomi_super_conductors"
+        );
+        assert_eq!(
+            repo.get_product_by_name("Sterile Conduits").unwrap().name,
+            "sterile_conduit"
+        );
+        assert!(repo.get_product_by_name("Nano Factories").is_none());
+    }
+
+    #[test]
+    fn test_borrowed_accessors() {
+        let mut repo = MemoryRepository::new();
+        repo.load_planets(r#"[{"id": "P1", "planet_type": "Gas", "resources": ["noble_gas"]}]"#)
+            .unwrap();
+
+        assert_eq!(repo.iter_products().count(), repo.get_all_products().len());
+        let nano = repo.get_product_ref("Nano-Factory").unwrap();
+        assert!(std::ptr::eq(
+            nano,
+            repo.get_product_ref("nano_factory").unwrap()
+        ));
+        assert_eq!(repo.iter_planets().next().unwrap().id, "P1");
+        assert_eq!(
+            repo.get_planet_ref("P1").unwrap().planet_type,
+            PlanetType::Gas
+        );
+        assert!(repo.get_character_ref("Nobody").is_none());
+    }
+
+    #[test]
+    fn test_tier_index() {
+        let mut repo = MemoryRepository::new();
+        let p4: Vec<&str> = repo
+            .iter_products_by_tier(ProductTier::P4)
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(p4.len(), 8);
+        assert!(p4.windows(2).all(|w| w[0] < w[1]));
+
+        // The index follows product replacements
+        repo.replace_products_data(vec![Product::new_raw_material(
+            "aqueous_liquids".to_string(),
+        )])
+        .unwrap();
+        assert_eq!(repo.iter_products_by_tier(ProductTier::P0).count(), 1);
+        assert!(repo.get_products_by_tier(ProductTier::P4).is_empty());
+    }
+
+    #[test]
+    fn test_search_products() {
+        let repo = MemoryRepository::new();
+        let names = |query: &str| -> Vec<String> {
+            repo.search_products(query)
+                .into_iter()
+                .map(|p| p.name)
+                .collect()
+        };
+
+        assert_eq!(names("coolant")[0], "coolant");
+        assert_eq!(names("Nano-Factory")[0], "nano_factory");
+
+        // Prefixes come before word prefixes, and substrings still match
+        let results = names("bio");
+        let position = |name: &str| results.iter().position(|n| n == name).unwrap();
+        assert!(position("biocells") < position("gel_matrix_biopaste"));
+        assert!(position("biomass") < position("gel_matrix_biopaste"));
+        let results = names("cells");
+        assert_eq!(results, vec!["biocells".to_string()]);
+
+        let results = names("metals");
+        assert!(results.contains(&"precious_metals".to_string()));
+        assert!(results.contains(&"base_metals".to_string()));
+
+        // Letters in order still find the product
+        assert!(names("brdcst").contains(&"broadcast_node".to_string()));
+
+        assert!(names("").is_empty());
+        assert!(names("zzzz").is_empty());
+    }
+
+    #[test]
+    fn test_product_type_ids() {
+        let repo = MemoryRepository::new();
+
+        let products = repo.get_all_products();
+        let mut type_ids: Vec<u32> = products.iter().map(|p| p.type_id).collect();
+        type_ids.sort();
+        type_ids.dedup();
+        assert_eq!(type_ids.len(), products.len());
+        assert!(!type_ids.contains(&0));
+
+        let water = repo.get_product_by_type_id(3645).unwrap();
+        assert_eq!(water.name, "water");
+        let node = repo.get_product_by_name("broadcast_node").unwrap();
+        assert_eq!(repo.get_product_by_type_id(node.type_id), Some(node));
+        assert!(repo.get_product_by_type_id(0).is_none());
+        assert!(repo.get_product_by_type_id(1).is_none());
+    }
+
+    #[test]
+    fn test_load_products_overrides_metadata() {
+        let mut repo = MemoryRepository::new();
+        assert!(
+            repo.get_product_by_name("sterile_conduit")
+                .unwrap()
+                .requires_mining
+        );
+        let count = repo.get_all_products().len();
+
+        let products_json = r#"[
+            {
+                "name": "sterile_conduit",
+                "tier": "P4",
+                "schematic": {
+                    "inputs": [["smartfab_units", 6], ["vaccines", 6], ["water", 40]],
+                    "output_quantity": 1
+                }
+            },
+            {
+                "name": "broadcast_node",
+                "tier": "P4",
+                "schematic": {
+                    "inputs": [["neocoms", 6], ["data_chips", 6], ["high_tech_transmitters", 6]],
+                    "output_quantity": 1
+                },
+                "requires_mining": true
+            }
+        ]"#;
+        repo.load_products(products_json).unwrap();
+
+        assert_eq!(repo.get_all_products().len(), count);
+        assert!(
+            !repo
+                .get_product_by_name("sterile_conduit")
+                .unwrap()
+                .requires_mining
+        );
+        assert!(
+            repo.get_product_by_name("broadcast_node")
+                .unwrap()
+                .requires_mining
+        );
+
+        // Recipes must only use known products
+        let result = repo.load_products(
+            r#"[{
+                "name": "widget",
+                "tier": "P1",
+                "schematic": { "inputs": [["unobtainium", 3000]], "output_quantity": 20 }
+            }]"#,
+        );
+        assert!(
+            matches!(result, Err(RepositoryError::ProductNotFound(name)) if name == "unobtainium")
+        );
+        assert!(repo.get_product_by_name("widget").is_none());
+    }
+
+    #[test]
+    fn test_replace_products() {
+        let mut repo = MemoryRepository::new();
+        let products_json = r#"[
+            { "name": "aqueous_liquids", "tier": "P0" },
+            {
+                "name": "water",
+                "tier": "P1",
+                "schematic": { "inputs": [["aqueous_liquids", 3000]], "output_quantity": 20 }
+            }
+        ]"#;
+        repo.replace_products(products_json).unwrap();
+
+        assert_eq!(repo.get_all_products().len(), 2);
+        assert!(repo.get_product_by_name("coolant").is_none());
+        assert_eq!(
+            repo.get_product_by_name("aqueous_liquids")
+                .unwrap()
+                .display_name,
+            "Aqueous Liquids"
+        );
+
+        // A replacement can't lean on the products it replaces
+        let result = repo.replace_products(
+            r#"[{
+                "name": "water",
+                "tier": "P1",
+                "schematic": { "inputs": [["aqueous_liquids", 3000]], "output_quantity": 20 }
+            }]"#,
+        );
+        assert!(matches!(result, Err(RepositoryError::ProductNotFound(_))));
+        assert_eq!(repo.get_all_products().len(), 2);
+
+        repo.reset_products();
+        assert!(repo.get_product_by_name("coolant").is_some());
+    }
+
+    #[test]
+    fn test_load_resource_map() {
+        let mut repo = MemoryRepository::new();
+        assert!(repo
+            .resource_planet_types("aqueous_liquids")
+            .unwrap()
+            .contains(&PlanetType::Oceanic));
+
+        // A patch moves aqueous liquids off oceanic planets entirely
+        repo.load_resource_map(r#"{ "aqueous_liquids": ["Barren"] }"#)
+            .unwrap();
+        assert_eq!(
+            repo.resource_planet_types("aqueous_liquids"),
+            Some(vec![PlanetType::Barren])
+        );
+        assert!(repo.resource_planet_types("base_metals").is_some());
+        assert!(crate::factory::find_valid_factory_configurations(
+            &repo,
+            PlanetType::Oceanic,
+            "water"
+        )
+        .is_empty());
+        assert!(!crate::factory::find_valid_factory_configurations(
+            &repo,
+            PlanetType::Barren,
+            "water"
+        )
+        .is_empty());
+
+        // Only P0 resources can be mapped
+        let result = repo.load_resource_map(r#"{ "water": ["Oceanic"] }"#);
+        assert!(matches!(result, Err(RepositoryError::InvalidData(_))));
+        let result = repo.load_resource_map(r#"{ "unobtainium": ["Oceanic"] }"#);
+        assert!(matches!(result, Err(RepositoryError::ProductNotFound(_))));
+
+        repo.reset_resource_map();
+        assert!(repo
+            .resource_planet_types("aqueous_liquids")
+            .unwrap()
+            .contains(&PlanetType::Oceanic));
+    }
+
+    #[test]
+    fn test_builder() {
+        let planet = |id: &str| {
+            Planet::new(
+                id.to_string(),
+                PlanetType::Barren,
+                vec!["base_metals".to_string()],
+            )
+        };
+        let repo = MemoryRepository::builder()
+            .with_planets(vec![planet("planet_1"), planet("planet_2")])
+            .with_jumps("Jita", "Perimeter", 1)
+            .build()
+            .unwrap();
+        assert_eq!(repo.iter_planets().count(), 2);
+        assert_eq!(repo.jumps_between("Perimeter", "Jita"), Some(1));
+
+        // Any bad part fails the whole build
+        let duplicate = MemoryRepository::builder()
+            .with_planets(vec![planet("planet_1"), planet("planet_1")])
+            .build();
+        assert!(matches!(duplicate, Err(RepositoryError::InvalidData(_))));
+
+        let skipped_tier = MemoryRepository::builder()
+            .with_products(vec![Product::new(
+                "coolant".to_string(),
+                ProductTier::P2,
+                vec!["aqueous_liquids".to_string()],
+            )])
+            .with_planets(vec![planet("planet_1")])
+            .build();
+        assert!(
+            matches!(skipped_tier, Err(RepositoryError::InvalidData(msg)) if msg.contains("coolant"))
+        );
+    }
+
+    #[test]
+    fn test_planet_and_character_mutation() {
+        let mut repo = MemoryRepository::new();
+        let planet = Planet::new(
+            "planet_1".to_string(),
+            PlanetType::Barren,
+            vec!["base_metals".to_string()],
+        );
+        repo.add_planet(planet.clone()).unwrap();
+        assert!(matches!(
+            repo.add_planet(planet.clone()),
+            Err(RepositoryError::InvalidData(_))
+        ));
+
+        let mut edited = planet.clone();
+        edited.poco_tax_rate = Some(0.05);
+        repo.update_planet(edited).unwrap();
+        assert_eq!(
+            repo.get_planet_by_id("planet_1").unwrap().poco_tax_rate,
+            Some(0.05)
+        );
+        assert_eq!(repo.remove_planet("planet_1").unwrap().id, "planet_1");
+        assert!(repo.get_all_planets().is_empty());
+        assert!(matches!(
+            repo.update_planet(planet),
+            Err(RepositoryError::PlanetNotFound(_))
+        ));
+
+        let character: Character = serde_json::from_str(
+            r#"{"name": "Alice", "planets": 3, "skills": {"command_center_upgrades": 4, "interplanetary_consolidation": 2}}"#,
+        )
+        .unwrap();
+        repo.add_character(character.clone()).unwrap();
+        let mut edited = character;
+        edited.planets = 6;
+        repo.update_character(edited).unwrap();
+        assert_eq!(repo.get_character_by_name("Alice").unwrap().planets, 6);
+        repo.remove_character("Alice").unwrap();
+        assert!(matches!(
+            repo.remove_character("Alice"),
+            Err(RepositoryError::CharacterNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_lenient_load_reports_bad_records() {
+        let mut repo = MemoryRepository::new();
+        let planets_json = r#"[
+            {"id": "planet_1", "planet_type": "Barren", "resources": ["base_metals"]},
+            {"id": "planet_2", "resources": ["noble_gas"]},
+            {"id": "planet_3", "planet_type": "Gas", "resources": ["noble_gas", 7]},
+            {"id": "planet_4", "planet_type": "Oceanic", "resources": []}
+        ]"#;
+
+        // A strict load rejects the whole array
+        assert!(repo.load_planets(planets_json).is_err());
+        assert!(repo.get_all_planets().is_empty());
+
+        let report = repo.load_planets_lenient(planets_json).unwrap();
+        assert_eq!(report.loaded, 2);
+        assert!(!report.is_complete());
+        assert_eq!(report.errors.len(), 2);
+        assert_eq!(report.errors[0].index, 1);
+        assert_eq!(report.errors[0].field.as_deref(), Some("planet_type"));
+        assert_eq!(report.errors[1].index, 2);
+        assert_eq!(report.errors[1].field.as_deref(), Some("resources[1]"));
+        assert!(repo.get_planet_by_id("planet_4").is_some());
+
+        let report = repo
+            .load_characters_lenient(r#"[{"name": "Alice", "planets": "six"}]"#)
+            .unwrap();
+        assert_eq!(report.loaded, 0);
+        assert_eq!(report.errors[0].field.as_deref(), Some("planets"));
+
+        assert!(matches!(
+            repo.load_planets_lenient(r#"{"id": "planet_1"}"#),
+            Err(RepositoryError::DeserializationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_export_and_import_state() {
+        let mut repo = MemoryRepository::new();
+        repo.load_planets_lenient(
+            r#"[{"id": "planet_1", "planet_type": "Barren", "resources": ["base_metals"], "system": "Jita"}]"#,
+        )
+        .unwrap();
+        repo.load_characters_csv(
+            "name,planets,command_center_upgrades,interplanetary_consolidation\nAlice,6,5,5\n",
+        )
+        .unwrap();
+        repo.load_resource_map(r#"{"aqueous_liquids": ["Oceanic"]}"#)
+            .unwrap();
+        let mut water = repo.get_product_by_name("water").unwrap();
+        water.display_name = "Purified Water".to_string();
+        repo.load_products_data(vec![water]).unwrap();
+        repo.set_jumps("Jita", "Perimeter", 1);
+
+        let mut state = repo.export_state();
+        assert_eq!(state.version, STATE_VERSION);
+        assert_eq!(state.products.len(), 1);
+        assert!(state.removed_products.is_empty());
+        assert_eq!(state.resource_map.len(), 1);
+        assert_eq!(state.jumps.len(), 1);
+        state.prices.quotes.insert(
+            "water".to_string(),
+            crate::pricing::MarketQuote {
+                buy: Some(400.0),
+                sell: Some(500.0),
+            },
+        );
+
+        let json = state.to_json().unwrap();
+        let mut restored = MemoryRepository::new();
+        let prices = restored
+            .import_state(RepositoryState::from_json(&json).unwrap())
+            .unwrap();
+        assert_eq!(prices.quotes.len(), 1);
+        assert_eq!(restored.get_all_planets().len(), 1);
+        assert_eq!(restored.get_character_by_name("Alice").unwrap().planets, 6);
+        assert_eq!(
+            restored.get_product_by_name("water").unwrap().display_name,
+            "Purified Water"
+        );
+        assert_eq!(
+            restored.resource_planet_types("aqueous_liquids"),
+            Some(vec![PlanetType::Oceanic])
+        );
+        assert_eq!(restored.jumps_between("Perimeter", "Jita"), Some(1));
+        assert_eq!(
+            restored.export_state().to_json().unwrap(),
+            repo.export_state().to_json().unwrap()
+        );
+
+        // Newer layouts are rejected, and a bad state leaves the repository alone
+        let newer = json.replacen("\"version\":1", "\"version\":99", 1);
+        assert!(RepositoryState::from_json(&newer).is_err());
+        let mut bad = repo.export_state();
+        bad.resource_map
+            .insert("water".to_string(), vec![PlanetType::Barren]);
+        assert!(restored.import_state(bad).is_err());
+        assert_eq!(restored.get_all_planets().len(), 1);
+    }
+
+    #[test]
+    fn test_snapshot_and_branch() {
+        let mut repo = MemoryRepository::new();
+        repo.load_planets(
+            r#"[{"id": "planet_1", "planet_type": "Barren", "resources": ["base_metals"]}]"#,
+        )
+        .unwrap();
+
+        // Snapshots share data until one side changes
+        let snapshot = repo.snapshot();
+        assert!(Arc::ptr_eq(&snapshot.planets, &repo.planets));
+        assert!(Arc::ptr_eq(&snapshot.products, &repo.products));
+        repo.remove_planet("planet_1").unwrap();
+        assert_eq!(snapshot.get_all_planets().len(), 1);
+        assert!(repo.get_all_planets().is_empty());
+
+        let lava = Planet::new(
+            "Lava1".to_string(),
+            PlanetType::Lava,
+            vec!["felsic_magma".to_string()],
+        );
+        let branch = snapshot.branch(&[ScenarioChange::AddPlanet(lava)]);
+        assert_eq!(branch.get_all_planets().len(), 2);
+        assert_eq!(snapshot.get_all_planets().len(), 1);
+        assert!(Arc::ptr_eq(&branch.products, &snapshot.products));
+    }
+
+    #[test]
+    fn test_unknown_fields_are_warnings() {
+        let mut repo = MemoryRepository::new();
+        let report = repo
+            .load_planets(
+                r#"[
+                    {"id": "planet_1", "planet_type": "Barren", "resources": ["base_metals"], "color": "grey"},
+                    {"id": "planet_2", "planet_type": "Gas", "resources": ["noble_gas"]}
+                ]"#,
+            )
+            .unwrap();
+        assert_eq!(report.loaded, 2);
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.warnings[0].index, 0);
+        assert_eq!(report.warnings[0].field.as_deref(), Some("color"));
+
+        let report = repo
+            .load_characters(
+                r#"[{"name": "Alice", "planets": 6, "portrait": 1,
+                     "skills": {"command_center_upgrades": 5, "interplanetary_consolidation": 5, "drones": 3}}]"#,
+            )
+            .unwrap();
+        let fields: Vec<&str> = report
+            .warnings
+            .iter()
+            .filter_map(|w| w.field.as_deref())
+            .collect();
+        assert_eq!(fields, vec!["portrait", "skills.drones"]);
+
+        // A strict load still fails as a whole, naming the bad record
+        let err = repo
+            .load_planets(r#"[{"id": "planet_3", "planet_type": "Rocky", "resources": []}]"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("record 0, field planet_type"));
+        assert!(repo.get_planet_by_id("planet_3").is_none());
+    }
+
+    #[test]
+    fn test_out_of_range_tax_rates_are_rejected() {
+        let mut repo = MemoryRepository::new();
+        let report = repo
+            .load_planets(
+                r#"[
+                    {"id": "planet_1", "planet_type": "Barren", "resources": [], "poco_tax_rate": 1.5},
+                    {"id": "planet_2", "planet_type": "Gas", "resources": [], "poco_tax_rate": 0.05, "system": "Jita"}
+                ]"#,
+            )
+            .unwrap();
+        assert_eq!(report.loaded, 2);
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.warnings[0].index, 0);
+        assert_eq!(report.warnings[0].field.as_deref(), Some("poco_tax_rate"));
+        assert_eq!(
+            repo.get_planet_by_id("planet_1").unwrap().poco_tax_rate,
+            None
+        );
+        assert_eq!(
+            repo.get_planet_by_id("planet_2").unwrap().poco_tax_rate,
+            Some(0.05)
+        );
+
+        let report = repo
+            .load_characters_lenient(
+                r#"[{"name": "Alice", "planets": 6, "poco_tax_rate": -0.1,
+                     "skills": {"command_center_upgrades": 5, "interplanetary_consolidation": 5}}]"#,
+            )
+            .unwrap();
+        assert_eq!(report.loaded, 1);
+        assert_eq!(report.warnings[0].field.as_deref(), Some("poco_tax_rate"));
+        let alice = repo.get_character_by_name("Alice").unwrap();
+        assert_eq!(alice.poco_tax_rate, None);
+
+        // Setters refuse the rate instead of dropping it
+        for tax_rate in [-0.1, 1.5, f64::NAN] {
+            assert!(repo.set_system_tax_rate("Jita", tax_rate).is_err());
+            let mut edited = alice.clone();
+            edited.poco_tax_rate = Some(tax_rate);
+            assert!(matches!(
+                repo.update_character(edited),
+                Err(RepositoryError::InvalidData(_))
+            ));
+        }
+        assert_eq!(
+            repo.get_planet_by_id("planet_2").unwrap().poco_tax_rate,
+            Some(0.05)
+        );
+        let mut planet = Planet::new("planet_3".to_string(), PlanetType::Ice, Vec::new());
+        planet.poco_tax_rate = Some(2.0);
+        assert!(repo.add_planet(planet.clone()).is_err());
+        assert!(repo.load_planets_data(vec![planet]).is_err());
+        assert!(repo.get_planet_by_id("planet_3").is_none());
+    }
+
+    #[test]
+    fn test_invalid_program_hours_are_rejected() {
+        let mut repo = MemoryRepository::new();
+        let report = repo
+            .load_characters(
+                r#"[
+                    {"name": "Alice", "planets": 6, "program_hours": 0,
+                     "skills": {"command_center_upgrades": 5, "interplanetary_consolidation": 5}},
+                    {"name": "Bob", "planets": 6, "program_hours": 72,
+                     "skills": {"command_center_upgrades": 5, "interplanetary_consolidation": 5}}
+                ]"#,
+            )
+            .unwrap();
+        assert_eq!(report.loaded, 2);
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.warnings[0].index, 0);
+        assert_eq!(report.warnings[0].field.as_deref(), Some("program_hours"));
+        assert_eq!(
+            repo.get_character_by_name("Alice").unwrap().program_hours,
+            None
+        );
+
+        let bob = repo.get_character_by_name("Bob").unwrap();
+        assert_eq!(bob.program_hours, Some(72.0));
+        for hours in [-24.0, f64::NAN, f64::INFINITY] {
+            let mut edited = bob.clone();
+            edited.program_hours = Some(hours);
+            assert!(matches!(
+                repo.update_character(edited),
+                Err(RepositoryError::InvalidData(_))
+            ));
+        }
+        assert_eq!(
+            repo.get_character_by_name("Bob").unwrap().program_hours,
+            Some(72.0)
+        );
+    }
 }
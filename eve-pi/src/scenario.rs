@@ -0,0 +1,325 @@
+use crate::compatibility::CompatibilityMatrix;
+use crate::domain::{Character, Planet, PlanetType, Product, ProductTier, ProductionPlan};
+use crate::graph::DependencyGraph;
+use crate::intern::{ProductIds, Recipes};
+use crate::migration::ColonyMigration;
+use crate::quantity::QuotaTarget;
+use crate::repository::{CharacterRepository, PlanetRepository, ProductRepository, Repository};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A hypothetical change to the planets and characters available to the solver
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ScenarioChange {
+    /// Make another planet available
+    AddPlanet(Planet),
+    /// Let a character manage one more planet
+    AddPlanetSlot(String),
+    /// Bring in another character
+    AddCharacter(Character),
+    /// Lose access to a planet
+    RemovePlanet(String),
+    /// Stop using a character
+    RemoveCharacter(String),
+    /// Train a character's skill to a new level
+    SetSkill {
+        character: String,
+        skill: PlanetarySkill,
+        level: u8,
+    },
+}
+
+/// Planetary industry skills a scenario can change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlanetarySkill {
+    CommandCenterUpgrades,
+    /// Each level lets the character manage one more planet
+    InterplanetaryConsolidation,
+    RemoteSensing,
+    PlanetaryProduction,
+    Planetology,
+    AdvancedPlanetology,
+    CustomsCodeExpertise,
+}
+
+impl PlanetarySkill {
+    /// Set this skill on a character, updating the planets it can manage
+    fn train(self, character: &mut Character, level: u8) {
+        let skills = &mut character.skills;
+        match self {
+            PlanetarySkill::CommandCenterUpgrades => skills.command_center_upgrades = level,
+            PlanetarySkill::InterplanetaryConsolidation => {
+                let gained = level as isize - skills.interplanetary_consolidation as isize;
+                skills.interplanetary_consolidation = level;
+                character.planets = character.planets.saturating_add_signed(gained);
+            }
+            PlanetarySkill::RemoteSensing => skills.remote_sensing = Some(level),
+            PlanetarySkill::PlanetaryProduction => skills.planetary_production = Some(level),
+            PlanetarySkill::Planetology => skills.planetology = Some(level),
+            PlanetarySkill::AdvancedPlanetology => skills.advanced_planetology = Some(level),
+            PlanetarySkill::CustomsCodeExpertise => skills.customs_code_expertise = Some(level),
+        }
+    }
+}
+
+impl ScenarioChange {
+    /// Add a planet of the given type with every resource that type can have
+    pub fn add_planet_of_type(repository: &dyn Repository, planet_type: PlanetType) -> Self {
+        ScenarioChange::AddPlanet(hypothetical_planet(repository, planet_type, 1))
+    }
+}
+
+/// The `n`th made-up planet of a type, with every resource that type can have according to
+/// the repository's resource map
+pub fn hypothetical_planet(
+    repository: &dyn Repository,
+    planet_type: PlanetType,
+    n: usize,
+) -> Planet {
+    let mut resources: Vec<String> = repository
+        .resource_map()
+        .into_iter()
+        .filter(|(_, types)| types.contains(&planet_type))
+        .map(|(resource, _)| resource)
+        .collect();
+    resources.sort();
+
+    Planet::new(
+        format!("hypothetical_{:?}_{}", planet_type, n).to_lowercase(),
+        planet_type,
+        resources,
+    )
+}
+
+/// Difference between the current setup and a hypothetical one for the same quotas
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhatIfReport {
+    pub changes: Vec<ScenarioChange>,
+    pub feasible_before: bool, // Whether every target could be planned at all
+    pub feasible_after: bool,
+    pub coverage_before: f64, // Share of the quotas the planets and characters cover
+    pub coverage_after: f64,
+    pub output_before: Vec<QuotaTarget>, // Achievable units per day of each target
+    pub output_after: Vec<QuotaTarget>,
+    pub colonies: Vec<ColonyMigration>, // How the plan's colonies change between the two
+}
+
+impl WhatIfReport {
+    /// Change in the share of the quotas that can be met
+    pub fn coverage_delta(&self) -> f64 {
+        self.coverage_after - self.coverage_before
+    }
+}
+
+/// How a set of quotas fares on one branch of the repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchOutcome {
+    pub branch: String,
+    pub feasible: bool,
+    pub coverage: f64,            // Share of the quotas the branch covers
+    pub output: Vec<QuotaTarget>, // Achievable units per day of each target
+    pub plan: Option<ProductionPlan>,
+}
+
+/// Read-only view of a repository with hypothetical changes applied, leaving the
+/// underlying repository untouched
+pub struct ScenarioRepository<'a> {
+    base: &'a dyn Repository,
+    planets: Vec<Planet>,
+    characters: Vec<Character>,
+}
+
+impl<'a> ScenarioRepository<'a> {
+    /// Apply a set of changes on top of a repository
+    pub fn new(base: &'a dyn Repository, changes: &[ScenarioChange]) -> Self {
+        let mut planets = base.get_all_planets();
+        let mut characters = base.get_all_characters();
+
+        for change in changes {
+            match change {
+                ScenarioChange::AddPlanet(planet) => {
+                    planets.retain(|p| p.id != planet.id);
+                    planets.push(planet.clone());
+                }
+                ScenarioChange::AddPlanetSlot(name) => {
+                    if let Some(character) = characters.iter_mut().find(|c| c.name == *name) {
+                        character.planets += 1;
+                    }
+                }
+                ScenarioChange::AddCharacter(character) => {
+                    characters.retain(|c| c.name != character.name);
+                    characters.push(character.clone());
+                }
+                ScenarioChange::RemovePlanet(id) => planets.retain(|p| p.id != *id),
+                ScenarioChange::RemoveCharacter(name) => characters.retain(|c| c.name != *name),
+                ScenarioChange::SetSkill {
+                    character,
+                    skill,
+                    level,
+                } => {
+                    if let Some(character) = characters.iter_mut().find(|c| c.name == *character) {
+                        skill.train(character, *level);
+                    }
+                }
+            }
+        }
+
+        Self {
+            base,
+            planets,
+            characters,
+        }
+    }
+}
+
+impl ProductRepository for ScenarioRepository<'_> {
+    fn iter_products(&self) -> Box<dyn Iterator<Item = &Product> + '_> {
+        self.base.iter_products()
+    }
+
+    fn get_product_ref(&self, name: &str) -> Option<&Product> {
+        self.base.get_product_ref(name)
+    }
+
+    fn iter_products_by_tier(&self, tier: ProductTier) -> Box<dyn Iterator<Item = &Product> + '_> {
+        self.base.iter_products_by_tier(tier)
+    }
+
+    fn product_ids(&self) -> Arc<ProductIds> {
+        self.base.product_ids()
+    }
+
+    fn recipes(&self) -> Arc<Recipes> {
+        self.base.recipes()
+    }
+
+    fn dependency_graph(&self) -> Arc<DependencyGraph> {
+        self.base.dependency_graph()
+    }
+}
+
+impl PlanetRepository for ScenarioRepository<'_> {
+    fn iter_planets(&self) -> Box<dyn Iterator<Item = &Planet> + '_> {
+        Box::new(self.planets.iter())
+    }
+
+    fn get_planet_ref(&self, id: &str) -> Option<&Planet> {
+        self.planets.iter().find(|p| p.id == id)
+    }
+
+    fn jumps_between(&self, from: &str, to: &str) -> Option<u32> {
+        self.base.jumps_between(from, to)
+    }
+
+    fn resource_map(&self) -> HashMap<String, Vec<PlanetType>> {
+        self.base.resource_map()
+    }
+
+    fn resource_planet_types(&self, resource: &str) -> Option<Vec<PlanetType>> {
+        self.base.resource_planet_types(resource)
+    }
+}
+
+impl CharacterRepository for ScenarioRepository<'_> {
+    fn iter_characters(&self) -> Box<dyn Iterator<Item = &Character> + '_> {
+        Box::new(self.characters.iter())
+    }
+
+    fn get_character_ref(&self, name: &str) -> Option<&Character> {
+        self.characters.iter().find(|c| c.name == name)
+    }
+}
+
+impl Repository for ScenarioRepository<'_> {
+    fn compatibility(&self) -> Arc<CompatibilityMatrix> {
+        self.base.compatibility()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::MemoryRepository;
+
+    #[test]
+    fn test_scenario_leaves_base_untouched() {
+        let mut repo = MemoryRepository::new();
+        repo.load_characters(
+            r#"[{
+                "name": "Character1",
+                "planets": 1,
+                "skills": { "command_center_upgrades": 5, "interplanetary_consolidation": 0 }
+            }]"#,
+        )
+        .unwrap();
+
+        let scenario = ScenarioRepository::new(
+            &repo,
+            &[
+                ScenarioChange::add_planet_of_type(&repo, PlanetType::Gas),
+                ScenarioChange::AddPlanetSlot("Character1".to_string()),
+            ],
+        );
+
+        assert_eq!(scenario.get_all_planets().len(), 1);
+        assert_eq!(scenario.get_all_planets()[0].planet_type, PlanetType::Gas);
+        assert!(!scenario.get_all_planets()[0].resources.is_empty());
+        assert_eq!(
+            scenario
+                .get_character_by_name("Character1")
+                .unwrap()
+                .planets,
+            2
+        );
+
+        assert!(repo.get_all_planets().is_empty());
+        assert_eq!(repo.get_character_by_name("Character1").unwrap().planets, 1);
+    }
+
+    #[test]
+    fn test_scenario_removals_and_skills() {
+        let mut repo = MemoryRepository::new();
+        repo.load_characters(
+            r#"[
+                {
+                    "name": "Character1",
+                    "planets": 1,
+                    "skills": { "command_center_upgrades": 5, "interplanetary_consolidation": 0 }
+                },
+                {
+                    "name": "Character2",
+                    "planets": 1,
+                    "skills": { "command_center_upgrades": 5, "interplanetary_consolidation": 0 }
+                }
+            ]"#,
+        )
+        .unwrap();
+        repo.load_planets(
+            r#"[{ "id": "Gas1", "planet_type": "Gas", "resources": ["noble_gas"] }]"#,
+        )
+        .unwrap();
+
+        let scenario = ScenarioRepository::new(
+            &repo,
+            &[
+                ScenarioChange::RemovePlanet("Gas1".to_string()),
+                ScenarioChange::RemoveCharacter("Character2".to_string()),
+                ScenarioChange::SetSkill {
+                    character: "Character1".to_string(),
+                    skill: PlanetarySkill::InterplanetaryConsolidation,
+                    level: 4,
+                },
+            ],
+        );
+
+        assert!(scenario.get_all_planets().is_empty());
+        assert!(scenario.get_character_by_name("Character2").is_none());
+        let character = scenario.get_character_by_name("Character1").unwrap();
+        assert_eq!(character.planets, 5);
+        assert_eq!(character.skills.interplanetary_consolidation, 4);
+
+        assert_eq!(repo.get_all_planets().len(), 1);
+        assert_eq!(repo.get_character_by_name("Character1").unwrap().planets, 1);
+    }
+}
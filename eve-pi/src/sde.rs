@@ -0,0 +1,337 @@
+use crate::domain::{create_product_database, Product, ProductTier, Schematic};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// SDE inventory groups holding planetary resources and commodities, by tier
+const PI_GROUPS: [(u32, ProductTier); 7] = [
+    (1032, ProductTier::P0), // Planet Solid - Raw Resource
+    (1033, ProductTier::P0), // Planet Liquid-Gas - Raw Resource
+    (1035, ProductTier::P0), // Planet Organic - Raw Resource
+    (1042, ProductTier::P1), // Basic Commodities
+    (1034, ProductTier::P2), // Refined Commodities
+    (1040, ProductTier::P3), // Specialized Commodities
+    (1041, ProductTier::P4), // Advanced Commodities
+];
+
+/// Error importing the Static Data Export
+#[derive(Debug, thiserror::Error)]
+pub enum SdeError {
+    #[error("Failed to read SDE: {0}")]
+    Io(String),
+    #[error("Invalid SDE data: {0}")]
+    Parse(String),
+    #[error("No schematic makes {0}")]
+    MissingSchematic(String), // A commodity no schematic makes
+}
+
+/// Localized name of an SDE entry, English plus whichever other languages it's translated to
+#[derive(Debug, Deserialize)]
+struct SdeName {
+    en: String,
+    #[serde(flatten)]
+    translations: HashMap<String, String>, // Other language codes to names
+}
+
+/// Entry of `typeIDs.yaml`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SdeType {
+    #[serde(rename = "groupID")]
+    group_id: u32,
+    name: SdeName,
+    #[serde(default = "published_by_default")]
+    published: bool,
+}
+
+fn published_by_default() -> bool {
+    true
+}
+
+/// Input or output of an SDE schematic
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SchematicType {
+    is_input: bool,
+    quantity: u32,
+}
+
+/// Entry of `planetSchematics.yaml`
+#[derive(Debug, Deserialize)]
+struct SdeSchematic {
+    types: BTreeMap<u32, SchematicType>,
+}
+
+/// Product key for an SDE type. Types already in the built-in database keep their key, so
+/// saved plans and characters still resolve; new ones get a snake_case key from their name.
+fn product_key(known: &HashMap<u32, String>, type_id: u32, name: &str) -> String {
+    if let Some(key) = known.get(&type_id) {
+        return key.clone();
+    }
+    name.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Tier of a published SDE type, when it's a planetary commodity
+fn commodity_tier(sde_type: &SdeType) -> Option<ProductTier> {
+    if !sde_type.published {
+        return None;
+    }
+    PI_GROUPS
+        .iter()
+        .find(|(group, _)| *group == sde_type.group_id)
+        .map(|(_, tier)| *tier)
+}
+
+fn parse_types(type_ids: &str) -> Result<BTreeMap<u32, SdeType>, SdeError> {
+    serde_yaml::from_str(type_ids).map_err(|e| SdeError::Parse(e.to_string()))
+}
+
+/// Build the product database from the contents of the SDE's `typeIDs.yaml` and
+/// `planetSchematics.yaml`
+pub fn products_from_sde(type_ids: &str, schematics: &str) -> Result<Vec<Product>, SdeError> {
+    let types = parse_types(type_ids)?;
+    let schematics: BTreeMap<u32, SdeSchematic> =
+        serde_yaml::from_str(schematics).map_err(|e| SdeError::Parse(e.to_string()))?;
+
+    let known: HashMap<u32, String> = create_product_database()
+        .into_values()
+        .filter(|p| p.type_id != 0)
+        .map(|p| (p.type_id, p.name))
+        .collect();
+    let commodities: BTreeMap<u32, (String, &SdeType, ProductTier)> = types
+        .iter()
+        .filter_map(|(&type_id, t)| {
+            let tier = commodity_tier(t)?;
+            Some((type_id, (product_key(&known, type_id, &t.name.en), t, tier)))
+        })
+        .collect();
+
+    // Every schematic makes a single output from its inputs
+    let mut recipes: HashMap<u32, Schematic> = HashMap::new();
+    for schematic in schematics.values() {
+        let Some((&output, made)) = schematic.types.iter().find(|(_, t)| !t.is_input) else {
+            continue;
+        };
+        let inputs = schematic
+            .types
+            .iter()
+            .filter(|(_, t)| t.is_input)
+            .filter_map(|(type_id, t)| {
+                commodities
+                    .get(type_id)
+                    .map(|(key, _, _)| (key.clone(), t.quantity))
+            })
+            .collect();
+        recipes.insert(output, Schematic::new(inputs, made.quantity));
+    }
+
+    let mut products = Vec::new();
+    for (type_id, (key, sde_type, tier)) in &commodities {
+        let mut product = if *tier == ProductTier::P0 {
+            Product::new_raw_material(key.clone())
+        } else {
+            let schematic = recipes
+                .remove(type_id)
+                .ok_or_else(|| SdeError::MissingSchematic(sde_type.name.en.clone()))?;
+            let mut product =
+                Product::new(key.clone(), *tier, Vec::new()).with_schematic(schematic);
+            // P4 factories that take a P1 input have to mine it on the same planet
+            let p1_input = product.ingredients().iter().any(|ingredient| {
+                commodities
+                    .values()
+                    .any(|(k, _, t)| k == ingredient && *t == ProductTier::P1)
+            });
+            if *tier == ProductTier::P4 && p1_input {
+                product = product.with_required_mining();
+            }
+            product
+        }
+        .with_type_id(*type_id);
+        product.display_name = sde_type.name.en.clone();
+        products.push(product);
+    }
+    products.sort_by(|a, b| a.tier.cmp(&b.tier).then(a.name.cmp(&b.name)));
+    Ok(products)
+}
+
+/// Names of every planetary commodity in the contents of the SDE's `typeIDs.yaml`, by type ID
+/// and language code, ready for `Localization::load_product_names`
+pub fn product_names_from_sde(
+    type_ids: &str,
+) -> Result<HashMap<u32, HashMap<String, String>>, SdeError> {
+    Ok(parse_types(type_ids)?
+        .into_iter()
+        .filter(|(_, t)| commodity_tier(t).is_some())
+        .map(|(type_id, t)| {
+            let mut names = t.name.translations;
+            names.insert("en".to_string(), t.name.en);
+            (type_id, names)
+        })
+        .collect())
+}
+
+/// Build the product database from an extracted SDE, reading `fsd/typeIDs.yaml` and
+/// `fsd/planetSchematics.yaml`
+pub fn load_sde_products(sde_dir: &Path) -> Result<Vec<Product>, SdeError> {
+    let read = |file: &str| {
+        let path = sde_dir.join("fsd").join(file);
+        std::fs::read_to_string(&path)
+            .map_err(|e| SdeError::Io(format!("{}: {}", path.display(), e)))
+    };
+    products_from_sde(&read("typeIDs.yaml")?, &read("planetSchematics.yaml")?)
+}
+
+/// Product names in every language of an extracted SDE, reading `fsd/typeIDs.yaml`
+pub fn load_sde_product_names(
+    sde_dir: &Path,
+) -> Result<HashMap<u32, HashMap<String, String>>, SdeError> {
+    let path = sde_dir.join("fsd").join("typeIDs.yaml");
+    let type_ids = std::fs::read_to_string(&path)
+        .map_err(|e| SdeError::Io(format!("{}: {}", path.display(), e)))?;
+    product_names_from_sde(&type_ids)
+}
+
+/// Serialize products in the layout of the embedded dataset, one product per line
+pub fn products_to_json(products: &[Product]) -> Result<String, SdeError> {
+    let lines = products
+        .iter()
+        .map(|product| serde_json::to_string(product).map(|line| format!("  {}", line)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| SdeError::Parse(e.to_string()))?;
+    Ok(format!("[\n{}\n]\n", lines.join(",\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::{Language, Localization};
+
+    const TYPE_IDS: &str = r#"
+2268:
+    groupID: 1033
+    name:
+        de: Wässrige Flüssigkeiten
+        en: Aqueous Liquids
+    published: true
+2309:
+    groupID: 1033
+    name:
+        en: Ionic Solutions
+2390:
+    groupID: 1042
+    name:
+        en: Electrolytes
+3645:
+    groupID: 1042
+    name:
+        en: Water
+9832:
+    groupID: 1034
+    name:
+        en: Coolant
+34:
+    groupID: 18
+    name:
+        en: Tritanium
+"#;
+
+    const SCHEMATICS: &str = r#"
+121:
+    cycleTime: 1800
+    nameID:
+        en: Water
+    types:
+        2268: { isInput: true, quantity: 3000 }
+        3645: { isInput: false, quantity: 20 }
+122:
+    cycleTime: 1800
+    nameID:
+        en: Electrolytes
+    types:
+        2309: { isInput: true, quantity: 3000 }
+        2390: { isInput: false, quantity: 20 }
+66:
+    cycleTime: 3600
+    nameID:
+        en: Coolant
+    types:
+        2390: { isInput: true, quantity: 40 }
+        3645: { isInput: true, quantity: 40 }
+        9832: { isInput: false, quantity: 5 }
+"#;
+
+    #[test]
+    fn test_products_from_sde() {
+        let products = products_from_sde(TYPE_IDS, SCHEMATICS).unwrap();
+
+        // Tritanium isn't a planetary commodity
+        assert_eq!(products.len(), 5);
+        let coolant = products.iter().find(|p| p.name == "coolant").unwrap();
+        assert_eq!(coolant.tier, ProductTier::P2);
+        assert_eq!(coolant.type_id, 9832);
+        assert_eq!(coolant.display_name, "Coolant");
+        assert_eq!(coolant.schematic.output_quantity, 5);
+        assert_eq!(
+            coolant.schematic.inputs,
+            vec![("electrolytes".to_string(), 40), ("water".to_string(), 40)]
+        );
+
+        // The imported recipes match the embedded dataset
+        let built_in = create_product_database();
+        for product in &products {
+            assert_eq!(built_in[&product.name].tier, product.tier);
+            assert_eq!(built_in[&product.name].type_id, product.type_id);
+        }
+        assert_eq!(
+            built_in["water"].schematic,
+            products
+                .iter()
+                .find(|p| p.name == "water")
+                .unwrap()
+                .schematic
+        );
+    }
+
+    #[test]
+    fn test_commodity_without_schematic() {
+        let err = products_from_sde(TYPE_IDS, "{}").unwrap_err();
+        assert!(matches!(err, SdeError::MissingSchematic(_)));
+    }
+
+    #[test]
+    fn test_product_names_from_sde() {
+        let names = product_names_from_sde(TYPE_IDS).unwrap();
+
+        // Tritanium isn't a planetary commodity
+        assert_eq!(names.len(), 5);
+        assert_eq!(names[&2268]["de"], "Wässrige Flüssigkeiten");
+        assert_eq!(names[&2268]["en"], "Aqueous Liquids");
+        assert_eq!(names[&9832].len(), 1);
+
+        let mut localization = Localization::new();
+        localization.load_product_names(names);
+        let products = products_from_sde(TYPE_IDS, SCHEMATICS).unwrap();
+        let aqueous = products.iter().find(|p| p.type_id == 2268).unwrap();
+        assert_eq!(
+            localization.product_name(aqueous, Language::De),
+            "Wässrige Flüssigkeiten"
+        );
+        assert_eq!(
+            localization.product_name(aqueous, Language::Fr),
+            "Aqueous Liquids"
+        );
+    }
+
+    #[test]
+    fn test_products_to_json_round_trips() {
+        let products = products_from_sde(TYPE_IDS, SCHEMATICS).unwrap();
+        let json = products_to_json(&products).unwrap();
+        assert_eq!(json.lines().count(), products.len() + 2);
+        let parsed: Vec<Product> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, products);
+    }
+}
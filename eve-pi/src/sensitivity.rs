@@ -0,0 +1,98 @@
+use crate::quantity::{ProductRequirement, QuotaPlan, QuotaTarget};
+use crate::scenario::ScenarioChange;
+use serde::{Deserialize, Serialize};
+
+/// Effect of a single hypothetical addition on how much of a quota can be met
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Unlock {
+    pub change: ScenarioChange,
+    pub coverage: f64, // Share of the quota covered with the addition
+    pub gain: f64,     // Coverage gained over the current setup
+}
+
+/// Which planet or resource limits the output of a plan, and what would relieve it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensitivityReport {
+    pub targets: Vec<QuotaTarget>,
+    pub coverage: f64, // Share of the quota covered by the current planets and characters
+    pub bottleneck_product: Option<String>, // Product with the least of its demand covered
+    pub bottleneck_resources: Vec<String>, // P0 resources mined for the bottleneck product
+    pub unlocks: Vec<Unlock>, // Every single addition considered, most useful first
+}
+
+impl SensitivityReport {
+    /// The single addition that unlocks the most additional output, if any helps at all
+    pub fn binding_constraint(&self) -> Option<&Unlock> {
+        self.unlocks.first().filter(|unlock| unlock.gain > 0.0)
+    }
+}
+
+/// Share of a quota that the assigned planets can actually supply, limited by the
+/// product with the smallest share of its required planets
+pub fn quota_coverage(quota: &QuotaPlan) -> f64 {
+    quota
+        .requirements
+        .iter()
+        .filter(|r| r.planets_required > 0)
+        .map(|r| (r.planets_assigned as f64 / r.planets_required as f64).min(1.0))
+        .fold(1.0, f64::min)
+}
+
+/// Product whose demand is covered the least, if any falls short
+pub fn bottleneck_product(quota: &QuotaPlan) -> Option<&str> {
+    quota
+        .requirements
+        .iter()
+        .filter(|r| r.planets_assigned < r.planets_required)
+        .min_by(|a, b| {
+            let share =
+                |r: &ProductRequirement| r.planets_assigned as f64 / r.planets_required as f64;
+            share(a).total_cmp(&share(b))
+        })
+        .map(|r| r.product.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{ProductTier, ProductionPlan};
+    use crate::quantity::PlanetRole;
+
+    fn requirement(product: &str, required: usize, assigned: usize) -> ProductRequirement {
+        ProductRequirement {
+            product: product.to_string(),
+            tier: ProductTier::P1,
+            role: PlanetRole::Extraction,
+            daily_units: 0.0,
+            daily_output_per_planet: 0.0,
+            planets_required: required,
+            planets_assigned: assigned,
+        }
+    }
+
+    #[test]
+    fn test_quota_coverage_and_bottleneck() {
+        let quota = QuotaPlan {
+            targets: Vec::new(),
+            plan: ProductionPlan {
+                assignments: Vec::new(),
+                flows: Vec::new(),
+                schedule: None,
+            },
+            requirements: vec![
+                requirement("water", 2, 2),
+                requirement("electrolytes", 4, 1),
+                requirement("oxygen", 3, 2),
+                requirement("stockpiled", 0, 0),
+            ],
+            extraction_planets: 9,
+            factory_planets: 0,
+            purchased_inputs: Vec::new(),
+            schedule: Vec::new(),
+            sufficient: false,
+        };
+
+        assert_eq!(quota_coverage(&quota), 0.25);
+        assert_eq!(bottleneck_product(&quota), Some("electrolytes"));
+    }
+}
@@ -1,299 +1,2010 @@
+use crate::advisor::{
+    planet_type_combinations, AltEstimate, PlanetAcquisition, SkillAdvice, SkillUpgrade,
+    MAX_ADVISOR_ADDITIONS,
+};
+use crate::colocation::colocate_p2_factories;
+use crate::coverage::{collect_raw_resources, coverage_report, Producibility};
 use crate::domain::{
-    Character, FactoryConfiguration, Planet, PlanetAssignment, PlanetType, ProductTier,
-    ProductionPlan,
+    customs_base_value, is_valid_program_hours, Character, CharacterSkills, FactoryConfiguration,
+    Planet, PlanetAssignment, PlanetType, PlanetTypeSet, Product, ProductTier, ProductionPlan,
+};
+use crate::factory::{
+    choose_command_center_level, estimate_pins_on_planet,
+    find_valid_factory_configurations_for_planet, search_factory_configurations,
+};
+use crate::intern::{ProductId, ProductIds};
+use crate::logistics::{
+    import_volume_per_hour, in_service_range, launch_schedule, map_import_sources, LogisticsOptions,
+};
+use crate::migration::{plan_migration, same_inputs, ExistingColony, MigrationPlan};
+use crate::ordering::{sort_plan, PlanOrder};
+use crate::presets::ColonyPreset;
+use crate::pricing::{value_plan, PriceProvider, TradeSettings};
+use crate::priority::{DropReason, DroppedTarget, PrioritizedPlan};
+use crate::quantity::{
+    achievable_scale, assignment_daily_output, character_daily_output, factory_daily_output,
+    phase_demand, propagate_demand, richness_factor, PhasedDemand, PlanetRole, ProductRequirement,
+    QuotaPlan, QuotaTarget, Stockpile, MAX_PROGRAM_HOURS, MIN_PROGRAM_HOURS,
 };
-use crate::factory::factory_planet;
+use crate::recommend::{rank_recommendations, sort_rankings, ProductRanking, TargetRecommendation};
 use crate::repository::{Repository, RepositoryError};
-use std::collections::{HashMap, HashSet};
+use crate::scenario::{
+    hypothetical_planet, BranchOutcome, PlanetarySkill, ScenarioChange, ScenarioRepository,
+    WhatIfReport,
+};
+use crate::sensitivity::{bottleneck_product, quota_coverage, SensitivityReport, Unlock};
+use crate::tax::output_tax;
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tsify::Tsify;
+
+/// Search nodes visited between calls to a solver's cancel check
+const CANCEL_CHECK_INTERVAL: usize = 64;
 
 /// Error types for solver operations
-#[derive(Debug)]
+#[derive(Debug, thiserror::Error)]
 pub enum SolverError {
-    RepositoryError(RepositoryError),
+    #[error("Repository error: {0}")]
+    RepositoryError(#[from] RepositoryError),
+    #[error("Product not found: {0}")]
     ProductNotFound(String),
+    #[error("No solution found: {0}")]
     NoSolutionFound(String),
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    #[error("Solve was cancelled")]
+    Cancelled,
+}
+
+/// Optional constraints applied by the solver. Every constraint is off by default.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Tsify)]
+pub struct SolverOptions {
+    /// Maximum number of distinct imported products a single planet may have
+    #[serde(default)]
+    pub max_imports_per_planet: Option<usize>,
+    /// Maximum number of planets dedicated to producing the same output
+    #[serde(default)]
+    pub max_planets_per_product: Option<usize>,
+    /// Route the most valuable exports through the lowest customs tax: outputs worth the most
+    /// in customs value per day are assigned first, to the planets and characters paying the
+    /// least tax there. The first feasible plan in that order is returned.
+    #[serde(default)]
+    pub prefer_low_tax_characters: bool,
+    /// Order of assignments in the returned plan, for stable exports and diffs
+    #[serde(default)]
+    pub plan_order: Option<PlanOrder>,
+    /// Treat every product below this tier as bought from the market instead of produced
+    #[serde(default)]
+    pub buy_below_tier: Option<ProductTier>,
+    /// Products sourced externally; they appear as imports but never get their own planets
+    #[serde(default)]
+    pub purchased_products: HashSet<String>,
+    /// Merge P2 factories sharing imported ingredients onto the same planet
+    #[serde(default)]
+    pub colocate_factories: bool,
+    /// Only use these colony layouts; every layout is allowed when empty
+    #[serde(default)]
+    pub presets: Vec<ColonyPreset>,
+    /// Extractor program length in hours, a daily program when unset
+    #[serde(default)]
+    pub program_hours: Option<f64>,
+    /// Prefer factory setups that haul the least volume between planets
+    #[serde(default)]
+    pub minimize_hauling: bool,
+    /// Launchpad size and cadence limits used for the plan's visit schedule
+    #[serde(default)]
+    pub logistics: LogisticsOptions,
+}
+
+impl SolverOptions {
+    /// Limit how many distinct products a single planet may import
+    pub fn max_imports_per_planet(mut self, limit: usize) -> Self {
+        self.max_imports_per_planet = Some(limit);
+        self
+    }
+
+    /// Limit how many planets may be dedicated to the same output
+    pub fn max_planets_per_product(mut self, limit: usize) -> Self {
+        self.max_planets_per_product = Some(limit);
+        self
+    }
+
+    /// Give the outputs worth the most in customs value per day first pick of the planets and
+    /// characters paying the least customs tax
+    pub fn prefer_low_tax_characters(mut self) -> Self {
+        self.prefer_low_tax_characters = true;
+        self
+    }
+
+    /// Sort the assignments of returned plans
+    pub fn plan_order(mut self, order: PlanOrder) -> Self {
+        self.plan_order = Some(order);
+        self
+    }
+
+    /// Buy every product below the given tier from the market, only planning factories
+    /// for this tier and above
+    pub fn buy_below_tier(mut self, tier: ProductTier) -> Self {
+        self.buy_below_tier = Some(tier);
+        self
+    }
+
+    /// Source a specific product externally instead of producing it
+    pub fn purchase(mut self, product: &str) -> Self {
+        self.purchased_products.insert(product.to_string());
+        self
+    }
+
+    /// Share one factory planet between P2 products that import the same ingredients
+    pub fn colocate_factories(mut self) -> Self {
+        self.colocate_factories = true;
+        self
+    }
+
+    /// Restrict planets to a known colony layout, on top of any presets already selected
+    pub fn preset(mut self, preset: ColonyPreset) -> Self {
+        if !self.presets.contains(&preset) {
+            self.presets.push(preset);
+        }
+        self
+    }
+
+    /// Run extractors on programs of the given length, from 1 hour to 14 days. Hours that
+    /// aren't a positive, finite number leave extractors on a daily program.
+    pub fn program_hours(mut self, hours: f64) -> Self {
+        self.program_hours = Some(hours)
+            .filter(|hours| is_valid_program_hours(*hours))
+            .map(|hours| hours.clamp(MIN_PROGRAM_HOURS, MAX_PROGRAM_HOURS));
+        self
+    }
+
+    /// Favor setups that mine their inputs over ones hauling bulky imports in
+    pub fn minimize_hauling(mut self) -> Self {
+        self.minimize_hauling = true;
+        self
+    }
+
+    /// Schedule planet visits with these launchpad and cadence limits
+    pub fn logistics(mut self, options: LogisticsOptions) -> Self {
+        self.logistics = options;
+        self
+    }
+
+    /// Reject options deserialized with a value outside its valid range
+    pub fn validate(&self) -> Result<(), SolverError> {
+        if let Some(hours) = self.program_hours {
+            require_positive(hours, || "Program length".to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Whether an (intermediate) product is sourced externally rather than produced
+    pub fn is_purchased(&self, product: &Product) -> bool {
+        self.purchased_products.contains(&product.name)
+            || self
+                .buy_below_tier
+                .is_some_and(|cutoff| product.tier < cutoff)
+    }
+
+    /// Check whether a factory configuration satisfies these options
+    fn allows(&self, config: &FactoryConfiguration) -> bool {
+        if let Some(limit) = self.max_imports_per_planet {
+            let distinct_imports: HashSet<&String> = config.imported_inputs.iter().collect();
+            if distinct_imports.len() > limit {
+                return false;
+            }
+        }
+
+        if !self.presets.is_empty() && !self.presets.iter().any(|p| p.matches(config)) {
+            return false;
+        }
+
+        true
+    }
 }
 
-impl From<RepositoryError> for SolverError {
-    fn from(err: RepositoryError) -> Self {
-        SolverError::RepositoryError(err)
+/// Shared flag used to abort a running solve from another thread or callback
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a token that hasn't been cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask every solve using this token to stop as soon as possible
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Clear a previous cancellation so the token can be reused
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+
+    /// Whether cancellation was requested
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
     }
 }
 
 /// The main solver for generating production plans
 pub struct Solver<'a> {
     repository: &'a dyn Repository,
+    options: SolverOptions,
+    existing: Vec<ExistingColony>, // Colonies the search tries to keep as they are
+    cancellation: Option<CancellationToken>,
+    cancel_check: Option<Rc<dyn Fn() -> bool>>, // Polled every few search nodes
+    nodes_since_check: Cell<usize>,
 }
 
 impl<'a> Solver<'a> {
     /// Create a new solver with a repository
     pub fn new(repository: &'a dyn Repository) -> Self {
-        Self { repository }
+        Self::with_options(repository, SolverOptions::default())
+    }
+
+    /// Create a new solver with a repository and a set of constraints
+    pub fn with_options(repository: &'a dyn Repository, options: SolverOptions) -> Self {
+        Self {
+            repository,
+            options,
+            existing: Vec::new(),
+            cancellation: None,
+            cancel_check: None,
+            nodes_since_check: Cell::new(0),
+        }
+    }
+
+    /// Abort the search with `SolverError::Cancelled` once the token is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Abort the search with `SolverError::Cancelled` once a check returns true. The check
+    /// is polled every few search nodes, so it suits signals that are slower to read than
+    /// a token, such as a flag shared with JavaScript.
+    pub fn with_cancel_check(mut self, check: impl Fn() -> bool + 'static) -> Self {
+        self.cancel_check = Some(Rc::new(check));
+        self
     }
 
     /// Generate a production plan for a target product using backtracking
     pub fn solve(&self, target_product: &str) -> Result<ProductionPlan, SolverError> {
-        // Verify the target product exists
-        let _product = self
-            .repository
-            .get_product_by_name(target_product)
-            .ok_or_else(|| SolverError::ProductNotFound(target_product.to_string()))?;
+        self.solve_many(&[target_product])
+    }
 
-        // Get all available planets and characters
-        let _planets = self.repository.get_all_planets();
-        let _characters = self.repository.get_all_characters();
+    /// Generate a production plan for several target products sharing the same planets
+    /// and characters. Each target is planned as an independent chain, so a product needed
+    /// by several targets gets a dedicated planet per chain.
+    pub fn solve_many(&self, target_products: &[&str]) -> Result<ProductionPlan, SolverError> {
+        self.solve_chains(target_products, false)
+    }
 
-        // Start with empty state
-        let mut assignments = Vec::new();
-        let mut assigned_planets = HashSet::new();
-        let mut character_assignments: HashMap<String, Vec<String>> = HashMap::new();
-
-        // Collect all products we need to produce (starting with target)
-        let mut products_to_produce = HashSet::new();
-        self.collect_required_products(target_product, &mut products_to_produce)?;
-
-        // Try to solve using backtracking
-        if self.solve_recursive(
-            &products_to_produce.into_iter().collect::<Vec<_>>(),
-            0,
-            &mut assignments,
-            &mut assigned_planets,
-            &mut character_assignments,
-        ) {
-            Ok(ProductionPlan { assignments })
+    /// Plan as many targets as possible in priority order, most important first. A target
+    /// that doesn't fit alongside the ones before it is dropped and the rest still tried.
+    pub fn solve_prioritized(
+        &self,
+        target_products: &[&str],
+    ) -> Result<PrioritizedPlan, SolverError> {
+        let mut satisfied: Vec<&str> = Vec::new();
+        let mut dropped = Vec::new();
+        let mut plan = ProductionPlan {
+            assignments: Vec::new(),
+            flows: Vec::new(),
+            schedule: None,
+        };
+
+        for (priority, target) in target_products.iter().enumerate() {
+            let mut candidate = satisfied.clone();
+            candidate.push(target);
+
+            let reason = match self.solve_many(&candidate) {
+                Ok(candidate_plan) => {
+                    plan = candidate_plan;
+                    satisfied.push(target);
+                    continue;
+                }
+                Err(SolverError::ProductNotFound(_)) => DropReason::UnknownProduct,
+                Err(SolverError::NoSolutionFound(_)) => match self.solve(target) {
+                    Ok(_) => DropReason::OutOfCapacity,
+                    Err(SolverError::NoSolutionFound(_)) => DropReason::Infeasible,
+                    Err(err) => return Err(err),
+                },
+                Err(err) => return Err(err),
+            };
+            dropped.push(DroppedTarget {
+                product: target.to_string(),
+                priority,
+                reason,
+            });
+        }
+
+        Ok(PrioritizedPlan {
+            plan,
+            satisfied: satisfied.iter().map(|s| s.to_string()).collect(),
+            dropped,
+        })
+    }
+
+    /// Plan several targets either as independent chains, or as one shared chain in which
+    /// every intermediate product is produced once for all targets
+    fn solve_chains(
+        &self,
+        target_products: &[&str],
+        shared: bool,
+    ) -> Result<ProductionPlan, SolverError> {
+        // Verify the target products exist, resolving pasted in-game names to product keys
+        let target_names = target_products
+            .iter()
+            .map(|target| self.product_key(target))
+            .collect::<Result<Vec<String>, SolverError>>()?;
+        let target_products: Vec<&str> = target_names.iter().map(String::as_str).collect();
+
+        // Collect all products each chain needs to produce (starting with its target)
+        let mut work_items = Vec::new();
+        if shared {
+            let mut products_to_produce = HashSet::new();
+            for target_product in &target_products {
+                self.collect_required_products(target_product, &mut products_to_produce)?;
+            }
+            work_items.extend(products_to_produce.into_iter().map(|p| (0, p)));
+        } else {
+            for (chain, target_product) in target_products.iter().enumerate() {
+                let mut products_to_produce = HashSet::new();
+                self.collect_required_products(target_product, &mut products_to_produce)?;
+                work_items.extend(products_to_produce.into_iter().map(|p| (chain, p)));
+            }
+        }
+
+        // Assign the outputs exporting the most customs value first so they get the lowest-tax
+        // planets and characters, or the highest tiers first so factories can claim the
+        // planets able to mine their whole chain
+        if self.options.prefer_low_tax_characters {
+            let daily_value = |product: &String| {
+                self.repository.get_product_ref(product).map_or(0.0, |p| {
+                    factory_daily_output(p.tier) * customs_base_value(p.tier)
+                })
+            };
+            work_items.sort_by(|(_, a), (_, b)| daily_value(b).total_cmp(&daily_value(a)));
+        } else if self.options.minimize_hauling {
+            work_items.sort_by_key(|(_, product)| {
+                std::cmp::Reverse(self.repository.get_product_ref(product).map(|p| p.tier))
+            });
+        }
+
+        // Work out up front which planets can host each product, and fail fast when one
+        // can't be hosted anywhere
+        let mut state = SearchState::new(self.repository.product_ids());
+        let work_items = intern_work_items(&state.ids, work_items)?;
+        state.service = self.service_matrix();
+        state.hostable =
+            Rc::new(self.hostable_configurations(&state.ids, &work_items, &state.service));
+        if let Some((_, product)) = work_items
+            .iter()
+            .find(|(_, product)| !state.hostable.values().any(|h| h.contains_key(product)))
+        {
+            return Err(SolverError::NoSolutionFound(format!(
+                "No available planet can host {}",
+                state.ids.name(*product)
+            )));
+        }
+
+        // Try to solve using backtracking, starting with empty state
+        if self.solve_recursive(&work_items, 0, &mut state) {
+            let mut assignments = state.assignments;
+            if self.options.colocate_factories {
+                assignments = colocate_p2_factories(
+                    self.repository,
+                    assignments,
+                    self.options.max_imports_per_planet,
+                );
+            }
+            Ok(self.finish_plan(assignments))
+        } else if self.is_cancelled() {
+            Err(SolverError::Cancelled)
         } else {
             Err(SolverError::NoSolutionFound(format!(
                 "Could not find a complete solution for {}",
-                target_product
+                target_products.join(", ")
             )))
         }
     }
 
-    /// Collect all products that need to be produced (including dependencies)
-    fn collect_required_products(
+    /// Re-plan the products that were produced on a planet that is no longer available,
+    /// keeping every other assignment of the plan untouched
+    pub fn repair(
         &self,
-        product_name: &str,
-        products_to_produce: &mut HashSet<String>,
-    ) -> Result<(), SolverError> {
-        // Skip if already processed
-        if products_to_produce.contains(product_name) {
-            return Ok(());
+        plan: &ProductionPlan,
+        removed_planet_id: &str,
+    ) -> Result<ProductionPlan, SolverError> {
+        let (lost, kept): (Vec<PlanetAssignment>, Vec<PlanetAssignment>) = plan
+            .assignments
+            .iter()
+            .cloned()
+            .partition(|a| a.planet == removed_planet_id);
+        if lost.is_empty() {
+            return Ok(plan.clone());
         }
 
-        // Add this product to the set
-        products_to_produce.insert(product_name.to_string());
-
-        // Get the product details
-        let product = self
-            .repository
-            .get_product_by_name(product_name)
-            .ok_or_else(|| SolverError::ProductNotFound(product_name.to_string()))?;
-
-        // For each planet type, check what factory configurations are available
-        let planet_types = vec![
-            PlanetType::Barren,
-            PlanetType::Gas,
-            PlanetType::Ice,
-            PlanetType::Lava,
-            PlanetType::Oceanic,
-            PlanetType::Plasma,
-            PlanetType::Storm,
-            PlanetType::Temperate,
-        ];
+        // Kept assignments form chain 0 and the lost outputs are forced onto chain 1, so
+        // they are re-planned even when another planet also produces them
+        let mut state = SearchState::new(self.repository.product_ids());
+        for assignment in kept {
+            state.push(0, assignment);
+        }
+        // Reserve the lost planet so the search never picks it again
+        state.assigned_planets.insert(removed_planet_id.to_string());
 
-        let mut found_config = false;
-        for planet_type in planet_types {
-            let configs = factory_planet(self.repository, planet_type, product_name);
-            if !configs.is_empty() {
-                found_config = true;
-                // For the first valid config, collect imported inputs recursively
-                let config = &configs[0];
-                for imported_input in &config.imported_inputs {
-                    self.collect_required_products(imported_input, products_to_produce)?;
-                }
-                break; // Found at least one config, that's enough for collection
+        let lost_outputs: Vec<&str> = lost
+            .iter()
+            .flat_map(|a| a.outputs.iter().map(String::as_str))
+            .collect();
+        let mut work_items: Vec<(usize, String)> = Vec::new();
+        let mut upstream = HashSet::new();
+        for output in &lost_outputs {
+            if !work_items.iter().any(|(_, p)| p == output) {
+                work_items.push((1, output.to_string()));
             }
+            self.collect_required_products(output, &mut upstream)?;
         }
-
-        if !found_config {
-            return Err(SolverError::NoSolutionFound(format!(
-                "No factory configuration found for product: {}",
-                product_name
-            )));
+        for output in &lost_outputs {
+            upstream.remove(*output);
+        }
+        work_items.extend(upstream.into_iter().map(|p| (0, p)));
+        let work_items = intern_work_items(&state.ids, work_items)?;
+        state.service = self.service_matrix();
+        state.hostable =
+            Rc::new(self.hostable_configurations(&state.ids, &work_items, &state.service));
+
+        if self.solve_recursive(&work_items, 0, &mut state) {
+            Ok(self.finish_plan(state.assignments))
+        } else if self.is_cancelled() {
+            Err(SolverError::Cancelled)
+        } else {
+            Err(SolverError::NoSolutionFound(format!(
+                "Could not re-plan {} without planet {}",
+                lost_outputs.join(", "),
+                removed_planet_id
+            )))
         }
+    }
 
-        Ok(())
+    /// Plan the target products starting from colonies that are already set up, preferring
+    /// to keep them as they are, and report which colonies stay, change, or go away
+    pub fn migrate(
+        &self,
+        target_products: &[&str],
+        existing: &[ExistingColony],
+    ) -> Result<MigrationPlan, SolverError> {
+        let solver = Solver {
+            repository: self.repository,
+            options: self.options.clone(),
+            existing: existing.to_vec(),
+            cancellation: self.cancellation.clone(),
+            cancel_check: self.cancel_check.clone(),
+            nodes_since_check: Cell::new(0),
+        };
+        let plan = solver.solve_many(target_products)?;
+        Ok(plan_migration(self.repository, existing, plan))
     }
 
-    /// Recursive backtracking solver
-    fn solve_recursive(
+    /// Generate a plan sized to produce `units_per_day` of the target product, duplicating
+    /// extraction and factory planets as needed. The returned plan reports whether the
+    /// available planets and characters cover every requirement.
+    pub fn solve_quota(
         &self,
-        products: &[String],
-        product_index: usize,
-        assignments: &mut Vec<PlanetAssignment>,
-        assigned_planets: &mut HashSet<String>,
-        character_assignments: &mut HashMap<String, Vec<String>>,
-    ) -> bool {
-        // Base case: all products assigned
-        if product_index >= products.len() {
-            return true;
+        target_product: &str,
+        units_per_day: f64,
+    ) -> Result<QuotaPlan, SolverError> {
+        self.solve_quotas(&[(target_product, units_per_day)])
+    }
+
+    /// Generate a plan producing several end products in a fixed ratio, e.g.
+    /// `[("broadcast_node", 2.0), ("wetware_mainframe", 1.0)]`, with `sets_per_day`
+    /// copies of the ratio produced daily
+    pub fn solve_ratio(
+        &self,
+        ratio: &[(&str, f64)],
+        sets_per_day: f64,
+    ) -> Result<QuotaPlan, SolverError> {
+        require_positive(sets_per_day, || "Sets per day".to_string())?;
+        for (product, share) in ratio {
+            require_positive(*share, || format!("Ratio share of {}", product))?;
         }
 
-        let current_product = &products[product_index];
+        let targets: Vec<(&str, f64)> = ratio
+            .iter()
+            .map(|(product, share)| (*product, share * sets_per_day))
+            .collect();
+        self.solve_quotas(&targets)
+    }
+
+    /// Generate a plan sized to daily quotas of several target products. Intermediates
+    /// shared between targets are planned once and sized for their combined demand.
+    pub fn solve_quotas(&self, targets: &[(&str, f64)]) -> Result<QuotaPlan, SolverError> {
+        self.plan_quotas(targets, None)
+    }
 
-        // Skip if this product is already produced by an existing assignment
-        if assignments.iter().any(|a| a.output == *current_product) {
-            return self.solve_recursive(
-                products,
-                product_index + 1,
-                assignments,
-                assigned_planets,
-                character_assignments,
-            );
-        }
+    /// Generate a quota plan that first uses up the stockpile on hand. Products covered by
+    /// stock for the whole horizon get no planets, and the plan includes a schedule of when
+    /// each factory needs to come online.
+    pub fn solve_quotas_with_stockpile(
+        &self,
+        targets: &[(&str, f64)],
+        stockpile: &Stockpile,
+    ) -> Result<QuotaPlan, SolverError> {
+        self.plan_quotas(targets, Some(stockpile))
+    }
 
-        // Get all planets and characters
-        let planets = self.repository.get_all_planets();
-        let characters = self.repository.get_all_characters();
+    fn plan_quotas(
+        &self,
+        targets: &[(&str, f64)],
+        stockpile: Option<&Stockpile>,
+    ) -> Result<QuotaPlan, SolverError> {
+        if targets.is_empty() {
+            return Err(SolverError::InvalidInput(
+                "No quota targets given".to_string(),
+            ));
+        }
+        for (product, units_per_day) in targets {
+            require_positive(*units_per_day, || format!("Daily quota of {}", product))?;
+        }
 
-        // Try each planet
-        for planet in &planets {
-            // Skip already assigned planets
-            if assigned_planets.contains(&planet.id) {
-                continue;
+        // Resolve pasted in-game names so demand is keyed by product keys
+        let target_names = targets
+            .iter()
+            .map(|(product, _)| self.product_key(product))
+            .collect::<Result<Vec<String>, SolverError>>()?;
+        let targets: Vec<(&str, f64)> = target_names
+            .iter()
+            .zip(targets)
+            .map(|(name, (_, units))| (name.as_str(), *units))
+            .collect();
+        let targets = targets.as_slice();
+
+        // Solve the shared chain once, then scale every product up to its daily demand
+        let target_products: Vec<&str> = targets.iter().map(|(product, _)| *product).collect();
+        let base = self.solve_chains(&target_products, true)?;
+        let demand = propagate_demand(self.repository, &base.assignments, targets);
+        let phased = stockpile
+            .map(|stockpile| phase_demand(self.repository, &base.assignments, targets, stockpile));
+
+        let mut state = SearchState::new(self.repository.product_ids());
+        let mut requirements = Vec::new();
+        let mut templates = Vec::new();
+        for template in &base.assignments {
+            let tier = self
+                .repository
+                .get_product_by_name(template.output())
+                .ok_or_else(|| SolverError::ProductNotFound(template.output().to_string()))?
+                .tier;
+            let daily_output_per_planet = assignment_daily_output(self.repository, template);
+
+            let (daily_units, needed) = match phased.as_ref().and_then(|p| p.get(template.output()))
+            {
+                Some(phase) => (phase.daily_units, phase.units_to_produce > 0.0),
+                // A planet making several products splits its output evenly between them, so
+                // its most demanded output sets how much it has to make in total
+                None => (
+                    template
+                        .outputs
+                        .iter()
+                        .filter_map(|output| demand.get(output))
+                        .fold(0.0, |most: f64, units| most.max(*units))
+                        * template.outputs.len() as f64,
+                    true,
+                ),
+            };
+            let planets_required = if !needed {
+                0
+            } else if daily_output_per_planet > 0.0 {
+                ((daily_units / daily_output_per_planet) - 1e-9)
+                    .ceil()
+                    .max(1.0) as usize
+            } else {
+                1
+            };
+
+            if planets_required > 0 {
+                state.push(0, template.clone());
             }
+            templates.push((template, planets_required));
+            requirements.push(ProductRequirement {
+                product: template.output().to_string(),
+                tier,
+                role: if template.mined_inputs.is_empty() {
+                    PlanetRole::Factory
+                } else {
+                    PlanetRole::Extraction
+                },
+                daily_units,
+                daily_output_per_planet,
+                planets_required,
+                planets_assigned: planets_required.min(1),
+            });
+        }
 
-            // Get valid factory configurations for this planet
-            let configs = factory_planet(self.repository, planet.planet_type, current_product);
-            if configs.is_empty() {
-                continue;
+        // Duplicate planets until every product meets its demand or we run out of planets
+        for ((template, planets_required), requirement) in templates.iter().zip(&mut requirements) {
+            while requirement.planets_assigned < *planets_required {
+                match self.find_duplicate_assignment(template, &state) {
+                    Some(duplicate) => {
+                        state.push(0, duplicate);
+                        requirement.planets_assigned += 1;
+                    }
+                    None => break,
+                }
             }
+        }
 
-            // Try each configuration
-            for config in &configs {
-                // Try each character
-                for character in &characters {
-                    // Check if character has reached planet limit
-                    let current_planet_count = character_assignments
-                        .get(&character.name)
-                        .map(|planets| planets.len())
-                        .unwrap_or(0);
+        requirements.sort_by(|a, b| b.tier.cmp(&a.tier).then(a.product.cmp(&b.product)));
+
+        let planets_for = |role: PlanetRole| -> usize {
+            requirements
+                .iter()
+                .filter(|r| r.role == role)
+                .map(|r| r.planets_required)
+                .sum()
+        };
+        let extraction_planets = planets_for(PlanetRole::Extraction);
+        let factory_planets = planets_for(PlanetRole::Factory);
+        let sufficient = requirements
+            .iter()
+            .all(|r| r.planets_assigned >= r.planets_required);
 
-                    if current_planet_count >= character.planets {
-                        continue;
-                    }
+        // Demand for products no planet produces has to be bought from the market
+        let mut purchased_inputs: Vec<QuotaTarget> = demand
+            .iter()
+            .filter(|(product, _)| !base.assignments.iter().any(|a| a.produces(product)))
+            .map(|(product, units_per_day)| QuotaTarget {
+                product: product.clone(),
+                units_per_day: *units_per_day,
+            })
+            .collect();
+        purchased_inputs.sort_by(|a, b| a.product.cmp(&b.product));
+
+        let mut schedule: Vec<PhasedDemand> = phased
+            .map(|phased| phased.into_values().collect())
+            .unwrap_or_default();
+        schedule.sort_by(|a, b| {
+            a.online_after_days
+                .total_cmp(&b.online_after_days)
+                .then(a.product.cmp(&b.product))
+        });
+
+        Ok(QuotaPlan {
+            targets: targets
+                .iter()
+                .map(|(product, units_per_day)| QuotaTarget {
+                    product: product.to_string(),
+                    units_per_day: *units_per_day,
+                })
+                .collect(),
+            plan: self.finish_plan(state.assignments),
+            requirements,
+            extraction_planets,
+            factory_planets,
+            purchased_inputs,
+            schedule,
+            sufficient,
+        })
+    }
 
-                    // Check if all imported inputs are already being produced or can be produced
-                    let mut can_satisfy_inputs = true;
-                    for imported_input in &config.imported_inputs {
-                        // Check if this input is already being produced
-                        let already_produced =
-                            assignments.iter().any(|a| a.output == *imported_input);
-
-                        // If not already produced, check if it can be produced
-                        if !already_produced {
-                            let mut temp_products = products.to_vec();
-                            if !temp_products.contains(imported_input) {
-                                temp_products.push(imported_input.clone());
-                            }
-                            // This is a simplified check - we assume if the product is in our list, it can be produced
-                            if !temp_products.contains(imported_input) {
-                                can_satisfy_inputs = false;
-                                break;
-                            }
-                        }
-                    }
+    /// Find what limits a quota: solve it as is, then again with one more planet of each
+    /// type and one more planet slot for each character, ranking the additions by how much
+    /// of the quota they unlock
+    pub fn sensitivity(&self, targets: &[(&str, f64)]) -> Result<SensitivityReport, SolverError> {
+        let quota = self.coverage_with(targets, &[])?;
+        let coverage = quota.as_ref().map(quota_coverage).unwrap_or(0.0);
+
+        let bottleneck = quota
+            .as_ref()
+            .and_then(|q| bottleneck_product(q).map(|p| (q, p.to_string())));
+        let mut bottleneck_resources: Vec<String> = bottleneck
+            .as_ref()
+            .map(|(q, product)| {
+                q.plan
+                    .assignments
+                    .iter()
+                    .filter(|a| a.produces(product))
+                    .flat_map(|a| a.mined_inputs.iter().cloned())
+                    .collect()
+            })
+            .unwrap_or_default();
+        bottleneck_resources.sort();
+        bottleneck_resources.dedup();
+
+        let mut candidates: Vec<ScenarioChange> = PlanetType::ALL
+            .into_iter()
+            .map(|planet_type| ScenarioChange::add_planet_of_type(self.repository, planet_type))
+            .collect();
+        let mut characters = self.repository.get_all_characters();
+        characters.sort_by(|a, b| a.name.cmp(&b.name));
+        candidates.extend(
+            characters
+                .into_iter()
+                .map(|c| ScenarioChange::AddPlanetSlot(c.name)),
+        );
+
+        let mut unlocks = Vec::new();
+        for change in candidates {
+            let unlocked = self
+                .coverage_with(targets, std::slice::from_ref(&change))?
+                .as_ref()
+                .map(quota_coverage)
+                .unwrap_or(0.0);
+            unlocks.push(Unlock {
+                change,
+                coverage: unlocked,
+                gain: unlocked - coverage,
+            });
+        }
+        // Stable sort keeps planet additions ahead of character slots on ties
+        unlocks.sort_by(|a, b| b.gain.total_cmp(&a.gain));
+
+        Ok(SensitivityReport {
+            targets: targets
+                .iter()
+                .map(|(product, units_per_day)| QuotaTarget {
+                    product: product.to_string(),
+                    units_per_day: *units_per_day,
+                })
+                .collect(),
+            coverage,
+            bottleneck_product: bottleneck.map(|(_, product)| product),
+            bottleneck_resources,
+            unlocks,
+        })
+    }
 
-                    if !can_satisfy_inputs {
-                        continue;
-                    }
+    /// Compare the plan for a set of quotas against the plan after hypothetical changes to
+    /// planets, characters, or skills, without touching the repository
+    pub fn what_if(
+        &self,
+        targets: &[(&str, f64)],
+        changes: &[ScenarioChange],
+    ) -> Result<WhatIfReport, SolverError> {
+        let before = self.coverage_with(targets, &[])?;
+        let after = self.coverage_with(targets, changes)?;
+
+        let coverage_of = |quota: &Option<QuotaPlan>| quota.as_ref().map(quota_coverage);
+        let output = |coverage: f64| -> Vec<QuotaTarget> {
+            targets
+                .iter()
+                .map(|(product, units_per_day)| QuotaTarget {
+                    product: product.to_string(),
+                    units_per_day: units_per_day * coverage,
+                })
+                .collect()
+        };
+        let coverage_before = coverage_of(&before).unwrap_or(0.0);
+        let coverage_after = coverage_of(&after).unwrap_or(0.0);
+        let feasible_after = after.is_some();
+
+        // Diff the two plans as if the current one were already set up in game
+        let current: Vec<ExistingColony> = before
+            .as_ref()
+            .map(|q| {
+                q.plan
+                    .assignments
+                    .iter()
+                    .map(ExistingColony::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let planned = after.map(|q| q.plan).unwrap_or(ProductionPlan {
+            assignments: Vec::new(),
+            flows: Vec::new(),
+            schedule: None,
+        });
+        let colonies = plan_migration(self.repository, &current, planned).changes;
+
+        Ok(WhatIfReport {
+            changes: changes.to_vec(),
+            feasible_before: before.is_some(),
+            feasible_after,
+            coverage_before,
+            coverage_after,
+            output_before: output(coverage_before),
+            output_after: output(coverage_after),
+            colonies,
+        })
+    }
 
-                    // Try this assignment
-                    let assignment = PlanetAssignment {
-                        character: character.name.clone(),
-                        planet: planet.id.clone(),
-                        planet_type: planet.planet_type,
-                        imported_inputs: config.imported_inputs.clone(),
-                        mined_inputs: config.mined_inputs.clone(),
-                        output: current_product.clone(),
-                    };
+    /// Solve the same quotas on several branches of the repository, like snapshots with
+    /// different planets, using this solver's options
+    pub fn compare_branches(
+        &self,
+        targets: &[(&str, f64)],
+        branches: &[(&str, &dyn Repository)],
+    ) -> Result<Vec<BranchOutcome>, SolverError> {
+        branches
+            .iter()
+            .map(|(name, repository)| {
+                let solver = Solver {
+                    repository: *repository,
+                    options: self.options.clone(),
+                    existing: self.existing.clone(),
+                    cancellation: self.cancellation.clone(),
+                    cancel_check: self.cancel_check.clone(),
+                    nodes_since_check: Cell::new(0),
+                };
+                let quota = solver.coverage_with(targets, &[])?;
+                let coverage = quota.as_ref().map_or(0.0, quota_coverage);
+                Ok(BranchOutcome {
+                    branch: name.to_string(),
+                    feasible: quota.is_some(),
+                    coverage,
+                    output: targets
+                        .iter()
+                        .map(|(product, units_per_day)| QuotaTarget {
+                            product: product.to_string(),
+                            units_per_day: units_per_day * coverage,
+                        })
+                        .collect(),
+                    plan: quota.map(|q| q.plan),
+                })
+            })
+            .collect()
+    }
 
-                    // Make the assignment
-                    assignments.push(assignment);
-                    assigned_planets.insert(planet.id.clone());
-
-                    // Update character assignments
-                    character_assignments
-                        .entry(character.name.clone())
-                        .or_insert_with(Vec::new)
-                        .push(planet.id.clone());
-
-                    // Recursively try to solve the rest
-                    if self.solve_recursive(
-                        products,
-                        product_index + 1,
-                        assignments,
-                        assigned_planets,
-                        character_assignments,
-                    ) {
-                        return true; // Found a solution!
-                    }
+    /// Suggest the fewest extra planets, by type, that would make an infeasible target
+    /// solvable, trying every combination of up to `max_additions` planets. At most
+    /// `MAX_ADVISOR_ADDITIONS` planets can be added.
+    pub fn advise_planets(
+        &self,
+        target_product: &str,
+        max_additions: usize,
+    ) -> Result<PlanetAcquisition, SolverError> {
+        if max_additions > MAX_ADVISOR_ADDITIONS {
+            return Err(SolverError::InvalidInput(format!(
+                "cannot advise more than {} additional planets, got {}",
+                MAX_ADVISOR_ADDITIONS, max_additions
+            )));
+        }
+        let mut advice = PlanetAcquisition {
+            product: target_product.to_string(),
+            already_feasible: false,
+            solvable: false,
+            planet_types: Vec::new(),
+        };
+
+        // Only combinations able to mine every raw resource in the chain are worth solving
+        let mut resources = BTreeSet::new();
+        collect_raw_resources(self.repository, target_product, &mut resources);
+        let compatibility = self.repository.compatibility();
+        let existing_types: PlanetTypeSet = self
+            .repository
+            .iter_planets()
+            .map(|p| p.planet_type)
+            .collect();
+
+        for size in 0..=max_additions {
+            for planet_types in planet_type_combinations(size) {
+                let available =
+                    existing_types.union(planet_types.iter().copied().collect::<PlanetTypeSet>());
+                let minable = resources.iter().all(|resource| {
+                    self.is_purchased(resource)
+                        || compatibility
+                            .mining_types(resource)
+                            .is_some_and(|types| types.intersects(available))
+                });
+                if !minable {
+                    continue;
+                }
 
-                    // Backtrack: undo the assignment
-                    assignments.pop();
-                    assigned_planets.remove(&planet.id);
-
-                    // Remove from character assignments
-                    if let Some(character_planets) = character_assignments.get_mut(&character.name)
-                    {
-                        character_planets.pop();
-                        if character_planets.is_empty() {
-                            character_assignments.remove(&character.name);
-                        }
+                let changes: Vec<ScenarioChange> = planet_types
+                    .iter()
+                    .enumerate()
+                    .map(|(i, t)| {
+                        ScenarioChange::AddPlanet(hypothetical_planet(self.repository, *t, i + 1))
+                    })
+                    .collect();
+                let scenario = ScenarioRepository::new(self.repository, &changes);
+                let solver = Solver {
+                    repository: &scenario,
+                    options: self.options.clone(),
+                    existing: self.existing.clone(),
+                    cancellation: self.cancellation.clone(),
+                    cancel_check: self.cancel_check.clone(),
+                    nodes_since_check: Cell::new(0),
+                };
+                match solver.solve(target_product) {
+                    Ok(_) => {
+                        advice.already_feasible = size == 0;
+                        advice.solvable = true;
+                        advice.planet_types = planet_types;
+                        return Ok(advice);
                     }
+                    Err(SolverError::NoSolutionFound(_)) => {}
+                    Err(err) => return Err(err),
                 }
             }
         }
 
-        // No valid assignment found for this product
-        false
+        Ok(advice)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::domain::{Character, Planet, PlanetType, Product, ProductTier};
-    use crate::repository::{CharacterRepository, MemoryRepository};
-    use std::collections::{HashMap, HashSet};
+    /// Report, per character, how much one more level of Interplanetary Consolidation or
+    /// Command Center Upgrades would raise the achievable share of a set of quotas
+    pub fn advise_skills(&self, targets: &[(&str, f64)]) -> Result<SkillAdvice, SolverError> {
+        let coverage = self.skill_aware_coverage(targets, &[])?;
+
+        let mut characters = self.repository.get_all_characters();
+        characters.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut upgrades = Vec::new();
+        for character in characters {
+            for (skill, level) in [
+                (
+                    PlanetarySkill::InterplanetaryConsolidation,
+                    character.skills.interplanetary_consolidation,
+                ),
+                (
+                    PlanetarySkill::CommandCenterUpgrades,
+                    character.skills.command_center_upgrades,
+                ),
+            ] {
+                if level >= 5 {
+                    continue;
+                }
+                let change = ScenarioChange::SetSkill {
+                    character: character.name.clone(),
+                    skill,
+                    level: level + 1,
+                };
+                let coverage_after = self.skill_aware_coverage(targets, &[change])?;
+                upgrades.push(SkillUpgrade {
+                    character: character.name.clone(),
+                    skill,
+                    from_level: level,
+                    to_level: level + 1,
+                    coverage_before: coverage,
+                    coverage_after,
+                    gain: coverage_after - coverage,
+                });
+            }
+        }
+        upgrades.sort_by(|a, b| b.gain.total_cmp(&a.gain));
 
-    // Helper function to create a test repository with minimal data
-    fn create_test_repository() -> MemoryRepository {
-        let mut repo = MemoryRepository::new();
+        Ok(SkillAdvice { coverage, upgrades })
+    }
 
-        // Add some test characters as JSON
-        let characters_json = r#"[
-            {
-                "name": "Character1",
-                "planets": 2,
-                "skills": {
-                    "command_center_upgrades": 5,
-                    "interplanetary_consolidation": 2
+    /// Count the extra characters with the given skills needed to meet a set of quotas,
+    /// adding one at a time up to `max_characters`. Extra characters only add planet slots,
+    /// so their command center skill shows up in the achievable coverage rather than the count.
+    pub fn estimate_alts(
+        &self,
+        targets: &[(&str, f64)],
+        skills: &CharacterSkills,
+        max_characters: usize,
+    ) -> Result<AltEstimate, SolverError> {
+        let planets_per_character = 1 + skills.interplanetary_consolidation as usize;
+        let mut changes = Vec::new();
+
+        loop {
+            let scenario = ScenarioRepository::new(self.repository, &changes);
+            let solver = Solver {
+                repository: &scenario,
+                options: self.options.clone(),
+                existing: self.existing.clone(),
+                cancellation: self.cancellation.clone(),
+                cancel_check: self.cancel_check.clone(),
+                nodes_since_check: Cell::new(0),
+            };
+            let quota = match solver.solve_quotas(targets) {
+                Ok(quota) => Some(quota),
+                Err(SolverError::NoSolutionFound(_)) => None,
+                Err(err) => return Err(err),
+            };
+
+            let sufficient = quota.as_ref().is_some_and(|q| q.sufficient);
+            let (planets_required, limited_by_planets) = match &quota {
+                Some(quota) => {
+                    let required: usize =
+                        quota.requirements.iter().map(|r| r.planets_required).sum();
+                    let missing: usize = quota
+                        .requirements
+                        .iter()
+                        .map(|r| r.planets_required.saturating_sub(r.planets_assigned))
+                        .sum();
+                    let slots: usize = solver.repository.iter_characters().map(|c| c.planets).sum();
+                    // Duplication only stops early with a free slot when no planet fits
+                    let free_slots = slots.saturating_sub(quota.plan.assignments.len());
+                    (required, missing > 0 && free_slots > 0)
                 }
-            },
+                None => (0, false),
+            };
+
+            if sufficient || limited_by_planets || changes.len() >= max_characters {
+                let coverage = quota
+                    .map(|q| {
+                        achievable_scale(&scenario, &q.plan.assignments, targets, |a| {
+                            character_daily_output(&scenario, a)
+                        })
+                        .min(1.0)
+                    })
+                    .unwrap_or(0.0);
+                return Ok(AltEstimate {
+                    additional_characters: changes.len(),
+                    planets_per_character,
+                    planets_required,
+                    sufficient,
+                    limited_by_planets,
+                    coverage,
+                });
+            }
+
+            changes.push(ScenarioChange::AddCharacter(Character {
+                name: format!("Additional character {}", changes.len() + 1),
+                planets: planets_per_character,
+                skills: skills.clone(),
+                poco_tax_rate: None,
+                program_hours: None,
+                home_system: None,
+                service_range: None,
+            }));
+        }
+    }
+
+    /// Share of a set of quotas the planned planets can supply, given each owner's
+    /// command center skill
+    fn skill_aware_coverage(
+        &self,
+        targets: &[(&str, f64)],
+        changes: &[ScenarioChange],
+    ) -> Result<f64, SolverError> {
+        let scenario = ScenarioRepository::new(self.repository, changes);
+        let solver = Solver {
+            repository: &scenario,
+            options: self.options.clone(),
+            existing: self.existing.clone(),
+            cancellation: self.cancellation.clone(),
+            cancel_check: self.cancel_check.clone(),
+            nodes_since_check: Cell::new(0),
+        };
+        match solver.solve_quotas(targets) {
+            Ok(quota) => Ok(
+                achievable_scale(&scenario, &quota.plan.assignments, targets, |a| {
+                    character_daily_output(&scenario, a)
+                })
+                .min(1.0),
+            ),
+            Err(SolverError::NoSolutionFound(_)) => Ok(0.0),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Solve quotas with hypothetical changes applied, treating an infeasible chain as
+    /// covering none of the quota
+    fn coverage_with(
+        &self,
+        targets: &[(&str, f64)],
+        changes: &[ScenarioChange],
+    ) -> Result<Option<QuotaPlan>, SolverError> {
+        let scenario = ScenarioRepository::new(self.repository, changes);
+        let solver = Solver {
+            repository: &scenario,
+            options: self.options.clone(),
+            existing: self.existing.clone(),
+            cancellation: self.cancellation.clone(),
+            cancel_check: self.cancel_check.clone(),
+            nodes_since_check: Cell::new(0),
+        };
+        match solver.solve_quotas(targets) {
+            Ok(quota) => Ok(Some(quota)),
+            Err(SolverError::NoSolutionFound(_)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Rank the end products with a known price by feasibility and estimated daily profit,
+    /// returning the best `n`. Products the planets can't build at all are skipped.
+    pub fn recommend_targets(
+        &self,
+        n: usize,
+        prices: &dyn PriceProvider,
+    ) -> Result<Vec<TargetRecommendation>, SolverError> {
+        let mut recommendations = Vec::new();
+
+        for candidate in coverage_report(self.repository, None).products {
+            if candidate.status == Producibility::Impossible
+                || prices.price(&candidate.product).is_none()
             {
-                "name": "Character2",
-                "planets": 3,
-                "skills": {
-                    "command_center_upgrades": 5,
-                    "interplanetary_consolidation": 3
+                continue;
+            }
+
+            let recommendation = match self.solve(&candidate.product) {
+                Ok(plan) => self.estimate_profit(&candidate.product, &plan, prices),
+                Err(SolverError::NoSolutionFound(reason)) => {
+                    TargetRecommendation::infeasible(&candidate.product, candidate.tier, reason)
                 }
+                Err(err) => return Err(err),
+            };
+            recommendations.push(recommendation);
+        }
+
+        rank_recommendations(&mut recommendations);
+        recommendations.truncate(n);
+        Ok(recommendations)
+    }
+
+    /// Plan every P4 product, and every P3 one too when asked, and value each plan at the
+    /// given prices and trade settings. Returns the full table, feasible products first and
+    /// then by net ISK per hour; products the planets can't build at all are listed as
+    /// infeasible.
+    pub fn rank_products(
+        &self,
+        include_p3: bool,
+        prices: &dyn PriceProvider,
+        trade: &TradeSettings,
+    ) -> Result<Vec<ProductRanking>, SolverError> {
+        let mut rankings = Vec::new();
+
+        for candidate in coverage_report(self.repository, None).products {
+            let wanted = match candidate.tier {
+                ProductTier::P4 => true,
+                ProductTier::P3 => include_p3,
+                _ => false,
+            };
+            if !wanted {
+                continue;
+            }
+            if candidate.status == Producibility::Impossible {
+                rankings.push(ProductRanking::infeasible(
+                    &candidate.product,
+                    candidate.tier,
+                    "No planet can host its factory".to_string(),
+                ));
+                continue;
             }
-        ]"#;
 
-        // Add some test planets as JSON
-        let planets_json = r#"[
-            {
+            let ranking = match self.solve(&candidate.product) {
+                Ok(plan) => {
+                    let valuation = value_plan(self.repository, &plan, prices, trade);
+                    let units_per_hour = valuation
+                        .sales
+                        .iter()
+                        .filter(|s| s.product == candidate.product)
+                        .map(|s| s.units_per_hour)
+                        .sum();
+                    ProductRanking {
+                        product: candidate.product.clone(),
+                        tier: candidate.tier,
+                        feasible: true,
+                        planets: plan.assignments.len(),
+                        units_per_hour,
+                        revenue_per_hour: valuation.revenue_per_hour,
+                        costs_per_hour: valuation.revenue_per_hour - valuation.net_isk_per_hour,
+                        net_isk_per_hour: valuation.net_isk_per_hour,
+                        unpriced: valuation.unpriced,
+                        reason: None,
+                    }
+                }
+                Err(SolverError::NoSolutionFound(reason)) => {
+                    ProductRanking::infeasible(&candidate.product, candidate.tier, reason)
+                }
+                Err(err) => return Err(err),
+            };
+            rankings.push(ranking);
+        }
+
+        sort_rankings(&mut rankings);
+        Ok(rankings)
+    }
+
+    /// Estimate the daily economics of a plan producing a single target
+    fn estimate_profit(
+        &self,
+        target: &str,
+        plan: &ProductionPlan,
+        prices: &dyn PriceProvider,
+    ) -> TargetRecommendation {
+        let tier = self
+            .repository
+            .get_product_by_name(target)
+            .map(|p| p.tier)
+            .unwrap_or(ProductTier::P0);
+
+        // The chain runs as fast as its slowest link allows
+        let per_unit = propagate_demand(self.repository, &plan.assignments, &[(target, 1.0)]);
+        let planets_producing = |product: &str| {
+            plan.assignments
+                .iter()
+                .filter(|a| a.produces(product))
+                .count()
+        };
+        let daily_units =
+            achievable_scale(self.repository, &plan.assignments, &[(target, 1.0)], |a| {
+                assignment_daily_output(self.repository, a)
+            });
+
+        let daily_input_cost = per_unit
+            .iter()
+            .filter(|(product, _)| planets_producing(product) == 0)
+            .map(|(product, demand)| demand * daily_units * prices.price(product).unwrap_or(0.0))
+            .sum();
+
+        // Every planet imports its inputs and exports its share of its product's flow
+        // through a customs office
+        let daily_tax = plan
+            .assignments
+            .iter()
+            .map(|assignment| {
+                let output_units: Vec<(&str, f64)> = assignment
+                    .outputs
+                    .iter()
+                    .map(|output| {
+                        let units = per_unit.get(output).copied().unwrap_or(0.0) * daily_units
+                            / planets_producing(output) as f64;
+                        (output.as_str(), units)
+                    })
+                    .collect();
+                output_tax(self.repository, assignment, &output_units).daily_tax
+            })
+            .sum();
+
+        let daily_revenue = daily_units * prices.price(target).unwrap_or(0.0);
+
+        TargetRecommendation {
+            product: target.to_string(),
+            tier,
+            feasible: true,
+            planets: plan.assignments.len(),
+            daily_units,
+            daily_revenue,
+            daily_input_cost,
+            daily_tax,
+            daily_profit: daily_revenue - daily_input_cost - daily_tax,
+            reason: None,
+        }
+    }
+
+    /// Whether a product is bought from the market according to the solver options
+    fn is_purchased(&self, product_name: &str) -> bool {
+        self.repository
+            .get_product_ref(product_name)
+            .is_some_and(|product| self.options.is_purchased(product))
+    }
+
+    /// Whether the solve was cancelled through its token or cancel check
+    fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+            || self.cancel_check.as_ref().is_some_and(|check| check())
+    }
+
+    /// Cancellation as seen from a search node, polling the cancel check only once every
+    /// `CANCEL_CHECK_INTERVAL` nodes
+    fn search_cancelled(&self) -> bool {
+        let nodes = self.nodes_since_check.get();
+        self.nodes_since_check
+            .set((nodes + 1) % CANCEL_CHECK_INTERVAL);
+        if nodes == 0 {
+            self.is_cancelled()
+        } else {
+            self.cancellation
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled)
+        }
+    }
+
+    /// Key of a product given by key or by its in-game display name
+    fn product_key(&self, product: &str) -> Result<String, SolverError> {
+        self.repository
+            .get_product_by_name(product)
+            .map(|p| p.name)
+            .ok_or_else(|| SolverError::ProductNotFound(product.to_string()))
+    }
+
+    /// Existing colony on a planet producing the given product
+    fn existing_colony(&self, planet: &str, product: &str) -> Option<&ExistingColony> {
+        self.existing
+            .iter()
+            .find(|c| c.planet == planet && c.produces(product))
+    }
+
+    /// Build the final plan, applying the requested ordering
+    fn finish_plan(&self, assignments: Vec<PlanetAssignment>) -> ProductionPlan {
+        let mut plan = ProductionPlan {
+            assignments,
+            flows: Vec::new(),
+            schedule: None,
+        };
+        for assignment in &mut plan.assignments {
+            assignment.import_m3_per_hour = import_volume_per_hour(self.repository, assignment);
+        }
+        if let Some(order) = self.options.plan_order {
+            sort_plan(&mut plan, order, self.repository);
+        }
+        plan.flows = map_import_sources(self.repository, &plan.assignments);
+        plan.schedule = Some(launch_schedule(
+            &plan,
+            self.repository,
+            &self.options.logistics,
+        ));
+        plan
+    }
+
+    /// Find a free planet and character that can run another copy of an assignment
+    fn find_duplicate_assignment(
+        &self,
+        template: &PlanetAssignment,
+        state: &SearchState,
+    ) -> Option<PlanetAssignment> {
+        if let Some(limit) = self.options.max_planets_per_product {
+            let producing = state
+                .ids
+                .id(template.output())
+                .map_or(0, |id| state.planets_producing(id));
+            if producing >= limit {
+                return None;
+            }
+        }
+
+        let characters: Vec<&Character> = self.repository.iter_characters().collect();
+        let has_free_slot = |character: &Character| {
+            state
+                .character_assignments
+                .get(&character.name)
+                .map(|planets| planets.len())
+                .unwrap_or(0)
+                < character.planets
+        };
+
+        self.repository
+            .iter_planets()
+            .filter(|planet| !state.assigned_planets.contains(&planet.id))
+            .find_map(|planet| {
+                let configs = find_valid_factory_configurations_for_planet(
+                    self.repository,
+                    planet,
+                    template.output(),
+                );
+                let config = configs
+                    .iter()
+                    .filter(|c| self.options.allows(c))
+                    .find(|c| {
+                        same_inputs(&c.imported_inputs, &template.imported_inputs)
+                            && same_inputs(&c.mined_inputs, &template.mined_inputs)
+                    })?;
+                let character = characters.iter().find(|c| {
+                    has_free_slot(c)
+                        && self.can_service(c, planet)
+                        && estimate_pins_on_planet(
+                            self.repository,
+                            config,
+                            c.skills.command_center_upgrades,
+                            planet,
+                        )
+                        .is_ok()
+                })?;
+
+                let command_center_level = choose_command_center_level(
+                    self.repository,
+                    config,
+                    character.skills.command_center_upgrades,
+                    planet,
+                );
+
+                Some(PlanetAssignment {
+                    character: character.name.clone(),
+                    planet: planet.id.clone(),
+                    planet_type: planet.planet_type,
+                    imported_inputs: template.imported_inputs.clone(),
+                    mined_inputs: template.mined_inputs.clone(),
+                    outputs: template.outputs.clone(),
+                    program_hours: self.program_hours(character, &template.mined_inputs),
+                    command_center_level,
+                    import_m3_per_hour: HashMap::new(),
+                })
+            })
+    }
+
+    /// Characters allowed to colonize each planet, worked out once so the search doesn't
+    /// redo the ownership and service range checks at every node
+    fn service_matrix(&self) -> ServiceMatrix {
+        let characters: Vec<&Character> = self.repository.iter_characters().collect();
+        self.repository
+            .iter_planets()
+            .map(|planet| {
+                let allowed = characters
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| self.can_service(c, planet))
+                    .map(|(index, _)| index)
+                    .collect();
+                (planet.id.clone(), allowed)
+            })
+            .collect()
+    }
+
+    /// Configurations each planet could host for the work items, given its type, resources,
+    /// the solver options, and the pin budget of the best command center any character
+    /// allowed on it has. Planets and products without a single candidate are left out.
+    fn hostable_configurations(
+        &self,
+        ids: &ProductIds,
+        products: &[(usize, ProductId)],
+        service: &ServiceMatrix,
+    ) -> HostableConfigurations {
+        let characters: Vec<&Character> = self.repository.iter_characters().collect();
+
+        let mut hostable = HostableConfigurations::new();
+        for planet in self.repository.iter_planets() {
+            let Some(best_level) = service
+                .get(&planet.id)
+                .into_iter()
+                .flatten()
+                .map(|&index| characters[index].skills.command_center_upgrades)
+                .max()
+            else {
+                continue;
+            };
+            for &(_, product) in products {
+                if hostable
+                    .get(&planet.id)
+                    .is_some_and(|h| h.contains_key(&product))
+                {
+                    continue;
+                }
+                let configs: Vec<HostableConfiguration> =
+                    find_valid_factory_configurations_for_planet(
+                        self.repository,
+                        planet,
+                        ids.name(product),
+                    )
+                    .into_iter()
+                    .filter(|c| {
+                        self.options.allows(c)
+                            && estimate_pins_on_planet(self.repository, c, best_level, planet)
+                                .is_ok()
+                    })
+                    .map(|config| HostableConfiguration {
+                        outputs: config
+                            .outputs
+                            .iter()
+                            .filter_map(|output| ids.id(output))
+                            .collect(),
+                        richness: richness_factor(
+                            self.repository,
+                            planet,
+                            ids.name(product),
+                            &config.imported_inputs,
+                            &config.mined_inputs,
+                        ),
+                        command_center_levels: RefCell::default(),
+                        config,
+                    })
+                    .collect();
+                if !configs.is_empty() {
+                    hostable
+                        .entry(planet.id.clone())
+                        .or_default()
+                        .insert(product, configs);
+                }
+            }
+        }
+        hostable
+    }
+
+    /// Whether a character may colonize a planet: nobody else owns it and it's within the
+    /// range the character flies from home
+    fn can_service(&self, character: &Character, planet: &Planet) -> bool {
+        planet.assignable_to(&character.name)
+            && in_service_range(self.repository, character, planet)
+    }
+
+    /// Extractor program length of a planet, set by how often its character resets
+    /// extractors or else by the solver options. Planets that mine nothing have none.
+    fn program_hours(&self, character: &Character, mined_inputs: &[String]) -> Option<f64> {
+        if mined_inputs.is_empty() {
+            return None;
+        }
+        character
+            .program_hours
+            .filter(|hours| is_valid_program_hours(*hours))
+            .or(self.options.program_hours)
+            .filter(|hours| is_valid_program_hours(*hours))
+            .map(|hours| hours.clamp(MIN_PROGRAM_HOURS, MAX_PROGRAM_HOURS))
+    }
+
+    /// Best deposit richness a planet offers across the configurations it can host for a
+    /// product, 1.0 when it mines nothing or hasn't been scanned
+    fn deposit_richness(&self, state: &SearchState, planet: &Planet, product: ProductId) -> f64 {
+        state
+            .hostable
+            .get(&planet.id)
+            .and_then(|h| h.get(&product))
+            .into_iter()
+            .flatten()
+            .map(|hostable| hostable.richness)
+            .reduce(f64::max)
+            .unwrap_or(1.0)
+    }
+
+    /// Collect all products that need to be produced (including dependencies)
+    fn collect_required_products(
+        &self,
+        product_name: &str,
+        products_to_produce: &mut HashSet<String>,
+    ) -> Result<(), SolverError> {
+        // Skip if already processed
+        if products_to_produce.contains(product_name) {
+            return Ok(());
+        }
+
+        // Add this product to the set
+        products_to_produce.insert(product_name.to_string());
+
+        // Verify the product exists
+        if !self.repository.dependency_graph().contains(product_name) {
+            return Err(SolverError::ProductNotFound(product_name.to_string()));
+        }
+
+        // For each planet type, check what factory configurations are available
+        let planet_types = vec![
+            PlanetType::Barren,
+            PlanetType::Gas,
+            PlanetType::Ice,
+            PlanetType::Lava,
+            PlanetType::Oceanic,
+            PlanetType::Plasma,
+            PlanetType::Storm,
+            PlanetType::Temperate,
+        ];
+
+        let mut found_config = false;
+        let mut reasons: Vec<String> = Vec::new();
+        for planet_type in planet_types {
+            let search = search_factory_configurations(self.repository, planet_type, product_name);
+            for reason in search.reasons() {
+                if !reasons.contains(&reason) {
+                    reasons.push(reason);
+                }
+            }
+            if !search.configurations.is_empty()
+                && !search.configurations.iter().any(|c| self.options.allows(c))
+            {
+                let reason = "every configuration is excluded by the solver options".to_string();
+                if !reasons.contains(&reason) {
+                    reasons.push(reason);
+                }
+            }
+
+            if let Some(config) = search
+                .configurations
+                .iter()
+                .find(|c| self.options.allows(c))
+            {
+                found_config = true;
+                // For the first valid config, collect imported inputs recursively
+                for imported_input in &config.imported_inputs {
+                    if !self.is_purchased(imported_input) {
+                        self.collect_required_products(imported_input, products_to_produce)?;
+                    }
+                }
+                break; // Found at least one config, that's enough for collection
+            }
+        }
+
+        if !found_config {
+            return Err(SolverError::NoSolutionFound(format!(
+                "No factory configuration found for product: {} ({})",
+                product_name,
+                reasons.join("; ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Recursive backtracking solver over (chain, product) work items
+    fn solve_recursive(
+        &self,
+        products: &[(usize, ProductId)],
+        product_index: usize,
+        state: &mut SearchState,
+    ) -> bool {
+        // Base case: all products assigned
+        if product_index >= products.len() {
+            return true;
+        }
+
+        // Give up once cancelled; callers report it instead of a missing solution
+        if self.search_cancelled() {
+            return false;
+        }
+
+        let (chain, current) = products[product_index];
+        let ids = Arc::clone(&state.ids);
+        let hostable = Rc::clone(&state.hostable);
+        let current_product = ids.name(current);
+
+        // Skip if this product is already produced by an existing assignment in this chain
+        if state.produces(chain, current) {
+            return self.solve_recursive(products, product_index + 1, state);
+        }
+
+        // Respect the cap on planets dedicated to a single product
+        if let Some(limit) = self.options.max_planets_per_product {
+            if state.planets_producing(current) >= limit {
+                return false;
+            }
+        }
+
+        // Get all planets and characters, trying planets already producing this product first
+        let characters: Vec<&Character> = self.repository.iter_characters().collect();
+        let mut planets: Vec<&Planet> = self.repository.iter_planets().collect();
+        if self.options.prefer_low_tax_characters {
+            // Planets some character pays the least tax on first, before the other orderings
+            let lowest_tax = |planet: &Planet| {
+                characters
+                    .iter()
+                    .map(|c| c.tax_rate_on(planet))
+                    .fold(f64::INFINITY, f64::min)
+            };
+            planets.sort_by(|a, b| lowest_tax(a).total_cmp(&lowest_tax(b)));
+        }
+        if planets.iter().any(|planet| !planet.richness.is_empty()) {
+            // Richer scanned deposits first
+            let mut by_richness: Vec<(f64, &Planet)> = planets
+                .into_iter()
+                .map(|planet| (self.deposit_richness(state, planet, current), planet))
+                .collect();
+            by_richness.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+            planets = by_richness.into_iter().map(|(_, planet)| planet).collect();
+        }
+        if self.options.minimize_hauling {
+            // Planets whose lightest setup hauls the least in first
+            let lightest_imports = |planet: &Planet| {
+                state
+                    .hostable
+                    .get(&planet.id)
+                    .and_then(|h| h.get(&current))
+                    .into_iter()
+                    .flatten()
+                    .map(|h| h.config.cargo.import_m3_per_cycle)
+                    .fold(f64::INFINITY, f64::min)
+            };
+            planets.sort_by(|a, b| lightest_imports(a).total_cmp(&lightest_imports(b)));
+        }
+        planets.sort_by_key(|planet| self.existing_colony(&planet.id, current_product).is_none());
+
+        // Try each planet
+        for &planet in &planets {
+            // Skip already assigned planets
+            if state.assigned_planets.contains(&planet.id) {
+                continue;
+            }
+
+            // Only consider configurations the pre-filter found this planet can host
+            let Some(configs) = hostable.get(&planet.id).and_then(|h| h.get(&current)) else {
+                continue;
+            };
+
+            // Multi-output configurations only pay off when the chain still needs every extra
+            // output, in which case they're tried first so one planet covers them all
+            let still_needed =
+                |id: &ProductId| products.contains(&(chain, *id)) && !state.produces(chain, *id);
+            let mut order: Vec<usize> = (0..configs.len())
+                .filter(|&index| {
+                    configs[index]
+                        .outputs
+                        .iter()
+                        .filter(|&&output| output != current)
+                        .all(still_needed)
+                })
+                .collect();
+            if self.options.minimize_hauling {
+                order.sort_by(|&a, &b| {
+                    let imports = |index: usize| configs[index].config.cargo.import_m3_per_cycle;
+                    imports(a).total_cmp(&imports(b))
+                });
+            }
+            order.sort_by_key(|&index| std::cmp::Reverse(configs[index].outputs.len()));
+            if order.is_empty() {
+                continue;
+            }
+
+            // An existing colony's setup and owner are tried before anything else
+            let mut planet_characters: Vec<&Character> = state
+                .service
+                .get(&planet.id)
+                .into_iter()
+                .flatten()
+                .map(|&index| characters[index])
+                .collect();
+            if self.options.prefer_low_tax_characters {
+                // The rate the planet's customs office actually charges each character
+                planet_characters
+                    .sort_by(|a, b| a.tax_rate_on(planet).total_cmp(&b.tax_rate_on(planet)));
+            }
+            if let Some(colony) = self.existing_colony(&planet.id, current_product) {
+                order.sort_by_key(|&index| {
+                    let c = &configs[index].config;
+                    !(same_inputs(&c.outputs, &colony.outputs)
+                        && same_inputs(&c.imported_inputs, &colony.imported_inputs)
+                        && same_inputs(&c.mined_inputs, &colony.mined_inputs))
+                });
+                planet_characters.sort_by_key(|c| c.name != colony.character);
+            }
+
+            // Try each configuration
+            for &index in &order {
+                let HostableConfiguration {
+                    config, outputs, ..
+                } = &configs[index];
+
+                // Try each character
+                for character in &planet_characters {
+                    // Check if character has reached planet limit
+                    let current_planet_count = state
+                        .character_assignments
+                        .get(&character.name)
+                        .map(|planets| planets.len())
+                        .unwrap_or(0);
+
+                    if current_planet_count >= character.planets {
+                        continue;
+                    }
+
+                    // Skip characters whose command center can't power the layout
+                    let Some(command_center_level) = configs[index].command_center_level(
+                        self.repository,
+                        planet,
+                        character.skills.command_center_upgrades,
+                    ) else {
+                        continue;
+                    };
+
+                    // Try this assignment
+                    let assignment = PlanetAssignment {
+                        character: character.name.clone(),
+                        planet: planet.id.clone(),
+                        planet_type: planet.planet_type,
+                        imported_inputs: config.imported_inputs.clone(),
+                        mined_inputs: config.mined_inputs.clone(),
+                        // The product being solved for comes first, then anything
+                        // else the configuration makes
+                        outputs: std::iter::once(current_product)
+                            .chain(
+                                config
+                                    .outputs
+                                    .iter()
+                                    .map(String::as_str)
+                                    .filter(|o| *o != current_product),
+                            )
+                            .map(str::to_string)
+                            .collect(),
+                        program_hours: self.program_hours(character, &config.mined_inputs),
+                        command_center_level,
+                        import_m3_per_hour: HashMap::new(),
+                    };
+
+                    // Make the assignment and recursively try to solve the rest
+                    state.push_interned(chain, assignment, outputs.clone());
+                    if self.solve_recursive(products, product_index + 1, state) {
+                        return true; // Found a solution!
+                    }
+
+                    // Backtrack: undo the assignment
+                    state.pop();
+                }
+            }
+        }
+
+        // No valid assignment found for this product
+        false
+    }
+}
+
+/// Characters allowed to colonize each planet, as indices into the repository's character
+/// order, keyed by planet id
+type ServiceMatrix = HashMap<String, Vec<usize>>;
+
+/// Configurations each planet can host, keyed by planet id and then by product
+type HostableConfigurations = HashMap<String, HashMap<ProductId, Vec<HostableConfiguration>>>;
+
+/// A configuration a planet can host, along with what the search needs to try it, so the
+/// search doesn't redo it at every node
+struct HostableConfiguration {
+    config: FactoryConfiguration,
+    outputs: Vec<ProductId>, // The configuration's outputs, interned
+    richness: f64,           // Deposit richness of its mined inputs on the planet
+    command_center_levels: RefCell<Vec<(u8, Option<Option<u8>>)>>, // Sized per skill on first use
+}
+
+impl HostableConfiguration {
+    /// Command center level a character with a Command Center Upgrades skill needs on the
+    /// planet, or None when the layout doesn't fit at that skill. Sizing the layout is the
+    /// costly part of trying an assignment, so it's done once per skill level.
+    fn command_center_level(
+        &self,
+        repository: &dyn Repository,
+        planet: &Planet,
+        skill: u8,
+    ) -> Option<Option<u8>> {
+        let cached = self
+            .command_center_levels
+            .borrow()
+            .iter()
+            .find(|(s, _)| *s == skill)
+            .map(|(_, level)| *level);
+        if let Some(level) = cached {
+            return level;
+        }
+
+        let level = estimate_pins_on_planet(repository, &self.config, skill, planet)
+            .is_ok()
+            .then(|| choose_command_center_level(repository, &self.config, skill, planet));
+        self.command_center_levels.borrow_mut().push((skill, level));
+        level
+    }
+}
+
+/// Resolve the products of (chain, product) work items to the IDs the search compares
+fn intern_work_items(
+    ids: &ProductIds,
+    work_items: Vec<(usize, String)>,
+) -> Result<Vec<(usize, ProductId)>, SolverError> {
+    work_items
+        .into_iter()
+        .map(|(chain, product)| match ids.id(&product) {
+            Some(id) => Ok((chain, id)),
+            None => Err(SolverError::ProductNotFound(product)),
+        })
+        .collect()
+}
+
+/// Reject a quantity that isn't a positive, finite number
+fn require_positive(value: f64, what: impl FnOnce() -> String) -> Result<(), SolverError> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(SolverError::InvalidInput(format!(
+            "{} must be a positive number, got {}",
+            what(),
+            value
+        )))
+    }
+}
+
+/// Mutable state threaded through the backtracking search
+#[derive(Default)]
+struct SearchState {
+    /// Numbering of the products in `outputs` and `hostable`
+    ids: Arc<ProductIds>,
+    assignments: Vec<PlanetAssignment>,
+    /// Outputs of each entry in `assignments`, interned
+    outputs: Vec<Vec<ProductId>>,
+    /// Chain index of each entry in `assignments`
+    chains: Vec<usize>,
+    assigned_planets: HashSet<String>,
+    character_assignments: HashMap<String, Vec<String>>,
+    /// Characters allowed on each planet
+    service: ServiceMatrix,
+    /// Pre-filtered configurations the search may pick from
+    hostable: Rc<HostableConfigurations>,
+}
+
+impl SearchState {
+    /// Empty state comparing products by the given numbering
+    fn new(ids: Arc<ProductIds>) -> Self {
+        Self {
+            ids,
+            ..Self::default()
+        }
+    }
+
+    /// Whether a chain already has a planet producing the product
+    fn produces(&self, chain: usize, product: ProductId) -> bool {
+        self.outputs
+            .iter()
+            .zip(&self.chains)
+            .any(|(outputs, c)| *c == chain && outputs.contains(&product))
+    }
+
+    /// Number of planets producing the product across all chains
+    fn planets_producing(&self, product: ProductId) -> usize {
+        self.outputs
+            .iter()
+            .filter(|outputs| outputs.contains(&product))
+            .count()
+    }
+
+    /// Record an assignment
+    fn push(&mut self, chain: usize, assignment: PlanetAssignment) {
+        let outputs = assignment
+            .outputs
+            .iter()
+            .filter_map(|output| self.ids.id(output))
+            .collect();
+        self.push_interned(chain, assignment, outputs);
+    }
+
+    /// Record an assignment whose outputs are already interned
+    fn push_interned(
+        &mut self,
+        chain: usize,
+        assignment: PlanetAssignment,
+        outputs: Vec<ProductId>,
+    ) {
+        self.outputs.push(outputs);
+        self.assigned_planets.insert(assignment.planet.clone());
+        self.character_assignments
+            .entry(assignment.character.clone())
+            .or_default()
+            .push(assignment.planet.clone());
+        self.assignments.push(assignment);
+        self.chains.push(chain);
+    }
+
+    /// Undo the most recent assignment
+    fn pop(&mut self) {
+        if let Some(assignment) = self.assignments.pop() {
+            self.outputs.pop();
+            self.chains.pop();
+            self.assigned_planets.remove(&assignment.planet);
+
+            if let Some(character_planets) =
+                self.character_assignments.get_mut(&assignment.character)
+            {
+                character_planets.pop();
+                if character_planets.is_empty() {
+                    self.character_assignments.remove(&assignment.character);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{PlanetType, COMMAND_CENTER_UPGRADE_COST};
+    use crate::migration::ColonyChange;
+    use crate::quantity::{command_center_factor, program_yield, DAILY_EXTRACTION_PER_PLANET};
+    use crate::repository::{
+        CharacterRepository, MemoryRepository, PlanetRepository, ProductRepository,
+    };
+    use std::collections::{HashMap, HashSet};
+
+    // Helper function to create a test repository with minimal data
+    fn create_test_repository() -> MemoryRepository {
+        let mut repo = MemoryRepository::new();
+
+        // Add some test characters as JSON
+        let characters_json = r#"[
+            {
+                "name": "Character1",
+                "planets": 2,
+                "skills": {
+                    "command_center_upgrades": 5,
+                    "interplanetary_consolidation": 2
+                }
+            },
+            {
+                "name": "Character2",
+                "planets": 3,
+                "skills": {
+                    "command_center_upgrades": 5,
+                    "interplanetary_consolidation": 3
+                }
+            }
+        ]"#;
+
+        // Add some test planets as JSON
+        let planets_json = r#"[
+            {
                 "id": "Barren1",
                 "planet_type": "Barren",
                 "resources": ["base_metals", "noble_metals"]
@@ -338,7 +2049,7 @@ mod tests {
 
         // Verify the plan contains expected planet assignments
         assert_eq!(plan.assignments.len(), 1);
-        assert_eq!(plan.assignments[0].output, "water");
+        assert_eq!(plan.assignments[0].output(), "water");
         assert!(plan.assignments[0].imported_inputs.is_empty());
         assert_eq!(plan.assignments[0].mined_inputs, vec!["aqueous_liquids"]);
         assert_eq!(plan.assignments[0].planet_type, PlanetType::Oceanic);
@@ -361,7 +2072,7 @@ mod tests {
         let p2_assignment = plan
             .assignments
             .iter()
-            .find(|a| a.output == "coolant")
+            .find(|a| a.output() == "coolant")
             .expect("Should have an assignment for coolant");
 
         // Check the imported inputs for the P2 factory
@@ -381,10 +2092,9 @@ mod tests {
         assert!(!plan.assignments.is_empty());
 
         // Check that we have an assignment for the target product
-        let target_assignment = plan
-            .assignments
+        plan.assignments
             .iter()
-            .find(|a| a.output == "coolant")
+            .find(|a| a.output() == "coolant")
             .expect("Should have an assignment for coolant");
     }
 
@@ -450,7 +2160,41 @@ mod tests {
     }
 
     #[test]
-    fn test_insufficient_planets() {
+    fn test_command_center_level_limits_layouts() {
+        let mut repo = MemoryRepository::new();
+        repo.load_characters(
+            r#"[{
+                "name": "Novice",
+                "planets": 1,
+                "skills": { "command_center_upgrades": 0, "interplanetary_consolidation": 0 }
+            }]"#,
+        )
+        .unwrap();
+        repo.load_planets(
+            r#"[{ "id": "Oceanic1", "planet_type": "Oceanic", "resources": ["aqueous_liquids"] }]"#,
+        )
+        .unwrap();
+
+        // An untrained command center can't power an extractor and its factory
+        let solver = Solver::new(&repo);
+        assert!(matches!(
+            solver.solve("water"),
+            Err(SolverError::NoSolutionFound(_))
+        ));
+
+        repo.load_characters(
+            r#"[{
+                "name": "Novice",
+                "planets": 1,
+                "skills": { "command_center_upgrades": 1, "interplanetary_consolidation": 0 }
+            }]"#,
+        )
+        .unwrap();
+        assert!(Solver::new(&repo).solve("water").is_ok());
+    }
+
+    #[test]
+    fn test_insufficient_planets() {
         // Create a scenario where there aren't enough planets of the right types
         let mut repo = MemoryRepository::new();
 
@@ -500,6 +2244,1186 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_max_imports_per_planet() {
+        let repo = create_test_repository();
+
+        // Coolant needs both water and electrolytes imported, since no planet can mine both
+        let options = SolverOptions::default().max_imports_per_planet(1);
+        let result = Solver::with_options(&repo, options).solve("coolant");
+        match result {
+            Err(SolverError::NoSolutionFound(_)) => {}
+            _ => panic!("Expected NoSolutionFound error"),
+        }
+
+        let options = SolverOptions::default().max_imports_per_planet(2);
+        let plan = Solver::with_options(&repo, options)
+            .solve("coolant")
+            .unwrap();
+        for assignment in &plan.assignments {
+            assert!(assignment.imported_inputs.len() <= 2);
+        }
+    }
+
+    #[test]
+    fn test_solve_many_plans_independent_chains() {
+        let repo = create_test_repository();
+        let solver = Solver::new(&repo);
+
+        let plan = solver.solve_many(&["water", "electrolytes"]).unwrap();
+        assert_eq!(plan.assignments.len(), 2);
+        assert!(plan.assignments.iter().any(|a| a.output() == "water"));
+        assert!(plan
+            .assignments
+            .iter()
+            .any(|a| a.output() == "electrolytes"));
+
+        // Each chain gets its own planet, even for the same product
+        let plan = solver
+            .solve_many(&["reactive_metals", "reactive_metals"])
+            .unwrap();
+        assert_eq!(plan.assignments.len(), 2);
+        assert_ne!(plan.assignments[0].planet, plan.assignments[1].planet);
+    }
+
+    #[test]
+    fn test_max_planets_per_product() {
+        let repo = create_test_repository();
+        let options = SolverOptions::default().max_planets_per_product(1);
+        let solver = Solver::with_options(&repo, options);
+
+        let result = solver.solve_many(&["reactive_metals", "reactive_metals"]);
+        match result {
+            Err(SolverError::NoSolutionFound(_)) => {}
+            _ => panic!("Expected NoSolutionFound error"),
+        }
+
+        assert!(solver.solve_many(&["reactive_metals", "water"]).is_ok());
+    }
+
+    #[test]
+    fn test_prefer_low_tax_characters() {
+        let mut repo = create_test_repository();
+        repo.load_characters(
+            r#"[
+                {
+                    "name": "Character2",
+                    "planets": 3,
+                    "skills": {
+                        "command_center_upgrades": 5,
+                        "interplanetary_consolidation": 3
+                    },
+                    "poco_tax_rate": 0.01
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        let options = SolverOptions::default().prefer_low_tax_characters();
+        let plan = Solver::with_options(&repo, options)
+            .solve("coolant")
+            .unwrap();
+
+        let coolant = plan
+            .assignments
+            .iter()
+            .find(|a| a.output() == "coolant")
+            .unwrap();
+        assert_eq!(coolant.character, "Character2");
+    }
+
+    #[test]
+    fn test_prefer_low_tax_uses_customs_office_rate() {
+        let mut repo = create_test_repository();
+        for planet in repo.get_all_planets() {
+            repo.update_planet(planet.with_tax_rate(0.05)).unwrap();
+        }
+        // A low standing rate doesn't matter where the customs office sets its own, but the
+        // customs skill still lowers what's paid there
+        repo.load_characters(
+            r#"[
+                {
+                    "name": "LowStanding",
+                    "planets": 6,
+                    "skills": { "command_center_upgrades": 5, "interplanetary_consolidation": 5 },
+                    "poco_tax_rate": 0.01
+                },
+                {
+                    "name": "Skilled",
+                    "planets": 6,
+                    "skills": {
+                        "command_center_upgrades": 5,
+                        "interplanetary_consolidation": 5,
+                        "customs_code_expertise": 5
+                    },
+                    "poco_tax_rate": 0.1
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        let options = SolverOptions::default().prefer_low_tax_characters();
+        let plan = Solver::with_options(&repo, options)
+            .solve("coolant")
+            .unwrap();
+        assert!(plan.assignments.iter().all(|a| a.character == "Skilled"));
+    }
+
+    #[test]
+    fn test_prefers_rich_deposits() {
+        let mut repo = MemoryRepository::new();
+        let planet = |id: &str, richness: f64| {
+            Planet::new(
+                id.to_string(),
+                PlanetType::Oceanic,
+                vec!["aqueous_liquids".to_string()],
+            )
+            .with_richness("aqueous_liquids", richness)
+        };
+        repo.load_planets_data(vec![planet("Poor", 0.3), planet("Rich", 1.5)])
+            .unwrap();
+        repo.load_characters(
+            r#"[
+                {
+                    "name": "Miner",
+                    "planets": 1,
+                    "skills": { "command_center_upgrades": 5, "interplanetary_consolidation": 0 }
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        let plan = Solver::new(&repo).solve("water").unwrap();
+        assert_eq!(plan.assignments.len(), 1);
+        assert_eq!(plan.assignments[0].planet, "Rich");
+    }
+
+    #[test]
+    fn test_program_hours_on_extraction_planets() {
+        let repo = create_test_repository();
+        let options = SolverOptions::default().program_hours(72.0);
+        let plan = Solver::with_options(&repo, options)
+            .solve("coolant")
+            .unwrap();
+
+        for assignment in &plan.assignments {
+            let expected = (!assignment.mined_inputs.is_empty()).then_some(72.0);
+            assert_eq!(assignment.program_hours, expected);
+        }
+        assert!(plan.assignments.iter().any(|a| a.program_hours.is_some()));
+    }
+
+    #[test]
+    fn test_invalid_program_hours_option() {
+        assert_eq!(
+            SolverOptions::default()
+                .program_hours(f64::NAN)
+                .program_hours,
+            None
+        );
+        assert_eq!(
+            SolverOptions::default().program_hours(0.0).program_hours,
+            None
+        );
+        assert_eq!(
+            SolverOptions::default().program_hours(1000.0).program_hours,
+            Some(MAX_PROGRAM_HOURS)
+        );
+
+        // Options deserialized from JavaScript skip the setter, so they're checked on their own
+        let options = SolverOptions {
+            program_hours: Some(-24.0),
+            ..SolverOptions::default()
+        };
+        assert!(matches!(
+            options.validate(),
+            Err(SolverError::InvalidInput(_))
+        ));
+        assert!(SolverOptions::default()
+            .program_hours(72.0)
+            .validate()
+            .is_ok());
+
+        // An unvalidated length is still never handed to the planner
+        let repo = create_test_repository();
+        let plan = Solver::with_options(&repo, options)
+            .solve("coolant")
+            .unwrap();
+        assert!(plan.assignments.iter().all(|a| a.program_hours.is_none()));
+    }
+
+    #[test]
+    fn test_solve_quota_sizes_planets() {
+        let repo = create_test_repository();
+        let solver = Solver::new(&repo);
+
+        // 50 coolant per day needs 400 water and 400 electrolytes, one planet each
+        let quota = solver.solve_quota("coolant", 50.0).unwrap();
+        assert!(quota.sufficient);
+        assert_eq!(quota.plan.assignments.len(), 3);
+        assert_eq!(quota.factory_planets, 1);
+        assert_eq!(quota.extraction_planets, 2);
+
+        // 2000 coolant per day needs two factories and far more extraction than we have
+        let quota = solver.solve_quota("coolant", 2_000.0).unwrap();
+        assert!(!quota.sufficient);
+        let coolant = quota
+            .requirements
+            .iter()
+            .find(|r| r.product == "coolant")
+            .unwrap();
+        assert_eq!(coolant.planets_required, 2);
+        let water = quota
+            .requirements
+            .iter()
+            .find(|r| r.product == "water")
+            .unwrap();
+        assert_eq!(water.daily_units, 16_000.0);
+        assert!(water.planets_assigned < water.planets_required);
+    }
+
+    #[test]
+    fn test_solve_quota_rejects_invalid_units() {
+        let repo = create_test_repository();
+        let solver = Solver::new(&repo);
+
+        for units_per_day in [0.0, -50.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                solver.solve_quota("coolant", units_per_day),
+                Err(SolverError::InvalidInput(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_quota_sizes_extraction_for_program_length() {
+        let mut repo = create_test_repository();
+        let water_planets = |repo: &MemoryRepository| {
+            let quota = Solver::new(repo).solve_quota("water", 600.0).unwrap();
+            quota.requirements[0].planets_required
+        };
+        assert_eq!(water_planets(&repo), 1);
+
+        // Characters resetting extractors weekly get far less out of each planet
+        let weekly: Vec<Character> = repo
+            .get_all_characters()
+            .into_iter()
+            .map(|c| c.with_program_hours(168.0))
+            .collect();
+        repo.load_characters_data(weekly).unwrap();
+        let per_planet = DAILY_EXTRACTION_PER_PLANET / 150.0 * program_yield(168.0).average_factor;
+        assert_eq!(water_planets(&repo), (600.0 / per_planet).ceil() as usize);
+        assert!(water_planets(&repo) > 1);
+    }
+
+    #[test]
+    fn test_assignments_record_command_center_level() {
+        let repo = create_test_repository();
+        let plan = Solver::new(&repo).solve("coolant").unwrap();
+
+        for assignment in &plan.assignments {
+            let level = assignment.command_center_level.unwrap();
+            assert!(level <= 5);
+        }
+        let expected: f64 = plan
+            .assignments
+            .iter()
+            .map(|a| COMMAND_CENTER_UPGRADE_COST[a.command_center_level.unwrap() as usize])
+            .sum();
+        assert_eq!(plan.command_center_upgrade_cost(), expected);
+        assert!(expected < COMMAND_CENTER_UPGRADE_COST[5] * plan.assignments.len() as f64);
+    }
+
+    #[test]
+    fn test_owned_planets_go_to_their_owner() {
+        let mut repo = create_test_repository();
+        let oceanic = repo.get_planet_by_id("Oceanic1").unwrap();
+        repo.load_planets_data(vec![oceanic.clone().with_owner("Character2")])
+            .unwrap();
+        for _ in 0..5 {
+            let plan = Solver::new(&repo).solve("water").unwrap();
+            assert_eq!(plan.assignments[0].planet, "Oceanic1");
+            assert_eq!(plan.assignments[0].character, "Character2");
+        }
+
+        // Nobody else may take over a colony whose owner isn't planning with us
+        repo.load_planets_data(vec![oceanic.with_owner("Stranger")])
+            .unwrap();
+        assert!(Solver::new(&repo).solve("water").is_err());
+    }
+
+    #[test]
+    fn test_characters_stay_in_range() {
+        let mut repo = create_test_repository();
+        let located: Vec<Planet> = repo
+            .get_all_planets()
+            .into_iter()
+            .map(|p| {
+                let system = if p.id == "Oceanic1" {
+                    "Home"
+                } else {
+                    "Elsewhere"
+                };
+                p.with_location(system, "Constellation", "Region", -0.5)
+            })
+            .collect();
+        repo.load_planets_data(located).unwrap();
+        let homebodies: Vec<Character> = repo
+            .get_all_characters()
+            .into_iter()
+            .map(|c| c.with_home("Home", crate::domain::ServiceRange::SameSystem))
+            .collect();
+        repo.load_characters_data(homebodies).unwrap();
+
+        let plan = Solver::new(&repo).solve("water").unwrap();
+        assert_eq!(plan.assignments[0].planet, "Oceanic1");
+        assert!(Solver::new(&repo).solve("coolant").is_err());
+    }
+
+    #[test]
+    fn test_plan_order_option() {
+        let repo = create_test_repository();
+        let options = SolverOptions::default().plan_order(PlanOrder::Topological);
+        let plan = Solver::with_options(&repo, options)
+            .solve("coolant")
+            .unwrap();
+
+        assert_eq!(plan.assignments.last().unwrap().output(), "coolant");
+    }
+
+    #[test]
+    fn test_solve_ratio_shares_intermediates() {
+        // Rocket fuel's plasmoids come from suspended plasma, which no shared planet has
+        let mut repo = create_test_repository();
+        repo.add_planet(Planet::new(
+            "Gas2".to_string(),
+            PlanetType::Gas,
+            vec!["suspended_plasma".to_string()],
+        ))
+        .unwrap();
+        let solver = Solver::new(&repo);
+
+        // Both P2 products need electrolytes, which should be planned once for both
+        let quota = solver
+            .solve_ratio(&[("coolant", 2.0), ("rocket_fuel", 1.0)], 10.0)
+            .unwrap_or_else(|err| panic!("Failed to solve ratio: {}", err));
+
+        let electrolytes = quota
+            .requirements
+            .iter()
+            .find(|r| r.product == "electrolytes")
+            .unwrap();
+        assert_eq!(electrolytes.daily_units, 20.0 * 8.0 + 10.0 * 8.0);
+        assert_eq!(
+            quota
+                .requirements
+                .iter()
+                .filter(|r| r.product == "electrolytes")
+                .count(),
+            1
+        );
+        assert_eq!(quota.targets.len(), 2);
+    }
+
+    #[test]
+    fn test_solve_ratio_rejects_invalid_input() {
+        let repo = create_test_repository();
+        let solver = Solver::new(&repo);
+        let ratio = [("coolant", 2.0), ("rocket_fuel", 1.0)];
+
+        for sets_per_day in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                solver.solve_ratio(&ratio, sets_per_day),
+                Err(SolverError::InvalidInput(_))
+            ));
+        }
+        for share in [0.0, -2.0, f64::NAN, f64::NEG_INFINITY] {
+            assert!(matches!(
+                solver.solve_ratio(&[("coolant", share), ("rocket_fuel", 1.0)], 10.0),
+                Err(SolverError::InvalidInput(_))
+            ));
+        }
+        assert!(matches!(
+            solver.solve_quotas(&[("coolant", 10.0), ("rocket_fuel", -10.0)]),
+            Err(SolverError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            solver.solve_quotas(&[]),
+            Err(SolverError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_buy_below_tier() {
+        let repo = create_test_repository();
+        let options = SolverOptions::default().buy_below_tier(ProductTier::P2);
+        let solver = Solver::with_options(&repo, options);
+
+        // Only the P2 factory is planned, the P1 inputs are bought
+        let plan = solver.solve("coolant").unwrap();
+        assert_eq!(plan.assignments.len(), 1);
+        assert_eq!(plan.assignments[0].output(), "coolant");
+
+        let quota = solver.solve_quota("coolant", 100.0).unwrap();
+        let purchased: Vec<&str> = quota
+            .purchased_inputs
+            .iter()
+            .map(|p| p.product.as_str())
+            .collect();
+        assert_eq!(purchased, vec!["electrolytes", "water"]);
+        assert_eq!(quota.purchased_inputs[0].units_per_day, 800.0);
+    }
+
+    #[test]
+    fn test_purchased_products() {
+        let repo = create_test_repository();
+        let options = SolverOptions::default().purchase("water");
+        let plan = Solver::with_options(&repo, options)
+            .solve("coolant")
+            .unwrap();
+
+        // Water is still imported by the coolant factory, but nobody produces it
+        assert!(plan.assignments.iter().all(|a| a.output() != "water"));
+        assert!(plan
+            .assignments
+            .iter()
+            .any(|a| a.output() == "electrolytes"));
+        let coolant = plan
+            .assignments
+            .iter()
+            .find(|a| a.output() == "coolant")
+            .unwrap();
+        assert!(coolant.imported_inputs.contains(&"water".to_string()));
+    }
+
+    #[test]
+    fn test_solve_quotas_with_stockpile() {
+        let repo = create_test_repository();
+        let solver = Solver::new(&repo);
+
+        // Enough water for the whole horizon, so no water planet is needed
+        let stockpile = Stockpile {
+            inventory: HashMap::from([("water".to_string(), 400.0 * 30.0)]),
+            horizon_days: 30.0,
+        };
+        let quota = solver
+            .solve_quotas_with_stockpile(&[("coolant", 50.0)], &stockpile)
+            .unwrap();
+
+        assert!(quota.sufficient);
+        assert!(quota.plan.assignments.iter().all(|a| a.output() != "water"));
+        let water = quota
+            .requirements
+            .iter()
+            .find(|r| r.product == "water")
+            .unwrap();
+        assert_eq!(water.planets_required, 0);
+
+        let schedule: Vec<&str> = quota.schedule.iter().map(|s| s.product.as_str()).collect();
+        assert_eq!(schedule, vec!["coolant", "electrolytes", "water"]);
+        assert_eq!(quota.schedule[2].online_after_days, 30.0);
+    }
+
+    #[test]
+    fn test_solve_quotas_shares_p2_hub() {
+        let repo = create_test_repository();
+        let solver = Solver::new(&repo);
+
+        // Coolant and synthetic oil share electrolytes, so one planet makes both
+        let quota = solver
+            .solve_quotas(&[("coolant", 10.0), ("synthetic_oil", 10.0)])
+            .unwrap();
+
+        let hubs: Vec<&PlanetAssignment> = quota
+            .plan
+            .assignments
+            .iter()
+            .filter(|a| a.produces("coolant") && a.produces("synthetic_oil"))
+            .collect();
+        assert_eq!(hubs.len(), 1);
+        assert_eq!(hubs[0].imported_inputs.len(), 3);
+        assert_eq!(quota.plan.assignments.len(), 4);
+        assert!(quota.purchased_inputs.is_empty());
+        assert!(quota.sufficient);
+
+        // A single target never gets a hub making something nobody asked for
+        let plan = solver.solve("coolant").unwrap();
+        assert!(plan
+            .assignments
+            .iter()
+            .all(|a| a.extra_outputs().is_empty()));
+    }
+
+    #[test]
+    fn test_colocate_factories_frees_planets() {
+        let mut repo = create_test_repository();
+        repo.load_planets(
+            r#"[{ "id": "Gas2", "planet_type": "Gas", "resources": ["ionic_solutions"] }]"#,
+        )
+        .unwrap();
+        repo.load_characters(
+            r#"[{
+                "name": "Character3",
+                "planets": 1,
+                "skills": { "command_center_upgrades": 5, "interplanetary_consolidation": 0 }
+            }]"#,
+        )
+        .unwrap();
+
+        // Independent chains each get their own P2 factory
+        let plan = Solver::new(&repo)
+            .solve_many(&["coolant", "synthetic_oil"])
+            .unwrap();
+        assert_eq!(plan.assignments.len(), 6);
+
+        let options = SolverOptions::default().colocate_factories();
+        let plan = Solver::with_options(&repo, options)
+            .solve_many(&["coolant", "synthetic_oil"])
+            .unwrap();
+        assert_eq!(plan.assignments.len(), 5);
+        assert!(plan
+            .assignments
+            .iter()
+            .any(|a| a.produces("coolant") && a.produces("synthetic_oil")));
+    }
+
+    #[test]
+    fn test_plan_routes_imports() {
+        let repo = create_test_repository();
+        let plan = Solver::new(&repo).solve("coolant").unwrap();
+
+        let coolant = plan
+            .assignments
+            .iter()
+            .find(|a| a.produces("coolant"))
+            .unwrap();
+        let mut sources: Vec<&str> = plan
+            .flows_into(&coolant.planet)
+            .map(|flow| flow.product.as_str())
+            .collect();
+        sources.sort();
+        assert_eq!(sources, vec!["electrolytes", "water"]);
+        for flow in &plan.flows {
+            assert!(plan
+                .flows_from(&flow.from_planet)
+                .all(|f| f.to_planet == coolant.planet));
+            assert!(flow.units_per_hour > 0.0);
+        }
+
+        // Solved plans come with a visit schedule for every planet
+        let schedule = plan.schedule.as_ref().unwrap();
+        assert_eq!(schedule.cadences.len(), plan.assignments.len());
+        let scheduled: usize = schedule.characters.iter().map(|c| c.planets.len()).sum();
+        assert_eq!(scheduled, plan.assignments.len());
+    }
+
+    #[test]
+    fn test_minimize_hauling() {
+        let mut repo = create_test_repository();
+        repo.load_planets(
+            r#"[{ "id": "Gas2", "planet_type": "Gas", "resources": ["aqueous_liquids", "ionic_solutions"] }]"#,
+        )
+        .unwrap();
+        let plan = Solver::new(&repo).solve("coolant").unwrap();
+        for assignment in &plan.assignments {
+            let mut hauled: Vec<&String> = assignment.import_m3_per_hour.keys().collect();
+            hauled.sort();
+            let mut imported: Vec<&String> = assignment.imported_inputs.iter().collect();
+            imported.sort();
+            assert_eq!(hauled, imported);
+        }
+
+        let options = SolverOptions::default().minimize_hauling();
+        let hauling_plan = Solver::with_options(&repo, options)
+            .solve("coolant")
+            .unwrap();
+        assert!(plan.hauling_m3_per_hour() > 0.0);
+
+        // Gas2 can mine everything coolant needs, so nothing is hauled between planets
+        assert_eq!(hauling_plan.hauling_m3_per_hour(), 0.0);
+        assert!(hauling_plan
+            .assignments
+            .iter()
+            .any(|a| a.produces("coolant") && a.planet == "Gas2"));
+    }
+
+    #[test]
+    fn test_no_configuration_error_explains_rejections() {
+        let repo = create_test_repository();
+        let options = SolverOptions::default().max_imports_per_planet(1);
+        let result = Solver::with_options(&repo, options).solve("coolant");
+
+        let Err(SolverError::NoSolutionFound(message)) = result else {
+            panic!("Expected NoSolutionFound error");
+        };
+        assert!(message.contains("coolant"));
+        assert!(message.contains("excluded by the solver options"));
+        assert!(message.contains("incorrect tier"));
+    }
+
+    #[test]
+    fn test_solve_by_display_name() {
+        let repo = create_test_repository();
+        let plan = Solver::new(&repo).solve("Precious Metals").unwrap();
+        assert!(plan
+            .assignments
+            .iter()
+            .any(|a| a.produces("precious_metals")));
+
+        let quota = Solver::new(&repo)
+            .solve_quota("Precious Metals", 100.0)
+            .unwrap();
+        assert!(quota
+            .requirements
+            .iter()
+            .any(|r| r.product == "precious_metals" && r.daily_units == 100.0));
+    }
+
+    #[test]
+    fn test_solve_raw_resource() {
+        let repo = create_test_repository();
+        let plan = Solver::new(&repo).solve("aqueous_liquids").unwrap();
+
+        assert_eq!(plan.assignments.len(), 1);
+        let assignment = &plan.assignments[0];
+        assert_eq!(assignment.planet, "Oceanic1");
+        assert_eq!(assignment.output(), "aqueous_liquids");
+        assert_eq!(assignment.mined_inputs, vec!["aqueous_liquids"]);
+    }
+
+    #[test]
+    fn test_unhostable_product_fails_before_searching() {
+        let repo = create_test_repository();
+
+        // Lava1 could extract heavy metals by type, but its scan found none
+        let err = Solver::new(&repo).solve("heavy_metals").unwrap_err();
+        assert!(matches!(
+            err,
+            SolverError::NoSolutionFound(ref msg) if msg.contains("No available planet can host heavy_metals")
+        ));
+
+        // The pre-filter keeps only planets with the right resources
+        let solver = Solver::new(&repo);
+        let ids = repo.product_ids();
+        let water = ids.id("water").unwrap();
+        let hostable =
+            solver.hostable_configurations(&ids, &[(0, water)], &solver.service_matrix());
+        assert_eq!(hostable.len(), 1);
+        assert!(hostable["Oceanic1"].contains_key(&water));
+    }
+
+    #[test]
+    fn test_solve_with_presets() {
+        let repo = create_test_repository();
+        let options = SolverOptions::default()
+            .preset(ColonyPreset::DualExtractor)
+            .preset(ColonyPreset::P2Hub);
+        let plan = Solver::with_options(&repo, options)
+            .solve("mechanical_parts")
+            .unwrap();
+
+        // Barren1 extracts both metals, feeding a hub on another planet
+        assert_eq!(plan.assignments.len(), 2);
+        let extractor = plan
+            .assignments
+            .iter()
+            .find(|a| a.planet == "Barren1")
+            .unwrap();
+        assert!(extractor.produces("reactive_metals") && extractor.produces("precious_metals"));
+
+        // Single extractor planets aren't a selected layout
+        let options = SolverOptions::default().preset(ColonyPreset::P2Hub);
+        assert!(Solver::with_options(&repo, options)
+            .solve("mechanical_parts")
+            .is_err());
+    }
+
+    #[test]
+    fn test_migrate_keeps_existing_colonies() {
+        let repo = create_test_repository();
+        let solver = Solver::new(&repo);
+
+        // Existing water colony on the only oceanic planet, plus an unrelated one
+        let existing = vec![
+            ExistingColony {
+                character: "Character2".to_string(),
+                planet: "Oceanic1".to_string(),
+                imported_inputs: Vec::new(),
+                mined_inputs: vec!["aqueous_liquids".to_string()],
+                outputs: vec!["water".to_string()],
+            },
+            ExistingColony {
+                character: "Character1".to_string(),
+                planet: "Lava1".to_string(),
+                imported_inputs: Vec::new(),
+                mined_inputs: vec!["felsic_magma".to_string()],
+                outputs: vec!["silicon".to_string()],
+            },
+        ];
+        let migration = solver.migrate(&["coolant"], &existing).unwrap();
+
+        let oceanic = migration
+            .changes
+            .iter()
+            .find(|c| c.planet == "Oceanic1")
+            .unwrap();
+        assert_eq!(oceanic.change, ColonyChange::Keep);
+        assert_eq!(oceanic.pins_torn_down, 0);
+        assert!(migration.changes_of(ColonyChange::Establish).count() >= 1);
+        assert!(migration
+            .changes
+            .iter()
+            .all(|c| c.change != ColonyChange::Keep || c.planet == "Oceanic1"));
+    }
+
+    #[test]
+    fn test_repair_replans_only_lost_planet() {
+        let repo = create_test_repository();
+        let solver = Solver::new(&repo);
+        let plan = solver.solve("coolant").unwrap();
+
+        let lost = plan
+            .assignments
+            .iter()
+            .find(|a| a.output() == "coolant")
+            .unwrap()
+            .clone();
+        let repaired = solver.repair(&plan, &lost.planet).unwrap();
+
+        assert_eq!(repaired.assignments.len(), plan.assignments.len());
+        assert!(repaired.assignments.iter().all(|a| a.planet != lost.planet));
+        for assignment in plan.assignments.iter().filter(|a| a.planet != lost.planet) {
+            assert!(repaired
+                .assignments
+                .iter()
+                .any(|a| a.planet == assignment.planet && a.output() == assignment.output()));
+        }
+        assert!(repaired.assignments.iter().any(|a| a.output() == "coolant"));
+
+        // Losing a planet that isn't part of the plan changes nothing
+        let unchanged = solver.repair(&plan, "NotAPlanet").unwrap();
+        assert_eq!(unchanged.assignments.len(), plan.assignments.len());
+    }
+
+    #[test]
+    fn test_repair_replans_every_lost_output() {
+        let repo = create_test_repository();
+        let solver = Solver::new(&repo);
+        let mut plan = solver.solve("coolant").unwrap();
+
+        // The coolant planet also makes reactive metals, which nothing upstream needs
+        let lost = plan
+            .assignments
+            .iter_mut()
+            .find(|a| a.produces("coolant"))
+            .unwrap();
+        lost.outputs.push("reactive_metals".to_string());
+        let lost_planet = lost.planet.clone();
+
+        let repaired = solver.repair(&plan, &lost_planet).unwrap();
+        assert!(repaired.assignments.iter().all(|a| a.planet != lost_planet));
+        assert!(repaired.assignments.iter().any(|a| a.produces("coolant")));
+        assert!(repaired
+            .assignments
+            .iter()
+            .any(|a| a.produces("reactive_metals")));
+    }
+
+    #[test]
+    fn test_repair_fails_without_replacement() {
+        let repo = create_test_repository();
+        let solver = Solver::new(&repo);
+        let plan = solver.solve("water").unwrap();
+
+        // Oceanic1 is the only planet with aqueous liquids
+        let result = solver.repair(&plan, "Oceanic1");
+        assert!(matches!(result, Err(SolverError::NoSolutionFound(_))));
+    }
+
+    #[test]
+    fn test_sensitivity_finds_binding_constraint() {
+        let mut repo = MemoryRepository::new();
+        repo.load_characters(
+            r#"[{
+                "name": "Character1",
+                "planets": 5,
+                "skills": { "command_center_upgrades": 5, "interplanetary_consolidation": 4 }
+            }]"#,
+        )
+        .unwrap();
+        repo.load_planets(
+            r#"[{ "id": "Oceanic1", "planet_type": "Oceanic", "resources": ["aqueous_liquids"] }]"#,
+        )
+        .unwrap();
+        let solver = Solver::new(&repo);
+
+        // 1000 water per day needs two extraction planets, but there is only one
+        let report = solver.sensitivity(&[("water", 1000.0)]).unwrap();
+        assert_eq!(report.coverage, 0.5);
+        assert_eq!(report.bottleneck_product.as_deref(), Some("water"));
+        assert_eq!(report.bottleneck_resources, vec!["aqueous_liquids"]);
+
+        let binding = report.binding_constraint().unwrap();
+        assert_eq!(binding.coverage, 1.0);
+        assert!(matches!(
+            &binding.change,
+            ScenarioChange::AddPlanet(planet)
+                if planet.resources.contains(&"aqueous_liquids".to_string())
+        ));
+
+        // Character slots are not the constraint here
+        assert!(report
+            .unlocks
+            .iter()
+            .filter(|u| matches!(u.change, ScenarioChange::AddPlanetSlot(_)))
+            .all(|u| u.gain == 0.0));
+    }
+
+    #[test]
+    fn test_compare_branches() {
+        let mut repo = MemoryRepository::new();
+        repo.load_characters(
+            r#"[{
+                "name": "Character1",
+                "planets": 5,
+                "skills": { "command_center_upgrades": 5, "interplanetary_consolidation": 4 }
+            }]"#,
+        )
+        .unwrap();
+        repo.load_planets(
+            r#"[{ "id": "Oceanic1", "planet_type": "Oceanic", "resources": ["aqueous_liquids"] }]"#,
+        )
+        .unwrap();
+        let more_oceans = repo.branch(&[ScenarioChange::add_planet_of_type(
+            &repo,
+            PlanetType::Oceanic,
+        )]);
+        let no_oceans = repo.branch(&[ScenarioChange::RemovePlanet("Oceanic1".to_string())]);
+
+        let outcomes = Solver::new(&repo)
+            .compare_branches(
+                &[("water", 1000.0)],
+                &[
+                    ("current", &repo),
+                    ("more_oceans", &more_oceans),
+                    ("no_oceans", &no_oceans),
+                ],
+            )
+            .unwrap();
+        assert_eq!(outcomes.len(), 3);
+        assert_eq!(outcomes[0].coverage, 0.5);
+        assert_eq!(outcomes[1].branch, "more_oceans");
+        assert_eq!(outcomes[1].coverage, 1.0);
+        assert_eq!(outcomes[1].output[0].units_per_day, 1000.0);
+        assert!(outcomes[1].plan.is_some());
+        assert!(!outcomes[2].feasible);
+        assert!(outcomes[2].plan.is_none());
+    }
+
+    #[test]
+    fn test_what_if_compares_scenarios() {
+        let repo = create_test_repository();
+        let solver = Solver::new(&repo);
+
+        // Without the only oceanic planet there is no water, so no coolant
+        let report = solver
+            .what_if(
+                &[("coolant", 50.0)],
+                &[ScenarioChange::RemovePlanet("Oceanic1".to_string())],
+            )
+            .unwrap();
+        assert!(report.feasible_before);
+        assert!(!report.feasible_after);
+        assert_eq!(report.coverage_delta(), -1.0);
+        assert_eq!(report.output_after[0].units_per_day, 0.0);
+        assert!(report
+            .colonies
+            .iter()
+            .all(|c| c.change == ColonyChange::Abandon));
+
+        // An unrelated change leaves the output as it is
+        let report = solver
+            .what_if(
+                &[("coolant", 50.0)],
+                &[ScenarioChange::AddPlanetSlot("Character1".to_string())],
+            )
+            .unwrap();
+        assert!(report.feasible_after);
+        assert_eq!(report.output_after[0].units_per_day, 50.0);
+
+        // The repository itself is never modified
+        assert_eq!(repo.get_all_planets().len(), 5);
+    }
+
+    #[test]
+    fn test_recommend_targets() {
+        let repo = create_test_repository();
+        let solver = Solver::new(&repo);
+
+        let prices: HashMap<String, f64> = HashMap::from([
+            ("water".to_string(), 500.0),
+            ("coolant".to_string(), 9_000.0),
+            ("electrolytes".to_string(), 450.0),
+            ("nano_factory".to_string(), 1_500_000.0),
+        ]);
+        let recommendations = solver.recommend_targets(3, &prices).unwrap();
+
+        assert_eq!(recommendations.len(), 3);
+        assert!(recommendations.iter().all(|r| r.feasible));
+        assert!(recommendations
+            .windows(2)
+            .all(|w| w[0].daily_profit >= w[1].daily_profit));
+
+        // Coolant is limited by its extraction planets, not the factory
+        let coolant = recommendations
+            .iter()
+            .find(|r| r.product == "coolant")
+            .unwrap();
+        assert!(coolant.daily_units > 0.0 && coolant.daily_units < 1_200.0);
+        assert!(coolant.daily_tax > 0.0);
+        assert_eq!(
+            coolant.daily_profit,
+            coolant.daily_revenue - coolant.daily_input_cost - coolant.daily_tax
+        );
+
+        let top = solver.recommend_targets(1, &prices).unwrap();
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].product, recommendations[0].product);
+    }
+
+    #[test]
+    fn test_rank_products() {
+        let repo = create_test_repository();
+        // Buying the P3 inputs lets a single factory planet make any P4
+        let options = SolverOptions::default().buy_below_tier(ProductTier::P4);
+        let solver = Solver::with_options(&repo, options);
+        let prices: HashMap<String, f64> =
+            HashMap::from([("nano_factory".to_string(), 1_500_000.0)]);
+        let trade = TradeSettings::default();
+
+        let rankings = solver.rank_products(false, &prices, &trade).unwrap();
+        assert!(rankings.iter().all(|r| r.tier == ProductTier::P4));
+        let p4_count = coverage_report(&repo, Some(ProductTier::P4)).products.len();
+        assert_eq!(rankings.len(), p4_count);
+        assert!(rankings.windows(2).all(|w| w[0].feasible >= w[1].feasible));
+
+        let nano = rankings
+            .iter()
+            .find(|r| r.product == "nano_factory")
+            .unwrap();
+        assert!(nano.feasible);
+        assert_eq!(rankings[0].product, "nano_factory");
+        assert!(!nano.unpriced.is_empty());
+        assert!(nano.units_per_hour > 0.0);
+        assert!((nano.revenue_per_hour - nano.units_per_hour * 1_500_000.0).abs() < 1e-6);
+        assert!(
+            (nano.net_isk_per_hour - (nano.revenue_per_hour - nano.costs_per_hour)).abs() < 1e-6
+        );
+        assert!(rankings
+            .iter()
+            .filter(|r| !r.feasible)
+            .all(|r| r.reason.is_some()));
+    }
+
+    #[test]
+    fn test_advise_planets() {
+        let mut repo = MemoryRepository::new();
+        repo.load_characters(
+            r#"[{
+                "name": "Character1",
+                "planets": 5,
+                "skills": { "command_center_upgrades": 5, "interplanetary_consolidation": 4 }
+            }]"#,
+        )
+        .unwrap();
+        repo.load_planets(
+            r#"[{ "id": "Oceanic1", "planet_type": "Oceanic", "resources": ["aqueous_liquids"] }]"#,
+        )
+        .unwrap();
+        let solver = Solver::new(&repo);
+
+        let water = solver.advise_planets("water", 2).unwrap();
+        assert!(water.already_feasible);
+        assert!(water.planet_types.is_empty());
+
+        // Coolant also needs electrolytes from ionic solutions and a factory planet
+        let coolant = solver.advise_planets("coolant", 2).unwrap();
+        assert!(coolant.solvable);
+        assert!(!coolant.already_feasible);
+        assert_eq!(coolant.planet_types.len(), 2);
+        let resource_map = repo.resource_map();
+        assert!(coolant
+            .planet_types
+            .iter()
+            .any(|t| resource_map["ionic_solutions"].contains(t)));
+
+        // A single extra planet is not enough
+        let limited = solver.advise_planets("coolant", 1).unwrap();
+        assert!(!limited.solvable);
+
+        // Every combination gets a full solve, so the search size is capped
+        assert!(matches!(
+            solver.advise_planets("coolant", MAX_ADVISOR_ADDITIONS + 1),
+            Err(SolverError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_advise_skills() {
+        let mut repo = MemoryRepository::new();
+        repo.load_characters(
+            r#"[{
+                "name": "Novice",
+                "planets": 1,
+                "skills": { "command_center_upgrades": 2, "interplanetary_consolidation": 0 }
+            }]"#,
+        )
+        .unwrap();
+        repo.load_planets(
+            r#"[
+                { "id": "Oceanic1", "planet_type": "Oceanic", "resources": ["aqueous_liquids"] },
+                { "id": "Oceanic2", "planet_type": "Oceanic", "resources": ["aqueous_liquids"] }
+            ]"#,
+        )
+        .unwrap();
+        let solver = Solver::new(&repo);
+
+        // 1000 water per day needs two fully built planets, but the novice runs one weak one
+        let advice = solver.advise_skills(&[("water", 1000.0)]).unwrap();
+        let per_planet = 100_000.0 / 150.0;
+        assert!((advice.coverage - per_planet * command_center_factor(2) / 1000.0).abs() < 1e-9);
+        assert_eq!(advice.upgrades.len(), 2);
+
+        // A second planet helps more than a better command center on the first
+        let best = advice.best_per_character();
+        assert_eq!(best.len(), 1);
+        assert_eq!(best[0].skill, PlanetarySkill::InterplanetaryConsolidation);
+        assert_eq!((best[0].from_level, best[0].to_level), (0, 1));
+        assert!(advice.upgrades[1].gain > 0.0);
+        assert!(advice.upgrades[0].gain > advice.upgrades[1].gain);
+    }
+
+    #[test]
+    fn test_estimate_alts() {
+        let mut repo = MemoryRepository::new();
+        repo.load_characters(
+            r#"[{
+                "name": "Main",
+                "planets": 1,
+                "skills": { "command_center_upgrades": 5, "interplanetary_consolidation": 0 }
+            }]"#,
+        )
+        .unwrap();
+        let planets: Vec<String> = (1..=6)
+            .map(|i| {
+                format!(
+                    r#"{{ "id": "Oceanic{}", "planet_type": "Oceanic", "resources": ["aqueous_liquids"] }}"#,
+                    i
+                )
+            })
+            .collect();
+        repo.load_planets(&format!("[{}]", planets.join(",")))
+            .unwrap();
+        let solver = Solver::new(&repo);
+
+        let skills = CharacterSkills {
+            command_center_upgrades: 5,
+            interplanetary_consolidation: 1,
+            remote_sensing: None,
+            planetary_production: None,
+            planetology: None,
+            advanced_planetology: None,
+            customs_code_expertise: None,
+        };
+
+        // 3000 water per day needs five planets; the main has one and each alt adds two
+        let estimate = solver
+            .estimate_alts(&[("water", 3000.0)], &skills, 10)
+            .unwrap();
+        assert_eq!(estimate.planets_required, 5);
+        assert_eq!(estimate.additional_characters, 2);
+        assert!(estimate.sufficient);
+        assert_eq!(estimate.coverage, 1.0);
+
+        // Ten planets' worth of water can't be reached with six planets, however many alts
+        let estimate = solver
+            .estimate_alts(&[("water", 6000.0)], &skills, 10)
+            .unwrap();
+        assert!(!estimate.sufficient);
+        assert!(estimate.limited_by_planets);
+        assert_eq!(estimate.additional_characters, 3);
+    }
+
+    #[test]
+    fn test_solve_prioritized_drops_lower_priorities() {
+        let repo = create_test_repository();
+        let solver = Solver::new(&repo);
+
+        // Coolant takes the only oceanic planet, so a second coolant chain can't fit, but
+        // one of the two base metal planets is still free for reactive metals
+        let result = solver
+            .solve_prioritized(&["coolant", "unknown_product", "coolant", "reactive_metals"])
+            .unwrap();
+
+        assert_eq!(result.satisfied, vec!["coolant", "reactive_metals"]);
+        let reasons: Vec<(usize, DropReason)> = result
+            .dropped
+            .iter()
+            .map(|d| (d.priority, d.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (1, DropReason::UnknownProduct),
+                (2, DropReason::OutOfCapacity)
+            ]
+        );
+        assert!(result
+            .plan
+            .assignments
+            .iter()
+            .any(|a| a.output() == "coolant"));
+    }
+
+    #[test]
+    fn test_cancelled_solve() {
+        let repo = create_test_repository();
+        let token = CancellationToken::new();
+        let solver = Solver::new(&repo).with_cancellation(token.clone());
+
+        token.cancel();
+        assert!(matches!(
+            solver.solve("coolant"),
+            Err(SolverError::Cancelled)
+        ));
+        assert!(matches!(
+            solver.solve_quota("coolant", 100.0),
+            Err(SolverError::Cancelled)
+        ));
+
+        token.reset();
+        assert!(solver.solve("coolant").is_ok());
+    }
+
+    #[test]
+    fn test_cancel_check() {
+        let repo = create_test_repository();
+        let polls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&polls);
+        let solver = Solver::new(&repo).with_cancel_check(move || {
+            counter.set(counter.get() + 1);
+            true
+        });
+        assert!(matches!(
+            solver.solve("coolant"),
+            Err(SolverError::Cancelled)
+        ));
+        assert!(polls.get() > 0);
+
+        let solver = Solver::new(&repo).with_cancel_check(|| false);
+        assert!(solver.solve("coolant").is_ok());
+    }
+
     #[test]
     fn test_assigned_planets_not_reused() {
         let repo = create_test_repository();
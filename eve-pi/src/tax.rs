@@ -0,0 +1,485 @@
+use crate::domain::{
+    customs_base_value, PlanetAssignment, ProductTier, ProductionPlan, DEFAULT_POCO_TAX_RATE,
+};
+use crate::quantity::{assignment_daily_output, inputs_per_unit};
+use crate::repository::Repository;
+use serde::{Deserialize, Serialize};
+
+/// Share of the export rate customs offices charge on imports
+pub const IMPORT_TAX_SHARE: f64 = 0.5;
+
+/// Customs office import and export tax for a single planet assignment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignmentTax {
+    pub character: String,
+    pub planet: String,
+    pub outputs: Vec<String>,  // Products exported, main output first
+    pub daily_units: f64,      // Estimated units exported per day, across every output
+    pub tax_rate: f64,         // Export tax rate paid on this planet by the assigned character
+    pub daily_import_tax: f64, // ISK paid in import tax per day
+    pub daily_export_tax: f64, // ISK paid in export tax per day
+    pub daily_tax: f64,        // Total ISK paid in customs tax per day
+}
+
+/// Customs tax paid at a single customs office, one per planet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomsOfficeTax {
+    pub planet: String,
+    pub system: Option<String>, // Solar system of the planet, when known
+    pub tax_rate: f64,
+    pub daily_tax: f64,
+}
+
+/// Customs tax of an extraction planet exporting its P1 to a factory, compared with making
+/// the P2 on the extraction planet and exporting that instead
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportBreakEven {
+    pub planet: String, // Extraction planet
+    pub p1: String,
+    pub p2: String,
+    pub factory_planet: String,
+    pub daily_p1_units: f64,
+    pub p1_route_daily_tax: f64, // P1 export here plus its import at the factory
+    pub p2_route_daily_tax: f64, // P2 export here, for the same P1
+    pub break_even_tax_rate: Option<f64>, // Factory tax rate where both routes cost the same
+}
+
+impl ExportBreakEven {
+    /// Whether exporting P2 from the extraction planet pays less customs tax
+    pub fn p2_cheaper(&self) -> bool {
+        self.p2_route_daily_tax < self.p1_route_daily_tax
+    }
+}
+
+/// ISK evaluation of the customs office taxes paid by a production plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxReport {
+    pub assignments: Vec<AssignmentTax>,
+    #[serde(default)]
+    pub customs_offices: Vec<CustomsOfficeTax>, // Most expensive first
+    #[serde(default)]
+    pub break_even: Vec<ExportBreakEven>,
+    pub total_daily_tax: f64,
+}
+
+/// Export tax paid per unit of a product at a given tax rate
+pub fn export_tax_per_unit(repository: &dyn Repository, product: &str, tax_rate: f64) -> f64 {
+    repository
+        .get_product_by_name(product)
+        .map(|p| customs_base_value(p.tier) * tax_rate)
+        .unwrap_or(0.0)
+}
+
+/// Import tax paid per unit of a product at a given export tax rate
+pub fn import_tax_per_unit(repository: &dyn Repository, product: &str, tax_rate: f64) -> f64 {
+    export_tax_per_unit(repository, product, tax_rate) * IMPORT_TAX_SHARE
+}
+
+/// Export tax rate the assigned character pays on the assigned planet. A rate set on the
+/// planet's customs office wins over the character's standings-based rate, and Customs Code
+/// Expertise discounts either.
+pub fn assignment_tax_rate(repository: &dyn Repository, assignment: &PlanetAssignment) -> f64 {
+    let character = repository.get_character_by_name(&assignment.character);
+    let planet = repository.get_planet_by_id(&assignment.planet);
+    match (character, planet) {
+        (Some(character), Some(planet)) => character.tax_rate_on(&planet),
+        (Some(character), None) => character.effective_tax_rate(),
+        (None, Some(planet)) => planet.poco_tax_rate.unwrap_or(DEFAULT_POCO_TAX_RATE),
+        (None, None) => DEFAULT_POCO_TAX_RATE,
+    }
+}
+
+/// Customs taxes for an assignment exporting a number of units per day, including the
+/// import tax on everything shipped in to make them. A planet making several products
+/// exports an even share of each.
+pub fn assignment_tax(
+    repository: &dyn Repository,
+    assignment: &PlanetAssignment,
+    daily_units: f64,
+) -> AssignmentTax {
+    let output_units: Vec<(&str, f64)> = assignment
+        .outputs
+        .iter()
+        .map(|output| {
+            (
+                output.as_str(),
+                daily_units * assignment.output_share(output),
+            )
+        })
+        .collect();
+    output_tax(repository, assignment, &output_units)
+}
+
+/// Customs taxes for an assignment exporting the given daily units of each of its outputs
+pub fn output_tax(
+    repository: &dyn Repository,
+    assignment: &PlanetAssignment,
+    output_units: &[(&str, f64)],
+) -> AssignmentTax {
+    let tax_rate = assignment_tax_rate(repository, assignment);
+
+    let mut daily_import_tax = 0.0;
+    let mut daily_export_tax = 0.0;
+    for &(output, units) in output_units {
+        daily_import_tax += inputs_per_unit(repository, output, &assignment.imported_inputs, &[])
+            .iter()
+            .map(|(input, per_unit)| {
+                per_unit * units * import_tax_per_unit(repository, input, tax_rate)
+            })
+            .sum::<f64>();
+        daily_export_tax += units * export_tax_per_unit(repository, output, tax_rate);
+    }
+
+    AssignmentTax {
+        character: assignment.character.clone(),
+        planet: assignment.planet.clone(),
+        outputs: output_units
+            .iter()
+            .map(|(output, _)| output.to_string())
+            .collect(),
+        daily_units: output_units.iter().map(|(_, units)| units).sum(),
+        tax_rate,
+        daily_import_tax,
+        daily_export_tax,
+        daily_tax: daily_import_tax + daily_export_tax,
+    }
+}
+
+/// Estimate the customs taxes of a production plan using each assignment's tax rate
+pub fn evaluate_plan_taxes(plan: &ProductionPlan, repository: &dyn Repository) -> TaxReport {
+    let assignments: Vec<AssignmentTax> = plan
+        .assignments
+        .iter()
+        .map(|assignment| {
+            let daily_units = assignment_daily_output(repository, assignment);
+            assignment_tax(repository, assignment, daily_units)
+        })
+        .collect();
+
+    let total_daily_tax = assignments.iter().map(|a| a.daily_tax).sum();
+
+    let mut customs_offices: Vec<CustomsOfficeTax> = Vec::new();
+    for tax in &assignments {
+        match customs_offices.iter_mut().find(|c| c.planet == tax.planet) {
+            Some(office) => office.daily_tax += tax.daily_tax,
+            None => customs_offices.push(CustomsOfficeTax {
+                planet: tax.planet.clone(),
+                system: repository
+                    .get_planet_by_id(&tax.planet)
+                    .and_then(|p| p.system),
+                tax_rate: tax.tax_rate,
+                daily_tax: tax.daily_tax,
+            }),
+        }
+    }
+    customs_offices.sort_by(|a, b| b.daily_tax.total_cmp(&a.daily_tax));
+
+    TaxReport {
+        break_even: export_break_even(plan, repository),
+        assignments,
+        customs_offices,
+        total_daily_tax,
+    }
+}
+
+/// For every extraction planet exporting P1 to a P2 factory, compare the customs tax of that
+/// route with making the P2 on the extraction planet. Only the tax is compared; whether the
+/// planet can mine every input of the P2 is left to the caller.
+pub fn export_break_even(
+    plan: &ProductionPlan,
+    repository: &dyn Repository,
+) -> Vec<ExportBreakEven> {
+    let tier_of = |product: &str| repository.get_product_by_name(product).map(|p| p.tier);
+
+    let mut results: Vec<ExportBreakEven> = Vec::new();
+    for extraction in &plan.assignments {
+        if extraction.mined_inputs.is_empty() {
+            continue;
+        }
+        let extraction_rate = assignment_tax_rate(repository, extraction);
+        let daily_units = assignment_daily_output(repository, extraction);
+
+        let p1_outputs = extraction
+            .outputs
+            .iter()
+            .filter(|p1| tier_of(p1) == Some(ProductTier::P1));
+        for p1 in p1_outputs {
+            let daily_p1_units = daily_units * extraction.output_share(p1);
+            for factory in &plan.assignments {
+                if !factory.imported_inputs.contains(p1) {
+                    continue;
+                }
+                for p2 in &factory.outputs {
+                    if tier_of(p2) != Some(ProductTier::P2)
+                        || results
+                            .iter()
+                            .any(|r| r.planet == extraction.planet && r.p1 == *p1 && r.p2 == *p2)
+                    {
+                        continue;
+                    }
+                    let p1_per_p2 = inputs_per_unit(
+                        repository,
+                        p2,
+                        &factory.imported_inputs,
+                        &factory.mined_inputs,
+                    )
+                    .get(p1)
+                    .copied()
+                    .unwrap_or(0.0);
+                    if p1_per_p2 <= 0.0 {
+                        continue;
+                    }
+
+                    let factory_rate = assignment_tax_rate(repository, factory);
+                    let p1_export = export_tax_per_unit(repository, p1, extraction_rate);
+                    let p2_export_per_p1 =
+                        export_tax_per_unit(repository, p2, extraction_rate) / p1_per_p2;
+                    // Import tax grows linearly with the factory's rate, so solve for where it
+                    // makes up the difference between the two exports
+                    let import_at_full_rate = import_tax_per_unit(repository, p1, 1.0);
+                    let break_even_tax_rate = (import_at_full_rate > 0.0)
+                        .then(|| (p2_export_per_p1 - p1_export) / import_at_full_rate);
+
+                    results.push(ExportBreakEven {
+                        planet: extraction.planet.clone(),
+                        p1: p1.to_string(),
+                        p2: p2.to_string(),
+                        factory_planet: factory.planet.clone(),
+                        daily_p1_units,
+                        p1_route_daily_tax: daily_p1_units
+                            * (p1_export + import_tax_per_unit(repository, p1, factory_rate)),
+                        p2_route_daily_tax: daily_p1_units * p2_export_per_p1,
+                        break_even_tax_rate,
+                    });
+                }
+            }
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Planet, PlanetType};
+    use crate::repository::MemoryRepository;
+
+    fn create_test_repository() -> MemoryRepository {
+        let mut repo = MemoryRepository::new();
+        repo.load_characters(
+            r#"[
+                {
+                    "name": "Standard",
+                    "planets": 1,
+                    "skills": { "command_center_upgrades": 5, "interplanetary_consolidation": 0 }
+                },
+                {
+                    "name": "Friendly",
+                    "planets": 1,
+                    "skills": { "command_center_upgrades": 5, "interplanetary_consolidation": 0 },
+                    "poco_tax_rate": 0.02
+                },
+                {
+                    "name": "Expert",
+                    "planets": 1,
+                    "skills": {
+                        "command_center_upgrades": 5,
+                        "interplanetary_consolidation": 0,
+                        "customs_code_expertise": 5
+                    }
+                }
+            ]"#,
+        )
+        .unwrap();
+        repo.load_planets_data(vec![
+            Planet::new(
+                "Friendly_planet".to_string(),
+                PlanetType::Barren,
+                Vec::new(),
+            )
+            .with_location("Jita", "Kimotoro", "The Forge", 0.9),
+            Planet::new("Owned".to_string(), PlanetType::Barren, Vec::new()).with_location(
+                "Jita",
+                "Kimotoro",
+                "The Forge",
+                0.9,
+            ),
+        ])
+        .unwrap();
+        repo
+    }
+
+    #[test]
+    fn test_export_tax_per_unit() {
+        let repo = create_test_repository();
+        assert_eq!(export_tax_per_unit(&repo, "water", 0.10), 40.0);
+        assert_eq!(export_tax_per_unit(&repo, "nonexistent_product", 0.10), 0.0);
+    }
+
+    #[test]
+    fn test_evaluate_plan_taxes_uses_character_rates() {
+        let repo = create_test_repository();
+
+        let standard = evaluate_plan_taxes(
+            &ProductionPlan {
+                assignments: vec![PlanetAssignment::for_test(
+                    "Standard",
+                    "Standard_planet",
+                    &["coolant"],
+                    &[],
+                )],
+                flows: Vec::new(),
+                schedule: None,
+            },
+            &repo,
+        );
+        let friendly = evaluate_plan_taxes(
+            &ProductionPlan {
+                assignments: vec![PlanetAssignment::for_test(
+                    "Friendly",
+                    "Friendly_planet",
+                    &["coolant"],
+                    &[],
+                )],
+                flows: Vec::new(),
+                schedule: None,
+            },
+            &repo,
+        );
+
+        assert_eq!(standard.assignments[0].tax_rate, DEFAULT_POCO_TAX_RATE);
+        assert_eq!(friendly.assignments[0].tax_rate, 0.02);
+        assert!(friendly.total_daily_tax < standard.total_daily_tax);
+        assert_eq!(
+            standard.total_daily_tax,
+            crate::quantity::factory_daily_output(crate::domain::ProductTier::P2) * 7_200.0 * 0.10
+        );
+    }
+
+    #[test]
+    fn test_tax_rate_prefers_planet_and_applies_skill() {
+        let mut repo = create_test_repository();
+        let friendly = PlanetAssignment::for_test("Friendly", "Friendly_planet", &["coolant"], &[]);
+        let expert = PlanetAssignment::for_test("Expert", "Expert_planet", &["coolant"], &[]);
+        assert_eq!(assignment_tax_rate(&repo, &friendly), 0.02);
+        assert!((assignment_tax_rate(&repo, &expert) - 0.05).abs() < 1e-9);
+
+        // A customs office owner's rate overrides the character's standings
+        assert_eq!(repo.set_system_tax_rate("Jita", 0.15).unwrap(), 2);
+        assert_eq!(assignment_tax_rate(&repo, &friendly), 0.15);
+        assert_eq!(assignment_tax_rate(&repo, &expert), 0.05);
+    }
+
+    #[test]
+    fn test_assignment_tax_includes_imports() {
+        let repo = create_test_repository();
+        let mut coolant =
+            PlanetAssignment::for_test("Standard", "Standard_planet", &["coolant"], &[]);
+        coolant.imported_inputs = vec!["electrolytes".to_string(), "water".to_string()];
+
+        let tax = assignment_tax(&repo, &coolant, 5.0);
+        assert_eq!(tax.daily_export_tax, 5.0 * 7_200.0 * 0.10);
+        // 40 of each P1 input per 5 coolant, taxed at half the export rate
+        assert_eq!(
+            tax.daily_import_tax,
+            2.0 * 40.0 * 400.0 * 0.10 * IMPORT_TAX_SHARE
+        );
+        assert_eq!(tax.daily_tax, tax.daily_import_tax + tax.daily_export_tax);
+    }
+
+    #[test]
+    fn test_two_output_assignment_splits_its_output() {
+        let mut repo = create_test_repository();
+        let mut factory =
+            PlanetAssignment::for_test("Standard", "Standard_planet", &["coolant"], &[]);
+        factory.outputs.push("mechanical_parts".to_string());
+        factory.imported_inputs = [
+            "electrolytes",
+            "water",
+            "precious_metals",
+            "reactive_metals",
+        ]
+        .map(String::from)
+        .to_vec();
+
+        // 5 of each P2, each made from 40 of two P1 inputs
+        let tax = assignment_tax(&repo, &factory, 10.0);
+        assert_eq!(tax.outputs, ["coolant", "mechanical_parts"]);
+        assert_eq!(tax.daily_export_tax, 10.0 * 7_200.0 * 0.10);
+        assert_eq!(
+            tax.daily_import_tax,
+            4.0 * 40.0 * 400.0 * 0.10 * IMPORT_TAX_SHARE
+        );
+
+        // Mining two P1 on one planet splits its extraction between them
+        repo.add_planet(Planet::new(
+            "Standard_planet".to_string(),
+            PlanetType::Barren,
+            Vec::new(),
+        ))
+        .unwrap();
+        let mut water = PlanetAssignment::for_test("Standard", "Standard_planet", &["water"], &[]);
+        water.mined_inputs = vec!["aqueous_liquids".to_string()];
+        let mut extraction = water.clone();
+        extraction.outputs.push("electrolytes".to_string());
+        extraction.mined_inputs.push("ionic_solutions".to_string());
+        let daily_units = assignment_daily_output(&repo, &extraction);
+        assert!(daily_units > 0.0);
+        assert!((daily_units - assignment_daily_output(&repo, &water)).abs() < 1e-9);
+
+        let mut coolant =
+            PlanetAssignment::for_test("Friendly", "Friendly_planet", &["coolant"], &[]);
+        coolant.imported_inputs = vec!["electrolytes".to_string(), "water".to_string()];
+        let plan = ProductionPlan {
+            assignments: vec![extraction, coolant],
+            flows: Vec::new(),
+            schedule: None,
+        };
+        let report = evaluate_plan_taxes(&plan, &repo);
+        assert_eq!(
+            report.assignments[0].daily_export_tax,
+            daily_units * 400.0 * 0.10
+        );
+        assert_eq!(report.break_even.len(), 2);
+        for break_even in &report.break_even {
+            assert_eq!(break_even.daily_p1_units, daily_units / 2.0);
+        }
+    }
+
+    #[test]
+    fn test_customs_offices_and_break_even() {
+        let repo = create_test_repository();
+        let mut water = PlanetAssignment::for_test("Standard", "Standard_planet", &["water"], &[]);
+        water.planet = "Owned".to_string();
+        water.mined_inputs = vec!["aqueous_liquids".to_string()];
+        let mut coolant =
+            PlanetAssignment::for_test("Friendly", "Friendly_planet", &["coolant"], &[]);
+        coolant.imported_inputs = vec!["electrolytes".to_string(), "water".to_string()];
+        let plan = ProductionPlan {
+            assignments: vec![water, coolant],
+            flows: Vec::new(),
+            schedule: None,
+        };
+        let report = evaluate_plan_taxes(&plan, &repo);
+
+        assert_eq!(report.customs_offices.len(), 2);
+        assert_eq!(report.customs_offices[0].system.as_deref(), Some("Jita"));
+        let by_office: f64 = report.customs_offices.iter().map(|c| c.daily_tax).sum();
+        assert!((by_office - report.total_daily_tax).abs() < 1e-6);
+        assert!(report.customs_offices[0].daily_tax >= report.customs_offices[1].daily_tax);
+
+        // 8 water per coolant: exporting coolant costs 900 base value per water instead of
+        // 400, which only pays off once the factory's import tax rate reaches 25%
+        assert_eq!(report.break_even.len(), 1);
+        let break_even = &report.break_even[0];
+        assert_eq!(break_even.planet, "Owned");
+        assert_eq!(break_even.p2, "coolant");
+        assert_eq!(break_even.factory_planet, "Friendly_planet");
+        assert!((break_even.break_even_tax_rate.unwrap() - 0.25).abs() < 1e-9);
+        let per_unit = break_even.p1_route_daily_tax / break_even.daily_p1_units;
+        assert!((per_unit - (400.0 * 0.10 + 200.0 * 0.02)).abs() < 1e-9);
+        let per_unit = break_even.p2_route_daily_tax / break_even.daily_p1_units;
+        assert!((per_unit - 900.0 * 0.10).abs() < 1e-9);
+        assert!(!break_even.p2_cheaper());
+    }
+}
@@ -0,0 +1,367 @@
+use crate::domain::{PlanetType, Product, ProductTier};
+use crate::repository::{ProductRepository, Repository};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// What is wrong with a product in the database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IssueKind {
+    /// A raw material with a recipe
+    P0WithIngredients,
+    /// A processed product nothing can make
+    MissingIngredients,
+    /// A recipe input that isn't in the database
+    UnknownIngredient { ingredient: String },
+    /// A recipe input from a tier that can't feed the product's tier
+    TierTransition {
+        ingredient: String,
+        ingredient_tier: ProductTier,
+    },
+    /// Products whose recipes depend on each other, in dependency order
+    Cycle { path: Vec<String> },
+}
+
+/// A single problem found in the product database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    pub product: String,
+    pub tier: ProductTier,
+    #[serde(flatten)]
+    pub kind: IssueKind,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            IssueKind::P0WithIngredients => {
+                write!(f, "P0 product {} has ingredients", self.product)
+            }
+            IssueKind::MissingIngredients => write!(
+                f,
+                "{:?} product {} has no ingredients",
+                self.tier, self.product
+            ),
+            IssueKind::UnknownIngredient { ingredient } => {
+                write!(f, "{} uses unknown ingredient {}", self.product, ingredient)
+            }
+            IssueKind::TierTransition {
+                ingredient,
+                ingredient_tier,
+            } => write!(
+                f,
+                "{} ({:?}) uses ingredient {} of tier {:?}",
+                self.product, self.tier, ingredient, ingredient_tier
+            ),
+            IssueKind::Cycle { path } => write!(f, "Recipe cycle: {}", path.join(" -> ")),
+        }
+    }
+}
+
+/// Result of checking the product database
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub products_checked: usize,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Whether the database can be planned with
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Issues found on one product
+    pub fn issues_of<'a>(&'a self, product: &'a str) -> impl Iterator<Item = &'a ValidationIssue> {
+        self.issues.iter().filter(move |i| i.product == product)
+    }
+}
+
+/// Whether a product of one tier may be made from an ingredient of another. Only P1 is
+/// refined from raw materials, and everything else from lower processed tiers.
+fn valid_transition(product: ProductTier, ingredient: ProductTier) -> bool {
+    match ingredient {
+        ProductTier::P0 => product == ProductTier::P1,
+        _ => ingredient < product,
+    }
+}
+
+/// Check that every ingredient exists, tiers only feed the tiers above them, raw materials
+/// have no recipe, and no recipes depend on each other in a cycle
+pub fn validate_products(repository: &dyn ProductRepository) -> ValidationReport {
+    let mut products = repository.get_all_products();
+    products.sort_by(|a, b| a.name.cmp(&b.name));
+    let by_name: HashMap<&str, _> = products.iter().map(|p| (p.name.as_str(), p)).collect();
+
+    let mut report = ValidationReport {
+        products_checked: products.len(),
+        issues: Vec::new(),
+    };
+    let mut issue = |product: &Product, kind: IssueKind| {
+        report.issues.push(ValidationIssue {
+            product: product.name.clone(),
+            tier: product.tier,
+            kind,
+        });
+    };
+
+    for product in &products {
+        let ingredients = product.ingredients();
+        if product.tier == ProductTier::P0 && !ingredients.is_empty() {
+            issue(product, IssueKind::P0WithIngredients);
+        }
+        if product.tier != ProductTier::P0 && ingredients.is_empty() {
+            issue(product, IssueKind::MissingIngredients);
+        }
+
+        for ingredient in ingredients {
+            match by_name.get(ingredient.as_str()) {
+                Some(input) if !valid_transition(product.tier, input.tier) => issue(
+                    product,
+                    IssueKind::TierTransition {
+                        ingredient_tier: input.tier,
+                        ingredient,
+                    },
+                ),
+                Some(_) => {}
+                None => issue(product, IssueKind::UnknownIngredient { ingredient }),
+            }
+        }
+    }
+
+    // Depth-first search over the recipes, reporting each cycle once from its first product
+    let mut finished: HashSet<&str> = HashSet::new();
+    for product in &products {
+        let mut path: Vec<&str> = Vec::new();
+        find_cycles(
+            product.name.as_str(),
+            &by_name,
+            &mut path,
+            &mut finished,
+            &mut issue,
+        );
+    }
+
+    report
+}
+
+fn find_cycles<'a>(
+    name: &'a str,
+    by_name: &HashMap<&'a str, &'a Product>,
+    path: &mut Vec<&'a str>,
+    finished: &mut HashSet<&'a str>,
+    issue: &mut impl FnMut(&Product, IssueKind),
+) {
+    if finished.contains(name) {
+        return;
+    }
+    let Some(product) = by_name.get(name) else {
+        return;
+    };
+    if let Some(start) = path.iter().position(|p| *p == name) {
+        let mut cycle: Vec<String> = path[start..].iter().map(|p| p.to_string()).collect();
+        cycle.push(name.to_string());
+        issue(product, IssueKind::Cycle { path: cycle });
+        return;
+    }
+
+    path.push(name);
+    for (ingredient, _) in &product.schematic.inputs {
+        if let Some((key, _)) = by_name.get_key_value(ingredient.as_str()) {
+            find_cycles(key, by_name, path, finished, issue);
+        }
+    }
+    path.pop();
+    finished.insert(name);
+}
+
+/// What is wrong with a resource listed on a planet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PlanetIssueKind {
+    /// Not a product at all, with the closest raw material if there is one
+    UnknownResource { suggestion: Option<String> },
+    /// A product that isn't a raw material
+    NotRawResource { tier: ProductTier },
+    /// A raw material that never appears on this planet type
+    WrongPlanetType { found_on: Vec<PlanetType> },
+}
+
+/// A problem with one resource of a loaded planet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanetWarning {
+    pub planet: String,
+    pub planet_type: PlanetType,
+    pub resource: String,
+    #[serde(flatten)]
+    pub kind: PlanetIssueKind,
+}
+
+impl fmt::Display for PlanetWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            PlanetIssueKind::UnknownResource {
+                suggestion: Some(s),
+            } => write!(
+                f,
+                "{} lists unknown resource {}, did you mean {}?",
+                self.planet, self.resource, s
+            ),
+            PlanetIssueKind::UnknownResource { suggestion: None } => write!(
+                f,
+                "{} lists unknown resource {}",
+                self.planet, self.resource
+            ),
+            PlanetIssueKind::NotRawResource { tier } => write!(
+                f,
+                "{} lists {} which is a {:?} product, not a raw resource",
+                self.planet, self.resource, tier
+            ),
+            PlanetIssueKind::WrongPlanetType { .. } => write!(
+                f,
+                "{} lists {} which isn't found on {:?} planets",
+                self.planet, self.resource, self.planet_type
+            ),
+        }
+    }
+}
+
+/// Check every loaded planet's resources are known raw materials that can appear on its
+/// planet type, sorted by planet
+pub fn validate_planets(repository: &dyn Repository) -> Vec<PlanetWarning> {
+    let mut planets = repository.get_all_planets();
+    planets.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut warnings = Vec::new();
+    for planet in &planets {
+        for resource in &planet.resources {
+            let kind = match repository.get_product_by_name(resource) {
+                None => PlanetIssueKind::UnknownResource {
+                    suggestion: repository
+                        .search_products(resource)
+                        .into_iter()
+                        .find(|p| p.tier == ProductTier::P0)
+                        .map(|p| p.name),
+                },
+                Some(product) if product.tier != ProductTier::P0 => {
+                    PlanetIssueKind::NotRawResource { tier: product.tier }
+                }
+                Some(product) => {
+                    let found_on = repository
+                        .resource_planet_types(&product.name)
+                        .unwrap_or_default();
+                    if found_on.contains(&planet.planet_type) {
+                        continue;
+                    }
+                    PlanetIssueKind::WrongPlanetType { found_on }
+                }
+            };
+            warnings.push(PlanetWarning {
+                planet: planet.id.clone(),
+                planet_type: planet.planet_type,
+                resource: resource.clone(),
+                kind,
+            });
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::MemoryRepository;
+
+    #[test]
+    fn test_built_in_products_are_valid() {
+        let report = validate_products(&MemoryRepository::new());
+        assert!(report.is_valid(), "{:?}", report.issues);
+        assert_eq!(report.products_checked, 80);
+    }
+
+    #[test]
+    fn test_validation_reports_issues() {
+        let mut repo = MemoryRepository::new();
+        repo.replace_products_data(vec![
+            Product::new_raw_material("aqueous_liquids".to_string()),
+            Product::new(
+                "water".to_string(),
+                ProductTier::P1,
+                vec!["aqueous_liquids".to_string()],
+            ),
+            // Two P2s feeding each other, one of them skipping a tier
+            Product::new(
+                "coolant".to_string(),
+                ProductTier::P2,
+                vec!["oxides".to_string(), "aqueous_liquids".to_string()],
+            ),
+            Product::new(
+                "oxides".to_string(),
+                ProductTier::P2,
+                vec!["coolant".to_string(), "water".to_string()],
+            ),
+        ])
+        .unwrap();
+        let report = repo.validate();
+
+        assert!(!report.is_valid());
+        let coolant: Vec<&IssueKind> = report.issues_of("coolant").map(|i| &i.kind).collect();
+        assert!(coolant.contains(&&IssueKind::TierTransition {
+            ingredient: "aqueous_liquids".to_string(),
+            ingredient_tier: ProductTier::P0,
+        }));
+        assert!(coolant.contains(&&IssueKind::TierTransition {
+            ingredient: "oxides".to_string(),
+            ingredient_tier: ProductTier::P2,
+        }));
+
+        let cycles: Vec<&ValidationIssue> = report
+            .issues
+            .iter()
+            .filter(|i| matches!(i.kind, IssueKind::Cycle { .. }))
+            .collect();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(
+            cycles[0].to_string(),
+            "Recipe cycle: coolant -> oxides -> coolant"
+        );
+        assert_eq!(report.issues_of("water").count(), 0);
+    }
+
+    #[test]
+    fn test_validate_planets() {
+        let mut repo = MemoryRepository::new();
+        repo.load_planets(
+            r#"[
+                {"id": "Ocean1", "planet_type": "Oceanic", "resources": ["aqueos_liquids", "planktic_colonies"]},
+                {"id": "Barren1", "planet_type": "Barren", "resources": ["base_metals", "noble_gas", "water"]}
+            ]"#,
+        )
+        .unwrap();
+        let warnings = validate_planets(&repo);
+
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(warnings[0].planet, "Barren1");
+        assert_eq!(warnings[0].resource, "noble_gas");
+        assert!(matches!(
+            &warnings[0].kind,
+            PlanetIssueKind::WrongPlanetType { found_on } if found_on.contains(&PlanetType::Gas)
+        ));
+        assert_eq!(
+            warnings[1].kind,
+            PlanetIssueKind::NotRawResource {
+                tier: ProductTier::P1
+            }
+        );
+        assert_eq!(
+            warnings[2].kind,
+            PlanetIssueKind::UnknownResource {
+                suggestion: Some("aqueous_liquids".to_string())
+            }
+        );
+        assert!(warnings[2]
+            .to_string()
+            .contains("did you mean aqueous_liquids"));
+    }
+}
@@ -1,8 +1,27 @@
-use crate::domain::ProductionPlan;
-use crate::repository::{MemoryRepository, Repository};
-use crate::solver::{Solver, SolverError};
+use crate::balance::check_plan_balance;
+use crate::corporation::{solve_for_corporation, Corporation};
+use crate::coverage::coverage_report;
+use crate::domain::{CharacterSkills, EsiSkills, PlanetType, ProductTier, ProductionPlan};
+use crate::error::{ErrorCode, ErrorContext, PiError};
+use crate::factory::{find_valid_factory_configurations, search_factory_configurations};
+use crate::flow::FlowGraph;
+use crate::health::run_self_test;
+use crate::i18n::{planet_type_name, Language, Localization};
+use crate::logistics::{launch_schedule, LogisticsOptions};
+use crate::migration::ExistingColony;
+use crate::pricing::{buy_vs_build, value_plan, PriceSnapshot, SnapshotEntry, TradeSettings};
+use crate::profitability::profitability_report;
+use crate::quantity::Stockpile;
+use crate::repository::{
+    LoadMode, LoadReport, MemoryRepository, ProductRepository, RepositoryError, RepositoryState,
+};
+use crate::scenario::ScenarioChange;
+use crate::solver::{Solver, SolverOptions};
+use crate::tax::evaluate_plan_taxes;
+use serde::Serialize;
 use std::sync::Mutex;
-use tracing::{debug, error, info, warn};
+use tracing::{error, info, warn};
+use tsify::Tsify;
 use wasm_bindgen::prelude::*;
 
 // Use `wee_alloc` as the global allocator to reduce code size
@@ -14,6 +33,16 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 #[wasm_bindgen]
 pub struct PiSolver {
     repository: Mutex<MemoryRepository>,
+    cancel_flag: Mutex<Option<js_sys::Int32Array>>, // Shared with the frontend to abort a solve
+    localization: Mutex<Localization>,
+    prices: Mutex<PriceSnapshot>, // Market prices the frontend fetched, used when none are passed
+    branches: Mutex<std::collections::HashMap<String, MemoryRepository>>, // Named scenario snapshots
+}
+
+impl Default for PiSolver {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[wasm_bindgen]
@@ -31,87 +60,544 @@ impl PiSolver {
 
         Self {
             repository: Mutex::new(MemoryRepository::new()),
+            cancel_flag: Mutex::new(None),
+            localization: Mutex::new(Localization::new()),
+            prices: Mutex::new(PriceSnapshot::default()),
+            branches: Mutex::new(std::collections::HashMap::new()),
         }
     }
 
-    /// Load planet data from JavaScript objects
+    /// Share a cancel flag with the frontend: an `Int32Array` over a `SharedArrayBuffer`. A
+    /// solve running in a worker fails with a cancellation error once another thread stores
+    /// a non-zero value at index 0 with `Atomics.store`. Solves never clear the flag, so the
+    /// frontend resets it before starting the next one. Pass `undefined` to stop checking.
+    #[wasm_bindgen]
+    pub fn set_cancel_flag(&self, flag: Option<js_sys::Int32Array>) -> Result<(), JsValue> {
+        info!("WASM: Setting cancel flag: {}", flag.is_some());
+        let mut cancel_flag = self.cancel_flag.lock().map_err(|_| {
+            error!("WASM: Failed to lock cancel flag");
+            js_error(ErrorCode::LockFailed, "Failed to lock cancel flag")
+        })?;
+        *cancel_flag = flag;
+        Ok(())
+    }
+
+    /// Load planet data from JavaScript objects, failing if any planet is malformed.
+    /// Returns a report listing unknown fields that were ignored.
     #[wasm_bindgen]
-    pub fn load_planets(&self, planets_js: JsValue) -> Result<(), JsValue> {
+    pub fn load_planets(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Planet[]")] planets_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
         info!("WASM: Starting load_planets");
+        self.load_records("planets", planets_js, |repo, records| {
+            repo.load_planet_records(records, LoadMode::Strict)
+        })
+    }
+
+    /// Load product data from JavaScript objects, overriding built-in products with the same name
+    #[wasm_bindgen]
+    pub fn load_products(&self, products_js: JsValue) -> Result<(), JsValue> {
+        info!("WASM: Starting load_products");
+
+        let mut repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let products: Vec<crate::domain::Product> = serde_wasm_bindgen::from_value(products_js)
+            .map_err(|err| {
+                error!("WASM: Failed to deserialize products: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize products: {}", err),
+                )
+            })?;
+
+        repo.load_products_data(products).map_err(|err| {
+            error!("WASM: repo.load_products_data failed: {}", err);
+            pi_error("Failed to load products", err)
+        })?;
+
+        info!("WASM: load_products completed successfully");
+        Ok(())
+    }
+
+    /// Replace the whole product set with products from JavaScript objects, or go back to
+    /// the built-in products when `products_js` is null or undefined
+    #[wasm_bindgen]
+    pub fn replace_products(&self, products_js: JsValue) -> Result<(), JsValue> {
+        info!("WASM: Starting replace_products");
 
         let mut repo = self.repository.lock().map_err(|_| {
             error!("WASM: Failed to lock repository");
-            JsValue::from_str("Failed to lock repository")
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
         })?;
 
-        info!("WASM: Successfully locked repository, deserializing planets");
+        if products_js.is_undefined() || products_js.is_null() {
+            repo.reset_products();
+            info!("WASM: Restored the built-in products");
+            return Ok(());
+        }
 
-        // Deserialize JavaScript objects directly using serde-wasm-bindgen
-        let planets: Vec<crate::domain::Planet> = serde_wasm_bindgen::from_value(planets_js)
+        let products: Vec<crate::domain::Product> = serde_wasm_bindgen::from_value(products_js)
             .map_err(|err| {
-                error!("WASM: Failed to deserialize planets: {:?}", err);
-                JsValue::from_str(&format!("Failed to deserialize planets: {:?}", err))
+                error!("WASM: Failed to deserialize products: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize products: {}", err),
+                )
+            })?;
+
+        repo.replace_products_data(products).map_err(|err| {
+            error!("WASM: repo.replace_products_data failed: {}", err);
+            pi_error("Failed to replace products", err)
+        })?;
+
+        info!("WASM: replace_products completed successfully");
+        Ok(())
+    }
+
+    /// Load planet types per P0 resource, like `{"base_metals": ["Barren", "Lava"]}`,
+    /// replacing the built-in entries of the listed resources
+    #[wasm_bindgen]
+    pub fn load_resource_map(&self, resource_map_js: JsValue) -> Result<(), JsValue> {
+        info!("WASM: Starting load_resource_map");
+
+        let mut repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let resource_map: std::collections::HashMap<String, Vec<PlanetType>> =
+            serde_wasm_bindgen::from_value(resource_map_js).map_err(|err| {
+                error!("WASM: Failed to deserialize resource map: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize resource map: {}", err),
+                )
             })?;
 
-        info!("WASM: Successfully deserialized planets, calling repo.load_planets_data");
+        repo.load_resource_map_data(resource_map).map_err(|err| {
+            error!("WASM: repo.load_resource_map_data failed: {}", err);
+            pi_error("Failed to load resource map", err)
+        })?;
+
+        info!("WASM: load_resource_map completed successfully");
+        Ok(())
+    }
+
+    /// Check the loaded products for unknown ingredients, bad tier transitions, and recipe
+    /// cycles, returning a structured validation report
+    #[wasm_bindgen]
+    pub fn validate(&self) -> Result<JsValue, JsValue> {
+        info!("WASM: Validating product database");
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let report = repo.validate();
+        if !report.is_valid() {
+            error!("WASM: Product database has {} issues", report.issues.len());
+        }
+
+        serde_wasm_bindgen::to_value(&report).map_err(|err| {
+            error!("WASM: Failed to serialize validation report: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize validation report: {}", err),
+            )
+        })
+    }
+
+    /// Check the loaded planets for unknown resources and resources their planet type can't
+    /// have, returning structured warnings
+    #[wasm_bindgen]
+    pub fn validate_planets(&self) -> Result<JsValue, JsValue> {
+        info!("WASM: Validating planets");
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let warnings = repo.validate_planets();
+        for warning in &warnings {
+            warn!("WASM: {}", warning);
+        }
+
+        serde_wasm_bindgen::to_value(&warnings).map_err(|err| {
+            error!("WASM: Failed to serialize planet warnings: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize planet warnings: {}", err),
+            )
+        })
+    }
+
+    /// Set the customs office tax rate of every loaded planet in a system
+    #[wasm_bindgen]
+    pub fn set_system_tax_rate(&self, system: &str, tax_rate: f64) -> Result<usize, JsValue> {
+        info!("WASM: Setting tax rate {} for system {}", tax_rate, system);
+
+        let mut repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        repo.set_system_tax_rate(system, tax_rate).map_err(|err| {
+            error!("WASM: Invalid tax rate {}: {}", tax_rate, err);
+            js_error(
+                ErrorCode::InvalidInput,
+                format!("Invalid tax rate: {}", tax_rate),
+            )
+        })
+    }
+
+    /// Store market prices fetched by the frontend, as an object mapping product names,
+    /// in-game names or type IDs to either a price or `{ buy, sell }` prices. Profit reports
+    /// use them whenever no prices are passed in. Returns how many products got a price.
+    #[wasm_bindgen]
+    pub fn set_prices(&self, prices_js: JsValue) -> Result<usize, JsValue> {
+        info!("WASM: Setting market prices");
+
+        let snapshot = self.parse_prices(prices_js)?;
+        let priced = snapshot.quotes.len();
+        *self.prices.lock().map_err(|_| {
+            error!("WASM: Failed to lock prices");
+            js_error(ErrorCode::LockFailed, "Failed to lock prices")
+        })? = snapshot;
+
+        info!("WASM: Stored prices for {} products", priced);
+        Ok(priced)
+    }
 
-        repo.load_planets_data(planets).map_err(|err| {
-            error!("WASM: repo.load_planets_data failed: {}", err);
-            JsValue::from_str(&format!("Failed to load planets: {}", err))
+    /// Export planets, characters, jumps, product and resource map overrides, and stored
+    /// prices as one versioned JSON document, for persisting a session in localStorage
+    #[wasm_bindgen]
+    pub fn export_state(&self) -> Result<String, JsValue> {
+        info!("WASM: Exporting state");
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+        let mut state = repo.export_state();
+        state.prices = self
+            .prices
+            .lock()
+            .map_err(|_| {
+                error!("WASM: Failed to lock prices");
+                js_error(ErrorCode::LockFailed, "Failed to lock prices")
+            })?
+            .clone();
+
+        state.to_json().map_err(|err| {
+            error!("WASM: Failed to serialize state: {}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize state: {}", err),
+            )
+        })
+    }
+
+    /// Restore a session exported with `export_state`, replacing everything loaded
+    #[wasm_bindgen]
+    pub fn import_state(&self, json: &str) -> Result<(), JsValue> {
+        info!("WASM: Importing state (length: {})", json.len());
+
+        let state = RepositoryState::from_json(json).map_err(|err| {
+            error!("WASM: Failed to parse state: {}", err);
+            pi_error("Failed to parse state", err)
+        })?;
+
+        let mut repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+        let prices = repo.import_state(state).map_err(|err| {
+            error!("WASM: repo.import_state failed: {}", err);
+            pi_error("Failed to import state", err)
         })?;
+        *self.prices.lock().map_err(|_| {
+            error!("WASM: Failed to lock prices");
+            js_error(ErrorCode::LockFailed, "Failed to lock prices")
+        })? = prices;
 
-        info!("WASM: load_planets completed successfully");
+        info!("WASM: import_state completed successfully");
         Ok(())
     }
 
-    /// Load character data from JavaScript objects
+    /// Set a character's skills and planet limit from an ESI `/characters/{id}/skills/`
+    /// response, adding the character if needed, and return the updated character
     #[wasm_bindgen]
-    pub fn load_characters(&self, characters_js: JsValue) -> Result<(), JsValue> {
+    pub fn import_esi_skills(&self, name: &str, skills_js: JsValue) -> Result<JsValue, JsValue> {
+        info!("WASM: Importing ESI skills for {}", name);
+
+        let esi: EsiSkills = serde_wasm_bindgen::from_value(skills_js).map_err(|err| {
+            error!("WASM: Failed to deserialize ESI skills: {:?}", err);
+            js_error(
+                ErrorCode::DeserializationFailed,
+                format!("Failed to deserialize ESI skills: {}", err),
+            )
+        })?;
+
+        let mut repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+        let character = repo.import_esi_skills(name, &esi);
+
+        serde_wasm_bindgen::to_value(&character).map_err(|err| {
+            error!("WASM: Failed to serialize character: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize character: {}", err),
+            )
+        })
+    }
+
+    /// Load character data from JavaScript objects, failing if any character is malformed.
+    /// Returns a report listing unknown fields that were ignored.
+    #[wasm_bindgen]
+    pub fn load_characters(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Character[]")] characters_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
         info!("WASM: Starting load_characters");
+        self.load_records("characters", characters_js, |repo, records| {
+            repo.load_character_records(records, LoadMode::Strict)
+        })
+    }
+
+    /// Load planet data from a spreadsheet CSV export, skipping bad rows and returning a
+    /// report of each skipped row's index, field and reason
+    #[wasm_bindgen]
+    pub fn load_planets_csv(&self, csv: &str) -> Result<JsValue, JsValue> {
+        info!("WASM: Starting load_planets_csv");
+
+        let mut repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let report = repo.load_planets_csv(csv).map_err(|err| {
+            error!("WASM: repo.load_planets_csv failed: {}", err);
+            pi_error("Failed to load planets", err)
+        })?;
+
+        serde_wasm_bindgen::to_value(&report).map_err(|err| {
+            error!("WASM: Failed to serialize load report: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize load report: {}", err),
+            )
+        })
+    }
+
+    /// Load character data from a spreadsheet CSV export, skipping bad rows and returning a
+    /// report of each skipped row's index, field and reason
+    #[wasm_bindgen]
+    pub fn load_characters_csv(&self, csv: &str) -> Result<JsValue, JsValue> {
+        info!("WASM: Starting load_characters_csv");
+
+        let mut repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let report = repo.load_characters_csv(csv).map_err(|err| {
+            error!("WASM: repo.load_characters_csv failed: {}", err);
+            pi_error("Failed to load characters", err)
+        })?;
+
+        serde_wasm_bindgen::to_value(&report).map_err(|err| {
+            error!("WASM: Failed to serialize load report: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize load report: {}", err),
+            )
+        })
+    }
+
+    /// Load planet data from JavaScript objects, skipping malformed records and returning a
+    /// report of each skipped record's index, field and reason
+    #[wasm_bindgen]
+    pub fn load_planets_lenient(&self, planets_js: JsValue) -> Result<JsValue, JsValue> {
+        info!("WASM: Starting load_planets_lenient");
+        self.load_records("planets", planets_js, |repo, records| {
+            repo.load_planet_records(records, LoadMode::Lenient)
+        })
+    }
+
+    /// Load character data from JavaScript objects, skipping malformed records and returning a
+    /// report of each skipped record's index, field and reason
+    #[wasm_bindgen]
+    pub fn load_characters_lenient(&self, characters_js: JsValue) -> Result<JsValue, JsValue> {
+        info!("WASM: Starting load_characters_lenient");
+        self.load_records("characters", characters_js, |repo, records| {
+            repo.load_character_records(records, LoadMode::Lenient)
+        })
+    }
+
+    /// Add a single planet from a JavaScript object
+    #[wasm_bindgen]
+    pub fn add_planet(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Planet")] planet_js: JsValue,
+    ) -> Result<(), JsValue> {
+        let planet: crate::domain::Planet =
+            serde_wasm_bindgen::from_value(planet_js).map_err(|err| {
+                error!("WASM: Failed to deserialize planet: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize planet: {}", err),
+                )
+            })?;
+        info!("WASM: Adding planet {}", planet.id);
 
         let mut repo = self.repository.lock().map_err(|_| {
-            error!("WASM: Failed to lock repository for characters");
-            JsValue::from_str("Failed to lock repository")
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
         })?;
 
-        info!("WASM: Successfully locked repository for characters, deserializing characters");
+        repo.add_planet(planet).map_err(|err| {
+            error!("WASM: repo.add_planet failed: {}", err);
+            pi_error("Failed to add planet", err)
+        })
+    }
 
-        // Deserialize JavaScript objects directly using serde-wasm-bindgen
-        let characters: Vec<crate::domain::Character> =
-            serde_wasm_bindgen::from_value(characters_js).map_err(|err| {
-                error!("WASM: Failed to deserialize characters: {:?}", err);
-                JsValue::from_str(&format!("Failed to deserialize characters: {:?}", err))
+    /// Update a single planet from a JavaScript object
+    #[wasm_bindgen]
+    pub fn update_planet(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Planet")] planet_js: JsValue,
+    ) -> Result<(), JsValue> {
+        let planet: crate::domain::Planet =
+            serde_wasm_bindgen::from_value(planet_js).map_err(|err| {
+                error!("WASM: Failed to deserialize planet: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize planet: {}", err),
+                )
             })?;
+        info!("WASM: Updating planet {}", planet.id);
+
+        let mut repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
 
-        info!("WASM: Successfully deserialized characters, calling repo.load_characters_data");
+        repo.update_planet(planet).map_err(|err| {
+            error!("WASM: repo.update_planet failed: {}", err);
+            pi_error("Failed to update planet", err)
+        })
+    }
 
-        info!("WASM: characters: {:?}", characters);
+    /// Remove a loaded planet by id
+    #[wasm_bindgen]
+    pub fn remove_planet(&self, id: &str) -> Result<(), JsValue> {
+        info!("WASM: Removing planet {}", id);
 
-        repo.load_characters_data(characters).map_err(|err| {
-            error!("WASM: Failed to load characters: {}", err);
-            JsValue::from_str(&format!("Failed to load characters: {}", err))
+        let mut repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
         })?;
 
-        info!("WASM: load_characters completed successfully");
-        Ok(())
+        repo.remove_planet(id).map(|_| ()).map_err(|err| {
+            error!("WASM: repo.remove_planet failed: {}", err);
+            pi_error("Failed to remove planet", err)
+        })
     }
 
-    /// Solve for a production plan for the target product
+    /// Add a single character from a JavaScript object
+    #[wasm_bindgen]
+    pub fn add_character(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Character")] character_js: JsValue,
+    ) -> Result<(), JsValue> {
+        let character: crate::domain::Character = serde_wasm_bindgen::from_value(character_js)
+            .map_err(|err| {
+                error!("WASM: Failed to deserialize character: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize character: {}", err),
+                )
+            })?;
+        info!("WASM: Adding character {}", character.name);
+
+        let mut repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        repo.add_character(character).map_err(|err| {
+            error!("WASM: repo.add_character failed: {}", err);
+            pi_error("Failed to add character", err)
+        })
+    }
+
+    /// Update a single character from a JavaScript object
+    #[wasm_bindgen]
+    pub fn update_character(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Character")] character_js: JsValue,
+    ) -> Result<(), JsValue> {
+        let character: crate::domain::Character = serde_wasm_bindgen::from_value(character_js)
+            .map_err(|err| {
+                error!("WASM: Failed to deserialize character: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize character: {}", err),
+                )
+            })?;
+        info!("WASM: Updating character {}", character.name);
+
+        let mut repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        repo.update_character(character).map_err(|err| {
+            error!("WASM: repo.update_character failed: {}", err);
+            pi_error("Failed to update character", err)
+        })
+    }
+
+    /// Remove a loaded character by name
     #[wasm_bindgen]
+    pub fn remove_character(&self, name: &str) -> Result<(), JsValue> {
+        info!("WASM: Removing character {}", name);
+
+        let mut repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        repo.remove_character(name).map(|_| ()).map_err(|err| {
+            error!("WASM: repo.remove_character failed: {}", err);
+            pi_error("Failed to remove character", err)
+        })
+    }
+
+    /// Solve for a production plan for the target product
+    #[wasm_bindgen(unchecked_return_type = "ProductionPlan")]
     pub fn solve(&self, target_product: String) -> Result<JsValue, JsValue> {
         info!("WASM: Starting solve for product: {}", target_product);
 
         let repo = self.repository.lock().map_err(|_| {
             error!("WASM: Failed to lock repository for solving");
-            JsValue::from_str("Failed to lock repository")
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
         })?;
 
         info!("WASM: Successfully locked repository for solving");
 
-        let solver = Solver::new(&*repo);
+        let solver = self.start_solver(&repo, SolverOptions::default());
         let plan = solver.solve(&target_product).map_err(|err| {
             error!("WASM: Failed to solve: {:?}", err);
-            JsValue::from_str(&format!("Failed to solve: {:?}", err))
+            pi_error("Failed to solve", err)
         })?;
 
         info!("WASM: Successfully solved, converting to JavaScript object");
@@ -119,16 +605,1451 @@ impl PiSolver {
         // Convert the plan directly to a JavaScript object using serde-wasm-bindgen
         serde_wasm_bindgen::to_value(&plan).map_err(|err| {
             error!("WASM: Failed to serialize plan: {:?}", err);
-            JsValue::from_str(&format!("Failed to serialize plan: {:?}", err))
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize plan: {}", err),
+            )
+        })
+    }
+
+    /// Solve for a production plan with solver constraints supplied as a JavaScript object
+    #[wasm_bindgen(unchecked_return_type = "ProductionPlan")]
+    pub fn solve_with_options(
+        &self,
+        target_product: String,
+        #[wasm_bindgen(unchecked_param_type = "SolverOptions")] options_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        info!(
+            "WASM: Starting solve_with_options for product: {}",
+            target_product
+        );
+
+        let options = solver_options(options_js)?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for solving");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let solver = self.start_solver(&repo, options);
+        let plan = solver.solve(&target_product).map_err(|err| {
+            error!("WASM: Failed to solve: {:?}", err);
+            pi_error("Failed to solve", err)
+        })?;
+
+        serde_wasm_bindgen::to_value(&plan).map_err(|err| {
+            error!("WASM: Failed to serialize plan: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize plan: {}", err),
+            )
+        })
+    }
+
+    /// Solve for several target products at once, each planned as its own chain
+    #[wasm_bindgen(unchecked_return_type = "ProductionPlan")]
+    pub fn solve_many(
+        &self,
+        target_products: Vec<String>,
+        #[wasm_bindgen(unchecked_optional_param_type = "SolverOptions")] options_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        info!(
+            "WASM: Starting solve_many for products: {:?}",
+            target_products
+        );
+
+        let options = solver_options(options_js)?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for solving");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let targets: Vec<&str> = target_products.iter().map(|s| s.as_str()).collect();
+        let solver = self.start_solver(&repo, options);
+        let plan = solver.solve_many(&targets).map_err(|err| {
+            error!("WASM: Failed to solve: {:?}", err);
+            pi_error("Failed to solve", err)
+        })?;
+
+        serde_wasm_bindgen::to_value(&plan).map_err(|err| {
+            error!("WASM: Failed to serialize plan: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize plan: {}", err),
+            )
+        })
+    }
+
+    /// Plan target products starting from the colonies already set up in game, returning
+    /// the new plan and which colonies are kept, repurposed, abandoned, or established
+    #[wasm_bindgen]
+    pub fn migrate(
+        &self,
+        target_products: Vec<String>,
+        colonies_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        info!("WASM: Starting migrate for products: {:?}", target_products);
+
+        let colonies: Vec<ExistingColony> =
+            serde_wasm_bindgen::from_value(colonies_js).map_err(|err| {
+                error!("WASM: Failed to deserialize existing colonies: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize existing colonies: {}", err),
+                )
+            })?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for solving");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let targets: Vec<&str> = target_products.iter().map(|s| s.as_str()).collect();
+        let migration = self
+            .start_solver(&repo, SolverOptions::default())
+            .migrate(&targets, &colonies)
+            .map_err(|err| {
+                error!("WASM: Failed to plan migration: {:?}", err);
+                pi_error("Failed to solve", err)
+            })?;
+
+        serde_wasm_bindgen::to_value(&migration).map_err(|err| {
+            error!("WASM: Failed to serialize migration plan: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize migration plan: {}", err),
+            )
+        })
+    }
+
+    /// Plan as many targets as fit in priority order, reporting the ones that were dropped
+    #[wasm_bindgen]
+    pub fn solve_prioritized(
+        &self,
+        target_products: Vec<String>,
+        #[wasm_bindgen(unchecked_optional_param_type = "SolverOptions")] options_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        info!(
+            "WASM: Starting solve_prioritized for products: {:?}",
+            target_products
+        );
+
+        let options = solver_options(options_js)?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for solving");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let targets: Vec<&str> = target_products.iter().map(|s| s.as_str()).collect();
+        let result = self
+            .start_solver(&repo, options)
+            .solve_prioritized(&targets)
+            .map_err(|err| {
+                error!("WASM: Failed to solve: {:?}", err);
+                pi_error("Failed to solve", err)
+            })?;
+
+        serde_wasm_bindgen::to_value(&result).map_err(|err| {
+            error!("WASM: Failed to serialize prioritized plan: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize prioritized plan: {}", err),
+            )
         })
     }
+
+    /// Plan production sized to a daily quota of the target product
+    #[wasm_bindgen]
+    pub fn solve_quota(
+        &self,
+        target_product: String,
+        units_per_day: f64,
+    ) -> Result<JsValue, JsValue> {
+        info!(
+            "WASM: Starting solve_quota for {} {} per day",
+            units_per_day, target_product
+        );
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for solving");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let solver = self.start_solver(&repo, SolverOptions::default());
+        let quota = solver
+            .solve_quota(&target_product, units_per_day)
+            .map_err(|err| {
+                error!("WASM: Failed to solve quota: {:?}", err);
+                pi_error("Failed to solve", err)
+            })?;
+
+        serde_wasm_bindgen::to_value(&quota).map_err(|err| {
+            error!("WASM: Failed to serialize quota plan: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize quota plan: {}", err),
+            )
+        })
+    }
+
+    /// Plan production of several end products in a fixed ratio, given as an object
+    /// mapping product names to their share (e.g. `{ broadcast_node: 2, wetware_mainframe: 1 }`)
+    #[wasm_bindgen]
+    pub fn solve_ratio(&self, ratio_js: JsValue, sets_per_day: f64) -> Result<JsValue, JsValue> {
+        let ratio: std::collections::HashMap<String, f64> =
+            serde_wasm_bindgen::from_value(ratio_js).map_err(|err| {
+                error!("WASM: Failed to deserialize ratio: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize ratio: {}", err),
+                )
+            })?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for solving");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let ratio: Vec<(&str, f64)> = ratio.iter().map(|(p, r)| (p.as_str(), *r)).collect();
+        let quota = self
+            .start_solver(&repo, SolverOptions::default())
+            .solve_ratio(&ratio, sets_per_day)
+            .map_err(|err| {
+                error!("WASM: Failed to solve ratio: {:?}", err);
+                pi_error("Failed to solve", err)
+            })?;
+
+        serde_wasm_bindgen::to_value(&quota).map_err(|err| {
+            error!("WASM: Failed to serialize quota plan: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize quota plan: {}", err),
+            )
+        })
+    }
+
+    /// Solve daily quotas while drawing down a stockpile first. `targets_js` maps products
+    /// to units per day and `stockpile_js` is `{ inventory, horizon_days }`
+    #[wasm_bindgen]
+    pub fn solve_quotas_with_stockpile(
+        &self,
+        targets_js: JsValue,
+        stockpile_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let targets: std::collections::HashMap<String, f64> =
+            serde_wasm_bindgen::from_value(targets_js).map_err(|err| {
+                error!("WASM: Failed to deserialize targets: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize targets: {}", err),
+                )
+            })?;
+        let stockpile: Stockpile = serde_wasm_bindgen::from_value(stockpile_js).map_err(|err| {
+            error!("WASM: Failed to deserialize stockpile: {:?}", err);
+            js_error(
+                ErrorCode::DeserializationFailed,
+                format!("Failed to deserialize stockpile: {}", err),
+            )
+        })?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for solving");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let targets: Vec<(&str, f64)> = targets.iter().map(|(p, u)| (p.as_str(), *u)).collect();
+        let quota = self
+            .start_solver(&repo, SolverOptions::default())
+            .solve_quotas_with_stockpile(&targets, &stockpile)
+            .map_err(|err| {
+                error!("WASM: Failed to solve quotas with stockpile: {:?}", err);
+                pi_error("Failed to solve", err)
+            })?;
+
+        serde_wasm_bindgen::to_value(&quota).map_err(|err| {
+            error!("WASM: Failed to serialize quota plan: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize quota plan: {}", err),
+            )
+        })
+    }
+
+    /// Report which planet type or character slot limits a set of daily quotas, given as
+    /// an object mapping product names to units per day
+    #[wasm_bindgen]
+    pub fn sensitivity(&self, targets_js: JsValue) -> Result<JsValue, JsValue> {
+        let targets: std::collections::HashMap<String, f64> =
+            serde_wasm_bindgen::from_value(targets_js).map_err(|err| {
+                error!("WASM: Failed to deserialize targets: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize targets: {}", err),
+                )
+            })?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for solving");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let targets: Vec<(&str, f64)> = targets.iter().map(|(p, u)| (p.as_str(), *u)).collect();
+        let report = self
+            .start_solver(&repo, SolverOptions::default())
+            .sensitivity(&targets)
+            .map_err(|err| {
+                error!("WASM: Failed to run sensitivity analysis: {:?}", err);
+                pi_error("Failed to solve", err)
+            })?;
+
+        serde_wasm_bindgen::to_value(&report).map_err(|err| {
+            error!("WASM: Failed to serialize sensitivity report: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize sensitivity report: {}", err),
+            )
+        })
+    }
+
+    /// Compare a set of daily quotas before and after hypothetical changes (added or
+    /// removed planets and characters, trained skills) without modifying the loaded data
+    #[wasm_bindgen]
+    pub fn what_if(&self, targets_js: JsValue, changes_js: JsValue) -> Result<JsValue, JsValue> {
+        let targets: std::collections::HashMap<String, f64> =
+            serde_wasm_bindgen::from_value(targets_js).map_err(|err| {
+                error!("WASM: Failed to deserialize targets: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize targets: {}", err),
+                )
+            })?;
+        let changes: Vec<ScenarioChange> =
+            serde_wasm_bindgen::from_value(changes_js).map_err(|err| {
+                error!("WASM: Failed to deserialize scenario changes: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize scenario changes: {}", err),
+                )
+            })?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for solving");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let targets: Vec<(&str, f64)> = targets.iter().map(|(p, u)| (p.as_str(), *u)).collect();
+        let report = self
+            .start_solver(&repo, SolverOptions::default())
+            .what_if(&targets, &changes)
+            .map_err(|err| {
+                error!("WASM: Failed to evaluate scenario: {:?}", err);
+                pi_error("Failed to solve", err)
+            })?;
+
+        serde_wasm_bindgen::to_value(&report).map_err(|err| {
+            error!("WASM: Failed to serialize what-if report: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize what-if report: {}", err),
+            )
+        })
+    }
+
+    /// Save a snapshot of the loaded data under a name, with optional hypothetical changes
+    /// (added or removed planets and characters, trained skills) applied to it
+    #[wasm_bindgen]
+    pub fn create_branch(&self, name: &str, changes_js: JsValue) -> Result<(), JsValue> {
+        info!("WASM: Creating branch {}", name);
+
+        let changes: Vec<ScenarioChange> = if changes_js.is_undefined() || changes_js.is_null() {
+            Vec::new()
+        } else {
+            serde_wasm_bindgen::from_value(changes_js).map_err(|err| {
+                error!("WASM: Failed to deserialize scenario changes: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize scenario changes: {}", err),
+                )
+            })?
+        };
+
+        let branch = self
+            .repository
+            .lock()
+            .map_err(|_| {
+                error!("WASM: Failed to lock repository");
+                js_error(ErrorCode::LockFailed, "Failed to lock repository")
+            })?
+            .branch(&changes);
+        self.lock_branches()?.insert(name.to_string(), branch);
+        Ok(())
+    }
+
+    /// Make a saved branch the loaded data, so every other method works on it. The data
+    /// loaded before is dropped unless it was saved as a branch.
+    #[wasm_bindgen]
+    pub fn checkout_branch(&self, name: &str) -> Result<(), JsValue> {
+        info!("WASM: Checking out branch {}", name);
+
+        let branch = self
+            .lock_branches()?
+            .get(name)
+            .map(MemoryRepository::snapshot)
+            .ok_or_else(|| {
+                error!("WASM: Unknown branch {}", name);
+                error_object(
+                    ErrorCode::BranchNotFound,
+                    format!("Unknown branch: {}", name),
+                    ErrorContext {
+                        branch: Some(name.to_string()),
+                        ..ErrorContext::default()
+                    },
+                )
+            })?;
+        *self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })? = branch;
+        Ok(())
+    }
+
+    /// Forget a saved branch, returning whether it existed
+    #[wasm_bindgen]
+    pub fn delete_branch(&self, name: &str) -> Result<bool, JsValue> {
+        info!("WASM: Deleting branch {}", name);
+        Ok(self.lock_branches()?.remove(name).is_some())
+    }
+
+    /// Names of the saved branches, sorted
+    #[wasm_bindgen]
+    pub fn branch_names(&self) -> Result<Vec<String>, JsValue> {
+        let mut names: Vec<String> = self.lock_branches()?.keys().cloned().collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Solve a set of daily quotas on each of the named branches and compare how much of
+    /// them every branch covers
+    #[wasm_bindgen]
+    pub fn compare_branches(
+        &self,
+        targets_js: JsValue,
+        names_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let targets: std::collections::HashMap<String, f64> =
+            serde_wasm_bindgen::from_value(targets_js).map_err(|err| {
+                error!("WASM: Failed to deserialize targets: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize targets: {}", err),
+                )
+            })?;
+        let names: Vec<String> = serde_wasm_bindgen::from_value(names_js).map_err(|err| {
+            error!("WASM: Failed to deserialize branch names: {:?}", err);
+            js_error(
+                ErrorCode::DeserializationFailed,
+                format!("Failed to deserialize branch names: {}", err),
+            )
+        })?;
+        info!("WASM: Comparing {} branches", names.len());
+
+        let branches = self.lock_branches()?;
+        let selected: Vec<(&str, &dyn crate::repository::Repository)> = names
+            .iter()
+            .map(|name| {
+                branches
+                    .get(name)
+                    .map(|branch| (name.as_str(), branch as &dyn crate::repository::Repository))
+                    .ok_or_else(|| {
+                        error!("WASM: Unknown branch {}", name);
+                        error_object(
+                            ErrorCode::BranchNotFound,
+                            format!("Unknown branch: {}", name),
+                            ErrorContext {
+                                branch: Some(name.to_string()),
+                                ..ErrorContext::default()
+                            },
+                        )
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for solving");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let targets: Vec<(&str, f64)> = targets.iter().map(|(p, u)| (p.as_str(), *u)).collect();
+        let outcomes = self
+            .start_solver(&repo, SolverOptions::default())
+            .compare_branches(&targets, &selected)
+            .map_err(|err| {
+                error!("WASM: Failed to compare branches: {:?}", err);
+                pi_error("Failed to solve", err)
+            })?;
+
+        serde_wasm_bindgen::to_value(&outcomes).map_err(|err| {
+            error!("WASM: Failed to serialize branch comparison: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize branch comparison: {}", err),
+            )
+        })
+    }
+
+    /// Classify every product (or only one tier, e.g. `"P4"`) as producible, producible
+    /// with bought inputs, or impossible with the loaded planets
+    #[wasm_bindgen]
+    pub fn coverage_report(&self, tier_js: JsValue) -> Result<JsValue, JsValue> {
+        let tier: Option<ProductTier> = if tier_js.is_undefined() || tier_js.is_null() {
+            None
+        } else {
+            Some(serde_wasm_bindgen::from_value(tier_js).map_err(|err| {
+                error!("WASM: Failed to deserialize tier: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize tier: {}", err),
+                )
+            })?)
+        };
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for coverage report");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let report = coverage_report(&*repo, tier);
+
+        serde_wasm_bindgen::to_value(&report).map_err(|err| {
+            error!("WASM: Failed to serialize coverage report: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize coverage report: {}", err),
+            )
+        })
+    }
+
+    /// Look up a product by its EVE type ID
+    #[wasm_bindgen]
+    pub fn get_product_by_type_id(&self, type_id: u32) -> Result<JsValue, JsValue> {
+        info!("WASM: Looking up product with type ID {}", type_id);
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for product lookup");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let product = repo.get_product_by_type_id(type_id).ok_or_else(|| {
+            error!("WASM: Unknown type ID {}", type_id);
+            pi_error(
+                "Failed to look up type ID",
+                RepositoryError::ProductNotFound(type_id.to_string()),
+            )
+        })?;
+
+        serde_wasm_bindgen::to_value(&product).map_err(|err| {
+            error!("WASM: Failed to serialize product: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize product: {}", err),
+            )
+        })
+    }
+
+    /// Products matching a partial name for autocomplete, best match first, at most `limit`
+    #[wasm_bindgen]
+    pub fn search_products(&self, query: String, limit: usize) -> Result<JsValue, JsValue> {
+        info!("WASM: Searching products for {}", query);
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for product search");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+        let mut products = repo.search_products(&query);
+        products.truncate(limit);
+
+        serde_wasm_bindgen::to_value(&products).map_err(|err| {
+            error!("WASM: Failed to serialize products: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize products: {}", err),
+            )
+        })
+    }
+
+    /// Load localized product names from an object mapping type IDs to language codes and
+    /// names, in the layout of the static data export
+    #[wasm_bindgen]
+    pub fn load_product_translations(&self, names_js: JsValue) -> Result<(), JsValue> {
+        info!("WASM: Loading product translations");
+
+        let names: std::collections::HashMap<u32, std::collections::HashMap<String, String>> =
+            serde_wasm_bindgen::from_value(names_js).map_err(|err| {
+                error!("WASM: Failed to deserialize translations: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize translations: {}", err),
+                )
+            })?;
+
+        let mut localization = self.localization.lock().map_err(|_| {
+            error!("WASM: Failed to lock localization");
+            js_error(ErrorCode::LockFailed, "Failed to lock localization")
+        })?;
+        localization.load_product_names(names);
+        Ok(())
+    }
+
+    /// Name of a product in a language given by code, e.g. "de"
+    #[wasm_bindgen]
+    pub fn product_name(&self, product: String, language: String) -> Result<String, JsValue> {
+        let language = parse_language(&language)?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for product name");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+        let product = repo.get_product_by_name(&product).ok_or_else(|| {
+            error!("WASM: Unknown product {}", product);
+            pi_error(
+                "Failed to look up product",
+                RepositoryError::ProductNotFound(product.clone()),
+            )
+        })?;
+
+        let localization = self.localization.lock().map_err(|_| {
+            error!("WASM: Failed to lock localization");
+            js_error(ErrorCode::LockFailed, "Failed to lock localization")
+        })?;
+        Ok(localization.product_name(&product, language))
+    }
+
+    /// Name of a planet type in a language given by code, e.g. "de"
+    #[wasm_bindgen]
+    pub fn planet_type_name(
+        &self,
+        planet_type_js: JsValue,
+        language: String,
+    ) -> Result<String, JsValue> {
+        let language = parse_language(&language)?;
+        let planet_type: PlanetType =
+            serde_wasm_bindgen::from_value(planet_type_js).map_err(|err| {
+                error!("WASM: Failed to deserialize planet type: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize planet type: {}", err),
+                )
+            })?;
+
+        Ok(planet_type_name(planet_type, language).to_string())
+    }
+
+    /// List every factory configuration a planet of the given type could run for a product,
+    /// best first, without running a solve
+    #[wasm_bindgen]
+    pub fn get_factory_configs(
+        &self,
+        planet_type_js: JsValue,
+        product: String,
+    ) -> Result<JsValue, JsValue> {
+        info!("WASM: Listing factory configurations for {}", product);
+
+        let planet_type: PlanetType =
+            serde_wasm_bindgen::from_value(planet_type_js).map_err(|err| {
+                error!("WASM: Failed to deserialize planet type: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize planet type: {}", err),
+                )
+            })?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for factory configurations");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        if repo.get_product_by_name(&product).is_none() {
+            error!("WASM: Unknown product {}", product);
+            return Err(pi_error(
+                "Failed to look up product",
+                RepositoryError::ProductNotFound(product.clone()),
+            ));
+        }
+        let configs = find_valid_factory_configurations(&*repo, planet_type, &product);
+
+        serde_wasm_bindgen::to_value(&configs).map_err(|err| {
+            error!(
+                "WASM: Failed to serialize factory configurations: {:?}",
+                err
+            );
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize factory configurations: {}", err),
+            )
+        })
+    }
+
+    /// Like `get_factory_configs`, but also explain why every other factory type was
+    /// rejected for the product
+    #[wasm_bindgen]
+    pub fn search_factory_configs(
+        &self,
+        planet_type_js: JsValue,
+        product: String,
+    ) -> Result<JsValue, JsValue> {
+        info!("WASM: Searching factory configurations for {}", product);
+
+        let planet_type: PlanetType =
+            serde_wasm_bindgen::from_value(planet_type_js).map_err(|err| {
+                error!("WASM: Failed to deserialize planet type: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize planet type: {}", err),
+                )
+            })?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for factory configurations");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let search = search_factory_configurations(&*repo, planet_type, &product);
+
+        serde_wasm_bindgen::to_value(&search).map_err(|err| {
+            error!("WASM: Failed to serialize factory search: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize factory search: {}", err),
+            )
+        })
+    }
+
+    /// Rank the products in `prices_js` (an object mapping product names to ISK per unit, or
+    /// the stored prices when left out) by feasibility and estimated daily profit, returning
+    /// the best `n`
+    #[wasm_bindgen]
+    pub fn recommend_targets(&self, n: usize, prices_js: JsValue) -> Result<JsValue, JsValue> {
+        info!("WASM: Recommending {} targets", n);
+
+        let prices = self.resolve_prices(prices_js)?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for solving");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let recommendations = self
+            .start_solver(&repo, SolverOptions::default())
+            .recommend_targets(n, &prices)
+            .map_err(|err| {
+                error!("WASM: Failed to recommend targets: {:?}", err);
+                pi_error("Failed to solve", err)
+            })?;
+
+        serde_wasm_bindgen::to_value(&recommendations).map_err(|err| {
+            error!("WASM: Failed to serialize recommendations: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize recommendations: {}", err),
+            )
+        })
+    }
+
+    /// Plan and value every P4 product, and every P3 one when `include_p3` is set, at the
+    /// prices in `prices_js` (or the stored ones) and the trade settings in `trade_js`.
+    /// Returns the whole table, feasible products first, by net ISK per hour.
+    #[wasm_bindgen]
+    pub fn rank_products(
+        &self,
+        include_p3: bool,
+        prices_js: JsValue,
+        trade_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        info!("WASM: Ranking products (P3 included: {})", include_p3);
+
+        let prices = self.resolve_prices(prices_js)?;
+        let trade = parse_trade_settings(trade_js)?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for solving");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let rankings = self
+            .start_solver(&repo, SolverOptions::default())
+            .rank_products(include_p3, &prices, &trade)
+            .map_err(|err| {
+                error!("WASM: Failed to rank products: {:?}", err);
+                pi_error("Failed to solve", err)
+            })?;
+
+        serde_wasm_bindgen::to_value(&rankings).map_err(|err| {
+            error!("WASM: Failed to serialize product rankings: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize product rankings: {}", err),
+            )
+        })
+    }
+
+    /// Suggest the fewest planets, by type, to add so an infeasible target becomes solvable
+    #[wasm_bindgen]
+    pub fn advise_planets(
+        &self,
+        target_product: String,
+        max_additions: usize,
+    ) -> Result<JsValue, JsValue> {
+        info!("WASM: Advising planets for {}", target_product);
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for solving");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let advice = self
+            .start_solver(&repo, SolverOptions::default())
+            .advise_planets(&target_product, max_additions)
+            .map_err(|err| {
+                error!("WASM: Failed to advise planets: {:?}", err);
+                pi_error("Failed to solve", err)
+            })?;
+
+        serde_wasm_bindgen::to_value(&advice).map_err(|err| {
+            error!("WASM: Failed to serialize planet advice: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize planet advice: {}", err),
+            )
+        })
+    }
+
+    /// Rank per-character skill upgrades by how much they raise the achievable share of a
+    /// set of daily quotas, given as an object mapping product names to units per day
+    #[wasm_bindgen]
+    pub fn advise_skills(&self, targets_js: JsValue) -> Result<JsValue, JsValue> {
+        let targets: std::collections::HashMap<String, f64> =
+            serde_wasm_bindgen::from_value(targets_js).map_err(|err| {
+                error!("WASM: Failed to deserialize targets: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize targets: {}", err),
+                )
+            })?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for solving");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let targets: Vec<(&str, f64)> = targets.iter().map(|(p, u)| (p.as_str(), *u)).collect();
+        let advice = self
+            .start_solver(&repo, SolverOptions::default())
+            .advise_skills(&targets)
+            .map_err(|err| {
+                error!("WASM: Failed to advise skills: {:?}", err);
+                pi_error("Failed to solve", err)
+            })?;
+
+        serde_wasm_bindgen::to_value(&advice).map_err(|err| {
+            error!("WASM: Failed to serialize skill advice: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize skill advice: {}", err),
+            )
+        })
+    }
+
+    /// Estimate how many extra characters with `skills_js` skills are needed to meet a set
+    /// of daily quotas, given as an object mapping product names to units per day
+    #[wasm_bindgen]
+    pub fn estimate_alts(
+        &self,
+        targets_js: JsValue,
+        skills_js: JsValue,
+        max_characters: usize,
+    ) -> Result<JsValue, JsValue> {
+        let targets: std::collections::HashMap<String, f64> =
+            serde_wasm_bindgen::from_value(targets_js).map_err(|err| {
+                error!("WASM: Failed to deserialize targets: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize targets: {}", err),
+                )
+            })?;
+        let skills: CharacterSkills = serde_wasm_bindgen::from_value(skills_js).map_err(|err| {
+            error!("WASM: Failed to deserialize skills: {:?}", err);
+            js_error(
+                ErrorCode::DeserializationFailed,
+                format!("Failed to deserialize skills: {}", err),
+            )
+        })?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for solving");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let targets: Vec<(&str, f64)> = targets.iter().map(|(p, u)| (p.as_str(), *u)).collect();
+        let estimate = self
+            .start_solver(&repo, SolverOptions::default())
+            .estimate_alts(&targets, &skills, max_characters)
+            .map_err(|err| {
+                error!("WASM: Failed to estimate characters: {:?}", err);
+                pi_error("Failed to solve", err)
+            })?;
+
+        serde_wasm_bindgen::to_value(&estimate).map_err(|err| {
+            error!("WASM: Failed to serialize character estimate: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize character estimate: {}", err),
+            )
+        })
+    }
+
+    /// Plan target products for a corporation (`{ name, members, planets }`), independent of
+    /// the loaded data, and return the plan grouped per member
+    #[wasm_bindgen]
+    pub fn solve_corporation(
+        &self,
+        corporation_js: JsValue,
+        target_products: Vec<String>,
+        #[wasm_bindgen(unchecked_optional_param_type = "SolverOptions")] options_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let corporation: Corporation =
+            serde_wasm_bindgen::from_value(corporation_js).map_err(|err| {
+                error!("WASM: Failed to deserialize corporation: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize corporation: {}", err),
+                )
+            })?;
+        let options = solver_options(options_js)?;
+        info!(
+            "WASM: Solving {:?} for corporation {}",
+            target_products, corporation.name
+        );
+
+        let targets: Vec<&str> = target_products.iter().map(|s| s.as_str()).collect();
+        let plan = solve_for_corporation(&corporation, &targets, options).map_err(|err| {
+            error!("WASM: Failed to solve for corporation: {:?}", err);
+            pi_error("Failed to solve", err)
+        })?;
+
+        serde_wasm_bindgen::to_value(&plan).map_err(|err| {
+            error!("WASM: Failed to serialize corporation plan: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize corporation plan: {}", err),
+            )
+        })
+    }
+
+    /// Run the product database, resource map, and canned solve checks and return a
+    /// structured health report, to detect corrupted or stale WASM deployments
+    #[wasm_bindgen]
+    pub fn self_test(&self) -> Result<JsValue, JsValue> {
+        info!("WASM: Running self test");
+
+        let report = run_self_test();
+        if !report.healthy {
+            error!("WASM: Self test failed: {:?}", report);
+        }
+
+        serde_wasm_bindgen::to_value(&report).map_err(|err| {
+            error!("WASM: Failed to serialize health report: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize health report: {}", err),
+            )
+        })
+    }
+
+    /// Compute the launch cadence of every planet in a plan and flag impractical ones
+    #[wasm_bindgen]
+    pub fn launch_schedule(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "ProductionPlan")] plan_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let plan: ProductionPlan = serde_wasm_bindgen::from_value(plan_js).map_err(|err| {
+            error!("WASM: Failed to deserialize plan: {:?}", err);
+            js_error(
+                ErrorCode::DeserializationFailed,
+                format!("Failed to deserialize plan: {}", err),
+            )
+        })?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for logistics");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let report = launch_schedule(&plan, &*repo, &LogisticsOptions::default());
+
+        serde_wasm_bindgen::to_value(&report).map_err(|err| {
+            error!("WASM: Failed to serialize logistics report: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize logistics report: {}", err),
+            )
+        })
+    }
+
+    /// Build the flow graph of a plan: planets as nodes, hauled products as edges with
+    /// their hourly units and volume
+    #[wasm_bindgen]
+    pub fn flow_graph(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "ProductionPlan")] plan_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let plan: ProductionPlan = serde_wasm_bindgen::from_value(plan_js).map_err(|err| {
+            error!("WASM: Failed to deserialize plan: {:?}", err);
+            js_error(
+                ErrorCode::DeserializationFailed,
+                format!("Failed to deserialize plan: {}", err),
+            )
+        })?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for flow graph");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let graph = FlowGraph::from_plan(&*repo, &plan);
+
+        serde_wasm_bindgen::to_value(&graph).map_err(|err| {
+            error!("WASM: Failed to serialize flow graph: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize flow graph: {}", err),
+            )
+        })
+    }
+
+    /// Flag planets whose inputs aren't covered upstream or whose outputs go unused
+    #[wasm_bindgen]
+    pub fn plan_balance(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "ProductionPlan")] plan_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let plan: ProductionPlan = serde_wasm_bindgen::from_value(plan_js).map_err(|err| {
+            error!("WASM: Failed to deserialize plan: {:?}", err);
+            js_error(
+                ErrorCode::DeserializationFailed,
+                format!("Failed to deserialize plan: {}", err),
+            )
+        })?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for balance check");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let report = check_plan_balance(&*repo, &plan);
+        info!(
+            "WASM: Plan has {} production imbalances",
+            report.imbalances.len()
+        );
+
+        serde_wasm_bindgen::to_value(&report).map_err(|err| {
+            error!("WASM: Failed to serialize balance report: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize balance report: {}", err),
+            )
+        })
+    }
+
+    /// Customs tax of a plan: ISK per day per customs office and in total, and whether
+    /// extraction planets would pay less exporting P2 than P1
+    #[wasm_bindgen]
+    pub fn tax_report(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "ProductionPlan")] plan_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let plan: ProductionPlan = serde_wasm_bindgen::from_value(plan_js).map_err(|err| {
+            error!("WASM: Failed to deserialize plan: {:?}", err);
+            js_error(
+                ErrorCode::DeserializationFailed,
+                format!("Failed to deserialize plan: {}", err),
+            )
+        })?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for tax report");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let report = evaluate_plan_taxes(&plan, &*repo);
+        info!(
+            "WASM: Plan pays {:.0} ISK per day in customs tax",
+            report.total_daily_tax
+        );
+
+        serde_wasm_bindgen::to_value(&report).map_err(|err| {
+            error!("WASM: Failed to serialize tax report: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize tax report: {}", err),
+            )
+        })
+    }
+
+    /// Value a plan at the prices in `prices_js` (an object mapping product names to ISK
+    /// per unit, or the stored prices when left out): revenue, input costs, taxes and net ISK
+    /// per hour. `trade_js` sets the seller's Accounting and Broker Relations levels and
+    /// whether sales and purchases use orders or fill immediately.
+    #[wasm_bindgen]
+    pub fn value_plan(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "ProductionPlan")] plan_js: JsValue,
+        prices_js: JsValue,
+        trade_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let plan: ProductionPlan = serde_wasm_bindgen::from_value(plan_js).map_err(|err| {
+            error!("WASM: Failed to deserialize plan: {:?}", err);
+            js_error(
+                ErrorCode::DeserializationFailed,
+                format!("Failed to deserialize plan: {}", err),
+            )
+        })?;
+        let prices = self.resolve_prices(prices_js)?;
+        let trade = parse_trade_settings(trade_js)?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for valuation");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let valuation = value_plan(&*repo, &plan, &prices, &trade);
+        info!(
+            "WASM: Plan nets {:.0} ISK per hour",
+            valuation.net_isk_per_hour
+        );
+
+        serde_wasm_bindgen::to_value(&valuation).map_err(|err| {
+            error!("WASM: Failed to serialize valuation: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize valuation: {}", err),
+            )
+        })
+    }
+
+    /// Compare building each intermediate of a plan against buying it, at the prices in
+    /// `prices_js` or the stored ones. `slot_isk_per_hour` is what a planet slot would earn
+    /// elsewhere, the plan's average per planet when left out.
+    #[wasm_bindgen]
+    pub fn buy_vs_build(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "ProductionPlan")] plan_js: JsValue,
+        prices_js: JsValue,
+        slot_isk_per_hour: Option<f64>,
+    ) -> Result<JsValue, JsValue> {
+        let plan: ProductionPlan = serde_wasm_bindgen::from_value(plan_js).map_err(|err| {
+            error!("WASM: Failed to deserialize plan: {:?}", err);
+            js_error(
+                ErrorCode::DeserializationFailed,
+                format!("Failed to deserialize plan: {}", err),
+            )
+        })?;
+        let prices = self.resolve_prices(prices_js)?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for buy vs build");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let comparisons = buy_vs_build(&*repo, &plan, &prices, slot_isk_per_hour);
+        info!(
+            "WASM: {} of {} intermediates are cheaper to buy",
+            comparisons.iter().filter(|c| c.cheaper_to_buy()).count(),
+            comparisons.len()
+        );
+
+        serde_wasm_bindgen::to_value(&comparisons).map_err(|err| {
+            error!("WASM: Failed to serialize buy vs build: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize buy vs build: {}", err),
+            )
+        })
+    }
+
+    /// Break a plan's ISK per hour down by planet and by character, with prices and trade
+    /// settings as in `value_plan`
+    #[wasm_bindgen]
+    pub fn profitability_report(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "ProductionPlan")] plan_js: JsValue,
+        prices_js: JsValue,
+        trade_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let plan: ProductionPlan = serde_wasm_bindgen::from_value(plan_js).map_err(|err| {
+            error!("WASM: Failed to deserialize plan: {:?}", err);
+            js_error(
+                ErrorCode::DeserializationFailed,
+                format!("Failed to deserialize plan: {}", err),
+            )
+        })?;
+        let prices = self.resolve_prices(prices_js)?;
+        let trade = parse_trade_settings(trade_js)?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for profitability");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let report = profitability_report(&*repo, &plan, &prices, &trade);
+
+        serde_wasm_bindgen::to_value(&report).map_err(|err| {
+            error!("WASM: Failed to serialize profitability report: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize profitability report: {}", err),
+            )
+        })
+    }
+
+    /// Re-plan only the products affected by losing access to a planet
+    #[wasm_bindgen(unchecked_return_type = "ProductionPlan")]
+    pub fn repair(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "ProductionPlan")] plan_js: JsValue,
+        removed_planet_id: String,
+    ) -> Result<JsValue, JsValue> {
+        info!("WASM: Repairing plan without planet {}", removed_planet_id);
+
+        let plan: ProductionPlan = serde_wasm_bindgen::from_value(plan_js).map_err(|err| {
+            error!("WASM: Failed to deserialize plan: {:?}", err);
+            js_error(
+                ErrorCode::DeserializationFailed,
+                format!("Failed to deserialize plan: {}", err),
+            )
+        })?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for solving");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let repaired = self
+            .start_solver(&repo, SolverOptions::default())
+            .repair(&plan, &removed_planet_id)
+            .map_err(|err| {
+                error!("WASM: Failed to repair plan: {:?}", err);
+                pi_error("Failed to solve", err)
+            })?;
+
+        serde_wasm_bindgen::to_value(&repaired).map_err(|err| {
+            error!("WASM: Failed to serialize plan: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize plan: {}", err),
+            )
+        })
+    }
+}
+
+impl PiSolver {
+    /// Create a solver for a new solve that stops once the shared cancel flag is raised.
+    /// The flag is read between search nodes, which is the only time a synchronous solve
+    /// can see it change.
+    fn start_solver<'a>(
+        &self,
+        repository: &'a MemoryRepository,
+        options: SolverOptions,
+    ) -> Solver<'a> {
+        let solver = Solver::with_options(repository, options);
+        match self.cancel_flag.lock().ok().and_then(|flag| flag.clone()) {
+            Some(flag) => solver.with_cancel_check(move || {
+                js_sys::Atomics::load(&flag, 0).is_ok_and(|value| value != 0)
+            }),
+            None => solver,
+        }
+    }
+
+    /// Load untyped records from JavaScript objects and return the load report
+    fn load_records(
+        &self,
+        kind: &str,
+        records_js: JsValue,
+        load: impl FnOnce(
+            &mut MemoryRepository,
+            Vec<serde_json::Value>,
+        ) -> Result<LoadReport, RepositoryError>,
+    ) -> Result<JsValue, JsValue> {
+        let records: Vec<serde_json::Value> =
+            serde_wasm_bindgen::from_value(records_js).map_err(|err| {
+                error!("WASM: Failed to deserialize {}: {:?}", kind, err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize {}: {}", kind, err),
+                )
+            })?;
+
+        let mut repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        let report = load(&mut repo, records).map_err(|err| {
+            error!("WASM: Failed to load {}: {}", kind, err);
+            pi_error(&format!("Failed to load {}", kind), err)
+        })?;
+        for warning in &report.warnings {
+            info!(
+                "WASM: Ignored unknown field {:?} of record {}",
+                warning.field, warning.index
+            );
+        }
+
+        serde_wasm_bindgen::to_value(&report).map_err(|err| {
+            error!("WASM: Failed to serialize load report: {:?}", err);
+            js_error(
+                ErrorCode::SerializationFailed,
+                format!("Failed to serialize load report: {}", err),
+            )
+        })
+    }
+
+    /// Lock the saved scenario branches
+    fn lock_branches(
+        &self,
+    ) -> Result<
+        std::sync::MutexGuard<'_, std::collections::HashMap<String, MemoryRepository>>,
+        JsValue,
+    > {
+        self.branches.lock().map_err(|_| {
+            error!("WASM: Failed to lock branches");
+            js_error(ErrorCode::LockFailed, "Failed to lock branches")
+        })
+    }
+
+    /// Parse a price object keyed by product name, in-game name or type ID
+    fn parse_prices(&self, prices_js: JsValue) -> Result<PriceSnapshot, JsValue> {
+        let entries: std::collections::HashMap<String, SnapshotEntry> =
+            serde_wasm_bindgen::from_value(prices_js).map_err(|err| {
+                error!("WASM: Failed to deserialize prices: {:?}", err);
+                js_error(
+                    ErrorCode::DeserializationFailed,
+                    format!("Failed to deserialize prices: {}", err),
+                )
+            })?;
+
+        let repo = self.repository.lock().map_err(|_| {
+            error!("WASM: Failed to lock repository for prices");
+            js_error(ErrorCode::LockFailed, "Failed to lock repository")
+        })?;
+
+        PriceSnapshot::from_entries(&*repo, entries).map_err(|err| {
+            error!("WASM: Invalid prices: {}", err);
+            pi_error("Invalid prices", err)
+        })
+    }
+
+    /// Prices passed to a call, or the stored ones when the caller left them out
+    fn resolve_prices(&self, prices_js: JsValue) -> Result<PriceSnapshot, JsValue> {
+        if !prices_js.is_undefined() && !prices_js.is_null() {
+            return self.parse_prices(prices_js);
+        }
+        self.prices
+            .lock()
+            .map(|prices| prices.clone())
+            .map_err(|_| {
+                error!("WASM: Failed to lock prices");
+                js_error(ErrorCode::LockFailed, "Failed to lock prices")
+            })
+    }
+}
+
+/// Error thrown to JavaScript, so the frontend can branch on `code` and point at the
+/// product or planet in `context` instead of parsing `message`
+#[derive(Debug, Serialize, Tsify)]
+struct WasmError {
+    code: ErrorCode,
+    message: String,
+    context: ErrorContext,
+}
+
+/// Build the JS error object, falling back to a plain string if it can't be serialized
+fn error_object(code: ErrorCode, message: String, context: ErrorContext) -> JsValue {
+    let err = WasmError {
+        code,
+        message,
+        context,
+    };
+    serde_wasm_bindgen::to_value(&err).unwrap_or_else(|_| JsValue::from_str(&err.message))
+}
+
+/// JS error object for a failure with no domain data, like a lock or (de)serialization
+fn js_error(code: ErrorCode, message: impl Into<String>) -> JsValue {
+    error_object(code, message.into(), ErrorContext::default())
+}
+
+/// JS error object for a domain error, keeping its code and context
+fn pi_error(action: &str, err: impl Into<PiError>) -> JsValue {
+    let err = err.into();
+    error_object(err.code(), format!("{}: {}", action, err), err.context())
+}
+
+/// Solver options supplied from JavaScript, the defaults when none were given. Options
+/// holding a value outside its valid range are rejected rather than solved with.
+fn solver_options(options_js: JsValue) -> Result<SolverOptions, JsValue> {
+    if options_js.is_undefined() || options_js.is_null() {
+        return Ok(SolverOptions::default());
+    }
+    let options: SolverOptions = serde_wasm_bindgen::from_value(options_js).map_err(|err| {
+        error!("WASM: Failed to deserialize solver options: {:?}", err);
+        js_error(
+            ErrorCode::DeserializationFailed,
+            format!("Failed to deserialize solver options: {}", err),
+        )
+    })?;
+    options.validate().map_err(|err| {
+        error!("WASM: Invalid solver options: {:?}", err);
+        pi_error("Invalid solver options", err)
+    })?;
+    Ok(options)
+}
+
+/// Parse a language code, rejecting languages EVE isn't localized in
+fn parse_language(code: &str) -> Result<Language, JsValue> {
+    Language::from_code(code).ok_or_else(|| {
+        error!("WASM: Unsupported language {}", code);
+        js_error(
+            ErrorCode::InvalidInput,
+            format!("Unsupported language: {}", code),
+        )
+    })
+}
+
+/// Parse trading skills and order types, untrained with orders both ways when left out
+fn parse_trade_settings(trade_js: JsValue) -> Result<TradeSettings, JsValue> {
+    if trade_js.is_undefined() || trade_js.is_null() {
+        return Ok(TradeSettings::default());
+    }
+    serde_wasm_bindgen::from_value(trade_js).map_err(|err| {
+        error!("WASM: Failed to deserialize trade settings: {:?}", err);
+        js_error(
+            ErrorCode::DeserializationFailed,
+            format!("Failed to deserialize trade settings: {}", err),
+        )
+    })
 }
 
 /// Export helper function to convert a production plan to a simpler JavaScript format
 #[wasm_bindgen]
-pub fn format_production_plan(plan_js: JsValue) -> Result<JsValue, JsValue> {
-    let plan: ProductionPlan = serde_wasm_bindgen::from_value(plan_js)
-        .map_err(|err| JsValue::from_str(&format!("Failed to deserialize plan: {:?}", err)))?;
+pub fn format_production_plan(
+    #[wasm_bindgen(unchecked_param_type = "ProductionPlan")] plan_js: JsValue,
+) -> Result<JsValue, JsValue> {
+    let plan: ProductionPlan = serde_wasm_bindgen::from_value(plan_js).map_err(|err| {
+        js_error(
+            ErrorCode::DeserializationFailed,
+            format!("Failed to deserialize plan: {}", err),
+        )
+    })?;
 
     // Create a simplified JavaScript-friendly structure
     let simplified_plan = plan
@@ -139,19 +2060,26 @@ pub fn format_production_plan(plan_js: JsValue) -> Result<JsValue, JsValue> {
                 "character": assignment.character,
                 "planet": assignment.planet,
                 "type": format!("{:?}", assignment.planet_type),
-                "output": assignment.output,
+                "output": assignment.output(),
+                "outputs": assignment.outputs,
                 "import": assignment.imported_inputs,
-                "mine": assignment.mined_inputs
+                "mine": assignment.mined_inputs,
+                "import_m3_per_hour": assignment.import_m3_per_hour
             })
         })
         .collect::<Vec<_>>();
 
     let result = serde_json::json!({
-        "plan": simplified_plan
+        "plan": simplified_plan,
+        "flows": plan.flows,
+        "schedule": plan.schedule
     });
 
     // Convert back to JsValue using serde-wasm-bindgen
     serde_wasm_bindgen::to_value(&result).map_err(|err| {
-        JsValue::from_str(&format!("Failed to serialize simplified plan: {:?}", err))
+        js_error(
+            ErrorCode::SerializationFailed,
+            format!("Failed to serialize simplified plan: {}", err),
+        )
     })
 }
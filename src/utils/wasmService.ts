@@ -1,4 +1,9 @@
 import init, { PiSolver as WasmPiSolver } from '../../pkg/eve_pi.js';
+import type {
+  Planet as SolverPlanet,
+  PlanetType,
+  WasmError,
+} from '../../pkg/eve_pi.js';
 
 export interface Character {
   name: string;
@@ -24,6 +29,7 @@ export interface ProductionStep {
   planet: string;
   type: string;
   output: string;
+  outputs: string[];
   import: string[];
   mine: string[];
 }
@@ -32,6 +38,12 @@ export interface ProductionPlan {
   plan: ProductionStep[];
 }
 
+export type PiSolverError = WasmError;
+
+function isPiSolverError(error: unknown): error is PiSolverError {
+  return typeof error === 'object' && error !== null && 'code' in error && 'message' in error;
+}
+
 export class PiSolverService {
   private static instance: PiSolverService | null = null;
   private solver: WasmPiSolver | null = null;
@@ -94,7 +106,7 @@ export class PiSolverService {
 
     try {
       // Convert planets to the format expected by the Rust code
-      const planetsForRust = planets.map(planet => ({
+      const planetsForRust: SolverPlanet[] = planets.map(planet => ({
         id: planet.id,
         planet_type: this.convertPlanetType(planet.type),
         resources: planet.resources
@@ -109,18 +121,20 @@ export class PiSolverService {
 
       // The plan is already a JavaScript object, no need for format_production_plan
       return {
-        plan: plan.assignments.map((assignment: any) => ({
+        plan: plan.assignments.map(assignment => ({
           character: assignment.character,
           planet: assignment.planet,
           type: assignment.planet_type,
-          output: assignment.output,
+          output: assignment.outputs[0],
+          outputs: assignment.outputs,
           import: assignment.imported_inputs || [],
           mine: assignment.mined_inputs || []
         }))
       };
     } catch (error) {
       console.error('Error calculating production plan:', error);
-      throw new Error(`Failed to calculate production plan: ${error}`);
+      const message = isPiSolverError(error) ? error.message : String(error);
+      throw new Error(`Failed to calculate production plan: ${message}`);
     }
   }
 
@@ -177,9 +191,9 @@ export class PiSolverService {
     return ['barren', 'gas', 'ice', 'lava', 'oceanic', 'plasma', 'storm', 'temperate'];
   }
 
-  private convertPlanetType(type: string): string {
+  private convertPlanetType(type: string): PlanetType {
     // Convert from frontend format to Rust enum format
-    const typeMap: Record<string, string> = {
+    const typeMap: Record<string, PlanetType> = {
       'barren': 'Barren',
       'gas': 'Gas',
       'ice': 'Ice',
@@ -189,7 +203,7 @@ export class PiSolverService {
       'storm': 'Storm',
       'temperate': 'Temperate'
     };
-    return typeMap[type] || type;
+    return typeMap[type] || (type as PlanetType);
   }
 }
 